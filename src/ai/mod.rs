@@ -110,9 +110,13 @@ impl Clone for GeminiAI {
 }
 
 impl GeminiAI {
-    pub fn new() -> Result<Self> {
-        let api_key = env::var("GEMINI_API_KEY")
-            .context("GEMINI_API_KEY environment variable not set")?;
+    /// Use `api_key` from the configuration, falling back to the GEMINI_API_KEY environment variable
+    pub fn new(api_key: Option<&str>) -> Result<Self> {
+        let api_key = match api_key {
+            Some(api_key) => api_key.to_string(),
+            None => env::var("GEMINI_API_KEY")
+                .context("No Gemini API key: api_key is not configured and GEMINI_API_KEY is not set")?,
+        };
         
         // Initialize with the system prompt
         let system_message = Message {
@@ -129,6 +133,10 @@ impl GeminiAI {
         })
     }
     
+    pub fn set_model(&mut self, model: &str) {
+        self.model = model.to_string();
+    }
+    
     pub fn add_user_message(&mut self, content: &str) {
         self.messages.push(Message {
             role: Role::User,
@@ -215,7 +223,7 @@ impl GeminiAI {
         
         // Send the request
         let response_text = self.client
-            .post("https://generativelanguage.googleapis.com/v1/models/gemini-1.5-pro:generateContent")
+            .post(format!("https://generativelanguage.googleapis.com/v1/models/{}:generateContent", self.model))
             .header("x-goog-api-key", &self.api_key)
             .header("Content-Type", "application/json")
             .body(request_body.to_string())
@@ -272,6 +280,25 @@ impl GeminiAI {
         }
     }
     
+    /// Check the API key with a model lookup, which costs no tokens
+    pub async fn check_api_key(&self) -> Result<()> {
+        let response = self.client
+            .get(format!("https://generativelanguage.googleapis.com/v1/models/{}", self.model))
+            .header("x-goog-api-key", &self.api_key)
+            .send()
+            .await
+            .context("Failed to reach the Gemini API")?;
+        
+        if response.status().is_success() {
+            return Ok(());
+        }
+        
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or_default();
+        Err(anyhow!("Gemini API error ({}): {}", status,
+            body["error"]["message"].as_str().unwrap_or("no details")))
+    }
+    
     pub fn clear_conversation(&mut self) {
         // Keep only the system prompt
         self.messages.retain(|msg| msg.role == Role::System);
@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+use clap::{Parser, Subcommand};
+
+/// AI-powered penetration testing assistant
+#[derive(Debug, Parser)]
+#[command(name = "hacksor", version, about)]
+pub struct Cli {
+    /// Domain or IP the engagement is about
+    #[arg(long)]
+    pub target: Option<String>,
+
+    /// File listing the in-scope hosts and ranges, one per line
+    #[arg(long)]
+    pub scope: Option<PathBuf>,
+
+    /// Configuration file to use instead of config.toml in the configuration directory
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// Run the playbook, wait for its follow-up actions and exit without prompting
+    #[arg(long, requires = "playbook", conflicts_with_all = ["confirm_all", "review_actions"])]
+    pub non_interactive: bool,
+
+    /// YAML playbook of commands to run at startup
+    #[arg(long)]
+    pub playbook: Option<PathBuf>,
+
+    /// Session name, used as the session's directory name; defaults to the start time
+    #[arg(long)]
+    pub session: Option<String>,
+
+    /// Ask for confirmation before every command
+    #[arg(long)]
+    pub confirm_all: bool,
+
+    /// Approve, skip or edit each follow-up action before it runs
+    #[arg(long)]
+    pub review_actions: bool,
+
+    /// Full-screen interface with separate chat, output, findings and command panes
+    #[arg(long, conflicts_with = "non_interactive")]
+    pub tui: bool,
+
+    /// Keep command output out of the chat; only summaries, findings and actions are shown
+    #[arg(long, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Echo every line every command prints, not just the first few of each
+    #[arg(long)]
+    pub verbose: bool,
+
+    /// Start without the banner and startup tips, e.g. for demos or piped output
+    #[arg(long)]
+    pub no_banner: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+impl Cli {
+    pub fn verbosity(&self) -> Verbosity {
+        match (self.quiet, self.verbose) {
+            (true, _) => Verbosity::Quiet,
+            (_, true) => Verbosity::Verbose,
+            _ => Verbosity::Normal,
+        }
+    }
+}
+
+/// How much command output is echoed into the chat
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Commands {
+    /// Work with the configuration file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Validate the configuration, directories, AI key and tool paths before an engagement
+    Check,
+}
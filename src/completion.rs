@@ -0,0 +1,158 @@
+use std::collections::BTreeSet;
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+
+use crate::help::COMMANDS;
+use crate::terminal::{CommandMonitor, CommandScheduler};
+use crate::terminal::auto_documentation::FindingStore;
+use crate::utils::extract_target_from_command;
+
+const FINDING_ACTIONS: &[&str] = &["show", "verify", "close", "reopen", "assign"];
+const SEVERITIES: &[&str] = &["critical", "high", "medium", "low", "info"];
+const EXPORT_FORMATS: &[&str] = &["json", "sarif", "defectdojo", "csv"];
+const REPORT_FORMATS: &[&str] = &["md", "html", "pdf", "raw"];
+const TRIGGER_ACTIONS: &[&str] = &["add", "list", "remove"];
+
+/// Longest description shown next to an ID in the completion list
+const DESCRIPTION_WIDTH: usize = 50;
+
+/// Tab completion for the prompt, looked up in the live session each time so
+/// new commands, findings and hosts are offered as soon as they appear
+pub struct SessionCompleter {
+    monitor: CommandMonitor,
+    findings: FindingStore,
+    scheduler: CommandScheduler,
+    /// Targets known before anything ran: `--target` and the scope
+    targets: Vec<String>,
+}
+
+impl SessionCompleter {
+    pub fn new(monitor: CommandMonitor, findings: FindingStore, scheduler: CommandScheduler, targets: Vec<String>) -> Self {
+        Self { monitor, findings, scheduler, targets }
+    }
+
+    /// Candidates for the word after `words`, the words already typed
+    fn candidates(&self, words: &[&str]) -> Vec<Pair> {
+        let Some(command) = words.first().map(|word| word.to_lowercase()) else {
+            return command_names();
+        };
+
+        match (command.as_str(), words.len()) {
+            ("!help", 1) => command_names(),
+            ("!abort", 1) => self.running_commands(),
+            ("!cancel-action", 1) => self.unfinished_actions(),
+            ("!unschedule", 1) => self.scheduled_commands(),
+            ("!fp", 1) | ("!note", 1) | ("!mirror", 1) => self.finding_ids(),
+            ("!finding", 1) => plain(FINDING_ACTIONS),
+            ("!finding", 2) => self.finding_ids(),
+            ("!findings", _) if words.last() == Some(&"--severity") => plain(SEVERITIES),
+            ("!findings", _) if words.last() != Some(&"--target") => plain(&["--severity", "--target"]),
+            ("!push", 1) => plain(&["jira"]),
+            ("!push", _) => {
+                let mut candidates = plain(&["--all"]);
+                candidates.extend(self.finding_ids());
+                candidates
+            },
+            ("!clear", 1) => plain(&["--keep-findings"]),
+            ("!session", 1) => plain(&["new", "switch"]),
+            ("!export", 1) => plain(EXPORT_FORMATS),
+            ("!import", 1) => plain(&["nessus", "gvm"]),
+            ("!gvm", 1) => plain(&["scan", "import"]),
+            ("!report", 1) => plain(REPORT_FORMATS),
+            ("!trigger", 1) => plain(TRIGGER_ACTIONS),
+            ("!diff", _) if words.last() != Some(&"--target") => plain(&["--target"]),
+            _ => self.seen_targets(),
+        }
+    }
+
+    fn running_commands(&self) -> Vec<Pair> {
+        self.monitor.get_active_commands().into_iter()
+            .map(|cmd| described(cmd.id, &cmd.command))
+            .collect()
+    }
+
+    fn unfinished_actions(&self) -> Vec<Pair> {
+        self.monitor.get_action_queue().unfinished().into_iter()
+            .map(|action| described(action.id, &action.description))
+            .collect()
+    }
+
+    fn scheduled_commands(&self) -> Vec<Pair> {
+        self.scheduler.get_scheduled().into_iter()
+            .map(|entry| described(entry.id, &entry.command))
+            .collect()
+    }
+
+    fn finding_ids(&self) -> Vec<Pair> {
+        let findings = self.findings.lock().unwrap();
+        let mut findings: Vec<_> = findings.values().collect();
+        findings.sort_by_key(|finding| finding.discovery_date);
+        findings.into_iter()
+            .map(|finding| described(finding.id.clone(), &finding.title))
+            .collect()
+    }
+
+    /// Hosts from `--target`, the scope, the asset inventory and earlier commands
+    fn seen_targets(&self) -> Vec<Pair> {
+        let mut targets: BTreeSet<String> = self.targets.iter().cloned().collect();
+        targets.extend(self.monitor.get_asset_inventory().hosts());
+        targets.extend(self.monitor.get_all_commands().iter()
+            .filter_map(|cmd| extract_target_from_command(&cmd.command)));
+
+        targets.into_iter()
+            .map(|target| Pair { display: target.clone(), replacement: target })
+            .collect()
+    }
+}
+
+impl Completer for SessionCompleter {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let before = &line[..pos];
+        let start = before.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let prefix = before[start..].to_lowercase();
+        let words: Vec<&str> = before[..start].split_whitespace().collect();
+
+        // Only `!` commands are completed at the start of a line; anything else there is chat
+        if words.is_empty() && !prefix.starts_with('!') {
+            return Ok((start, Vec::new()));
+        }
+
+        let candidates = self.candidates(&words).into_iter()
+            .filter(|candidate| candidate.replacement.to_lowercase().starts_with(&prefix))
+            .collect();
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for SessionCompleter {
+    type Hint = String;
+}
+
+impl Highlighter for SessionCompleter {}
+
+impl Validator for SessionCompleter {}
+
+impl Helper for SessionCompleter {}
+
+fn command_names() -> Vec<Pair> {
+    COMMANDS.iter()
+        .map(|command| Pair { display: command.name.to_string(), replacement: command.name.to_string() })
+        .collect()
+}
+
+fn plain(words: &[&str]) -> Vec<Pair> {
+    words.iter()
+        .map(|word| Pair { display: word.to_string(), replacement: word.to_string() })
+        .collect()
+}
+
+/// An ID listed with what it refers to
+fn described(id: String, description: &str) -> Pair {
+    let description: String = description.chars().take(DESCRIPTION_WIDTH).collect();
+    Pair { display: format!("{}  {}", id, description), replacement: id }
+}
@@ -0,0 +1,156 @@
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+use anyhow::{Result, Context};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+
+use super::Config;
+use super::paths::HacksorDirs;
+use crate::ai::GeminiAI;
+use crate::terminal::CommandPolicy;
+use crate::terminal::action_plugins::CommandHook;
+use crate::terminal::command_monitor::verify_tool;
+use crate::terminal::dns::Resolver;
+use crate::terminal::finding_ids::FindingIdScheme;
+use crate::terminal::jira::JiraClient;
+use crate::terminal::report_templates::ReportTemplates;
+use crate::terminal::safety_policy::SafetyPolicy;
+use crate::terminal::severity_matrix::SeverityMatrix;
+use crate::terminal::testing_window::TestingWindow;
+use crate::terminal::throttle::Throttle;
+
+/// Prints one line per check and counts the failures
+#[derive(Default)]
+struct Report {
+    problems: usize,
+}
+
+impl Report {
+    fn check<T>(&mut self, label: &str, result: Result<T>) -> Result<()> {
+        match result {
+            Ok(_) => execute!(
+                io::stdout(),
+                SetForegroundColor(Color::Green),
+                Print(format!("[OK] {}\n", label)),
+                ResetColor
+            )?,
+            Err(e) => {
+                self.problems += 1;
+                execute!(
+                    io::stdout(),
+                    SetForegroundColor(Color::Red),
+                    Print(format!("[FAIL] {}: {:#}\n", label, e)),
+                    ResetColor
+                )?;
+            },
+        }
+        Ok(())
+    }
+
+    fn note(&self, label: &str, note: impl Display) -> Result<()> {
+        execute!(
+            io::stdout(),
+            SetForegroundColor(Color::Yellow),
+            Print(format!("[SKIP] {}: {}\n", label, note)),
+            ResetColor
+        )?;
+        Ok(())
+    }
+}
+
+/// Check everything an engagement depends on up front: that the configuration
+/// and the files it points at parse, the directories are writable, the AI key
+/// works and the configured tools are in place. Returns the number of problems.
+pub async fn check(config_path: &Path, dirs: &HacksorDirs) -> Result<usize> {
+    let mut report = Report::default();
+
+    let config = match Config::load(&config_path.to_path_buf())
+        .context(format!("Failed to parse {}", config_path.display()))
+    {
+        Ok(config) => config,
+        Err(e) => {
+            report.check("Configuration", Err::<(), _>(e))?;
+            return Ok(report.problems);
+        },
+    };
+    report.check(&format!("Configuration {}", config_path.display()), Ok(()))?;
+
+    report.check("Command policy", CommandPolicy::from_config(&config.command_policy))?;
+    report.check("Severity overrides", SeverityMatrix::from_config(&config.severity_overrides))?;
+    report.check("Finding ID format", FindingIdScheme::validate(&config.finding_ids))?;
+    report.check("Report templates", ReportTemplates::load(&dirs.config, &config.report))?;
+    if let Some(window) = &config.testing_window {
+        report.check("Testing window", TestingWindow::from_config(window))?;
+    }
+    for hook in &config.action_hooks {
+        report.check(&format!("Action hook {}", hook.name), CommandHook::from_config(hook))?;
+    }
+
+    if let Some(file) = &config.banner.file {
+        let banner = dirs.config.join(file);
+        report.check("Banner file", fs::read_to_string(&banner).map(|_| ()).context(format!("Failed to read {}", banner.display())))?;
+    }
+
+    let safety_policy = dirs.config.join(&config.safety_policy);
+    if safety_policy.exists() {
+        report.check("Safety policy", SafetyPolicy::load_or_create(&safety_policy))?;
+    } else {
+        report.note("Safety policy", format!("{} will be created with the defaults", safety_policy.display()))?;
+    }
+
+    if let Some(jira) = &config.jira {
+        report.check("Jira credentials", JiraClient::new(jira.clone()))?;
+    }
+
+    if let Some(dns) = &config.dns {
+        let resolvers = dns.resolvers.iter().map(|entry| Resolver::parse(entry)).collect::<Result<Vec<_>>>();
+        report.check("DNS resolvers", resolvers)?;
+    }
+
+    report.check("Working directory", check_writable(&dirs.data.join(&config.working_dir)))?;
+
+    let api_key = Some(config.api_key.as_str()).filter(|key| !key.is_empty());
+    match GeminiAI::new(api_key) {
+        Ok(ai) => report.check("Gemini API key", ai.check_api_key().await)?,
+        Err(e) => report.check("Gemini API key", Err::<(), _>(e))?,
+    }
+
+    // Tools run inside a container aren't installed on the host
+    if config.container.enabled {
+        report.note("Tools", "run inside the container image")?;
+    } else {
+        for tool in &config.tools {
+            report.check(&format!("Tool {} ({})", tool.name, tool.path.display()), verify_tool(tool).await)?;
+        }
+
+        if let Some(proxy) = config.proxy.as_ref().filter(|proxy| proxy.proxychains) {
+            let found = Command::new("which").arg("proxychains4").output()
+                .is_ok_and(|output| output.status.success());
+            let result = match found {
+                true => Ok(()),
+                false => Err(anyhow::anyhow!("proxychains4 is not installed, needed to route tools without a proxy option through {}", proxy.url)),
+            };
+            report.check("proxychains", result)?;
+        }
+
+        if Throttle::new(config.rate_limit.clone()).bandwidth_unavailable() {
+            report.check("trickle", Err::<(), _>(anyhow::anyhow!("trickle is not installed, needed to apply rate_limit bandwidth_kbps")))?;
+        }
+    }
+
+    Ok(report.problems)
+}
+
+/// Create the directory if needed and write a file to it
+fn check_writable(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir).context(format!("Failed to create {}", dir.display()))?;
+    let probe = dir.join(".hacksor-write-check");
+    fs::write(&probe, b"").context(format!("{} is not writable", dir.display()))?;
+    fs::remove_file(&probe)?;
+    Ok(())
+}
@@ -1,59 +1,1067 @@
 use serde::{Deserialize, Serialize};
+use crate::terminal::command_monitor::FindingSeverity;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
-use anyhow::Result;
+use anyhow::{Result, Context, anyhow};
+
+pub mod check;
+pub mod paths;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
     pub api_key: String,
+    /// Gemini model the assistant talks to
+    #[serde(default = "default_model")]
+    pub model: String,
     pub working_dir: PathBuf,
     pub tools: Vec<ToolConfig>,
     pub rate_limit: RateLimitConfig,
+    #[serde(default)]
+    pub container: ContainerConfig,
+    /// Ask for confirmation before every command is executed
+    #[serde(default)]
+    pub confirm_all: bool,
+    #[serde(default)]
+    pub command_policy: CommandPolicyConfig,
+    #[serde(default)]
+    pub default_credentials: DefaultCredentialsConfig,
+    #[serde(default)]
+    pub alerts: AlertConfig,
+    /// Chat webhooks told about command completions, findings and the end of the session
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+    #[serde(default)]
+    pub events: EventStreamConfig,
+    #[serde(default)]
+    pub screenshots: ScreenshotConfig,
+    /// Jira project findings are pushed to with `!push jira`
+    #[serde(default)]
+    pub jira: Option<JiraConfig>,
+    #[serde(default)]
+    pub passive_recon: PassiveReconConfig,
+    #[serde(default)]
+    pub shodan: ShodanConfig,
+    #[serde(default)]
+    pub censys: CensysConfig,
+    #[serde(default)]
+    pub crtsh: CrtshConfig,
+    #[serde(default)]
+    pub wayback: WaybackConfig,
+    #[serde(default)]
+    pub github: GitHubConfig,
+    #[serde(default)]
+    pub virustotal: VirusTotalConfig,
+    #[serde(default)]
+    pub securitytrails: SecurityTrailsConfig,
+    #[serde(default)]
+    pub nvd: NvdConfig,
+    #[serde(default)]
+    pub gvm: GvmConfig,
+    #[serde(default)]
+    pub interactsh: InteractshConfig,
+    /// Client-specific severities applied when findings are documented
+    #[serde(default)]
+    pub severity_overrides: Vec<SeverityOverride>,
+    #[serde(default)]
+    pub report: ReportConfig,
+    #[serde(default)]
+    pub evidence_git: EvidenceGitConfig,
+    #[serde(default)]
+    pub finding_ids: FindingIdConfig,
+    #[serde(default)]
+    pub actions: ActionConfig,
+    /// Hours testing is allowed in; follow-up actions wait for the window to open
+    #[serde(default)]
+    pub testing_window: Option<TestingWindowConfig>,
+    /// Extra follow-up commands for matching findings
+    #[serde(default)]
+    pub action_hooks: Vec<ActionHookConfig>,
+    /// Proxy all testing traffic is routed through, e.g. Burp or a SOCKS tunnel
+    #[serde(default)]
+    pub proxy: Option<ProxyConfig>,
+    /// DNS servers for the engagement, e.g. the client's internal resolvers
+    #[serde(default)]
+    pub dns: Option<DnsConfig>,
+    /// Per-target scan limits, relative to the configuration directory; written with defaults if missing
+    #[serde(default = "default_safety_policy")]
+    pub safety_policy: PathBuf,
+    #[serde(default)]
+    pub banner: BannerConfig,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+fn default_model() -> String {
+    "gemini-1.5-pro".to_string()
+}
+
+fn default_safety_policy() -> PathBuf {
+    PathBuf::from("safety_policy.toml")
+}
+
+/// A tool run from a specific path, with arguments added to every invocation
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolConfig {
     pub name: String,
     pub path: PathBuf,
+    #[serde(default)]
     pub args: Vec<String>,
+    /// Refuse to run the tool unless its version output names this version, e.g. "7.94"
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Arguments that make the tool print its version
+    #[serde(default = "default_version_args")]
+    pub version_args: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+fn default_version_args() -> Vec<String> {
+    vec!["--version".to_string()]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RateLimitConfig {
     pub requests_per_minute: u32,
     pub concurrent_connections: u32,
+    /// Inject throttling into executed commands
+    #[serde(default)]
+    pub enabled: bool,
+    /// Optional bandwidth cap applied through trickle for tools without a rate flag
+    #[serde(default)]
+    pub bandwidth_kbps: Option<u32>,
+    /// Per-target overrides, matched against the command's target
+    #[serde(default)]
+    pub targets: Vec<TargetRateLimit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetRateLimit {
+    /// Domain or IP, optionally with a leading `*.` wildcard
+    pub pattern: String,
+    pub requests_per_minute: u32,
+    #[serde(default)]
+    pub bandwidth_kbps: Option<u32>,
+}
+
+/// Allow/deny lists enforced before any command is executed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandPolicyConfig {
+    /// If non-empty, only these binaries may be run
+    pub allowed_binaries: Vec<String>,
+    pub denied_binaries: Vec<String>,
+    /// Regex patterns matched against the full command line
+    pub denied_patterns: Vec<String>,
+}
+
+/// Default credentials tried against detected admin interfaces
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DefaultCredentialsConfig {
+    /// Queue a follow-up action testing the list when an admin interface is found
+    pub enabled: bool,
+    /// `user:password` pairs
+    pub credentials: Vec<String>,
+}
+
+impl Default for DefaultCredentialsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            credentials: vec![
+                "admin:admin".to_string(),
+                "admin:password".to_string(),
+                "tomcat:tomcat".to_string(),
+                "tomcat:s3cret".to_string(),
+                "root:root".to_string(),
+            ],
+        }
+    }
+}
+
+/// Alerts raised when a severe finding is documented
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertConfig {
+    pub enabled: bool,
+    /// Lowest severity that triggers an alert
+    pub threshold: FindingSeverity,
+    /// Ring the terminal bell for every alert
+    pub bell: bool,
+    /// Sound per severity, played even below the threshold: `bell` rings the
+    /// terminal bell, anything else is run as a shell command
+    pub sounds: HashMap<FindingSeverity, String>,
+    /// Show a desktop notification while the terminal isn't the focused window
+    pub desktop: bool,
+    /// Also notify the desktop when a command that ran at least this many
+    /// seconds finishes; 0 turns it off
+    pub long_command_seconds: u64,
+    /// POST a JSON payload to this URL, as a generic webhook with the alert threshold
+    pub webhook_url: Option<String>,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold: FindingSeverity::High,
+            bell: true,
+            sounds: HashMap::new(),
+            desktop: true,
+            long_command_seconds: 120,
+            webhook_url: None,
+        }
+    }
+}
+
+/// Chat service a webhook posts to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookKind {
+    Slack,
+    Discord,
+    Telegram,
+    /// Any URL, sent a JSON payload with the message as `text` and, for
+    /// findings, their id, title, severity, CVSS score, description and command
+    Generic,
+}
+
+/// A Slack, Discord or generic incoming webhook, or a Telegram bot, posted to as the session runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub kind: WebhookKind,
+    /// Incoming webhook URL (Slack, Discord, generic)
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Bot token (Telegram); falls back to the `TELEGRAM_BOT_TOKEN` environment variable
+    #[serde(default)]
+    pub bot_token: Option<String>,
+    /// Chat the bot posts to (Telegram)
+    #[serde(default)]
+    pub chat_id: Option<String>,
+    /// Post finished commands
+    #[serde(default = "default_true")]
+    pub commands: bool,
+    /// Only post commands that ran at least this many seconds
+    #[serde(default = "default_webhook_command_seconds")]
+    pub min_command_seconds: u64,
+    /// Lowest severity of new findings posted
+    #[serde(default = "default_webhook_threshold")]
+    pub threshold: FindingSeverity,
+    /// Post a summary when the session ends
+    #[serde(default = "default_true")]
+    pub session_summary: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_webhook_command_seconds() -> u64 {
+    60
+}
+
+fn default_webhook_threshold() -> FindingSeverity {
+    FindingSeverity::High
+}
+
+/// Machine-readable stream of finding events for external dashboards and scripts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventStreamConfig {
+    /// Append every created/updated finding to `findings.jsonl` in the session directory
+    pub enabled: bool,
+    /// Also serve the events to clients connecting to this Unix socket
+    pub socket_path: Option<PathBuf>,
+}
+
+impl Default for EventStreamConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            socket_path: None,
+        }
+    }
+}
+
+/// Screenshots of discovered web paths and subdomains, embedded in finding documentation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScreenshotConfig {
+    pub enabled: bool,
+    /// Capture command; `{url}` and `{output}` are replaced with the page and the PNG path
+    pub command: String,
+    /// Most screenshots taken for a single finding
+    pub max_per_finding: usize,
+    pub timeout_secs: u64,
+}
+
+impl Default for ScreenshotConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            command: "chromium --headless --disable-gpu --hide-scrollbars --window-size=1280,800 --screenshot={output} {url}".to_string(),
+            max_per_finding: 5,
+            timeout_secs: 30,
+        }
+    }
+}
+
+/// What is printed at startup before the session begins
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BannerConfig {
+    /// Clear the screen and show the banner; `--no-banner` turns it off for one run
+    pub enabled: bool,
+    /// Text file shown instead of the built-in ASCII art, relative to the configuration directory
+    pub file: Option<PathBuf>,
+    /// Show the feature tips after the AI's greeting
+    pub tips: bool,
+}
+
+impl Default for BannerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            file: None,
+            tips: true,
+        }
+    }
+}
+
+/// A service that has already collected data about hosts, looked up without
+/// contacting the target
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PassiveSource {
+    Shodan,
+    Censys,
+    /// Certificate transparency logs, searched through crt.sh
+    Crtsh,
+    VirusTotal,
+    SecurityTrails,
+    /// URLs archived by the Wayback Machine, for parameter-based testing
+    Wayback,
+    /// Public code mentioning the target, searched for leaked secrets
+    GitHub,
+}
+
+impl PassiveSource {
+    pub const ALL: [PassiveSource; 7] = [
+        PassiveSource::Shodan,
+        PassiveSource::Censys,
+        PassiveSource::Crtsh,
+        PassiveSource::VirusTotal,
+        PassiveSource::SecurityTrails,
+        PassiveSource::Wayback,
+        PassiveSource::GitHub,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PassiveSource::Shodan => "Shodan",
+            PassiveSource::Censys => "Censys",
+            PassiveSource::Crtsh => "crt.sh",
+            PassiveSource::VirusTotal => "VirusTotal",
+            PassiveSource::SecurityTrails => "SecurityTrails",
+            PassiveSource::Wayback => "Wayback Machine",
+            PassiveSource::GitHub => "GitHub",
+        }
+    }
+
+    /// The `!` command looking a host up on demand, without the `!`
+    pub fn command(&self) -> &'static str {
+        match self {
+            PassiveSource::Shodan => "shodan",
+            PassiveSource::Censys => "censys",
+            PassiveSource::Crtsh => "crtsh",
+            PassiveSource::VirusTotal => "virustotal",
+            PassiveSource::SecurityTrails => "securitytrails",
+            PassiveSource::Wayback => "wayback",
+            PassiveSource::GitHub => "github",
+        }
+    }
+}
+
+/// Passive reconnaissance before anything is sent to the target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PassiveReconConfig {
+    /// Look up the session's target when Hacksor starts, before any command runs
+    pub on_start: bool,
+    /// Sources looked up at startup, in order; those needing credentials are
+    /// skipped without them
+    pub sources: Vec<PassiveSource>,
+}
+
+impl Default for PassiveReconConfig {
+    fn default() -> Self {
+        Self {
+            on_start: true,
+            sources: PassiveSource::ALL.to_vec(),
+        }
+    }
+}
+
+/// Shodan host lookups
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShodanConfig {
+    /// API key; falls back to the `SHODAN_API_KEY` environment variable
+    pub api_key: Option<String>,
+    pub timeout_secs: u64,
+}
+
+impl Default for ShodanConfig {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            timeout_secs: 20,
+        }
+    }
+}
+
+/// crt.sh certificate transparency searches for subdomains
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CrtshConfig {
+    /// Include expired certificates, which often name forgotten hosts
+    pub expired: bool,
+    /// crt.sh is slow for domains with many certificates
+    pub timeout_secs: u64,
+}
+
+impl Default for CrtshConfig {
+    fn default() -> Self {
+        Self {
+            expired: true,
+            timeout_secs: 60,
+        }
+    }
+}
+
+/// Wayback Machine URL collection for parameter-based testing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WaybackConfig {
+    /// Most archived URLs fetched per lookup, before deduplication
+    pub limit: usize,
+    /// The CDX API is slow for domains with large archives
+    pub timeout_secs: u64,
+}
+
+impl Default for WaybackConfig {
+    fn default() -> Self {
+        Self {
+            limit: 50000,
+            timeout_secs: 120,
+        }
+    }
+}
+
+/// GitHub code search for secrets leaked alongside the target's domain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GitHubConfig {
+    /// Personal access token; falls back to the `GITHUB_TOKEN` environment variable
+    pub token: Option<String>,
+    /// Code search queries, `{domain}` replaced with the target; each takes
+    /// six seconds of the search rate limit
+    pub dorks: Vec<String>,
+    /// Files fetched per query, at most 100
+    pub max_results: usize,
+    pub timeout_secs: u64,
+}
+
+impl Default for GitHubConfig {
+    fn default() -> Self {
+        Self {
+            token: None,
+            dorks: vec![
+                "\"{domain}\" password".to_string(),
+                "\"{domain}\" secret".to_string(),
+                "\"{domain}\" api_key".to_string(),
+                "\"{domain}\" token".to_string(),
+                "\"{domain}\" filename:.env".to_string(),
+            ],
+            max_results: 30,
+            timeout_secs: 20,
+        }
+    }
+}
+
+/// VirusTotal domain reports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VirusTotalConfig {
+    /// API key; falls back to the `VIRUSTOTAL_API_KEY` environment variable
+    pub api_key: Option<String>,
+    pub timeout_secs: u64,
+}
+
+impl Default for VirusTotalConfig {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            timeout_secs: 20,
+        }
+    }
+}
+
+/// SecurityTrails historical DNS and subdomain lookups
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SecurityTrailsConfig {
+    /// API key; falls back to the `SECURITYTRAILS_API_KEY` environment variable
+    pub api_key: Option<String>,
+    /// DNS record types whose history is fetched, one API call each: a, aaaa,
+    /// mx, ns, soa or txt
+    pub record_types: Vec<String>,
+    /// Include subdomains that no longer resolve
+    pub include_inactive: bool,
+    pub timeout_secs: u64,
+}
+
+impl Default for SecurityTrailsConfig {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            record_types: vec!["a".to_string(), "mx".to_string(), "ns".to_string()],
+            include_inactive: true,
+            timeout_secs: 20,
+        }
+    }
+}
+
+/// NVD lookups of the CVEs that appear in command output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NvdConfig {
+    pub enabled: bool,
+    /// API key; falls back to the `NVD_API_KEY` environment variable. Without
+    /// one NVD allows a request every six seconds
+    pub api_key: Option<String>,
+    /// Days a cached CVE record is used before it is fetched again
+    pub cache_days: u64,
+    pub timeout_secs: u64,
+}
+
+impl Default for NvdConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            api_key: None,
+            cache_days: 7,
+            timeout_secs: 20,
+        }
+    }
+}
+
+/// OpenVAS scans run through gvmd's management protocol (GMP)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GvmConfig {
+    /// gvmd's Unix socket
+    pub socket: PathBuf,
+    pub username: String,
+    /// Falls back to the `GVM_PASSWORD` environment variable
+    pub password: Option<String>,
+    /// Scan configuration new tasks use, "Full and fast" by default
+    pub scan_config: String,
+    /// Scanner new tasks run on, the default OpenVAS scanner by default
+    pub scanner: String,
+    /// Ports new targets are scanned on, "All IANA assigned TCP" by default
+    pub port_list: String,
+    /// Seconds between checks on a running task
+    pub poll_secs: u64,
+}
+
+impl Default for GvmConfig {
+    fn default() -> Self {
+        Self {
+            socket: PathBuf::from("/run/gvmd/gvmd.sock"),
+            username: "admin".to_string(),
+            password: None,
+            scan_config: "daba56c8-73ec-11df-a475-002264764cea".to_string(),
+            scanner: "08b69003-5fc2-4037-a479-93b440211c73".to_string(),
+            port_list: "33d0cd82-57c6-11e1-8ed1-406186ea4fc5".to_string(),
+            poll_secs: 30,
+        }
+    }
+}
+
+/// Out-of-band callbacks for blind checks, received through `interactsh-client`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InteractshConfig {
+    pub enabled: bool,
+    pub command: String,
+    /// Self-hosted server; the client's public default otherwise
+    pub server: Option<String>,
+    /// Token for a protected server; falls back to `INTERACTSH_TOKEN`
+    pub token: Option<String>,
+}
+
+impl Default for InteractshConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            command: "interactsh-client".to_string(),
+            server: None,
+            token: None,
+        }
+    }
+}
+
+/// Censys Search host and certificate lookups
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CensysConfig {
+    /// API ID; falls back to the `CENSYS_API_ID` environment variable
+    pub api_id: Option<String>,
+    /// API secret; falls back to the `CENSYS_API_SECRET` environment variable
+    pub api_secret: Option<String>,
+    /// Search certificates naming the target for its subdomains
+    pub certificates: bool,
+    pub timeout_secs: u64,
+}
+
+impl Default for CensysConfig {
+    fn default() -> Self {
+        Self {
+            api_id: None,
+            api_secret: None,
+            certificates: true,
+            timeout_secs: 20,
+        }
+    }
+}
+
+/// Jira Cloud/Server project that findings are exported to as issues
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JiraConfig {
+    /// Base URL, e.g. `https://acme.atlassian.net`
+    pub url: String,
+    pub email: String,
+    /// API token; falls back to the `JIRA_API_TOKEN` environment variable
+    #[serde(default)]
+    pub api_token: Option<String>,
+    pub project: String,
+    #[serde(default = "default_jira_issue_type")]
+    pub issue_type: String,
+    #[serde(default = "default_jira_labels")]
+    pub labels: Vec<String>,
+    /// Jira priority name per finding severity; severities left out get no priority
+    #[serde(default = "default_jira_priorities")]
+    pub priorities: BTreeMap<String, String>,
+    /// Extra fields set on every issue, e.g. `customfield_10010 = "{cvss}"`.
+    /// `{id}`, `{title}`, `{severity}`, `{cvss}`, `{cwe}`, `{owasp}` and `{target}` are filled in.
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+}
+
+fn default_jira_issue_type() -> String {
+    "Bug".to_string()
+}
+
+fn default_jira_labels() -> Vec<String> {
+    vec!["hacksor".to_string(), "security".to_string()]
+}
+
+fn default_jira_priorities() -> BTreeMap<String, String> {
+    [("Critical", "Highest"), ("High", "High"), ("Medium", "Medium"), ("Low", "Low"), ("Info", "Lowest")]
+        .iter()
+        .map(|(severity, priority)| (severity.to_string(), priority.to_string()))
+        .collect()
+}
+
+/// Replaces the analyzer's severity for matching findings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeverityOverride {
+    /// Regex matched against finding titles
+    pub title: String,
+    /// Domain or IP, optionally with a leading `*.` wildcard; `None` applies to every target
+    #[serde(default)]
+    pub target: Option<String>,
+    pub severity: FindingSeverity,
+}
+
+/// Branding injected into every report's header and footer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReportConfig {
+    pub client: Option<String>,
+    /// Image embedded at the top of reports
+    pub logo: Option<PathBuf>,
+    pub tester: Option<String>,
+    pub contact: Option<String>,
+    /// Marking such as `CONFIDENTIAL`, repeated at the top and bottom of reports
+    pub classification: Option<String>,
+    /// Converts the HTML report to PDF; `{input}` and `{output}` are replaced with the two paths
+    pub pdf_command: String,
+}
+
+impl Default for ReportConfig {
+    fn default() -> Self {
+        Self {
+            client: None,
+            logo: None,
+            tester: None,
+            contact: None,
+            classification: None,
+            pdf_command: "wkhtmltopdf --quiet {input} {output}".to_string(),
+        }
+    }
+}
+
+/// Commit the session directory to a local git repository after each documentation event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EvidenceGitConfig {
+    pub enabled: bool,
+    pub author_name: String,
+    pub author_email: String,
+}
+
+impl Default for EvidenceGitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            author_name: "Hacksor".to_string(),
+            author_email: "hacksor@localhost".to_string(),
+        }
+    }
+}
+
+/// How documented findings are numbered
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FindingIdConfig {
+    /// e.g. `ACME-WEB-{seq:03}`; see the README for placeholders
+    pub format: String,
+}
+
+impl Default for FindingIdConfig {
+    fn default() -> Self {
+        Self {
+            format: "FINDING-{uuid}".to_string(),
+        }
+    }
+}
+
+/// Follow-up actions run automatically for new findings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActionConfig {
+    /// Longest an action's command may run before it is killed and marked failed
+    pub timeout_secs: u64,
+    /// Further attempts for an action that fails with a transient network error
+    pub max_retries: u32,
+    /// Wait before the first retry; doubled for each later one
+    pub retry_backoff_secs: u64,
+    /// Most actions started against the same target in a minute; 0 for no limit
+    pub max_per_target_per_minute: u32,
+    /// Ask the user to approve, skip or edit each action's command before it runs
+    pub review: bool,
+}
+
+impl Default for ActionConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 300,
+            max_retries: 2,
+            retry_backoff_secs: 5,
+            max_per_target_per_minute: 10,
+            review: false,
+        }
+    }
+}
+
+/// Follow-up command queued for every new finding whose title matches, e.g. a
+/// notification or custom script
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionHookConfig {
+    pub name: String,
+    /// Regex matched against the finding title
+    pub title: String,
+    /// Shell command; {id}, {title}, {severity}, {target} and {file} are replaced
+    pub command: String,
+    /// Always ask before running the command
+    #[serde(default)]
+    pub requires_confirmation: bool,
+}
+
+/// Testing hours from the rules of engagement, in local time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestingWindowConfig {
+    /// When the window opens, e.g. "22:00"
+    pub start: String,
+    /// When it closes; earlier than `start` for a window spanning midnight
+    pub end: String,
+    /// Days the window opens on ("Mon", "Tue", ...); empty for every day
+    #[serde(default)]
+    pub days: Vec<String>,
+}
+
+/// Outbound proxy, passed to tools with a proxy flag and applied to the rest through proxychains
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProxyConfig {
+    /// e.g. "http://127.0.0.1:8080" or "socks5://127.0.0.1:9050"
+    pub url: String,
+    /// Wrap tools without a proxy flag in proxychains; it must be set up for the same proxy
+    #[serde(default = "default_proxychains")]
+    pub proxychains: bool,
+    /// proxychains configuration file, passed with -f
+    #[serde(default)]
+    pub proxychains_config: Option<PathBuf>,
+    /// Tools that never go through the proxy, e.g. local utilities
+    #[serde(default = "default_proxy_bypass")]
+    pub bypass: Vec<String>,
+}
+
+/// Resolvers used for Hacksor's own lookups and passed to DNS tools
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsConfig {
+    /// e.g. "10.0.0.53", "10.0.0.53:5353" or "https://dns.example/dns-query";
+    /// tools are only given the plain ones
+    pub resolvers: Vec<String>,
+    #[serde(default = "default_dns_timeout")]
+    pub timeout_secs: u64,
+}
+
+fn default_dns_timeout() -> u64 {
+    5
+}
+
+fn default_proxychains() -> bool {
+    true
+}
+
+fn default_proxy_bypass() -> Vec<String> {
+    ["cat", "echo", "grep", "jq", "ls", "searchsploit", "sort"]
+        .iter()
+        .map(|tool| tool.to_string())
+        .collect()
+}
+
+/// Settings for running tools inside a container instead of on the host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContainerConfig {
+    pub enabled: bool,
+    pub runtime: String,
+    pub image: String,
+    pub mount_path: String,
+    pub network: String,
+    pub extra_args: Vec<String>,
+    /// Host directories mounted at the same path, for files outside the work dir
+    pub mounts: Vec<PathBuf>,
+}
+
+impl Default for ContainerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            runtime: "docker".to_string(),
+            image: "kalilinux/kali-rolling".to_string(),
+            mount_path: "/work".to_string(),
+            network: "host".to_string(),
+            extra_args: Vec::new(),
+            mounts: Vec::new(),
+        }
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             api_key: String::new(),
+            model: default_model(),
             working_dir: PathBuf::from("sessions"),
             tools: Vec::new(),
             rate_limit: RateLimitConfig {
                 requests_per_minute: 60,
                 concurrent_connections: 10,
+                enabled: false,
+                bandwidth_kbps: None,
+                targets: Vec::new(),
             },
+            container: ContainerConfig::default(),
+            confirm_all: false,
+            command_policy: CommandPolicyConfig::default(),
+            default_credentials: DefaultCredentialsConfig::default(),
+            alerts: AlertConfig::default(),
+            webhooks: Vec::new(),
+            events: EventStreamConfig::default(),
+            screenshots: ScreenshotConfig::default(),
+            jira: None,
+            passive_recon: PassiveReconConfig::default(),
+            shodan: ShodanConfig::default(),
+            censys: CensysConfig::default(),
+            crtsh: CrtshConfig::default(),
+            wayback: WaybackConfig::default(),
+            github: GitHubConfig::default(),
+            virustotal: VirusTotalConfig::default(),
+            securitytrails: SecurityTrailsConfig::default(),
+            nvd: NvdConfig::default(),
+            gvm: GvmConfig::default(),
+            interactsh: InteractshConfig::default(),
+            severity_overrides: Vec::new(),
+            report: ReportConfig::default(),
+            evidence_git: EvidenceGitConfig::default(),
+            finding_ids: FindingIdConfig::default(),
+            actions: ActionConfig::default(),
+            testing_window: None,
+            action_hooks: Vec::new(),
+            proxy: None,
+            dns: None,
+            safety_policy: default_safety_policy(),
+            banner: BannerConfig::default(),
         }
     }
 }
 
+/// Prefix of the environment variables that override settings
+const ENV_PREFIX: &str = "HACKSOR_";
+
 impl Config {
-    #[allow(dead_code)]
+    /// Load the file (or the defaults if there is none) with any `HACKSOR_*`
+    /// environment overrides applied on top
     pub fn load(path: &PathBuf) -> Result<Self> {
-        let config = if path.exists() {
+        // The file goes over the defaults so overrides can tell what type each setting is
+        let mut settings = toml::Value::try_from(Config::default())?;
+        if path.exists() {
             let content = std::fs::read_to_string(path)?;
-            toml::from_str(&content)?
-        } else {
-            Config::default()
-        };
+            merge_settings(&mut settings, toml::from_str(&content)?);
+        }
         
+        apply_env_overrides(&mut settings, std::env::vars())?;
+        
+        let config = settings.try_into()
+            .context("Invalid setting in the configuration or a HACKSOR_* environment variable")?;
         Ok(config)
     }
     
-    #[allow(dead_code)]
     pub fn save(&self, path: &PathBuf) -> Result<()> {
         let content = toml::to_string_pretty(self)?;
         std::fs::write(path, content)?;
         Ok(())
     }
-} 
\ No newline at end of file
+}
+
+/// Lay one set of settings over another, section by section
+fn merge_settings(base: &mut toml::Value, over: toml::Value) {
+    match (base, over) {
+        (toml::Value::Table(base), toml::Value::Table(over)) => {
+            for (key, value) in over {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_settings(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    },
+                }
+            }
+        },
+        (base, over) => *base = over,
+    }
+}
+
+/// Layer `HACKSOR_*` variables over the settings. Nested keys are separated by
+/// a double underscore, e.g. HACKSOR_RATE_LIMIT__REQUESTS_PER_MINUTE=30 sets
+/// `requests_per_minute` in `[rate_limit]`; an empty value removes a setting.
+/// A setting that is already a string stays one; a value for a setting that
+/// isn't there yet can be quoted (`"2024"`) to keep it a string.
+fn apply_env_overrides(settings: &mut toml::Value, vars: impl Iterator<Item = (String, String)>) -> Result<()> {
+    for (name, raw) in vars {
+        // HACKSOR_HOME picks the directories rather than a setting
+        let Some(key) = name.strip_prefix(ENV_PREFIX).filter(|_| name != paths::HOME_VAR) else {
+            continue;
+        };
+        let path: Vec<String> = key.split("__").map(|part| part.to_lowercase()).collect();
+        let (last, sections) = path.split_last().context(format!("Empty setting name in {}", name))?;
+        
+        let mut table = settings.as_table_mut().context("The configuration is not a table")?;
+        for section in sections {
+            table = table.entry(section.clone())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+                .as_table_mut()
+                .ok_or_else(|| anyhow!("{} overrides a setting inside `{}`, which is not a section", name, section))?;
+        }
+        
+        match table.get(last) {
+            Some(toml::Value::String(_)) => {
+                table.insert(last.clone(), toml::Value::String(raw));
+            },
+            _ if raw.is_empty() => {
+                table.remove(last);
+            },
+            _ => {
+                table.insert(last.clone(), parse_env_value(&raw));
+            },
+        }
+    }
+    
+    Ok(())
+}
+
+/// Numbers, booleans and arrays are written as in TOML; anything else is a string
+fn parse_env_value(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(file: &str, vars: &[(&str, &str)]) -> Result<Config> {
+        let mut settings = toml::Value::try_from(Config::default())?;
+        merge_settings(&mut settings, toml::from_str(file)?);
+        let vars = vars.iter().map(|(name, value)| (name.to_string(), value.to_string()));
+        apply_env_overrides(&mut settings, vars)?;
+        Ok(settings.try_into()?)
+    }
+
+    #[test]
+    fn overrides_nested_settings() {
+        let config = load("[rate_limit]\nenabled = true\n", &[
+            ("HACKSOR_RATE_LIMIT__REQUESTS_PER_MINUTE", "30"),
+            ("HACKSOR_CONFIRM_ALL", "true"),
+        ]).unwrap();
+        assert!(config.rate_limit.enabled);
+        assert_eq!(config.rate_limit.requests_per_minute, 30);
+        assert_eq!(config.rate_limit.concurrent_connections, 10);
+        assert!(config.confirm_all);
+    }
+
+    #[test]
+    fn keeps_numeric_strings_as_strings() {
+        let file = "[jira]\nurl = \"https://acme.atlassian.net\"\nemail = \"a@acme.test\"\nproject = \"OPS\"\n";
+        let config = load(file, &[
+            ("HACKSOR_JIRA__PROJECT", "2024"),
+            ("HACKSOR_MODEL", "2024"),
+        ]).unwrap();
+        assert_eq!(config.jira.unwrap().project, "2024");
+        assert_eq!(config.model, "2024");
+        
+        // Settings the defaults don't have are quoted instead
+        let config = load(file, &[("HACKSOR_JIRA__API_TOKEN", "\"12345\"")]).unwrap();
+        assert_eq!(config.jira.unwrap().api_token.as_deref(), Some("12345"));
+    }
+
+    #[test]
+    fn empty_values_remove_settings() {
+        let config = load("[rate_limit]\nbandwidth_kbps = 512\n", &[
+            ("HACKSOR_RATE_LIMIT__BANDWIDTH_KBPS", ""),
+        ]).unwrap();
+        assert_eq!(config.rate_limit.bandwidth_kbps, None);
+    }
+
+    #[test]
+    fn skips_the_home_variable() {
+        let mut settings = toml::Value::try_from(Config::default()).unwrap();
+        let before = settings.clone();
+        let vars = [(paths::HOME_VAR.to_string(), "/tmp/hacksor".to_string())];
+        apply_env_overrides(&mut settings, vars.into_iter()).unwrap();
+        assert_eq!(settings, before);
+    }
+}
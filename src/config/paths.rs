@@ -0,0 +1,128 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use anyhow::{Result, Context};
+
+/// Files moved to the configuration directory when migrating from ~/.hacksor;
+/// everything else is data
+const CONFIG_ENTRIES: &[&str] = &["rules", "templates"];
+
+/// Puts configuration and data in one directory instead of the XDG ones
+pub const HOME_VAR: &str = "HACKSOR_HOME";
+
+/// Where Hacksor keeps its files. Configuration (config.toml, the safety
+/// policy, analyzer rules, report templates) lives in `$XDG_CONFIG_HOME/hacksor`
+/// and data (sessions, the CVE dataset) in `$XDG_DATA_HOME/hacksor`, unless
+/// HACKSOR_HOME puts everything in one directory.
+#[derive(Debug, Clone)]
+pub struct HacksorDirs {
+    pub config: PathBuf,
+    pub data: PathBuf,
+}
+
+impl HacksorDirs {
+    pub fn locate() -> Self {
+        if let Some(dir) = env::var_os(HOME_VAR).filter(|dir| !dir.is_empty()) {
+            return Self {
+                config: PathBuf::from(&dir),
+                data: PathBuf::from(dir),
+            };
+        }
+
+        // Relative XDG paths are invalid and ignored, as the spec requires
+        let base = |var: &str, default: &str| env::var_os(var)
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .unwrap_or_else(|| home_dir().join(default))
+            .join("hacksor");
+
+        Self {
+            config: base("XDG_CONFIG_HOME", ".config"),
+            data: base("XDG_DATA_HOME", ".local/share"),
+        }
+    }
+
+    /// Move the files of an existing ~/.hacksor into these directories;
+    /// returns the old directory if it was migrated. The old directory is only
+    /// removed once everything is out of it, so an interrupted migration picks
+    /// up where it stopped on the next run.
+    pub fn migrate_legacy(&self) -> Result<Option<PathBuf>> {
+        let legacy = home_dir().join(".hacksor");
+        if !legacy.is_dir() || self.config == legacy || self.data == legacy {
+            return Ok(None);
+        }
+
+        fs::create_dir_all(&self.config)?;
+        fs::create_dir_all(&self.data)?;
+        for entry in fs::read_dir(&legacy)? {
+            let from = entry?.path();
+            let Some(name) = from.file_name() else {
+                continue;
+            };
+            let is_config = from.extension().is_some_and(|ext| ext == "toml")
+                || CONFIG_ENTRIES.iter().any(|entry| name == *entry);
+            let to = if is_config { self.config.join(name) } else { self.data.join(name) };
+
+            move_entry(&from, &to).context(format!(
+                "Failed to move {} to {}; move it yourself or set HACKSOR_HOME={} to keep using the old directory",
+                from.display(),
+                to.display(),
+                legacy.display()
+            ))?;
+        }
+        fs::remove_dir(&legacy)?;
+
+        Ok(Some(legacy))
+    }
+}
+
+/// Move a file or directory without replacing anything already at `to`,
+/// copying it when the two are on different filesystems
+fn move_entry(from: &Path, to: &Path) -> Result<()> {
+    if to.exists() {
+        anyhow::bail!("{} already exists", to.display());
+    }
+
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            // Copied under a temporary name first, so a copy cut short is never
+            // taken for the real thing
+            let mut partial = to.as_os_str().to_owned();
+            partial.push(".partial");
+            let partial = PathBuf::from(partial);
+            if partial.is_dir() {
+                fs::remove_dir_all(&partial)?;
+            } else if partial.exists() {
+                fs::remove_file(&partial)?;
+            }
+
+            copy_entry(from, &partial)?;
+            fs::rename(&partial, to)?;
+            if from.is_dir() {
+                fs::remove_dir_all(from)?;
+            } else {
+                fs::remove_file(from)?;
+            }
+            Ok(())
+        },
+        result => Ok(result?),
+    }
+}
+
+fn copy_entry(from: &Path, to: &Path) -> io::Result<()> {
+    if from.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_entry(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+fn home_dir() -> PathBuf {
+    env::var_os("HOME").map(PathBuf::from).unwrap_or_else(|| Path::new(".").to_path_buf())
+}
@@ -1,13 +1,20 @@
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use async_trait::async_trait;
-use anyhow::Result;
+use anyhow::{Result, Context, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
 
 pub mod security_commands;
 
 // Re-export security command related types
 pub use security_commands::SecurityCommandExecutor;
 
-#[derive(Debug, Clone)]
+/// Sessions file in the data directory
+pub const SESSIONS_FILE: &str = "pentest_sessions.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct Target {
     pub domain: String,
@@ -15,15 +22,16 @@ pub struct Target {
     pub excluded: Vec<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct PentestSession {
     pub target: Target,
     pub session_id: String,
     pub status: SessionStatus,
+    pub created: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub enum SessionStatus {
     Initialized,
@@ -43,35 +51,108 @@ pub trait PentestModule {
     fn get_name(&self) -> &str;
 }
 
+/// Sessions as saved between runs
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SavedSessions {
+    current: Option<String>,
+    sessions: Vec<PentestSession>,
+}
+
 #[allow(dead_code)]
 pub struct PentestEngine {
     modules: Vec<Box<dyn PentestModule>>,
+    sessions: Vec<Arc<PentestSession>>,
     current_session: Option<Arc<PentestSession>>,
     command_executor: SecurityCommandExecutor,
+    /// Where sessions are saved whenever they change, if anywhere
+    state_file: Option<PathBuf>,
 }
 
 impl PentestEngine {
     pub fn new() -> Self {
         Self {
             modules: Vec::new(),
+            sessions: Vec::new(),
             current_session: None,
             command_executor: SecurityCommandExecutor::new(),
+            state_file: None,
         }
     }
 
+    /// Engine with the sessions saved in `state_file`, which later changes are saved back to
+    pub fn load(state_file: &Path) -> Result<Self> {
+        let saved: SavedSessions = if state_file.exists() {
+            let content = fs::read_to_string(state_file)?;
+            serde_json::from_str(&content)
+                .context(format!("Failed to parse sessions file {:?}", state_file))?
+        } else {
+            SavedSessions::default()
+        };
+
+        let mut engine = Self::new();
+        engine.sessions = saved.sessions.into_iter().map(Arc::new).collect();
+        engine.current_session = saved.current
+            .and_then(|id| engine.sessions.iter().find(|session| session.session_id == id).cloned());
+        engine.state_file = Some(state_file.to_path_buf());
+        Ok(engine)
+    }
+
     pub fn add_module(&mut self, module: Box<dyn PentestModule>) {
         self.modules.push(module);
     }
 
-    pub async fn start_session(&mut self, target: Target) -> Result<()> {
-        let session = PentestSession {
+    pub async fn start_session(&mut self, target: Target) -> Result<Arc<PentestSession>> {
+        let session = Arc::new(PentestSession {
             target,
             session_id: uuid::Uuid::new_v4().to_string(),
             status: SessionStatus::Initialized,
-        };
+            created: Utc::now(),
+        });
         
-        self.current_session = Some(Arc::new(session));
-        Ok(())
+        self.sessions.push(session.clone());
+        self.current_session = Some(session.clone());
+        self.save()?;
+        Ok(session)
+    }
+
+    /// Make an earlier session the current one
+    pub fn switch_session(&mut self, session_id: &str) -> Result<Arc<PentestSession>> {
+        let session = self.sessions.iter()
+            .find(|session| session.session_id == session_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("No session with ID: {}", session_id))?;
+        
+        self.current_session = Some(session.clone());
+        self.save()?;
+        Ok(session)
+    }
+
+    /// The most recent session against `domain`, if there is one
+    pub fn find_session(&self, domain: &str) -> Option<Arc<PentestSession>> {
+        self.sessions.iter().rev()
+            .find(|session| session.target.domain.eq_ignore_ascii_case(domain))
+            .cloned()
+    }
+
+    /// Every session, oldest first
+    pub fn get_sessions(&self) -> &[Arc<PentestSession>] {
+        &self.sessions
+    }
+
+    pub fn get_current_session(&self) -> Option<Arc<PentestSession>> {
+        self.current_session.clone()
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(state_file) = &self.state_file else {
+            return Ok(());
+        };
+        let saved = SavedSessions {
+            current: self.current_session.as_ref().map(|session| session.session_id.clone()),
+            sessions: self.sessions.iter().map(|session| session.as_ref().clone()).collect(),
+        };
+        fs::write(state_file, serde_json::to_string_pretty(&saved)?)
+            .context(format!("Failed to save sessions to {:?}", state_file))
     }
 
     pub async fn run_modules(&mut self) -> Result<()> {
@@ -0,0 +1,256 @@
+/// A `!` command understood at the prompt
+pub struct BangCommand {
+    pub name: &'static str,
+    /// Arguments, in the usual `<required> [optional]` notation
+    pub args: &'static str,
+    pub description: &'static str,
+    pub examples: &'static [&'static str],
+}
+
+/// Every `!` command handled by the main loop, in the order `!help` lists them
+pub const COMMANDS: &[BangCommand] = &[
+    BangCommand {
+        name: "!help",
+        args: "[command]",
+        description: "List the commands, or show one in detail",
+        examples: &["!help", "!help schedule"],
+    },
+    BangCommand {
+        name: "!exec",
+        args: "[--force] <command>",
+        description: "Run a command directly, within the safety policy. A command that ran in the last hour shows its earlier result instead unless --force is given",
+        examples: &["!exec nmap -sV example.com", "!exec --force whatweb example.com"],
+    },
+    BangCommand {
+        name: "!history",
+        args: "[n]",
+        description: "List the last n commands (20 by default) with their IDs, durations, statuses and summaries",
+        examples: &["!history", "!history 50"],
+    },
+    BangCommand {
+        name: "!grep",
+        args: "<pattern>",
+        description: "Search every command output log and the findings' evidence in this session for a regular expression; (?i) ignores case",
+        examples: &["!grep admin", "!grep (?i)x-powered-by"],
+    },
+    BangCommand {
+        name: "!abort",
+        args: "[command-id]",
+        description: "Stop a running command; without an ID, list the running commands",
+        examples: &["!abort", "!abort 3f2b9c1e-8d4a-4c57-a0e2-5b7f6d1c9a84"],
+    },
+    BangCommand {
+        name: "!schedule",
+        args: "[\"<command>\" [--at HH:MM] [--every 30m|6h|1d]]",
+        description: "Run a command later or on a recurring interval; without arguments, list the scheduled commands",
+        examples: &["!schedule \"nmap -sV example.com\" --at 02:00", "!schedule \"nuclei -u https://example.com\" --every 6h"],
+    },
+    BangCommand {
+        name: "!unschedule",
+        args: "<id>",
+        description: "Remove a scheduled command",
+        examples: &["!unschedule 3f2b9c1e"],
+    },
+    BangCommand {
+        name: "!actions",
+        args: "[--all]",
+        description: "List the follow-up actions that are waiting or running; --all includes finished ones",
+        examples: &["!actions", "!actions --all"],
+    },
+    BangCommand {
+        name: "!cancel-action",
+        args: "<id>",
+        description: "Stop a follow-up action that is waiting or running",
+        examples: &["!cancel-action 9c0d7e21-4b6a-4f3e-8a15-2d9e6b7c3f10"],
+    },
+    BangCommand {
+        name: "!findings",
+        args: "[--severity <critical|high|medium|low|info>] [--target <host>]",
+        description: "List the documented findings with their IDs, severity and status, most severe first; * marks the ones not listed before",
+        examples: &["!findings", "!findings --severity high --target example.com"],
+    },
+    BangCommand {
+        name: "!finding",
+        args: "<show|verify|close|reopen|assign> <finding-id> [assignee]",
+        description: "Show a finding in full, or move it through its lifecycle",
+        examples: &["!finding show ACME-WEB-003", "!finding verify ACME-WEB-003", "!finding assign ACME-WEB-003 alice"],
+    },
+    BangCommand {
+        name: "!fp",
+        args: "<finding-id>",
+        description: "Mark a finding as a false positive so it is no longer raised or reported",
+        examples: &["!fp ACME-WEB-007"],
+    },
+    BangCommand {
+        name: "!note",
+        args: "<finding-id> <text>",
+        description: "Add a timestamped note to a finding",
+        examples: &["!note ACME-WEB-003 Confirmed manually with Burp"],
+    },
+    BangCommand {
+        name: "!mirror",
+        args: "<finding-id> [edb-id...]",
+        description: "List the Exploit-DB candidates found for a version finding, or copy the given ones into the session's exploits/ directory with searchsploit -m",
+        examples: &["!mirror ACME-WEB-004", "!mirror ACME-WEB-004 50383 50406"],
+    },
+    BangCommand {
+        name: "!push",
+        args: "jira <finding-id>... | jira --all",
+        description: "Create Jira issues from documented findings",
+        examples: &["!push jira ACME-WEB-001 ACME-WEB-003", "!push jira --all"],
+    },
+    BangCommand {
+        name: "!export",
+        args: "<json|sarif|defectdojo|csv> [file]",
+        description: "Write the session's findings for other tools",
+        examples: &["!export sarif", "!export csv findings.csv"],
+    },
+    BangCommand {
+        name: "!report",
+        args: "[md|html|pdf|raw] [file]",
+        description: "Write a report of the documented findings, by default Markdown to reports/ in the session directory. raw lists every analyzer finding per command, documented or not",
+        examples: &["!report", "!report html", "!report pdf acme-report.pdf"],
+    },
+    BangCommand {
+        name: "!diff",
+        args: "<baseline-session> [retest-session] [--target <host>]",
+        description: "Compare this session, or another, with a baseline session for a retest report",
+        examples: &["!diff acme-2024-q1", "!diff acme-2024-q1 acme-2024-q2 --target example.com"],
+    },
+    BangCommand {
+        name: "!asset",
+        args: "[host]",
+        description: "Show everything known about a host, or list the known hosts",
+        examples: &["!asset", "!asset api.example.com"],
+    },
+    BangCommand {
+        name: "!tech",
+        args: "[target | --using <technology>]",
+        description: "Show the fingerprinted technologies, for one target, or the targets running a technology",
+        examples: &["!tech", "!tech example.com", "!tech --using nginx"],
+    },
+    BangCommand {
+        name: "!shodan",
+        args: "[target]",
+        description: "Look up what Shodan has seen of a host, by default the session's target, without contacting it; its services and reported CVEs become findings",
+        examples: &["!shodan", "!shodan 203.0.113.10"],
+    },
+    BangCommand {
+        name: "!censys",
+        args: "[target]",
+        description: "Look up what Censys has seen of a host, by default the session's target, without contacting it; its services and the subdomains named in its certificates become findings",
+        examples: &["!censys", "!censys example.com"],
+    },
+    BangCommand {
+        name: "!crtsh",
+        args: "[domain]",
+        description: "Search certificate transparency logs on crt.sh for a domain's subdomains, by default the session's target; those no other source found become a finding",
+        examples: &["!crtsh", "!crtsh example.com"],
+    },
+    BangCommand {
+        name: "!virustotal",
+        args: "[domain]",
+        description: "Fetch VirusTotal's report on a domain, by default the session's target: its past resolutions and categories go on the domain's !asset profile, and URLs flagged by antivirus engines become a finding",
+        examples: &["!virustotal", "!virustotal example.com"],
+    },
+    BangCommand {
+        name: "!securitytrails",
+        args: "[domain]",
+        description: "Fetch a domain's DNS history and subdomains from SecurityTrails, by default the session's target: the history goes on the domain's !asset profile, and addresses it used to resolve to and new subdomains become findings",
+        examples: &["!securitytrails", "!securitytrails example.com"],
+    },
+    BangCommand {
+        name: "!wayback",
+        args: "[domain]",
+        description: "Collect the URLs the Wayback Machine archived for a domain and its subdomains, by default the session's target: in-scope URLs are deduplicated, and those taking parameters go into the asset inventory and params.txt for parameter-based testing",
+        examples: &["!wayback", "!wayback example.com"],
+    },
+    BangCommand {
+        name: "!github",
+        args: "[domain]",
+        description: "Search public GitHub code for a domain, by default the session's target, next to credential keywords: files holding recognisable secrets become findings with the repository and file, the secrets redacted",
+        examples: &["!github", "!github example.com"],
+    },
+    BangCommand {
+        name: "!resolve",
+        args: "[host]",
+        description: "Resolve a host, by default the session's target, through each resolver in [dns] and show their answers side by side, so split-horizon names stand out; the addresses go into the asset inventory",
+        examples: &["!resolve", "!resolve intranet.example.com"],
+    },
+    BangCommand {
+        name: "!import",
+        args: "<nessus|gvm> <file> [--info]",
+        description: "Import a Nessus export or a GVM XML report: each result becomes a finding (informational ones only with --info), open ports go into the asset inventory, and the AI is asked to plan manual verification",
+        examples: &["!import nessus ~/scans/acme_external.nessus", "!import gvm ~/scans/report-6c2b.xml"],
+    },
+    BangCommand {
+        name: "!gvm",
+        args: "scan [target] | import <report-id>",
+        description: "Start an OpenVAS scan of a host through gvmd, by default the session's target, importing its results when it finishes; or import the results of a report already in gvmd",
+        examples: &["!gvm scan", "!gvm scan 203.0.113.10", "!gvm import 6c2b1f4e-9a43-4a8e-b3a1-2f0e6d7c5b91"],
+    },
+    BangCommand {
+        name: "!trigger",
+        args: "add <severity> <text> => <title> | list | remove <name>",
+        description: "Manage engagement-specific triggers that turn text in command output into findings",
+        examples: &["!trigger add High ACME-DEBUG-TOKEN => ACME debug token exposed", "!trigger remove trigger-1"],
+    },
+    BangCommand {
+        name: "!clear",
+        args: "[--keep-findings]",
+        description: "Reset the AI conversation, keeping the target and scope and, with --keep-findings, the documented findings. Running commands carry on",
+        examples: &["!clear", "!clear --keep-findings"],
+    },
+    BangCommand {
+        name: "!sessions",
+        args: "",
+        description: "List the sessions, marking the current one with *",
+        examples: &["!sessions"],
+    },
+    BangCommand {
+        name: "!session",
+        args: "new <target> | switch <session-id|target>",
+        description: "Start a session against another target, or go back to an earlier one by its ID or target; the AI is told about the change",
+        examples: &["!session new api.example.com", "!session switch example.com", "!session switch 3f2b9c1e-8d4a-4c57-a0e2-5b7f6d1c9a84"],
+    },
+    BangCommand {
+        name: "!reload-config",
+        args: "",
+        description: "Re-read the configuration file and apply what can change mid-engagement",
+        examples: &["!reload-config"],
+    },
+];
+
+/// The command a line starts with, if it is one of ours
+pub fn find(input: &str) -> Option<&'static BangCommand> {
+    let name = input.split_whitespace().next()?.to_lowercase();
+    let name = if name.starts_with('!') { name } else { format!("!{}", name) };
+    COMMANDS.iter().find(|command| command.name == name)
+}
+
+/// `!help` output: every command in brief, or one in full
+pub fn help(topic: &str) -> String {
+    if topic.is_empty() {
+        let mut text = String::from("Commands:\n");
+        for command in COMMANDS {
+            text.push_str(&format!("  {}\n      {}\n", usage(command), command.description));
+        }
+        text.push_str("\nAnything else is sent to the AI. Type exit or quit to end the session; !help <command> shows examples.");
+        return text;
+    }
+
+    match find(topic) {
+        Some(command) => {
+            let mut text = format!("{}\n  {}\n\nExamples:\n", usage(command), command.description);
+            for example in command.examples {
+                text.push_str(&format!("  {}\n", example));
+            }
+            text.trim_end().to_string()
+        },
+        None => format!("Unknown command: {}. Type !help to list the commands.", topic),
+    }
+}
+
+pub fn usage(command: &BangCommand) -> String {
+    format!("{} {}", command.name, command.args).trim_end().to_string()
+}
@@ -0,0 +1,207 @@
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc as sync_mpsc;
+use std::thread;
+use std::time::Duration;
+use anyhow::{Result, anyhow};
+use rustyline::{CompletionType, Config, Editor, ExternalPrinter};
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+use tokio::sync::mpsc;
+
+use crate::completion::SessionCompleter;
+
+/// Entries kept in the history file
+const HISTORY_SIZE: usize = 1000;
+
+/// Seconds the session has to stay ready before a script's next line is read
+const SCRIPT_SETTLE_SECONDS: u32 = 3;
+
+/// What came back from the prompt
+#[derive(Debug)]
+pub enum Input {
+    Line(String),
+    /// Ctrl-C discarded the line being typed
+    Cancelled,
+    /// Ctrl-D or the terminal went away
+    Closed,
+}
+
+/// Line editing with history for the interactive prompt.
+///
+/// The editor runs on its own thread and only reads when asked, so output from
+/// background tasks keeps printing above the line being typed.
+pub struct LineEditor {
+    requests: sync_mpsc::Sender<String>,
+    lines: mpsc::Receiver<Input>,
+    printer: Printer,
+    /// Reading a script from stdin: whether the session is ready for its next line
+    ready: Option<Box<dyn Fn() -> bool + Send>>,
+}
+
+impl LineEditor {
+    /// Start the editor, loading the history persisted from earlier sessions
+    pub fn start(history: PathBuf, completer: SessionCompleter) -> Result<Self> {
+        let (requests, prompts) = sync_mpsc::channel::<String>();
+        let (lines_tx, lines) = mpsc::channel(1);
+        let (ready_tx, ready) = sync_mpsc::channel();
+
+        thread::spawn(move || {
+            let mut editor = match new_editor(&history, completer) {
+                Ok(editor) => editor,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                },
+            };
+            let printer = editor.create_external_printer().ok()
+                .map(|printer| Box::new(printer) as Box<dyn ExternalPrinter + Send>);
+            let _ = ready_tx.send(Ok(printer));
+
+            while let Ok(prompt) = prompts.recv() {
+                let input = match editor.readline(&prompt) {
+                    Ok(line) => {
+                        if !line.trim().is_empty() && editor.add_history_entry(line.as_str()).unwrap_or(false) {
+                            if let Err(e) = editor.save_history(&history) {
+                                eprintln!("Failed to save input history {:?}: {}", history, e);
+                            }
+                        }
+                        Input::Line(line)
+                    },
+                    Err(ReadlineError::Interrupted) => Input::Cancelled,
+                    Err(_) => Input::Closed,
+                };
+                if lines_tx.blocking_send(input).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let printer = ready.recv().map_err(|_| anyhow!("The line editor stopped unexpectedly"))??;
+        Ok(Self {
+            requests,
+            lines,
+            printer: Printer::new(printer),
+            ready: None,
+        })
+    }
+
+    /// Read instructions piped on stdin instead, one line each time the
+    /// session has stayed `ready` for a few seconds. Blank lines and `#`
+    /// comments are skipped
+    pub fn script(ready: impl Fn() -> bool + Send + 'static) -> Self {
+        let (requests, prompts) = sync_mpsc::channel::<String>();
+        let (lines_tx, lines) = mpsc::channel(1);
+
+        thread::spawn(move || {
+            let mut stdin = io::stdin().lock();
+            while let Ok(prompt) = prompts.recv() {
+                let input = loop {
+                    let mut line = String::new();
+                    match stdin.read_line(&mut line) {
+                        Ok(0) | Err(_) => break Input::Closed,
+                        Ok(_) if line.trim().is_empty() || line.trim_start().starts_with('#') => continue,
+                        Ok(_) => {
+                            // Echo the line so the transcript shows what each output belongs to
+                            println!("{}{}", prompt, line.trim_end());
+                            break Input::Line(line);
+                        },
+                    }
+                };
+                if lines_tx.blocking_send(input).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            requests,
+            lines,
+            printer: Printer::new(None),
+            ready: Some(Box::new(ready)),
+        }
+    }
+
+    /// Show the prompt and wait for a line. Safe to cancel: a read cut short is
+    /// picked up again by the next call
+    pub async fn read_line(&mut self, prompt: &str) -> Input {
+        if !self.printer.reading.load(Ordering::SeqCst) {
+            if let Some(ready) = &self.ready {
+                settle(ready.as_ref()).await;
+            }
+            if self.requests.send(prompt.to_string()).is_err() {
+                return Input::Closed;
+            }
+            self.printer.reading.store(true, Ordering::SeqCst);
+        }
+
+        let input = self.lines.recv().await.unwrap_or(Input::Closed);
+        self.printer.reading.store(false, Ordering::SeqCst);
+        input
+    }
+
+    /// A handle that prints above the prompt, for use while something else
+    /// holds the editor waiting for a line
+    pub fn printer(&self) -> Printer {
+        self.printer.clone()
+    }
+}
+
+/// Prints lines without disturbing the prompt, if one is showing
+#[derive(Clone)]
+pub struct Printer {
+    external: Arc<Mutex<Option<Box<dyn ExternalPrinter + Send>>>>,
+    /// A prompt is showing and its line hasn't been collected yet
+    reading: Arc<AtomicBool>,
+}
+
+impl Printer {
+    fn new(external: Option<Box<dyn ExternalPrinter + Send>>) -> Self {
+        Self {
+            external: Arc::new(Mutex::new(external)),
+            reading: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn print(&self, text: String) -> Result<()> {
+        match self.external.lock().unwrap().as_mut() {
+            Some(printer) if self.reading.load(Ordering::SeqCst) => printer.print(format!("{}\n", text))?,
+            _ => {
+                let mut stdout = io::stdout();
+                writeln!(stdout, "{}", text)?;
+                stdout.flush()?;
+            },
+        }
+        Ok(())
+    }
+}
+
+/// Wait until `ready` has held for `SCRIPT_SETTLE_SECONDS` in a row, giving
+/// the commands an instruction started time to show up
+async fn settle(ready: &(dyn Fn() -> bool + Send)) {
+    let mut check = tokio::time::interval(Duration::from_secs(1));
+    let mut settled = 0;
+    while settled < SCRIPT_SETTLE_SECONDS {
+        check.tick().await;
+        settled = if ready() { settled + 1 } else { 0 };
+    }
+}
+
+fn new_editor(history: &PathBuf, completer: SessionCompleter) -> Result<Editor<SessionCompleter, DefaultHistory>> {
+    let config = Config::builder()
+        .max_history_size(HISTORY_SIZE)?
+        .history_ignore_dups(true)?
+        .completion_type(CompletionType::List)
+        .build();
+    let mut editor = Editor::with_config(config)?;
+    editor.set_helper(Some(completer));
+
+    match editor.load_history(history) {
+        Ok(()) => {},
+        Err(ReadlineError::Io(e)) if e.kind() == io::ErrorKind::NotFound => {},
+        Err(e) => eprintln!("Failed to load input history {:?}: {}", history, e),
+    }
+    Ok(editor)
+}
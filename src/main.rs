@@ -3,37 +3,169 @@ mod ai;
 mod terminal;
 mod config;
 mod utils;
+mod cli;
+mod completion;
+mod help;
+mod line_editor;
+mod pager;
+mod plain_output;
+mod progress;
+mod tui;
 
-use anyhow::Result;
-use std::path::PathBuf;
-use std::io::{self, Write};
+use anyhow::{Result, Context};
+use clap::Parser;
+use std::path::{Path, PathBuf};
+use std::io::{self, IsTerminal, Write};
 use crossterm::{
     execute,
-    style::{Color, Print, ResetColor, SetForegroundColor},
+    style::{Color, Print, ResetColor, SetForegroundColor, Stylize},
     terminal::{Clear, ClearType},
     cursor::{MoveTo}
 };
 use std::process::Command;
 use core::security_commands::SecurityCommandExecutor;
+use core::{PentestEngine, PentestSession, Target, SESSIONS_FILE};
 use terminal::{
     TerminalManager, OutputAnalyzer, 
-    AutoDocumentation, ActionExecutor, CommandType, CommandStatus
+    AutoDocumentation, ActionExecutor, CommandType, CommandStatus, ExecutionBackend,
+    CommandMonitor, CommandScheduler, MonitoredCommand, Throttle, CommandPolicy, PolicyViolation
 };
+use terminal::command_monitor::determine_command_type;
+use terminal::auto_documentation::{DocumentedFinding, FindingNote, FollowUpAction, FindingStatus, FindingStore, FINDINGS_FILE, load_findings, reported_findings, save_findings, write_finding_file};
+use utils::extract_target_from_command;
+use terminal::export;
+use terminal::assets::{Asset, AssetKind};
+use terminal::alerts::Alerter;
+use terminal::notifier;
+use terminal::report_templates::ReportTemplates;
+use terminal::screenshots::Screenshotter;
+use terminal::jira::{JiraClient, JIRA_REF};
+use terminal::shodan::{self, ShodanClient};
+use terminal::censys::{self, CensysClient};
+use terminal::crtsh::{self, CrtshClient};
+use terminal::virustotal::{self, VirusTotalClient};
+use terminal::securitytrails::{self, SecurityTrailsClient};
+use terminal::wayback::{self, WaybackClient};
+use terminal::github::{self, GitHubClient};
+use terminal::nvd::NvdClient;
+use terminal::searchsploit::{self, EXPLOITS_DIR};
+use terminal::nessus::{self, NessusReport};
+use terminal::gvm::{self, GmpClient, GvmReport, GvmScan};
+use terminal::interactsh::{InteractshListener, OOB_PLACEHOLDER};
+use terminal::session_diff::SessionDiff;
+use terminal::evidence_git::EvidenceRepo;
+use terminal::severity_matrix::SeverityMatrix;
+use terminal::finding_ids::FindingIdScheme;
+use terminal::testing_window::TestingWindow;
+use terminal::action_plugins::CommandHook;
+use terminal::timeline::ActivityKind;
+use terminal::approval::ApprovalDecision;
+use terminal::events::{FindingEventKind, FindingEventStream};
+use terminal::suppression::Suppression;
+use terminal::command_monitor::FindingSeverity;
+use terminal::playbook::Playbook;
+use terminal::proxy::ProxyRouter;
+use terminal::dns::{self, DnsResolvers, RESOLVERS_FILE};
+use terminal::safety_policy::SafetyPolicy;
+use config::{BannerConfig, Config, PassiveSource};
+use config::paths::HacksorDirs;
+use cli::{Cli, Commands, ConfigCommand, Verbosity};
+use completion::SessionCompleter;
+use line_editor::{Input, LineEditor};
+use plain_output::PlainStdout;
+use progress::ScanProgress;
 use tokio::sync::mpsc;
 use std::env;
-use regex;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// How long a finished command's results are reused instead of re-running it
+const DUPLICATE_WINDOW_MINUTES: i64 = 60;
+
+/// Commands `!history` lists when no count is given
+const HISTORY_LENGTH: usize = 20;
+
+/// Longest command summary shown by `!history`
+const HISTORY_SUMMARY_WIDTH: usize = 100;
+
+/// Matches `!grep` lists before stopping
+const GREP_MAX_MATCHES: usize = 500;
+
+/// Lines of each command's output echoed into the chat unless --verbose or --quiet is given
+const ECHOED_LINES_PER_COMMAND: usize = 20;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Setup terminal UI
-    setup_terminal()?;
+    let cli = Cli::parse();
+    
+    // Piped or redirected output goes without colors; the interface needs a terminal anyway
+    let _plain_stdout = if cli.tui { None } else { PlainStdout::start()? };
+    
+    // Setup the configuration and data directories, moving over the files of
+    // an existing ~/.hacksor
+    let dirs = HacksorDirs::locate();
+    let migrated_from = dirs.migrate_legacy()?;
+    let config_path = match &cli.config {
+        Some(path) => path.clone(),
+        None => dirs.config.join("config.toml"),
+    };
+    
+    // `hacksor config check` reports problems and exits without starting a session
+    if let Some(Commands::Config { command: ConfigCommand::Check }) = &cli.command {
+        if let Some(legacy) = &migrated_from {
+            print_migration(legacy, &dirs)?;
+        }
+        let problems = config::check::check(&config_path, &dirs).await?;
+        if problems > 0 {
+            return Err(anyhow::anyhow!("Found {} configuration problem(s)", problems));
+        }
+        return Ok(());
+    }
+    
+    // Load configuration from --config or the configuration directory, writing
+    // the defaults on first run so there is a file to edit
+    let created_config = cli.config.is_none() && !config_path.exists();
+    if created_config {
+        std::fs::create_dir_all(&dirs.config)?;
+        Config::default().save(&config_path)?;
+    }
+    let config = Config::load(&config_path)
+        .context(format!("Failed to load configuration {}", config_path.display()))?;
+    
+    // Setup terminal UI and display the welcome message, unless turned off
+    // for demos or piped output
+    let show_banner = !cli.no_banner && config.banner.enabled;
+    if show_banner {
+        setup_terminal()?;
+        display_hacksor_welcome(&config.banner, &dirs.config)?;
+    }
     
-    // Display welcome message
-    display_hacksor_welcome()?;
+    if let Some(legacy) = &migrated_from {
+        print_migration(legacy, &dirs)?;
+    }
+    if created_config {
+        execute!(
+            io::stdout(),
+            SetForegroundColor(Color::Cyan),
+            Print(format!("[Hacksor Info] Created a default configuration at {}\n", config_path.display())),
+            ResetColor
+        )?;
+    }
+    
+    // Read the scope and playbook up front so mistakes surface before anything runs
+    let scope = cli.scope.as_ref()
+        .map(|path| utils::parse_scope_file(path).context(format!("Failed to read scope file {}", path.display())))
+        .transpose()?
+        .unwrap_or_default();
+    let playbook = cli.playbook.as_deref().map(Playbook::load).transpose()?;
+    if let Some(playbook) = &playbook {
+        playbook.commands(cli.target.as_deref())?;
+    }
     
     // Initialize AI
-    let mut ai = match ai::GeminiAI::new() {
+    let mut ai = match ai::GeminiAI::new(Some(config.api_key.as_str()).filter(|key| !key.is_empty())) {
         Ok(ai) => ai,
         Err(e) => {
             let mut stdout = io::stdout();
@@ -41,7 +173,7 @@ async fn main() -> Result<()> {
                 stdout,
                 SetForegroundColor(Color::Red),
                 Print(format!("\n[ERROR] Failed to initialize AI: {}\n", e)),
-                Print("\nMake sure you have set the GEMINI_API_KEY environment variable:\n"),
+                Print(format!("\nSet api_key in {} or the GEMINI_API_KEY environment variable:\n", config_path.display())),
                 SetForegroundColor(Color::Yellow),
                 Print("export GEMINI_API_KEY=\"your-api-key\"\n\n"),
                 ResetColor
@@ -50,16 +182,125 @@ async fn main() -> Result<()> {
         }
     };
     
-    // Setup working directory
-    let home_dir = env::var("HOME").unwrap_or_else(|_| ".".to_string());
-    let work_dir = PathBuf::from(home_dir).join(".hacksor");
+    ai.set_model(&config.model);
+    
+    // Findings, logs and reports live in a per-session directory so concurrent
+    // engagements don't interleave their evidence
+    let session = session_name(cli.session.as_deref());
+    let work_dir = dirs.data.join(&config.working_dir).join(&session);
     
     // Initialize terminal manager
     let terminal_mgr = TerminalManager::new(work_dir.clone())?;
+    execute!(
+        io::stdout(),
+        SetForegroundColor(Color::Cyan),
+        Print(format!("[Hacksor Info] Session {}: evidence is saved to {}\n", session, work_dir.display())),
+        ResetColor
+    )?;
     
     // Get command monitor
     let command_monitor = terminal_mgr.get_command_monitor();
     
+    // Run tools inside a container if configured
+    let backend = ExecutionBackend::from_config(&config.container);
+    if let ExecutionBackend::Container(container) = &backend {
+        execute!(
+            io::stdout(),
+            SetForegroundColor(Color::Cyan),
+            Print(format!("[Hacksor Info] Running tools inside container image: {}\n", container.image)),
+            ResetColor
+        )?;
+    }
+    command_monitor.set_execution_backend(backend);
+    
+    // Run configured tools from their paths with their default arguments
+    command_monitor.set_tools(config.tools.clone());
+    
+    // Throttle tools according to the configured rate limits
+    let throttle = Throttle::new(config.rate_limit.clone());
+    if throttle.bandwidth_unavailable() {
+        execute!(
+            io::stdout(),
+            SetForegroundColor(Color::Red),
+            Print("[ERROR] trickle is not installed, so rate_limit bandwidth_kbps is not applied\n"),
+            ResetColor
+        )?;
+    }
+    command_monitor.set_throttle(throttle);
+    
+    // Send testing traffic through the configured proxy
+    if let Some(proxy) = &config.proxy {
+        let proxy = ProxyRouter::new(proxy.clone());
+        execute!(
+            io::stdout(),
+            SetForegroundColor(Color::Cyan),
+            Print(format!("[Hacksor Info] Routing tool traffic through {}\n", proxy.url())),
+            ResetColor
+        )?;
+        command_monitor.set_proxy(Some(proxy));
+    }
+    
+    // Resolve names through the engagement's DNS servers, ours and the tools'
+    if let Some(dns) = &config.dns {
+        let resolvers = DnsResolvers::new(dns, &work_dir)?;
+        let listed: Vec<String> = resolvers.resolvers().iter().map(|resolver| resolver.to_string()).collect();
+        execute!(
+            io::stdout(),
+            SetForegroundColor(Color::Cyan),
+            Print(format!("[Hacksor Info] Resolving through {}; DNS tools are given {}\n", listed.join(", "), RESOLVERS_FILE)),
+            ResetColor
+        )?;
+        command_monitor.set_resolvers(Some(resolvers));
+    }
+    
+    // Hand blind checks callback domains and watch for interactions with them
+    let oob_enabled = config.interactsh.enabled && match InteractshListener::start(&config.interactsh, command_monitor.clone()) {
+        Ok(listener) => {
+            command_monitor.set_oob_listener(listener);
+            true
+        },
+        Err(e) => {
+            execute!(
+                io::stdout(),
+                SetForegroundColor(Color::Red),
+                Print(format!("[ERROR] Out-of-band callbacks are unavailable: {:#}\n", e)),
+                ResetColor
+            )?;
+            false
+        },
+    };
+    
+    // Enforce command allow/deny lists
+    command_monitor.set_command_policy(CommandPolicy::from_config(&config.command_policy)?);
+    command_monitor.set_notify_after(long_command_notice(&config));
+    command_monitor.get_notifier().set_webhooks(notifier::configured_webhooks(&config));
+    
+    // Route every command through a confirmation prompt if requested
+    // (never in non-interactive runs, where nobody is there to answer)
+    let confirm_all = !cli.non_interactive && (config.confirm_all || cli.confirm_all);
+    let approval_gate = command_monitor.get_approval_gate();
+    approval_gate.set_enabled(confirm_all);
+    if confirm_all {
+        execute!(
+            io::stdout(),
+            SetForegroundColor(Color::Cyan),
+            Print("[Hacksor Info] Approval mode enabled: every command needs your confirmation before it runs.\n"),
+            ResetColor
+        )?;
+    }
+    
+    // Present generated follow-up actions for approve/skip/edit instead of running them autonomously
+    let review_actions = !cli.non_interactive && (config.actions.review || cli.review_actions);
+    approval_gate.set_review_actions(review_actions);
+    if review_actions {
+        execute!(
+            io::stdout(),
+            SetForegroundColor(Color::Cyan),
+            Print("[Hacksor Info] Action review enabled: follow-up actions wait for you to approve, skip or edit them.\n"),
+            ResetColor
+        )?;
+    }
+    
     // Set up output analysis system
     let mut output_rx = command_monitor.get_output_receiver();
     let mut output_analyzer = OutputAnalyzer::new(
@@ -67,6 +308,62 @@ async fn main() -> Result<()> {
         command_monitor.get_output_receiver()
     );
     
+    // Load user-defined detection rules from the configuration directory
+    match terminal::analyzer_rules::load_rules_from_dir(&dirs.config.join("rules")) {
+        Ok(rules) => {
+            if !rules.is_empty() {
+                execute!(
+                    io::stdout(),
+                    SetForegroundColor(Color::Cyan),
+                    Print(format!("[Hacksor Info] Loaded {} custom analyzer rules\n", rules.len())),
+                    ResetColor
+                )?;
+            }
+            output_analyzer.set_custom_rules(rules);
+        },
+        Err(e) => {
+            execute!(
+                io::stdout(),
+                SetForegroundColor(Color::Red),
+                Print(format!("[ERROR] Failed to load analyzer rules: {:#}\n", e)),
+                ResetColor
+            )?;
+        }
+    }
+    
+    // Load the local CVE dataset used to correlate version disclosures
+    match terminal::cve_db::CveDatabase::load(&dirs.data.join("cve_data.json")) {
+        Ok(cve_db) => {
+            if !cve_db.is_empty() {
+                execute!(
+                    io::stdout(),
+                    SetForegroundColor(Color::Cyan),
+                    Print(format!("[Hacksor Info] Loaded {} CVE records for version correlation\n", cve_db.len())),
+                    ResetColor
+                )?;
+            }
+            output_analyzer.set_cve_database(cve_db);
+        },
+        Err(e) => {
+            execute!(
+                io::stdout(),
+                SetForegroundColor(Color::Red),
+                Print(format!("[ERROR] Failed to load CVE dataset: {:#}\n", e)),
+                ResetColor
+            )?;
+        }
+    }
+    
+    // Describe the CVEs that appear in output from NVD, cached across sessions
+    let nvd = if config.nvd.enabled {
+        Some(NvdClient::new(&config.nvd, &dirs.data.join("nvd"))?)
+    } else {
+        None
+    };
+    if let Some(nvd) = &nvd {
+        output_analyzer.set_nvd_client(nvd.clone());
+    }
+    
     // Set up channels for follow-up actions
     let (action_tx, action_rx) = mpsc::channel(100);
     let (result_tx, mut result_rx) = mpsc::channel(100);
@@ -78,18 +375,122 @@ async fn main() -> Result<()> {
         action_tx.clone(),
         work_dir.clone()
     )?;
+    auto_doc.set_default_credentials(config.default_credentials.clone());
+    for hook in &config.action_hooks {
+        auto_doc.register_action_plugin(Box::new(CommandHook::from_config(hook)?));
+    }
+    auto_doc.set_alerter(Alerter::new(config.alerts.clone()));
+    auto_doc.set_severity_matrix(SeverityMatrix::from_config(&config.severity_overrides)?);
+    auto_doc.set_finding_ids(FindingIdScheme::new(&config.finding_ids, &work_dir.join("finding_counters.json"))?);
+    if let Some(nvd) = nvd {
+        auto_doc.set_nvd_client(nvd);
+    }
+    
+    // Screenshot discovered web pages into the session directory
+    if config.screenshots.enabled {
+        auto_doc.set_screenshotter(Screenshotter::new(config.screenshots.clone(), &work_dir)?);
+    }
+    
+    // Report layouts can be overridden from the working directory
+    match ReportTemplates::load(&dirs.config, &config.report) {
+        Ok(templates) => auto_doc.set_report_templates(templates),
+        Err(e) => {
+            execute!(
+                io::stdout(),
+                SetForegroundColor(Color::Red),
+                Print(format!("[ERROR] Failed to load report templates: {:#}\n", e)),
+                ResetColor
+            )?;
+        }
+    }
+    
+    // Keep a tamper-evident git history of the session's evidence
+    let evidence_repo = if config.evidence_git.enabled {
+        match EvidenceRepo::init(&work_dir, &config.evidence_git) {
+            Ok(repo) => {
+                auto_doc.set_evidence_repo(repo.clone());
+                Some(repo)
+            },
+            Err(e) => {
+                execute!(
+                    io::stdout(),
+                    SetForegroundColor(Color::Red),
+                    Print(format!("[ERROR] {:#}\n", e)),
+                    ResetColor
+                )?;
+                None
+            }
+        }
+    } else {
+        None
+    };
+    
+    // Publish finding events for external dashboards and scripts
+    let finding_events = if config.events.enabled {
+        match FindingEventStream::open(&work_dir.join("findings.jsonl")) {
+            Ok(events) => {
+                if let Some(socket_path) = &config.events.socket_path {
+                    if let Err(e) = events.listen(socket_path) {
+                        execute!(
+                            io::stdout(),
+                            SetForegroundColor(Color::Red),
+                            Print(format!("[ERROR] {:#}\n", e)),
+                            ResetColor
+                        )?;
+                    }
+                }
+                auto_doc.set_event_stream(events.clone());
+                Some(events)
+            },
+            Err(e) => {
+                execute!(
+                    io::stdout(),
+                    SetForegroundColor(Color::Red),
+                    Print(format!("[ERROR] {:#}\n", e)),
+                    ResetColor
+                )?;
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let finding_store = auto_doc.get_finding_store();
+    let report_generator = auto_doc.get_report_generator();
     
     // Set up action executor
     let mut action_executor = ActionExecutor::new(
         Arc::new(command_monitor.clone()),
         action_rx,
         result_tx.clone(),
-        2 // max concurrent actions
+        2, // max concurrent actions
+        &config.actions,
     );
+    let action_canceller = action_executor.canceller();
+    
+    // Hold follow-up actions until the rules of engagement allow testing
+    let testing_window = config.testing_window.as_ref()
+        .map(TestingWindow::from_config)
+        .transpose()?;
+    if let Some(window) = &testing_window {
+        action_executor.set_testing_window(window.clone());
+        execute!(
+            io::stdout(),
+            SetForegroundColor(Color::Cyan),
+            Print(format!("[Hacksor Info] Testing window {}: follow-up actions outside it are scheduled until it opens.\n", window)),
+            ResetColor
+        )?;
+    }
+    
+    // Set up scheduler for scheduled and recurring commands
+    let scheduler = CommandScheduler::new(Arc::new(command_monitor.clone()))?;
     
     // Security command executor (for direct intent analysis)
     let command_executor = SecurityCommandExecutor::new();
     
+    // Per-target limits on how aggressive commands may be
+    let mut safety_policy = SafetyPolicy::load_or_create(&dirs.config.join(&config.safety_policy))?;
+    
     // Start background tasks
     let _output_analyzer_handle = tokio::spawn(async move {
         if let Err(e) = output_analyzer.start().await {
@@ -109,17 +510,69 @@ async fn main() -> Result<()> {
         }
     });
     
+    // Resume follow-up actions left unfinished by an earlier run of this session
+    let unfinished_actions = command_monitor.get_action_queue().unfinished();
+    if !unfinished_actions.is_empty() {
+        execute!(
+            io::stdout(),
+            SetForegroundColor(Color::Cyan),
+            Print(format!("[Hacksor Info] Resuming {} unfinished follow-up actions. List them with !actions.\n", unfinished_actions.len())),
+            ResetColor
+        )?;
+        for action in unfinished_actions {
+            action_tx.send(action).await?;
+        }
+    }
+    
+    let scheduler_clone = scheduler.clone();
+    let _scheduler_handle = tokio::spawn(async move {
+        if let Err(e) = scheduler_clone.start().await {
+            eprintln!("Scheduler error: {}", e);
+        }
+    });
+    
     // Channel for sending command output from background tasks to main loop
     let (cmd_output_tx, mut cmd_output_rx) = mpsc::channel(100);
     
-    // Start task to forward output from command monitor
+    // Output kept out of the chat by the verbosity level, which the AI still sees
+    let (unseen_output_tx, mut unseen_output_rx) = mpsc::channel(100);
+    
+    // Start task to forward output from command monitor. The full-screen
+    // interface has its own pane for output, so it gets every line
     let cmd_output_tx_clone = cmd_output_tx.clone();
+    let verbosity = if cli.tui { Verbosity::Verbose } else { cli.verbosity() };
+    let echo_monitor = command_monitor.clone();
     tokio::spawn(async move {
+        let mut echoed: HashMap<String, usize> = HashMap::new();
         while let Some(output) = output_rx.recv().await {
-            if let Err(e) = cmd_output_tx_clone.send(format!("[{}] {}", 
+            let line = format!("[{}] {}", 
                 if output.is_error { "ERROR" } else { "INFO" }, 
                 output.line
-            )).await {
+            );
+            
+            let count = echoed.entry(output.command_id.clone()).or_insert(0);
+            *count += 1;
+            let shown = match verbosity {
+                Verbosity::Quiet => false,
+                Verbosity::Normal => *count <= ECHOED_LINES_PER_COMMAND,
+                Verbosity::Verbose => true,
+            };
+            if !shown {
+                if *count == ECHOED_LINES_PER_COMMAND + 1 && verbosity == Verbosity::Normal {
+                    if let Some(cmd) = echo_monitor.get_command(&output.command_id) {
+                        let _ = cmd_output_tx_clone.send(format!(
+                            "[Hacksor] More output from `{}` is in {}; --verbose shows it all",
+                            cmd.command, cmd.output_file.display()
+                        )).await;
+                    }
+                }
+                if unseen_output_tx.send(line).await.is_err() {
+                    break;
+                }
+                continue;
+            }
+            
+            if let Err(e) = cmd_output_tx_clone.send(line).await {
                 eprintln!("Failed to send command output: {}", e);
                 break;
             }
@@ -128,6 +581,9 @@ async fn main() -> Result<()> {
     
     // Start task to forward action results
     let cmd_output_tx_clone = cmd_output_tx.clone();
+    let exploit_findings = finding_store.clone();
+    let exploit_events = finding_events.clone();
+    let exploit_evidence = evidence_repo.clone();
     tokio::spawn(async move {
         while let Some(action) = result_rx.recv().await {
             let status_str = match action.status {
@@ -143,6 +599,14 @@ async fn main() -> Result<()> {
                 break;
             }
             
+            // Exploit searches following up on a version disclosure attach their results to it
+            if let Some(offer) = attach_exploit_candidates(&action, &exploit_findings, exploit_events.as_ref(), exploit_evidence.as_ref()) {
+                if let Err(e) = cmd_output_tx_clone.send(offer).await {
+                    eprintln!("Failed to send action result: {}", e);
+                    break;
+                }
+            }
+            
             // If there's a result, send that too (truncated if very long)
             if let Some(result) = action.result {
                 let result = if result.len() > 200 {
@@ -151,121 +615,1735 @@ async fn main() -> Result<()> {
                     result
                 };
                 
-                if let Err(e) = cmd_output_tx_clone.send(format!("[RESULT] {}", result)).await {
-                    eprintln!("Failed to send action result: {}", e);
-                    break;
+                if let Err(e) = cmd_output_tx_clone.send(format!("[RESULT] {}", result)).await {
+                    eprintln!("Failed to send action result: {}", e);
+                    break;
+                }
+            }
+        }
+    });
+    
+    // Handle Ctrl-C: the first press asks what to do with running commands,
+    // a second press terminates them and exits immediately
+    let shutdown_requested = Arc::new(AtomicBool::new(false));
+    let shutdown_flag = shutdown_requested.clone();
+    let shutdown_monitor = command_monitor.clone();
+    let shutdown_findings = finding_store.clone();
+    let shutdown_evidence = evidence_repo.clone();
+    let shutdown_work_dir = work_dir.clone();
+    tokio::spawn(async move {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            let running = shutdown_monitor.get_active_commands();
+            
+            if running.is_empty() || shutdown_flag.load(Ordering::SeqCst) {
+                let _ = shutdown_session(&shutdown_monitor, &shutdown_findings, shutdown_evidence.as_ref(), &shutdown_work_dir, true).await;
+                progress::restore_terminal();
+                std::process::exit(0);
+            }
+            
+            shutdown_flag.store(true, Ordering::SeqCst);
+            let _ = print_running_commands(&shutdown_monitor);
+            let _ = execute!(
+                io::stdout(),
+                SetForegroundColor(Color::Yellow),
+                Print("[Hacksor] Type 'terminate' or 'detach' to exit (Ctrl-C again terminates immediately)\n> "),
+                ResetColor
+            );
+            let _ = io::stdout().flush();
+        }
+    });
+    
+    // Scripted runs: run the playbook, wait for the follow-up actions it
+    // triggers and exit, failing if a step failed
+    if let (true, Some(playbook)) = (cli.non_interactive, &playbook) {
+        if let Some(target) = &cli.target {
+            passive_recon_on_start(&config, &command_monitor, &work_dir, target, &scope).await?;
+        }
+        
+        let runner = playbook.clone();
+        let runner_monitor = command_monitor.clone();
+        let runner_target = cli.target.clone();
+        let runner_tx = cmd_output_tx.clone();
+        let mut run = tokio::spawn(async move {
+            runner.run(&runner_monitor, runner_target.as_deref(), runner_tx).await
+        });
+        
+        let mut outcome = None;
+        let mut idle_ticks = 0;
+        let mut idle_check = tokio::time::interval(tokio::time::Duration::from_secs(1));
+        loop {
+            tokio::select! {
+                Some(output) = cmd_output_rx.recv() => {
+                    execute!(
+                        io::stdout(),
+                        SetForegroundColor(Color::Blue),
+                        Print(format!("{}\n", output)),
+                        ResetColor
+                    )?;
+                }
+                // No AI to show it to on a scripted run
+                Some(_) = unseen_output_rx.recv() => {}
+                result = &mut run, if outcome.is_none() => {
+                    outcome = Some(result??);
+                }
+                _ = idle_check.tick(), if outcome.is_some() => {
+                    // Give findings from the last step a moment to turn into actions
+                    let idle = command_monitor.get_active_commands().is_empty()
+                        && command_monitor.get_action_queue().unfinished().is_empty();
+                    idle_ticks = if idle { idle_ticks + 1 } else { 0 };
+                    if idle_ticks >= 3 {
+                        break;
+                    }
+                }
+            }
+        }
+        
+        shutdown_session(&command_monitor, &finding_store, evidence_repo.as_ref(), &work_dir, true).await?;
+        
+        let outcome = outcome.unwrap_or_default();
+        execute!(
+            io::stdout(),
+            SetForegroundColor(if outcome.failed.is_empty() { Color::Green } else { Color::Red }),
+            Print(format!("[Hacksor] Playbook {} finished: {} succeeded, {} failed, {} skipped\n",
+                playbook.name, outcome.succeeded, outcome.failed.len(), outcome.skipped)),
+            ResetColor
+        )?;
+        if !outcome.failed.is_empty() {
+            return Err(anyhow::anyhow!("Playbook steps failed: {}", outcome.failed.join(", ")));
+        }
+        return Ok(());
+    }
+    
+    // Start conversation loop
+    let mut stdout = io::stdout();
+    let mut conversation_active = true;
+    
+    // Sessions persist across runs: `--target` picks up the latest session against
+    // that target or starts one, and without it the last session carries on
+    let mut engine = PentestEngine::load(&dirs.data.join(SESSIONS_FILE))?;
+    match (&cli.target, engine.get_current_session()) {
+        (Some(target), _) => match engine.find_session(target) {
+            Some(session) => {
+                engine.switch_session(&session.session_id)?;
+            },
+            None => {
+                engine.start_session(Target {
+                    domain: target.clone(),
+                    scope: scope.clone(),
+                    excluded: Vec::new(),
+                }).await?;
+            },
+        },
+        (None, Some(session)) => {
+            execute!(
+                io::stdout(),
+                SetForegroundColor(Color::Cyan),
+                Print(format!("[Hacksor Info] Continuing session {} against {}. !sessions lists the others.\n",
+                    session.session_id, session.target.domain)),
+                ResetColor
+            )?;
+        },
+        (None, None) => {},
+    }
+    
+    // Tell the AI what the engagement is about before it greets the user
+    if let Some(brief) = session_brief(&engine, &scope) {
+        ai.add_user_message(&brief);
+    }
+    if oob_enabled {
+        ai.add_user_message(&format!(
+            "For blind checks (SSRF, blind XSS, XXE, log4shell-style injection) put {} in the payload where a callback host is needed, e.g. http://{}/ or ${{jndi:ldap://{}/a}}. Each command gets its own callback domain, and any DNS or HTTP interaction with it is reported as a Critical finding.",
+            OOB_PLACEHOLDER, OOB_PLACEHOLDER, OOB_PLACEHOLDER
+        ));
+    }
+    
+    // Passive recon comes first: what Shodan and Censys have already seen of
+    // the target is known before the AI suggests anything that touches it
+    let session_target = engine.get_current_session().map(|session| session.target.clone());
+    if let Some(target) = session_target {
+        for note in passive_recon_on_start(&config, &command_monitor, &work_dir, &target.domain, &target.scope).await? {
+            ai.add_user_message(&note);
+        }
+    }
+    
+    // The full-screen interface takes over from here, on the same session
+    if cli.tui {
+        if let Some(playbook) = playbook {
+            spawn_playbook(playbook, command_monitor.clone(), cli.target.clone(), cmd_output_tx.clone());
+        }
+        let terminate = tui::run(tui::Session {
+            ai,
+            monitor: command_monitor.clone(),
+            findings: finding_store.clone(),
+            safety_policy,
+            output_rx: cmd_output_rx,
+            output_tx: cmd_output_tx.clone(),
+        }).await?;
+        shutdown_session(&command_monitor, &finding_store, evidence_repo.as_ref(), &work_dir, terminate).await?;
+        return Ok(());
+    }
+    
+    // Get initial response from AI to start the conversation
+    match ai.get_response().await {
+        Ok(response) => {
+            execute!(
+                stdout,
+                SetForegroundColor(Color::Green),
+                Print(format!("[Hacksor] {}\n", response)),
+                ResetColor
+            )?;
+            
+            // Add feature hint for users
+            if !cli.no_banner && config.banner.tips {
+                execute!(
+                    stdout,
+                    SetForegroundColor(Color::Cyan),
+                    Print("\n[Hacksor Info] I now analyze target profiles (like universities, .edu domains) and automatically use less aggressive scanning by default for prestigious targets. I'll progressively increase scan intensity when you request deeper analysis.\n\n"),
+                    ResetColor
+                )?;
+            }
+        },
+        Err(e) => {
+            execute!(
+                stdout,
+                SetForegroundColor(Color::Red),
+                Print(format!("\n[ERROR] Failed to get AI response: {}\n", e)),
+                ResetColor
+            )?;
+            return Ok(());
+        }
+    }
+    
+    // Run the startup playbook in the background so its commands can still be
+    // approved at the prompt
+    if let Some(playbook) = playbook {
+        spawn_playbook(playbook, command_monitor.clone(), cli.target.clone(), cmd_output_tx.clone());
+    }
+    
+    // Prompt with line editing, history kept across sessions and completion
+    // from the session's commands, findings and targets
+    let completer = SessionCompleter::new(
+        command_monitor.clone(),
+        finding_store.clone(),
+        scheduler.clone(),
+        cli.target.iter().chain(&scope).cloned().collect(),
+    );
+    let mut line_editor = if io::stdin().is_terminal() {
+        LineEditor::start(dirs.data.join("history"), completer)?
+    } else {
+        // Instructions piped on stdin run in turn, each once the commands it
+        // started are done or an approval is waiting for the next line
+        let monitor = command_monitor.clone();
+        let approvals = approval_gate.clone();
+        LineEditor::script(move || approvals.has_pending()
+            || (monitor.get_active_commands().is_empty() && monitor.get_action_queue().unfinished().is_empty()))
+    };
+    
+    // A spinner under the prompt while commands run, so long scans don't look stuck
+    let _scan_progress = (io::stdin().is_terminal() && io::stdout().is_terminal())
+        .then(|| ScanProgress::start(command_monitor.clone()));
+    
+    // Findings from earlier runs of this session have been seen; the prompt
+    // counts the ones documented since until they are listed or shown
+    let mut read_findings: HashSet<String> = finding_store.lock().unwrap().keys().cloned().collect();
+    
+    // Background output is printed above the prompt, and keeps printing while
+    // a line is being handled
+    let printer = line_editor.printer();
+    
+    while conversation_active {
+        // Wait for a line, printing command output from background tasks as it arrives
+        let prompt = status_prompt(&engine, &terminal_mgr.get_command_monitor(), &finding_store, &read_findings);
+        let user_input = tokio::select! {
+            Some(output) = cmd_output_rx.recv() => {
+                // Printed above the prompt, which stays as it was
+                printer.print(output.as_str().blue().to_string())?;
+                
+                // Add the terminal output to the AI context to make it aware of findings
+                observe_output(&mut ai, &terminal_mgr.get_command_monitor(), &output);
+                continue;
+            }
+            
+            // Output not echoed at this verbosity still reaches the AI
+            Some(output) = unseen_output_rx.recv() => {
+                observe_output(&mut ai, &terminal_mgr.get_command_monitor(), &output);
+                continue;
+            }
+            
+            // Ctrl-C only discards the line being typed and Ctrl-D exits like `exit`
+            input = line_editor.read_line(&prompt) => match input {
+                Input::Line(line) => line,
+                Input::Cancelled => continue,
+                Input::Closed => "exit".to_string(),
+            },
+        };
+        
+        // Handle the line to the end, however long the AI takes. Output that
+        // arrives meanwhile is printed straight away and shown to the AI after
+        let mut observed = Vec::new();
+        {
+            let handling = async {
+                let user_input = user_input.trim();
+                
+                // Clone terminal_mgr for use in this async block
+                let terminal_mgr_clone = terminal_mgr.clone();
+                
+                // Complete a shutdown started with Ctrl-C
+                if shutdown_requested.load(Ordering::SeqCst) {
+                    let terminate = match user_input.to_lowercase().as_str() {
+                        "t" | "terminate" => true,
+                        "d" | "detach" => false,
+                        _ => {
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Yellow),
+                                Print("[Hacksor] Type 'terminate' or 'detach' to exit (Ctrl-C again terminates immediately)\n"),
+                                ResetColor
+                            )?;
+                            return Ok::<(), anyhow::Error>(());
+                        },
+                    };
+                    shutdown_session(&terminal_mgr_clone.get_command_monitor(), &finding_store, evidence_repo.as_ref(), &work_dir, terminate).await?;
+                    conversation_active = false;
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // Answer a pending command approval
+                if approval_gate.has_pending() {
+                    if let Some((command, decision)) = approval_gate.answer(user_input) {
+                        let summary = record_approval(&terminal_mgr_clone.get_command_monitor(), &command, &decision);
+                        let color = match decision {
+                            ApprovalDecision::Reject => Color::Yellow,
+                            _ => Color::Green,
+                        };
+                        execute!(
+                            stdout,
+                            SetForegroundColor(color),
+                            Print(format!("[Hacksor] {}\n", summary)),
+                            ResetColor
+                        )?;
+                    }
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // Check for exit command
+                if user_input.to_lowercase() == "exit" || user_input.to_lowercase() == "quit" {
+                    let monitor = terminal_mgr_clone.get_command_monitor();
+                    let mut terminate = true;
+                    
+                    // Ask what to do with commands that are still running
+                    if !monitor.get_active_commands().is_empty() {
+                        print_running_commands(&monitor)?;
+                        execute!(
+                            stdout,
+                            SetForegroundColor(Color::Yellow),
+                            Print("[Hacksor] [t]erminate them, [d]etach and leave them running, or [c]ancel exit?\n"),
+                            ResetColor
+                        )?;
+                        
+                        // Only an explicit answer terminates; anything else asks again
+                        loop {
+                            let choice = match line_editor.read_line("> ").await {
+                                Input::Line(line) => line,
+                                Input::Cancelled => "c".to_string(),
+                                Input::Closed => "t".to_string(),
+                            };
+                            
+                            match choice.trim().to_lowercase().as_str() {
+                                "c" | "cancel" => return Ok::<(), anyhow::Error>(()),
+                                "d" | "detach" => terminate = false,
+                                "t" | "terminate" => {},
+                                _ => {
+                                    execute!(
+                                        stdout,
+                                        SetForegroundColor(Color::Yellow),
+                                        Print("[Hacksor] Answer t, d or c\n"),
+                                        ResetColor
+                                    )?;
+                                    continue;
+                                },
+                            }
+                            break;
+                        }
+                    }
+                    
+                    shutdown_session(&monitor, &finding_store, evidence_repo.as_ref(), &work_dir, terminate).await?;
+                    
+                    execute!(
+                        stdout,
+                        SetForegroundColor(Color::Yellow),
+                        Print("\n[Hacksor] Session terminated. Goodbye!\n"),
+                        ResetColor
+                    )?;
+                    conversation_active = false;
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // List the ! commands, or show one with examples
+                if user_input.to_lowercase().starts_with("!help") {
+                    let topic = user_input["!help".len()..].trim();
+                    execute!(
+                        stdout,
+                        SetForegroundColor(Color::Cyan),
+                        Print(format!("\n{}\n", help::help(topic))),
+                        ResetColor
+                    )?;
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // Schedule a command to run later or on a recurring interval
+                if user_input.to_lowercase().starts_with("!schedule") {
+                    let args = user_input["!schedule".len()..].trim();
+                    
+                    if args.is_empty() {
+                        let scheduled = scheduler.get_scheduled();
+                        if scheduled.is_empty() {
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Blue),
+                                Print("\n[Hacksor] No scheduled commands. Usage: !schedule \"<command>\" [--at HH:MM] [--every 30m|6h|1d]\n"),
+                                ResetColor
+                            )?;
+                        } else {
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Blue),
+                                Print("\n[Hacksor] Scheduled commands:\n"),
+                                ResetColor
+                            )?;
+                            
+                            for entry in scheduled {
+                                let every = entry.every
+                                    .map(|every| format!(", every {} min", every.num_minutes()))
+                                    .unwrap_or_default();
+                                execute!(
+                                    stdout,
+                                    SetForegroundColor(Color::Blue),
+                                    Print(format!("ID: {} - Command: {} (next run {}{}, {} runs so far)\n",
+                                        entry.id,
+                                        entry.command,
+                                        entry.next_run.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+                                        every,
+                                        entry.runs)),
+                                    ResetColor
+                                )?;
+                            }
+                        }
+                        
+                        return Ok::<(), anyhow::Error>(());
+                    }
+                    
+                    let scheduled = terminal::scheduler::parse_schedule_args(args).and_then(|(command, next_run, every)| {
+                        let command = safety_policy.apply(&command);
+                        let id = scheduler.schedule(&command, determine_command_type(&command), next_run, every)?;
+                        Ok((command, id, next_run))
+                    });
+                    match scheduled {
+                        Ok((command, id, next_run)) => {
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Green),
+                                Print(format!("\n[Hacksor] Scheduled command {} (ID: {}), first run at {}\n",
+                                    command,
+                                    id,
+                                    next_run.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"))),
+                                ResetColor
+                            )?;
+                        },
+                        Err(e) => {
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Red),
+                                Print(format!("\n[ERROR] Failed to schedule command: {}\n", e)),
+                                ResetColor
+                            )?;
+                        }
+                    }
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // Remove a scheduled command
+                if user_input.to_lowercase().starts_with("!unschedule") {
+                    let id = user_input["!unschedule".len()..].trim();
+                    match scheduler.unschedule(id) {
+                        Ok(_) => execute!(
+                            stdout,
+                            SetForegroundColor(Color::Green),
+                            Print(format!("\n[Hacksor] Removed scheduled command {}\n", id)),
+                            ResetColor
+                        )?,
+                        Err(e) => execute!(
+                            stdout,
+                            SetForegroundColor(Color::Red),
+                            Print(format!("\n[ERROR] {}\n", e)),
+                            ResetColor
+                        )?,
+                    }
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // Mark a finding as a false positive so it is no longer raised or reported
+                if user_input.to_lowercase().starts_with("!fp") {
+                    let finding_id = user_input["!fp".len()..].trim();
+                    let marked = {
+                        let mut findings = finding_store.lock().unwrap();
+                        findings.get_mut(finding_id).map(|finding| {
+                            finding.status = FindingStatus::FalsePositive;
+                            finding.clone()
+                        })
+                    };
+                    
+                    match marked {
+                        Some(finding) => {
+                            write_finding_file(&finding)?;
+                            if let Some(events) = &finding_events {
+                                events.emit(FindingEventKind::Updated, &finding);
+                            }
+                            let target = extract_target_from_command(&finding.discovery_command);
+                            terminal_mgr_clone.get_command_monitor().get_suppressions()
+                                .add(Suppression::for_finding(&finding.id, &finding.title, target.clone()))?;
+                            terminal_mgr_clone.get_command_monitor().get_timeline().record(
+                                ActivityKind::Decision,
+                                format!("Marked \"{}\" as a false positive", finding.title),
+                                Some(&finding.id),
+                            );
+                            if let Some(evidence) = &evidence_repo {
+                                evidence.commit(format!("Mark {} as a false positive", finding.id));
+                            }
+                            
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Green),
+                                Print(format!(
+                                    "\n[Hacksor] Marked {} as a false positive. \"{}\" will no longer be raised for {}.\n",
+                                    finding.id,
+                                    finding.title,
+                                    target.as_deref().unwrap_or("any target")
+                                )),
+                                ResetColor
+                            )?;
+                        },
+                        None => execute!(
+                            stdout,
+                            SetForegroundColor(Color::Red),
+                            Print(format!("\n[ERROR] Finding not found: {}. Usage: !fp <finding-id>\n", finding_id)),
+                            ResetColor
+                        )?,
+                    }
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // List the documented findings, optionally for one severity or target
+                if user_input.to_lowercase().starts_with("!findings") {
+                    let args: Vec<&str> = user_input["!findings".len()..].split_whitespace().collect();
+                    let mut severity = None;
+                    let mut target = None;
+                    let mut valid = true;
+                    let mut i = 0;
+                    while i < args.len() {
+                        match (args[i], args.get(i + 1)) {
+                            ("--severity", Some(name)) => {
+                                severity = FindingSeverity::parse(name);
+                                valid &= severity.is_some();
+                            },
+                            ("--target", Some(host)) => target = Some(host.to_string()),
+                            _ => valid = false,
+                        }
+                        i += 2;
+                    }
+                    if !valid {
+                        execute!(
+                            stdout,
+                            SetForegroundColor(Color::Red),
+                            Print("\n[ERROR] Usage: !findings [--severity <critical|high|medium|low|info>] [--target <host>]\n"),
+                            ResetColor
+                        )?;
+                        return Ok::<(), anyhow::Error>(());
+                    }
+                    
+                    execute!(
+                        stdout,
+                        SetForegroundColor(Color::Blue),
+                        Print(format!("\n[Hacksor] {}\n", list_findings(&finding_store, severity.as_ref(), target.as_deref(), &mut read_findings))),
+                        ResetColor
+                    )?;
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // Move a finding through its lifecycle: verify, close, reopen or assign it
+                if user_input.to_lowercase().starts_with("!finding ") {
+                    let args: Vec<&str> = user_input["!finding".len()..].split_whitespace().collect();
+                    let (action, finding_id) = match (args.first(), args.get(1)) {
+                        (Some(action), Some(finding_id)) => (action.to_lowercase(), *finding_id),
+                        _ => {
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Red),
+                                Print("\n[ERROR] Usage: !finding <show|verify|close|reopen|assign> <finding-id> [assignee]\n"),
+                                ResetColor
+                            )?;
+                            return Ok::<(), anyhow::Error>(());
+                        },
+                    };
+                    let assignee = args[2..].join(" ");
+                    
+                    if action == "show" {
+                        let finding = finding_store.lock().unwrap().get(finding_id).cloned();
+                        if finding.is_some() {
+                            read_findings.insert(finding_id.to_string());
+                        }
+                        match finding {
+                            Some(finding) => execute!(
+                                stdout,
+                                SetForegroundColor(Color::Blue),
+                                Print(format!("\n[Hacksor] {}\n", describe_finding(&finding))),
+                                ResetColor
+                            )?,
+                            None => execute!(
+                                stdout,
+                                SetForegroundColor(Color::Red),
+                                Print(format!("\n[ERROR] Finding not found: {}\n", finding_id)),
+                                ResetColor
+                            )?,
+                        }
+                        return Ok::<(), anyhow::Error>(());
+                    }
+                    
+                    let updated = {
+                        let mut findings = finding_store.lock().unwrap();
+                        findings.get_mut(finding_id).map(|finding| {
+                            let previous = finding.status.clone();
+                            match action.as_str() {
+                                "verify" => finding.status = FindingStatus::Verified,
+                                "close" => finding.status = FindingStatus::Closed,
+                                "reopen" => finding.status = FindingStatus::New,
+                                "assign" if !assignee.is_empty() => {
+                                    finding.assignee = Some(assignee.clone());
+                                    if finding.status == FindingStatus::New {
+                                        finding.status = FindingStatus::InProgress;
+                                    }
+                                },
+                                _ => return None,
+                            }
+                            Some((previous, finding.clone()))
+                        })
+                    };
+                    
+                    match updated {
+                        Some(Some((previous, finding))) => {
+                            write_finding_file(&finding)?;
+                            if let Some(events) = &finding_events {
+                                events.emit(FindingEventKind::Updated, &finding);
+                            }
+                            // A reopened false positive should be raised again
+                            if previous == FindingStatus::FalsePositive && finding.status == FindingStatus::New {
+                                terminal_mgr_clone.get_command_monitor().get_suppressions()
+                                    .remove_for_finding(&finding.id)?;
+                            }
+                            
+                            let assigned = finding.assignee.as_deref()
+                                .map(|assignee| format!(", assigned to {}", assignee))
+                                .unwrap_or_default();
+                            terminal_mgr_clone.get_command_monitor().get_timeline().record(
+                                ActivityKind::Decision,
+                                format!("{:?} -> {:?}{}", previous, finding.status, assigned),
+                                Some(&finding.id),
+                            );
+                            if let Some(evidence) = &evidence_repo {
+                                evidence.commit(format!("{}: {:?} -> {:?}{}", finding.id, previous, finding.status, assigned));
+                            }
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Green),
+                                Print(format!(
+                                    "\n[Hacksor] {} ({}): {:?} -> {:?}{}\n",
+                                    finding.id,
+                                    finding.title,
+                                    previous,
+                                    finding.status,
+                                    assigned
+                                )),
+                                ResetColor
+                            )?;
+                        },
+                        Some(None) => execute!(
+                            stdout,
+                            SetForegroundColor(Color::Red),
+                            Print("\n[ERROR] Usage: !finding <show|verify|close|reopen|assign> <finding-id> [assignee]\n"),
+                            ResetColor
+                        )?,
+                        None => execute!(
+                            stdout,
+                            SetForegroundColor(Color::Red),
+                            Print(format!("\n[ERROR] Finding not found: {}\n", finding_id)),
+                            ResetColor
+                        )?,
+                    }
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // Append a timestamped note to a finding
+                if user_input.to_lowercase().starts_with("!note") {
+                    let args = user_input["!note".len()..].trim();
+                    let (finding_id, text) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+                    let text = text.trim();
+                    if finding_id.is_empty() || text.is_empty() {
+                        execute!(
+                            stdout,
+                            SetForegroundColor(Color::Red),
+                            Print("\n[ERROR] Usage: !note <finding-id> <text>\n"),
+                            ResetColor
+                        )?;
+                        return Ok::<(), anyhow::Error>(());
+                    }
+                    
+                    let noted = {
+                        let mut findings = finding_store.lock().unwrap();
+                        findings.get_mut(finding_id).map(|finding| {
+                            finding.notes.push(FindingNote {
+                                timestamp: chrono::Utc::now(),
+                                text: text.to_string(),
+                            });
+                            finding.clone()
+                        })
+                    };
+                    
+                    match noted {
+                        Some(finding) => {
+                            write_finding_file(&finding)?;
+                            if let Some(events) = &finding_events {
+                                events.emit(FindingEventKind::Updated, &finding);
+                            }
+                            if let Some(evidence) = &evidence_repo {
+                                evidence.commit(format!("Add note to {}", finding.id));
+                            }
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Green),
+                                Print(format!("\n[Hacksor] Added note to {} ({} notes)\n", finding.id, finding.notes.len())),
+                                ResetColor
+                            )?;
+                        },
+                        None => execute!(
+                            stdout,
+                            SetForegroundColor(Color::Red),
+                            Print(format!("\n[ERROR] Finding not found: {}. Usage: !note <finding-id> <text>\n", finding_id)),
+                            ResetColor
+                        )?,
+                    }
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // Copy exploit candidates attached to a finding into the session
+                if user_input.to_lowercase().starts_with("!mirror") {
+                    let mut args = user_input["!mirror".len()..].split_whitespace();
+                    let finding_id = args.next().unwrap_or("");
+                    let edb_ids: Vec<&str> = args.map(|id| id.trim_start_matches("EDB-")).collect();
+                    let Some(finding) = finding_store.lock().unwrap().get(finding_id).cloned() else {
+                        execute!(
+                            stdout,
+                            SetForegroundColor(Color::Red),
+                            Print(format!("\n[ERROR] Finding not found: {}. Usage: !mirror <finding-id> [edb-id...]\n", finding_id)),
+                            ResetColor
+                        )?;
+                        return Ok::<(), anyhow::Error>(());
+                    };
+                    
+                    // Without IDs, list the candidates to choose from
+                    if edb_ids.is_empty() {
+                        let listing = if finding.exploits.is_empty() {
+                            format!("{} has no exploit candidates", finding.id)
+                        } else {
+                            let exploits: Vec<String> = finding.exploits.iter()
+                                .map(|exploit| match &exploit.mirrored {
+                                    Some(path) => format!("{} -> {}", exploit.summary(), path.display()),
+                                    None => exploit.summary(),
+                                })
+                                .collect();
+                            format!("Exploit candidates for {}:\n  {}\nUsage: !mirror {} <edb-id>...", finding.id, exploits.join("\n  "), finding.id)
+                        };
+                        execute!(
+                            stdout,
+                            SetForegroundColor(Color::Cyan),
+                            Print(format!("\n[Hacksor] {}\n", listing)),
+                            ResetColor
+                        )?;
+                        return Ok::<(), anyhow::Error>(());
+                    }
+                    
+                    let (selected, unknown): (Vec<&str>, Vec<&str>) = edb_ids.into_iter()
+                        .partition(|id| finding.exploits.iter().any(|exploit| exploit.edb_id == *id));
+                    if !unknown.is_empty() {
+                        execute!(
+                            stdout,
+                            SetForegroundColor(Color::Red),
+                            Print(format!("\n[ERROR] Not an exploit candidate of {}: {}\n", finding.id, unknown.join(", "))),
+                            ResetColor
+                        )?;
+                        return Ok::<(), anyhow::Error>(());
+                    }
+                    
+                    let exploits: Vec<_> = finding.exploits.iter()
+                        .filter(|exploit| selected.contains(&exploit.edb_id.as_str()))
+                        .cloned()
+                        .collect();
+                    match searchsploit::mirror(&exploits, &work_dir.join(EXPLOITS_DIR)).await {
+                        Ok(mirrored) => {
+                            let updated = finding_store.lock().unwrap().get_mut(&finding.id).map(|finding| {
+                                for (edb_id, path) in &mirrored {
+                                    if let Some(exploit) = finding.exploits.iter_mut().find(|exploit| &exploit.edb_id == edb_id) {
+                                        exploit.mirrored = Some(path.clone());
+                                    }
+                                }
+                                finding.clone()
+                            });
+                            if let Some(finding) = updated {
+                                write_finding_file(&finding)?;
+                                if let Some(events) = &finding_events {
+                                    events.emit(FindingEventKind::Updated, &finding);
+                                }
+                            }
+                            if let Some(evidence) = &evidence_repo {
+                                evidence.commit(format!("Mirror {} exploits for {}", mirrored.len(), finding.id));
+                            }
+                            let paths: Vec<String> = mirrored.iter()
+                                .map(|(edb_id, path)| format!("EDB-{} -> {}", edb_id, path.display()))
+                                .collect();
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Green),
+                                Print(format!("\n[Hacksor] Mirrored {} exploits for {}:\n  {}\n", mirrored.len(), finding.id, paths.join("\n  "))),
+                                ResetColor
+                            )?;
+                        },
+                        Err(e) => execute!(
+                            stdout,
+                            SetForegroundColor(Color::Red),
+                            Print(format!("\n[ERROR] Failed to mirror exploits: {:#}\n", e)),
+                            ResetColor
+                        )?,
+                    }
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // Create Jira issues from documented findings
+                if user_input.to_lowercase().starts_with("!push jira") {
+                    let args: Vec<&str> = user_input["!push jira".len()..].split_whitespace().collect();
+                    
+                    let client = match config.jira.clone().map(JiraClient::new) {
+                        Some(Ok(client)) => client,
+                        Some(Err(e)) => {
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Red),
+                                Print(format!("\n[ERROR] {:#}\n", e)),
+                                ResetColor
+                            )?;
+                            return Ok::<(), anyhow::Error>(());
+                        },
+                        None => {
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Red),
+                                Print("\n[ERROR] Jira is not configured. Add a [jira] section to config.toml.\n"),
+                                ResetColor
+                            )?;
+                            return Ok::<(), anyhow::Error>(());
+                        },
+                    };
+                    
+                    // Bulk mode pushes every finding that isn't a false positive or already in Jira
+                    let findings: Vec<_> = {
+                        let store = finding_store.lock().unwrap();
+                        if args.first() == Some(&"--all") {
+                            store.values()
+                                .filter(|finding| finding.status != FindingStatus::FalsePositive)
+                                .filter(|finding| !finding.external_refs.contains_key(JIRA_REF))
+                                .cloned()
+                                .collect()
+                        } else {
+                            args.iter().filter_map(|id| store.get(*id).cloned()).collect()
+                        }
+                    };
+                    
+                    if findings.is_empty() {
+                        execute!(
+                            stdout,
+                            SetForegroundColor(Color::Yellow),
+                            Print("\n[Hacksor] No findings to push. Usage: !push jira <finding-id>... | !push jira --all\n"),
+                            ResetColor
+                        )?;
+                        return Ok::<(), anyhow::Error>(());
+                    }
+                    
+                    for mut finding in findings {
+                        if let Some(issue) = finding.external_refs.get(JIRA_REF) {
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Yellow),
+                                Print(format!("\n[Hacksor] {} is already in Jira as {}\n", finding.id, issue)),
+                                ResetColor
+                            )?;
+                            continue;
+                        }
+                        
+                        match client.create_issue(&finding).await {
+                            Ok(issue) => {
+                                finding.external_refs.insert(JIRA_REF.to_string(), issue.clone());
+                                if let Some(stored) = finding_store.lock().unwrap().get_mut(&finding.id) {
+                                    stored.external_refs.insert(JIRA_REF.to_string(), issue.clone());
+                                }
+                                write_finding_file(&finding)?;
+                                if let Some(events) = &finding_events {
+                                    events.emit(FindingEventKind::Updated, &finding);
+                                }
+                                if let Some(evidence) = &evidence_repo {
+                                    evidence.commit(format!("Link {} to Jira issue {}", finding.id, issue));
+                                }
+                                execute!(
+                                    stdout,
+                                    SetForegroundColor(Color::Green),
+                                    Print(format!("\n[Hacksor] Created {} for {}: {}\n", issue, finding.id, finding.title)),
+                                    ResetColor
+                                )?;
+                            },
+                            Err(e) => execute!(
+                                stdout,
+                                SetForegroundColor(Color::Red),
+                                Print(format!("\n[ERROR] {:#}\n", e)),
+                                ResetColor
+                            )?,
+                        }
+                    }
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // Compare this session (or another) with a baseline session for retest reports
+                if user_input.to_lowercase().starts_with("!diff") {
+                    let args: Vec<&str> = user_input["!diff".len()..].split_whitespace().collect();
+                    let target = args.windows(2)
+                        .find(|pair| pair[0] == "--target")
+                        .map(|pair| pair[1]);
+                    let sessions: Vec<&str> = args.iter()
+                        .enumerate()
+                        .filter(|(i, arg)| **arg != "--target" && (*i == 0 || args[i - 1] != "--target"))
+                        .map(|(_, arg)| *arg)
+                        .collect();
+                    
+                    let Some(baseline) = sessions.first() else {
+                        execute!(
+                            stdout,
+                            SetForegroundColor(Color::Red),
+                            Print("\n[ERROR] Usage: !diff <baseline-session> [retest-session] [--target <host>]\n"),
+                            ResetColor
+                        )?;
+                        return Ok::<(), anyhow::Error>(());
+                    };
+                    let sessions_dir = work_dir.parent().unwrap_or(&work_dir);
+                    let retest = sessions.get(1).copied().unwrap_or(session.as_str());
+                    
+                    // The running session has no snapshot yet, so use its live findings
+                    let loaded = load_findings(&sessions_dir.join(baseline)).and_then(|baseline_findings| {
+                        let retest_findings = if retest == session {
+                            finding_store.lock().unwrap().values().cloned().collect()
+                        } else {
+                            load_findings(&sessions_dir.join(retest))?
+                        };
+                        Ok((baseline_findings, retest_findings))
+                    });
+                    let (baseline_findings, retest_findings) = match loaded {
+                        Ok(findings) => findings,
+                        Err(e) => {
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Red),
+                                Print(format!("\n[ERROR] {:#}\n", e)),
+                                ResetColor
+                            )?;
+                            return Ok::<(), anyhow::Error>(());
+                        },
+                    };
+                    
+                    let diff = SessionDiff::compare(&baseline_findings, &retest_findings, target);
+                    let content = ReportTemplates::load(&dirs.config, &config.report)?.render_diff(baseline, retest, target, &diff)?;
+                    let reports_dir = work_dir.join("reports");
+                    std::fs::create_dir_all(&reports_dir)?;
+                    let output_file = reports_dir.join(format!("diff_{}_{}.md", baseline, retest));
+                    std::fs::write(&output_file, content)?;
+                    if let Some(evidence) = &evidence_repo {
+                        evidence.commit(format!("Generate retest report against {}", baseline));
+                    }
+                    
+                    execute!(
+                        stdout,
+                        SetForegroundColor(Color::Green),
+                        Print(format!(
+                            "\n[Hacksor] {} vs {}: {} new, {} fixed, {} persisting. Report saved to {}\n",
+                            baseline,
+                            retest,
+                            diff.new.len(),
+                            diff.fixed.len(),
+                            diff.persisting.len(),
+                            output_file.display()
+                        )),
+                        ResetColor
+                    )?;
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // Export findings for other tools: JSON, SARIF or a DefectDojo import
+                if user_input.to_lowercase().starts_with("!export") {
+                    let args: Vec<&str> = user_input["!export".len()..].split_whitespace().collect();
+                    let (writer, extension): (export::Exporter, &str) =
+                        match args.first().map(|format| format.to_lowercase()).as_deref() {
+                            Some("json") => (export::write_json, "json"),
+                            Some("sarif") => (export::write_sarif, "sarif"),
+                            Some("defectdojo") => (export::write_defectdojo, "defectdojo.json"),
+                            Some("csv") => (export::write_csv, "csv"),
+                            _ => {
+                                execute!(
+                                    stdout,
+                                    SetForegroundColor(Color::Red),
+                                    Print("\n[ERROR] Usage: !export <json|sarif|defectdojo|csv> [file]\n"),
+                                    ResetColor
+                                )?;
+                                return Ok::<(), anyhow::Error>(());
+                            },
+                        };
+                    
+                    let output_file = match args.get(1) {
+                        Some(file) => PathBuf::from(file),
+                        None => {
+                            let reports_dir = work_dir.join("reports");
+                            std::fs::create_dir_all(&reports_dir)?;
+                            reports_dir.join(format!("findings.{}", extension))
+                        },
+                    };
+                    
+                    let findings = reported_findings(&finding_store, false, None);
+                    match writer(&findings, &output_file) {
+                        Ok(()) => {
+                            if let Some(evidence) = &evidence_repo {
+                                evidence.commit(format!("Export findings to {}", output_file.display()));
+                            }
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Green),
+                                Print(format!("\n[Hacksor] Exported {} findings to {}\n", findings.len(), output_file.display())),
+                                ResetColor
+                            )?;
+                        },
+                        Err(e) => execute!(
+                            stdout,
+                            SetForegroundColor(Color::Red),
+                            Print(format!("\n[ERROR] {:#}\n", e)),
+                            ResetColor
+                        )?,
+                    }
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // Generate a report of the session's findings on demand
+                if user_input.to_lowercase().starts_with("!report") {
+                    let args: Vec<&str> = user_input["!report".len()..].split_whitespace().collect();
+                    let format = args.first().map(|format| format.to_lowercase()).unwrap_or_else(|| "md".to_string());
+                    let file_name = match format.as_str() {
+                        "md" => "summary.md",
+                        "html" => "report.html",
+                        "pdf" => "report.pdf",
+                        "raw" => "raw_findings.md",
+                        _ => {
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Red),
+                                Print("\n[ERROR] Usage: !report [md|html|pdf|raw] [file]\n"),
+                                ResetColor
+                            )?;
+                            return Ok::<(), anyhow::Error>(());
+                        },
+                    };
+                    
+                    let output_file = match args.get(1) {
+                        Some(file) => PathBuf::from(file),
+                        None => {
+                            let reports_dir = work_dir.join("reports");
+                            std::fs::create_dir_all(&reports_dir)?;
+                            reports_dir.join(file_name)
+                        },
+                    };
+                    
+                    let generated = match format.as_str() {
+                        "md" => report_generator.generate_summary_report(&output_file, false, None),
+                        "html" => report_generator.generate_html_report(&output_file, false, None),
+                        "pdf" => report_generator.generate_pdf_report(&output_file, &config.report.pdf_command, false, None).await,
+                        _ => terminal_mgr_clone.get_command_monitor().generate_findings_report(&output_file, false, None),
+                    };
+                    match generated {
+                        Ok(()) => execute!(
+                            stdout,
+                            SetForegroundColor(Color::Green),
+                            Print(format!("\n[Hacksor] Report written to {}\n", output_file.display())),
+                            ResetColor
+                        )?,
+                        Err(e) => execute!(
+                            stdout,
+                            SetForegroundColor(Color::Red),
+                            Print(format!("\n[ERROR] {:#}\n", e)),
+                            ResetColor
+                        )?,
+                    }
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // Show everything known about a host, or list the known hosts
+                if user_input.to_lowercase().starts_with("!asset") {
+                    let monitor = terminal_mgr_clone.get_command_monitor();
+                    let assets = monitor.get_asset_inventory();
+                    let host = user_input.split_whitespace().nth(1).unwrap_or("");
+                    
+                    let view = if host.is_empty() {
+                        let hosts = assets.hosts();
+                        if hosts.is_empty() {
+                            "No assets discovered yet.".to_string()
+                        } else {
+                            format!("Known hosts:\n{}", hosts.join("\n"))
+                        }
+                    } else {
+                        describe_asset(host, &assets.assets_for(host), &monitor, &finding_store)
+                    };
+                    
+                    execute!(
+                        stdout,
+                        SetForegroundColor(Color::Blue),
+                        Print(format!("\n[Hacksor] {}\n", view)),
+                        ResetColor
+                    )?;
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // Show the technology inventory, for a single target, or the targets running a technology
+                if user_input.to_lowercase().starts_with("!tech") {
+                    let inventory = terminal_mgr_clone.get_command_monitor().get_tech_inventory();
+                    let target = user_input["!tech".len()..].trim();
+                    
+                    let listing = if target.is_empty() {
+                        inventory.summary()
+                    } else if let Some(name) = target.strip_prefix("--using") {
+                        inventory.targets_using(name.trim()).join("\n")
+                    } else {
+                        inventory.technologies_for(target).iter()
+                            .map(|tech| format!(
+                                "{}{} (via {})",
+                                tech.name,
+                                tech.version.as_ref().map(|v| format!(" {}", v)).unwrap_or_default(),
+                                tech.source
+                            ))
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    };
+                    
+                    if listing.is_empty() {
+                        execute!(
+                            stdout,
+                            SetForegroundColor(Color::Blue),
+                            Print("\n[Hacksor] No technologies fingerprinted yet. Run whatweb, httpx -td or nmap -sV.\n"),
+                            ResetColor
+                        )?;
+                    } else {
+                        execute!(
+                            stdout,
+                            SetForegroundColor(Color::Blue),
+                            Print(format!("\n[Hacksor] Technology inventory:\n{}\n", listing)),
+                            ResetColor
+                        )?;
+                    }
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // Look a host up on a passive recon source without sending it anything
+                let passive_command = user_input.split_whitespace().next().unwrap_or("").to_lowercase();
+                if let Some(source) = PassiveSource::ALL.into_iter().find(|source| passive_command == format!("!{}", source.command())) {
+                    let target = match user_input[passive_command.len()..].trim() {
+                        "" => engine.get_current_session().map(|session| session.target.domain.clone()),
+                        target => Some(target.to_string()),
+                    };
+                    let scope = engine.get_current_session().map(|session| session.target.scope.clone()).unwrap_or_default();
+                    let Some(target) = target else {
+                        execute!(
+                            stdout,
+                            SetForegroundColor(Color::Yellow),
+                            Print(format!("\n[Hacksor] No target to look up. Usage: {} [target]\n", passive_command)),
+                            ResetColor
+                        )?;
+                        return Ok::<(), anyhow::Error>(());
+                    };
+                    
+                    if let Some(note) = passive_lookup(&config, source, &terminal_mgr_clone.get_command_monitor(), &target, &scope).await? {
+                        ai.add_user_message(&note);
+                    }
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // Resolve a host through the engagement's DNS servers, comparing their answers
+                if passive_command == "!resolve" {
+                    let monitor = terminal_mgr_clone.get_command_monitor();
+                    let Some(resolvers) = monitor.get_resolvers() else {
+                        execute!(
+                            stdout,
+                            SetForegroundColor(Color::Yellow),
+                            Print("\n[Hacksor] No resolvers are configured; add them to [dns] in the configuration.\n"),
+                            ResetColor
+                        )?;
+                        return Ok::<(), anyhow::Error>(());
+                    };
+                    let host = match user_input["!resolve".len()..].trim() {
+                        "" => engine.get_current_session().map(|session| session.target.domain.clone()),
+                        host => Some(host.to_string()),
+                    };
+                    let Some(host) = host else {
+                        execute!(
+                            stdout,
+                            SetForegroundColor(Color::Yellow),
+                            Print("\n[Hacksor] No host to resolve. Usage: !resolve [host]\n"),
+                            ResetColor
+                        )?;
+                        return Ok::<(), anyhow::Error>(());
+                    };
+                    
+                    match dns::lookup(&resolvers, &monitor, &host).await {
+                        Ok(summary) => {
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Cyan),
+                                Print(format!("\n[Hacksor Info] {}\n", summary)),
+                                ResetColor
+                            )?;
+                            ai.add_user_message(&format!("Resolved through the engagement's DNS servers: {}", summary));
+                        },
+                        Err(e) => {
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Red),
+                                Print(format!("\n[ERROR] Failed to resolve {}: {:#}\n", host, e)),
+                                ResetColor
+                            )?;
+                        },
+                    }
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // Merge an existing scanner's results into the session's findings
+                if user_input.to_lowercase().starts_with("!import") {
+                    let args: Vec<&str> = user_input["!import".len()..].split_whitespace().collect();
+                    let include_info = args.contains(&"--info");
+                    let args: Vec<&str> = args.into_iter().filter(|arg| *arg != "--info").collect();
+                    let [format, file] = args.as_slice() else {
+                        execute!(
+                            stdout,
+                            SetForegroundColor(Color::Red),
+                            Print("\n[ERROR] Usage: !import <nessus|gvm> <file> [--info]\n"),
+                            ResetColor
+                        )?;
+                        return Ok::<(), anyhow::Error>(());
+                    };
+                    
+                    // Hosts already imported from this file are skipped so re-running the import is harmless
+                    let monitor = terminal_mgr_clone.get_command_monitor();
+                    let path = Path::new(file);
+                    let source = path.file_name().map(|name| name.to_string_lossy().replace(char::is_whitespace, "_")).unwrap_or_default();
+                    let imported = match format.to_lowercase().as_str() {
+                        "nessus" => match NessusReport::load(path) {
+                            Ok(report) => nessus::import_report(&report, &source, &monitor, include_info).await
+                                .map(|(raised, hosts)| (format!("Nessus scan \"{}\"", report.name), raised, hosts)),
+                            Err(e) => Err(e),
+                        },
+                        "gvm" => match GvmReport::load(path) {
+                            Ok(report) => gvm::import_report(&report, &source, &monitor, include_info).await
+                                .map(|(raised, hosts)| (format!("OpenVAS report {}", report.id), raised, hosts)),
+                            Err(e) => Err(e),
+                        },
+                        _ => Err(anyhow::anyhow!("Unknown import format: {}. Supported: nessus, gvm", format)),
+                    };
+                    
+                    match imported {
+                        Ok((_, _, hosts)) if hosts.is_empty() => execute!(
+                            stdout,
+                            SetForegroundColor(Color::Yellow),
+                            Print(format!("\n[Hacksor] Every host in {} has already been imported\n", file)),
+                            ResetColor
+                        )?,
+                        Ok((scan, raised, hosts)) => {
+                            let summary = format!("Imported {} from {}: {} findings across {} hosts ({})", scan, file, raised, hosts.len(), hosts.join(", "));
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Green),
+                                Print(format!("\n[Hacksor] {}\n", summary)),
+                                ResetColor
+                            )?;
+                            
+                            // Let the AI plan manual verification of what the scanner reported
+                            ai.add_user_message(&scanner_import_note(&summary));
+                        },
+                        Err(e) => execute!(
+                            stdout,
+                            SetForegroundColor(Color::Red),
+                            Print(format!("\n[ERROR] {:#}\n", e)),
+                            ResetColor
+                        )?,
+                    }
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // Run an OpenVAS scan through gvmd, or pull in the results of one run elsewhere
+                if user_input.to_lowercase().starts_with("!gvm") {
+                    let args: Vec<&str> = user_input["!gvm".len()..].split_whitespace().collect();
+                    let client = match GmpClient::new(&config.gvm) {
+                        Ok(client) => client,
+                        Err(e) => {
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Red),
+                                Print(format!("\n[ERROR] {:#}\n", e)),
+                                ResetColor
+                            )?;
+                            return Ok::<(), anyhow::Error>(());
+                        },
+                    };
+                    
+                    match args.as_slice() {
+                        ["scan", rest @ ..] if rest.len() <= 1 => {
+                            let target = match rest.first() {
+                                Some(target) => Some(target.to_string()),
+                                None => engine.get_current_session().map(|session| session.target.domain.clone()),
+                            };
+                            let Some(target) = target else {
+                                execute!(
+                                    stdout,
+                                    SetForegroundColor(Color::Yellow),
+                                    Print("\n[Hacksor] No target to scan. Usage: !gvm scan [target]\n"),
+                                    ResetColor
+                                )?;
+                                return Ok::<(), anyhow::Error>(());
+                            };
+                            
+                            let monitor = terminal_mgr_clone.get_command_monitor();
+                            let scan = match client.start_scan(&target).await {
+                                Ok(scan) => scan,
+                                Err(e) => {
+                                    execute!(
+                                        stdout,
+                                        SetForegroundColor(Color::Red),
+                                        Print(format!("\n[ERROR] Failed to start OpenVAS scan: {:#}\n", e)),
+                                        ResetColor
+                                    )?;
+                                    return Ok::<(), anyhow::Error>(());
+                                },
+                            };
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Green),
+                                Print(format!("\n[Hacksor] Started OpenVAS task {} against {}; its results are imported when it finishes\n", scan.task_id, target)),
+                                ResetColor
+                            )?;
+                            
+                            // Poll the task in the background and import its report once it stops
+                            let gvm_tx = cmd_output_tx.clone();
+                            tokio::spawn(async move {
+                                let message = match import_gvm_scan(&client, &scan, &monitor).await {
+                                    Ok(summary) => format!("[INFO] {}. {}", summary, scanner_import_note("")),
+                                    Err(e) => format!("[ERROR] OpenVAS task {} failed: {:#}", scan.task_id, e),
+                                };
+                                let _ = gvm_tx.send(message).await;
+                            });
+                        },
+                        ["import", report_id] => {
+                            let monitor = terminal_mgr_clone.get_command_monitor();
+                            let imported = match client.report(report_id).await {
+                                Ok(report) => gvm::import_report(&report, report_id, &monitor, false).await,
+                                Err(e) => Err(e),
+                            };
+                            match imported {
+                                Ok((raised, hosts)) => {
+                                    let summary = format!("Imported OpenVAS report {}: {} findings across {} hosts", report_id, raised, hosts.len());
+                                    execute!(
+                                        stdout,
+                                        SetForegroundColor(Color::Green),
+                                        Print(format!("\n[Hacksor] {}\n", summary)),
+                                        ResetColor
+                                    )?;
+                                    if !hosts.is_empty() {
+                                        ai.add_user_message(&scanner_import_note(&summary));
+                                    }
+                                },
+                                Err(e) => execute!(
+                                    stdout,
+                                    SetForegroundColor(Color::Red),
+                                    Print(format!("\n[ERROR] Failed to import OpenVAS report {}: {:#}\n", report_id, e)),
+                                    ResetColor
+                                )?,
+                            }
+                        },
+                        _ => execute!(
+                            stdout,
+                            SetForegroundColor(Color::Red),
+                            Print("\n[ERROR] Usage: !gvm scan [target] | !gvm import <report-id>\n"),
+                            ResetColor
+                        )?,
+                    }
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // List follow-up actions and their status
+                if user_input.to_lowercase().starts_with("!actions") {
+                    let show_all = user_input["!actions".len()..].trim() == "--all";
+                    let actions: Vec<_> = terminal_mgr_clone.get_command_monitor().get_action_queue().actions()
+                        .into_iter()
+                        .filter(|action| show_all || !action.status.is_finished())
+                        .collect();
+                    
+                    if actions.is_empty() {
+                        execute!(
+                            stdout,
+                            SetForegroundColor(Color::Blue),
+                            Print(if show_all {
+                                "\n[Hacksor] No follow-up actions yet.\n"
+                            } else {
+                                "\n[Hacksor] No pending follow-up actions. Use !actions --all to include finished ones.\n"
+                            }),
+                            ResetColor
+                        )?;
+                    } else {
+                        let listing = actions.iter()
+                            .map(|action| format!(
+                                "[{:?}] ({:?}) {} {}{}{}",
+                                action.status,
+                                action.priority,
+                                &action.id[..8.min(action.id.len())],
+                                action.description,
+                                action.finding_id.as_ref().map(|id| format!(" ({})", id)).unwrap_or_default(),
+                                action.command.as_ref().map(|cmd| format!("\n    $ {}", cmd)).unwrap_or_default()
+                            ))
+                            .collect::<Vec<_>>()
+                            .join("\n");
+                        let schedule = match &testing_window {
+                            Some(window) if actions.iter().any(|action| action.status == terminal::ActionStatus::Scheduled) => format!(
+                                "Scheduled actions start when the testing window ({}) next opens: {}\n",
+                                window,
+                                window.next_open(chrono::Local::now()).format("%a %Y-%m-%d %H:%M")
+                            ),
+                            _ => String::new(),
+                        };
+                        execute!(
+                            stdout,
+                            SetForegroundColor(Color::Blue),
+                            Print(format!("\n[Hacksor] Follow-up actions:\n{}\n{}", listing, schedule)),
+                            ResetColor
+                        )?;
+                    }
+                    
+                    return Ok::<(), anyhow::Error>(());
                 }
-            }
-        }
-    });
-    
-    // Start conversation loop
-    let mut stdout = io::stdout();
-    let mut conversation_active = true;
-    
-    // Get initial response from AI to start the conversation
-    match ai.get_response().await {
-        Ok(response) => {
-            execute!(
-                stdout,
-                SetForegroundColor(Color::Green),
-                Print(format!("[Hacksor] {}\n", response)),
-                ResetColor
-            )?;
-            
-            // Add feature hint for users
-            execute!(
-                stdout,
-                SetForegroundColor(Color::Cyan),
-                Print("\n[Hacksor Info] I now analyze target profiles (like universities, .edu domains) and automatically use less aggressive scanning by default for prestigious targets. I'll progressively increase scan intensity when you request deeper analysis.\n\n"),
-                ResetColor
-            )?;
-        },
-        Err(e) => {
-            execute!(
-                stdout,
-                SetForegroundColor(Color::Red),
-                Print(format!("\n[ERROR] Failed to get AI response: {}\n", e)),
-                ResetColor
-            )?;
-            return Ok(());
-        }
-    }
-    
-    while conversation_active {
-        // This tokio::select will allow us to handle both user input and background output
-        tokio::select! {
-            // Handle command output from background tasks
-            Some(output) = cmd_output_rx.recv() => {
-                execute!(
-                    stdout,
-                    SetForegroundColor(Color::Blue),
-                    Print(format!("{}\n", output)),
-                    ResetColor
-                )?;
                 
-                // Add the terminal output to the AI context to make it aware of findings
-                if output.starts_with("[INFO]") || output.starts_with("[ACTION") || output.starts_with("[RESULT]") {
-                    ai.add_assistant_message(&format!("I observed the following in the terminal: {}", output));
-                    
-                    // Extract command results to help with future queries
-                    if output.starts_with("[RESULT]") {
-                        // Extract the command ID from previous output if available
-                        let mut cmd_id = None;
-                        let mut cmd_text = None;
-                        
-                        // Get the most recently executed command
-                        let all_commands = terminal_mgr.get_command_monitor().get_all_commands();
-                        if !all_commands.is_empty() {
-                            if let Some(latest_cmd) = all_commands.iter()
-                                .max_by_key(|cmd| cmd.start_time) {
-                                cmd_id = Some(latest_cmd.id.clone());
-                                cmd_text = Some(latest_cmd.command.clone());
-                            }
+                // Stop a follow-up action that is waiting or running
+                if user_input.to_lowercase().starts_with("!cancel-action") {
+                    let id = user_input["!cancel-action".len()..].trim();
+                    if id.is_empty() {
+                        execute!(
+                            stdout,
+                            SetForegroundColor(Color::Red),
+                            Print("\n[ERROR] Usage: !cancel-action <id> (the ID shown by !actions)\n"),
+                            ResetColor
+                        )?;
+                        return Ok::<(), anyhow::Error>(());
+                    }
+                    
+                    match action_canceller.cancel(id) {
+                        Ok(action) => {
+                            terminal_mgr_clone.get_command_monitor().get_timeline().record(
+                                ActivityKind::Decision,
+                                format!("Cancelled follow-up action: {}", action.description),
+                                action.finding_id.as_deref(),
+                            );
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Green),
+                                Print(format!("\n[Hacksor] Cancelled follow-up action: {}\n", action.description)),
+                                ResetColor
+                            )?;
+                        },
+                        Err(e) => {
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Red),
+                                Print(format!("\n[ERROR] {:#}\n", e)),
+                                ResetColor
+                            )?;
                         }
-                        
-                        // Store the command result
-                        if let (Some(cmd), Some(id)) = (cmd_text, cmd_id) {
-                            let result_text = output.trim_start_matches("[RESULT] ").to_string();
-                            ai.add_command_result(&cmd, &result_text);
-                            
-                            // Also update the command summary
-                            let _ = terminal_mgr.get_command_monitor().update_command_summary(&id, &result_text);
+                    }
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // Re-read the configuration file and apply what can change mid-engagement
+                if user_input.to_lowercase() == "!reload-config" {
+                    let monitor = terminal_mgr_clone.get_command_monitor();
+                    match reload_config(&config_path, &dirs.config, &monitor) {
+                        Ok((config, policy)) => {
+                            safety_policy = policy;
+                            ai.set_model(&config.model);
+                            approval_gate.set_enabled(config.confirm_all || cli.confirm_all);
+                            approval_gate.set_review_actions(config.actions.review || cli.review_actions);
+                            monitor.get_timeline().record(
+                                ActivityKind::Decision,
+                                format!("Reloaded configuration {}", config_path.display()),
+                                None,
+                            );
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Green),
+                                Print(format!("\n[Hacksor] Reloaded {}: tools, rate limits, proxy, resolvers, command policy, safety policy, approvals and model ({}) are updated. Other settings apply after a restart.\n",
+                                    config_path.display(), config.model)),
+                                ResetColor
+                            )?;
+                        },
+                        Err(e) => {
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Red),
+                                Print(format!("\n[ERROR] Kept the current settings: {:#}\n", e)),
+                                ResetColor
+                            )?;
+                        }
+                    }
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // Manage engagement-specific triggers: text in output => finding
+                if user_input.to_lowercase().starts_with("!trigger") {
+                    let triggers = terminal_mgr_clone.get_command_monitor().get_triggers();
+                    let args = user_input["!trigger".len()..].trim();
+                    let usage = "Usage: !trigger add <severity> <text> => <title> | !trigger list | !trigger remove <name>";
+                    
+                    let result: Result<String> = if let Some(spec) = args.strip_prefix("add ") {
+                        let spec = spec.trim();
+                        let (severity, rest) = spec.split_once(char::is_whitespace).unwrap_or((spec, ""));
+                        match (FindingSeverity::parse(severity), rest.split_once("=>")) {
+                            (Some(severity), Some((text, title))) if !text.trim().is_empty() && !title.trim().is_empty() => {
+                                triggers.add(text.trim(), severity, title.trim())
+                                    .map(|name| format!("Added {}: output containing \"{}\" now raises \"{}\"", name, text.trim(), title.trim()))
+                            },
+                            _ => Err(anyhow::anyhow!(usage)),
+                        }
+                    } else if let Some(name) = args.strip_prefix("remove ") {
+                        triggers.remove(name.trim()).map(|_| format!("Removed {}", name.trim()))
+                    } else if args.is_empty() || args == "list" {
+                        let rules = triggers.rules();
+                        if rules.is_empty() {
+                            Ok(format!("No triggers defined. {}", usage))
+                        } else {
+                            Ok(rules.iter()
+                                .map(|trigger| format!(
+                                    "{}: {:?} \"{}\" => {}",
+                                    trigger.rule.name,
+                                    trigger.rule.severity,
+                                    trigger.rule.description.as_deref().unwrap_or(&trigger.rule.pattern),
+                                    trigger.rule.title
+                                ))
+                                .collect::<Vec<_>>()
+                                .join("\n"))
                         }
+                    } else {
+                        Err(anyhow::anyhow!(usage))
+                    };
+                    
+                    match result {
+                        Ok(message) => execute!(
+                            stdout,
+                            SetForegroundColor(Color::Blue),
+                            Print(format!("\n[Hacksor] {}\n", message)),
+                            ResetColor
+                        )?,
+                        Err(e) => execute!(
+                            stdout,
+                            SetForegroundColor(Color::Red),
+                            Print(format!("\n[ERROR] {}\n", e)),
+                            ResetColor
+                        )?,
                     }
+                    
+                    return Ok::<(), anyhow::Error>(());
                 }
                 
-                // Check if there are more messages in the queue
-                // If not, show the prompt
-                if cmd_output_rx.try_recv().is_err() {
-                    print!("> ");
-                    stdout.flush()?;
+                // Search the session's command output and finding evidence
+                if user_input.to_lowercase().starts_with("!grep") {
+                    let pattern = user_input["!grep".len()..].trim();
+                    let pattern = match regex::Regex::new(pattern) {
+                        Ok(regex) if !pattern.is_empty() => regex,
+                        Ok(_) => {
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Red),
+                                Print("\n[ERROR] Usage: !grep <pattern>\n"),
+                                ResetColor
+                            )?;
+                            return Ok::<(), anyhow::Error>(());
+                        },
+                        Err(e) => {
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Red),
+                                Print(format!("\n[ERROR] Invalid pattern: {}\n", e)),
+                                ResetColor
+                            )?;
+                            return Ok::<(), anyhow::Error>(());
+                        },
+                    };
+                    
+                    let matches = grep_session(&pattern, &terminal_mgr_clone.get_command_monitor().get_all_commands(), &finding_store);
+                    pager::page(&format!("\n[Hacksor] {}", matches), Color::Blue)?;
+                    
+                    return Ok::<(), anyhow::Error>(());
                 }
-            }
-            
-            // Handle user input
-            _ = async {
-                // Get user input
-                print!("> ");
-                stdout.flush()?;
-                let mut user_input = String::new();
-                io::stdin().read_line(&mut user_input)?;
                 
-                let user_input = user_input.trim();
+                // List the most recent commands with their IDs, durations and outcomes
+                if user_input.to_lowercase().starts_with("!history") {
+                    let arg = user_input["!history".len()..].trim();
+                    let count = if arg.is_empty() { Ok(HISTORY_LENGTH) } else { arg.parse::<usize>() };
+                    match count {
+                        Ok(count) if count > 0 => execute!(
+                            stdout,
+                            SetForegroundColor(Color::Blue),
+                            Print(format!("\n[Hacksor] {}\n", list_history(&terminal_mgr_clone.get_command_monitor().get_all_commands(), count))),
+                            ResetColor
+                        )?,
+                        _ => execute!(
+                            stdout,
+                            SetForegroundColor(Color::Red),
+                            Print("\n[ERROR] Usage: !history [n]\n"),
+                            ResetColor
+                        )?,
+                    }
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
                 
-                // Clone ai and terminal_mgr for use in this async block
-                let mut ai_clone = ai.clone();
-                let terminal_mgr_clone = terminal_mgr.clone();
+                // Start the conversation over without touching running commands
+                if user_input.to_lowercase().starts_with("!clear") {
+                    let keep_findings = match user_input["!clear".len()..].trim() {
+                        "" => false,
+                        "--keep-findings" => true,
+                        _ => {
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Red),
+                                Print("\n[ERROR] Usage: !clear [--keep-findings]\n"),
+                                ResetColor
+                            )?;
+                            return Ok::<(), anyhow::Error>(());
+                        },
+                    };
+                    
+                    ai.clear_conversation();
+                    // The target and scope are the engagement, not the conversation, so they stay
+                    if let Some(brief) = session_brief(&engine, &scope) {
+                        ai.add_user_message(&brief);
+                    }
+                    let kept = match findings_brief(&finding_store).filter(|_| keep_findings) {
+                        Some(brief) => {
+                            ai.add_user_message(&brief);
+                            " Documented findings were kept."
+                        },
+                        None => "",
+                    };
+                    
+                    execute!(
+                        stdout,
+                        SetForegroundColor(Color::Green),
+                        Print(format!("\n[Hacksor] Conversation cleared.{} Running commands are unaffected.\n", kept)),
+                        ResetColor
+                    )?;
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
                 
-                // Check for exit command
-                if user_input.to_lowercase() == "exit" || user_input.to_lowercase() == "quit" {
+                // List the sessions, marking the current one
+                if user_input.to_lowercase() == "!sessions" {
                     execute!(
                         stdout,
-                        SetForegroundColor(Color::Yellow),
-                        Print("\n[Hacksor] Session terminated. Goodbye!\n"),
+                        SetForegroundColor(Color::Blue),
+                        Print(format!("\n[Hacksor] {}\n", list_sessions(&engine))),
                         ResetColor
                     )?;
-                    conversation_active = false;
+                    
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
+                // Start a session against a new target, or go back to an earlier one
+                if user_input.to_lowercase().starts_with("!session ") {
+                    let args: Vec<&str> = user_input["!session".len()..].split_whitespace().collect();
+                    let switched = match (args.first().map(|action| action.to_lowercase()).as_deref(), args.get(1)) {
+                        (Some("new"), Some(target)) if args.len() == 2 => engine.start_session(Target {
+                            domain: target.to_string(),
+                            scope: Vec::new(),
+                            excluded: Vec::new(),
+                        }).await,
+                        // A target stands for its latest session
+                        (Some("switch"), Some(wanted)) if args.len() == 2 => {
+                            let session_id = engine.find_session(wanted)
+                                .map(|session| session.session_id.clone())
+                                .unwrap_or_else(|| wanted.to_string());
+                            engine.switch_session(&session_id)
+                        },
+                        _ => {
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Red),
+                                Print("\n[ERROR] Usage: !session new <target> | !session switch <session-id|target>\n"),
+                                ResetColor
+                            )?;
+                            return Ok::<(), anyhow::Error>(());
+                        },
+                    };
+                    
+                    match switched {
+                        Ok(session) => {
+                            // Point the AI at the new target
+                            if let Some(brief) = engagement_brief(Some(&session.target.domain), &session.target.scope) {
+                                ai.add_user_message(&format!("We are now working on a different session. {}", brief));
+                            }
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Green),
+                                Print(format!("\n[Hacksor] Session {} is now current, targeting {}\n", session.session_id, session.target.domain)),
+                                ResetColor
+                            )?;
+                        },
+                        Err(e) => execute!(
+                            stdout,
+                            SetForegroundColor(Color::Red),
+                            Print(format!("\n[ERROR] {:#}\n", e)),
+                            ResetColor
+                        )?,
+                    }
+                    
                     return Ok::<(), anyhow::Error>(());
                 }
                 
@@ -349,10 +2427,27 @@ async fn main() -> Result<()> {
                 if user_input.to_lowercase().starts_with("!exec") {
                     let command = user_input.trim_start_matches("!exec").trim();
                     
+                    // --force re-runs a command even if it ran recently
+                    let force = command.starts_with("--force");
+                    let command = command.trim_start_matches("--force").trim();
+                    
                     // Check if the command would be modified based on target safety
-                    let safe_command = apply_target_based_safety(&[command.to_string()])[0].clone();
+                    let safe_command = safety_policy.apply(command);
                     let cmd_modified = command != safe_command;
                     
+                    if !force {
+                        if let Some(previous) = find_duplicate(&terminal_mgr_clone.get_command_monitor(), &safe_command) {
+                            let cached = describe_cached_result(&previous);
+                            execute!(
+                                stdout,
+                                SetForegroundColor(Color::Cyan),
+                                Print(format!("\n{}\n[Hacksor] Use !exec --force {} to run it again.\n", cached, safe_command)),
+                                ResetColor
+                            )?;
+                            return Ok::<(), anyhow::Error>(());
+                        }
+                    }
+                    
                     execute!(
                         stdout,
                         SetForegroundColor(Color::Yellow),
@@ -365,7 +2460,7 @@ async fn main() -> Result<()> {
                         execute!(
                             stdout,
                             SetForegroundColor(Color::Cyan),
-                            Print(format!("[Hacksor] Target appears prestigious - using safer command: {}\n", safe_command)),
+                            Print(format!("[Hacksor] Target safety policy applies - using safer command: {}\n", safe_command)),
                             ResetColor
                         )?;
                     }
@@ -374,6 +2469,7 @@ async fn main() -> Result<()> {
                     let safe_command_clone = safe_command.clone();
                     
                     // Execute in a separate task and await completion
+                    let policy_tx = cmd_output_tx.clone();
                     tokio::spawn(async move {
                         match terminal_mgr_clone.execute_monitored_command(&safe_command_clone, CommandType::Generic).await {
                             Ok(cmd_id) => {
@@ -437,6 +2533,7 @@ async fn main() -> Result<()> {
                                     Print(format!("[ERROR] Failed to execute command: {}\n", e)),
                                     ResetColor
                                 );
+                                report_policy_refusal(&policy_tx, &e).await;
                                 
                                 // Print the prompt
                                 let _ = execute!(
@@ -453,8 +2550,23 @@ async fn main() -> Result<()> {
                     return Ok::<(), anyhow::Error>(());
                 } 
                 
+                // A ! command no handler took is mistyped rather than meant for the AI
+                if user_input.starts_with('!') {
+                    let message = match help::find(user_input) {
+                        Some(command) => format!("Usage: {}", help::usage(command)),
+                        None => help::help(user_input.split_whitespace().next().unwrap_or(user_input)),
+                    };
+                    execute!(
+                        stdout,
+                        SetForegroundColor(Color::Red),
+                        Print(format!("\n[ERROR] {}\n", message)),
+                        ResetColor
+                    )?;
+                    return Ok::<(), anyhow::Error>(());
+                }
+                
                 // First, analyze the user message for security testing intent
-                if let Some((command_name, params)) = ai_clone.analyze_user_intent(user_input) {
+                if let Some((command_name, params)) = ai.analyze_user_intent(user_input) {
                     // We detected an intent that maps to a specific security command
                     execute!(
                         stdout,
@@ -474,9 +2586,23 @@ async fn main() -> Result<()> {
                         })
                         .unwrap_or_else(|| format!("{} {:?}", command_name, params));
                     
+                    // Reuse recent results instead of scanning the same target again
+                    if let Some(previous) = find_duplicate(&terminal_mgr_clone.get_command_monitor(), &cmd) {
+                        let cached = describe_cached_result(&previous);
+                        execute!(
+                            stdout,
+                            SetForegroundColor(Color::Cyan),
+                            Print(format!("{}\n[Hacksor] Use !exec --force {} to run it again.\n", cached, cmd)),
+                            ResetColor
+                        )?;
+                        ai.add_assistant_message(&cached);
+                        return Ok::<(), anyhow::Error>(());
+                    }
+                    
                     // Execute the command in a background task and wait for results
                     let cmd_clone = cmd.clone();
                     
+                    let policy_tx = cmd_output_tx.clone();
                     tokio::spawn(async move {
                         // Determine command type
                         let cmd_type = determine_command_type(&cmd_clone);
@@ -544,6 +2670,7 @@ async fn main() -> Result<()> {
                                     Print(format!("[ERROR] Failed to execute command: {}\n", e)),
                                     ResetColor
                                 );
+                                report_policy_refusal(&policy_tx, &e).await;
                                 
                                 // Print the prompt
                                 let _ = execute!(
@@ -557,17 +2684,17 @@ async fn main() -> Result<()> {
                     });
                     
                     // Add the command execution to AI context
-                    ai_clone.add_assistant_message(&format!("I'm running the command: {} and will monitor the results.", cmd));
+                    ai.add_assistant_message(&format!("I'm running the command: {} and will monitor the results.", cmd));
                     
                     // Don't show the prompt right away
                     return Ok::<(), anyhow::Error>(());
                 }
                 
                 // Add user message to conversation
-                ai_clone.add_user_message(user_input);
+                ai.add_user_message(user_input);
                 
                 // Check if user is asking about previous command results
-                if ai_clone.is_asking_about_results(user_input) {
+                if ai.is_asking_about_results(user_input) {
                     // Prepare a response about the most recent command results
                     let mut result_response = String::from("Based on the previous commands, ");
                     
@@ -628,32 +2755,31 @@ async fn main() -> Result<()> {
                     }
                     
                     // Display the response about results
-                    execute!(
-                        stdout,
-                        SetForegroundColor(Color::Green),
-                        Print(format!("[Hacksor] {}\n", result_response)),
-                        ResetColor
-                    )?;
+                    pager::page(&format!("[Hacksor] {}", result_response), Color::Green)?;
                     
                     // Add this explanation to AI context
-                    ai_clone.add_assistant_message(&result_response);
+                    ai.add_assistant_message(&result_response);
                     
                     return Ok::<(), anyhow::Error>(());
                 }
                 
+                // Give the AI the fingerprinted stack so it can pick relevant follow-up checks
+                let inventory = terminal_mgr_clone.get_command_monitor().get_tech_inventory();
+                if !inventory.is_empty() {
+                    ai.add_assistant_message(&format!(
+                        "Technology stack fingerprinted so far:\n{}",
+                        inventory.summary()
+                    ));
+                }
+                
                 // Get AI response
-                match ai_clone.get_response().await {
+                match ai.get_response().await {
                     Ok(response) => {
                         // Process AI response to extract commands
-                        let (display_response, commands) = process_response(&response);
+                        let (display_response, commands) = process_response(&response, &safety_policy);
                         
-                        // Display the response
-                        execute!(
-                            stdout,
-                            SetForegroundColor(Color::Green),
-                            Print(format!("[Hacksor] {}\n", display_response)),
-                            ResetColor
-                        )?;
+                        // Display the response, paged if it is longer than the screen
+                        pager::page(&format!("[Hacksor] {}", display_response), Color::Green)?;
                         
                         // Execute commands sequentially (not all at once)
                         if !commands.is_empty() {
@@ -672,8 +2798,23 @@ async fn main() -> Result<()> {
                             let context_clone = command_execution_context.clone();
                             
                             // Spawn a background task to execute commands sequentially
+                            let policy_tx = cmd_output_tx.clone();
+                            let mut ai_clone = ai.clone();
                             tokio::spawn(async move {
                                 for (i, cmd) in commands.iter().enumerate() {
+                                    // Skip commands that already ran recently and surface their results
+                                    if let Some(previous) = find_duplicate(&terminal_mgr_clone.get_command_monitor(), cmd) {
+                                        let cached = describe_cached_result(&previous);
+                                        let _ = execute!(
+                                            io::stdout(),
+                                            SetForegroundColor(Color::Cyan),
+                                            Print(format!("{}\n[Hacksor] Use !exec --force {} to run it again.\n", cached, cmd)),
+                                            ResetColor
+                                        );
+                                        ai_clone.add_assistant_message(&cached);
+                                        continue;
+                                    }
+                                    
                                     // Notify that we're starting this command
                                     let _ = execute!(
                                         io::stdout(),
@@ -742,6 +2883,8 @@ async fn main() -> Result<()> {
                                                 ResetColor
                                             );
                                             
+                                            report_policy_refusal(&policy_tx, &e).await;
+                                            
                                             // Add the error to the AI context
                                             ai_clone.add_assistant_message(&format!(
                                                 "I tried to execute command: {} but encountered error: {}", 
@@ -837,15 +2980,773 @@ async fn main() -> Result<()> {
                 }
                 
                 Ok::<(), anyhow::Error>(())
-            } => {}
+            };
+            tokio::pin!(handling);
+            
+            loop {
+                tokio::select! {
+                    _ = &mut handling => break,
+                    Some(output) = cmd_output_rx.recv() => {
+                        printer.print(output.as_str().blue().to_string())?;
+                        observed.push(output);
+                    }
+                    Some(output) = unseen_output_rx.recv() => observed.push(output),
+                }
+            }
+        }
+        for output in observed {
+            observe_output(&mut ai, &terminal_mgr.get_command_monitor(), &output);
+        }
+    }
+
+    Ok(())
+}
+
+/// Re-read the configuration and apply the settings that can change mid-engagement
+fn reload_config(config_path: &Path, config_dir: &Path, monitor: &CommandMonitor) -> Result<(Config, SafetyPolicy)> {
+    let config = Config::load(&config_path.to_path_buf())
+        .context(format!("Failed to load configuration {}", config_path.display()))?;
+    
+    // Build everything before applying anything so a mistake leaves the running settings alone
+    let command_policy = CommandPolicy::from_config(&config.command_policy)?;
+    let safety_policy = SafetyPolicy::load_or_create(&config_dir.join(&config.safety_policy))?;
+    let resolvers = config.dns.as_ref()
+        .map(|dns| DnsResolvers::new(dns, monitor.get_work_dir()))
+        .transpose()?;
+    
+    monitor.set_tools(config.tools.clone());
+    monitor.set_throttle(Throttle::new(config.rate_limit.clone()));
+    monitor.set_proxy(config.proxy.clone().map(ProxyRouter::new));
+    monitor.set_resolvers(resolvers);
+    monitor.set_command_policy(command_policy);
+    monitor.set_notify_after(long_command_notice(&config));
+    monitor.get_notifier().set_webhooks(notifier::configured_webhooks(&config));
+    
+    Ok((config, safety_policy))
+}
+
+/// How long a command has to run to get a desktop notification when it finishes
+fn long_command_notice(config: &Config) -> Option<chrono::Duration> {
+    let seconds = config.alerts.long_command_seconds;
+    (config.alerts.desktop && seconds > 0).then(|| chrono::Duration::seconds(seconds as i64))
+}
+
+fn print_migration(legacy: &Path, dirs: &HacksorDirs) -> Result<()> {
+    execute!(
+        io::stdout(),
+        SetForegroundColor(Color::Cyan),
+        Print(format!("[Hacksor Info] Moved {} to {} (configuration) and {} (sessions and data)\n",
+            legacy.display(), dirs.config.display(), dirs.data.display())),
+        ResetColor
+    )?;
+    Ok(())
+}
+
+/// Run a playbook in the background, reporting its progress and outcome on `tx`
+fn spawn_playbook(playbook: Playbook, monitor: CommandMonitor, target: Option<String>, tx: mpsc::Sender<String>) {
+    tokio::spawn(async move {
+        let message = match playbook.run(&monitor, target.as_deref(), tx.clone()).await {
+            Ok(outcome) => format!("[PLAYBOOK] {} finished: {} succeeded, {} failed, {} skipped",
+                playbook.name, outcome.succeeded, outcome.failed.len(), outcome.skipped),
+            Err(e) => format!("[PLAYBOOK] {} failed: {:#}", playbook.name, e),
+        };
+        let _ = tx.send(message).await;
+    });
+}
+
+/// Add output from commands and follow-up actions to the AI context so it is
+/// aware of findings, storing action results against the latest command
+fn observe_output(ai: &mut ai::GeminiAI, monitor: &CommandMonitor, output: &str) {
+    if !(output.starts_with("[INFO]") || output.starts_with("[ACTION") || output.starts_with("[RESULT]") ||
+       output.starts_with("[POLICY]")) {
+        return;
+    }
+    ai.add_assistant_message(&format!("I observed the following in the terminal: {}", output));
+    
+    if output.starts_with("[RESULT]") {
+        if let Some(latest) = monitor.get_all_commands().into_iter().max_by_key(|cmd| cmd.start_time) {
+            let result_text = output.trim_start_matches("[RESULT] ");
+            ai.add_command_result(&latest.command, result_text);
+            let _ = monitor.update_command_summary(&latest.id, result_text);
+        }
+    }
+}
+
+/// Record the user's answer to an approval prompt on the timeline, returning a summary of it
+fn record_approval(monitor: &CommandMonitor, command: &str, decision: &ApprovalDecision) -> String {
+    let summary = match decision {
+        ApprovalDecision::Approve => format!("Approved `{}`", command),
+        ApprovalDecision::Reject => format!("Rejected `{}`", command),
+        ApprovalDecision::Edit(edited) => format!("Edited `{}` to `{}`", command, edited),
+    };
+    monitor.get_timeline().record(ActivityKind::Decision, summary.clone(), None);
+    summary
+}
+
+/// Forward a policy refusal to the main loop so the AI learns which commands are off limits
+async fn report_policy_refusal(tx: &mpsc::Sender<String>, error: &anyhow::Error) {
+    if let Some(violation) = error.downcast_ref::<PolicyViolation>() {
+        let _ = tx.send(format!("[POLICY] Refused to run `{}`: {}. Do not propose it again.",
+            violation.command, violation.reason)).await;
+    }
+}
+
+/// Opening message describing the `--target` and `--scope` of the engagement
+fn engagement_brief(target: Option<&str>, scope: &[String]) -> Option<String> {
+    let mut brief = match target {
+        Some(target) => format!("The target of this engagement is {}.", target),
+        None if scope.is_empty() => return None,
+        None => String::new(),
+    };
+    
+    if !scope.is_empty() {
+        brief.push_str(&format!(" Only these hosts and ranges are in scope, do not test anything else: {}.",
+            scope.join(", ")));
+    }
+    
+    Some(brief.trim_start().to_string())
+}
+
+/// Engagement brief for the current session, or for the scope alone without one
+fn session_brief(engine: &PentestEngine, scope: &[String]) -> Option<String> {
+    match engine.get_current_session() {
+        Some(session) => engagement_brief(Some(&session.target.domain), &session.target.scope),
+        None => engagement_brief(None, scope),
+    }
+}
+
+/// Look the target up with each passive recon source that has credentials,
+/// unless an earlier run of this session did, returning notes for the AI
+async fn passive_recon_on_start(config: &Config, monitor: &CommandMonitor, work_dir: &Path, target: &str, scope: &[String]) -> Result<Vec<String>> {
+    let mut notes = Vec::new();
+    if !config.passive_recon.on_start {
+        return Ok(notes);
+    }
+    let earlier = load_findings(work_dir).unwrap_or_default();
+    for &source in &config.passive_recon.sources {
+        let (configured, lookup) = match source {
+            PassiveSource::Shodan => (ShodanClient::new(&config.shodan).is_ok(), shodan::lookup_command(target)),
+            PassiveSource::Censys => (CensysClient::new(&config.censys).is_ok(), censys::lookup_command(target)),
+            // Certificate transparency only knows domains
+            PassiveSource::Crtsh => (target.parse::<IpAddr>().is_err(), crtsh::lookup_command(target)),
+            PassiveSource::VirusTotal => (
+                VirusTotalClient::new(&config.virustotal).is_ok() && target.parse::<IpAddr>().is_err(),
+                virustotal::lookup_command(target),
+            ),
+            PassiveSource::SecurityTrails => (
+                SecurityTrailsClient::new(&config.securitytrails).is_ok() && target.parse::<IpAddr>().is_err(),
+                securitytrails::lookup_command(target),
+            ),
+            PassiveSource::Wayback => (true, wayback::lookup_command(target)),
+            PassiveSource::GitHub => (
+                GitHubClient::new(&config.github).is_ok() && target.parse::<IpAddr>().is_err(),
+                github::lookup_command(target),
+            ),
+        };
+        if !configured || earlier.iter().any(|finding| finding.discovery_command == lookup) {
+            continue;
+        }
+        if let Some(note) = passive_lookup(config, source, monitor, target, scope).await? {
+            notes.push(note);
+        }
+    }
+    Ok(notes)
+}
+
+/// Look a host up on a passive recon source, printing what it has seen, and
+/// return a note for the AI. Collected URLs are kept to the scope
+async fn passive_lookup(config: &Config, source: PassiveSource, monitor: &CommandMonitor, target: &str, scope: &[String]) -> Result<Option<String>> {
+    let mut stdout = io::stdout();
+    execute!(
+        stdout,
+        SetForegroundColor(Color::Cyan),
+        Print(format!("[Hacksor Info] Looking up {} on {}, without contacting it\n", target, source.name())),
+        ResetColor
+    )?;
+    
+    let lookup = match source {
+        PassiveSource::Shodan => match ShodanClient::new(&config.shodan) {
+            Ok(client) => shodan::passive_recon(&client, monitor, target).await,
+            Err(e) => Err(e),
+        },
+        PassiveSource::Censys => match CensysClient::new(&config.censys) {
+            Ok(client) => censys::passive_recon(&client, monitor, target).await,
+            Err(e) => Err(e),
+        },
+        PassiveSource::Crtsh => match CrtshClient::new(&config.crtsh) {
+            Ok(client) => crtsh::passive_recon(&client, monitor, target).await,
+            Err(e) => Err(e),
+        },
+        PassiveSource::VirusTotal => match VirusTotalClient::new(&config.virustotal) {
+            Ok(client) => virustotal::passive_recon(&client, monitor, target).await,
+            Err(e) => Err(e),
+        },
+        PassiveSource::SecurityTrails => match SecurityTrailsClient::new(&config.securitytrails) {
+            Ok(client) => securitytrails::passive_recon(&client, monitor, target).await,
+            Err(e) => Err(e),
+        },
+        PassiveSource::Wayback => match WaybackClient::new(&config.wayback) {
+            Ok(client) => wayback::passive_recon(&client, monitor, target, scope).await,
+            Err(e) => Err(e),
+        },
+        PassiveSource::GitHub => match GitHubClient::new(&config.github) {
+            Ok(client) => github::passive_recon(&client, monitor, target).await,
+            Err(e) => Err(e),
+        },
+    };
+    match lookup {
+        Ok(summary) => {
+            execute!(
+                stdout,
+                SetForegroundColor(Color::Cyan),
+                Print(format!("[Hacksor Info] {}\n", summary)),
+                ResetColor
+            )?;
+            Ok(Some(format!("Passive reconnaissance from {}, gathered without contacting the target: {}", source.name(), summary)))
+        },
+        Err(e) => {
+            execute!(
+                stdout,
+                SetForegroundColor(Color::Red),
+                Print(format!("[ERROR] {} lookup for {} failed: {:#}\n", source.name(), target, e)),
+                ResetColor
+            )?;
+            Ok(None)
+        },
+    }
+}
+
+/// The documented findings, restated for the AI after its conversation is cleared
+fn findings_brief(finding_store: &FindingStore) -> Option<String> {
+    let findings = finding_store.lock().unwrap();
+    if findings.is_empty() {
+        return None;
+    }
+    
+    let mut findings: Vec<&DocumentedFinding> = findings.values().collect();
+    findings.sort_by_key(|finding| finding.discovery_date);
+    let mut brief = String::from("Findings documented so far in this engagement:");
+    for finding in findings {
+        brief.push_str(&format!(
+            "\n- {} [{:?}] {} ({:?}) on {}",
+            finding.id,
+            finding.severity,
+            finding.title,
+            finding.status,
+            extract_target_from_command(&finding.discovery_command).unwrap_or_else(|| "an unknown target".to_string())
+        ));
+    }
+    Some(brief)
+}
+
+/// Session name from `--session <name>`, or the start time; used as the session's directory name
+fn session_name(requested: Option<&str>) -> String {
+    // Keep the name safe to use as a single path component
+    let name = requested
+        .map(|name| name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+            .collect::<String>()
+            .trim_start_matches('.')
+            .to_string())
+        .unwrap_or_default();
+    
+    if name.is_empty() {
+        chrono::Local::now().format("%Y%m%d_%H%M%S").to_string()
+    } else {
+        name
+    }
+}
+
+/// Find a recent run of the same command against the same target
+fn find_duplicate(monitor: &CommandMonitor, command: &str) -> Option<MonitoredCommand> {
+    monitor.find_recent_duplicate(command, chrono::Duration::minutes(DUPLICATE_WINDOW_MINUTES))
+}
+
+/// Describe the cached result of a previous run of a command
+fn describe_cached_result(previous: &MonitoredCommand) -> String {
+    let when = previous.start_time.with_timezone(&chrono::Local).format("%H:%M");
+    
+    if matches!(previous.status, CommandStatus::Running) {
+        return format!("[Hacksor] Skipping `{}`: the same command is already running (ID: {}, started {}).",
+            previous.command, previous.id, when);
+    }
+    
+    let summary = previous.summary()
+        .unwrap_or_else(|| format!("{} findings recorded", previous.findings.len()));
+    
+    format!("[Hacksor] Skipping `{}`: it already ran at {} (ID: {}). Cached summary: {}",
+        previous.command, when, previous.id, summary)
+}
+
+/// Consolidated view of a host: subdomains, services, URLs, technologies and findings
+fn describe_asset(host: &str, assets: &[Asset], monitor: &CommandMonitor, finding_store: &FindingStore) -> String {
+    if assets.is_empty() {
+        return format!("Nothing is known about {} yet.", host);
+    }
+    
+    let values = |kind: AssetKind| -> Vec<String> {
+        assets.iter()
+            .filter(|asset| asset.kind == kind)
+            .map(|asset| match &asset.service {
+                Some(service) if asset.host != host => format!("{} {} ({})", asset.host, asset.value, service),
+                Some(service) => format!("{} ({})", asset.value, service),
+                None if kind == AssetKind::Service && asset.host != host => format!("{} {}", asset.host, asset.value),
+                None => asset.value.clone(),
+            })
+            .collect()
+    };
+    
+    let mut view = format!("Everything known about {}:", host);
+    for (label, items) in [
+        ("Subdomains", values(AssetKind::Subdomain)),
+        ("Services", values(AssetKind::Service)),
+        ("URLs", values(AssetKind::Url)),
+    ] {
+        if !items.is_empty() {
+            view.push_str(&format!("\n{}:\n  {}", label, items.join("\n  ")));
+        }
+    }
+    
+    let technologies: Vec<String> = monitor.get_tech_inventory().technologies_for(host).iter()
+        .map(|tech| match &tech.version {
+            Some(version) => format!("{} {}", tech.name, version),
+            None => tech.name.clone(),
+        })
+        .collect();
+    if !technologies.is_empty() {
+        view.push_str(&format!("\nTechnologies:\n  {}", technologies.join("\n  ")));
+    }
+    
+    let intel = monitor.get_asset_inventory().intel_for(host);
+    if !intel.is_empty() {
+        view.push_str("\nPassive intel:");
+        for report in intel {
+            view.push_str(&format!("\n  {} ({}):", report.source, report.collected.format("%Y-%m-%d %H:%M UTC")));
+            for line in &report.lines {
+                view.push_str(&format!("\n    {}", line));
+            }
+        }
+    }
+    
+    let findings = finding_store.lock().unwrap();
+    let related: Vec<String> = assets.iter()
+        .flat_map(|asset| asset.finding_ids.iter())
+        .filter_map(|id| findings.get(id))
+        .map(|finding| format!("{} [{:?}] {} ({:?})", finding.id, finding.severity, finding.title, finding.status))
+        .collect();
+    if !related.is_empty() {
+        view.push_str(&format!("\nFindings:\n  {}", related.join("\n  ")));
+    }
+    
+    let mut commands: Vec<&String> = assets.iter().flat_map(|asset| asset.command_ids.iter()).collect();
+    commands.sort();
+    commands.dedup();
+    view.push_str(&format!("\nSeen in output of {} command(s)", commands.len()));
+    
+    view
+}
+
+/// The sessions for `!sessions`, oldest first
+fn list_sessions(engine: &PentestEngine) -> String {
+    let sessions = engine.get_sessions();
+    if sessions.is_empty() {
+        return "No sessions yet. Start one with !session new <target>.".to_string();
+    }
+    
+    let current = engine.get_current_session();
+    let is_current = |session: &PentestSession| current.as_ref().is_some_and(|current| current.session_id == session.session_id);
+    let mut listing = format!("{} session(s):", sessions.len());
+    for session in sessions {
+        listing.push_str(&format!(
+            "\n{} {} {} ({:?}, started {})",
+            if is_current(session) { "*" } else { " " },
+            session.session_id,
+            session.target.domain,
+            session.status,
+            session.created.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")
+        ));
+    }
+    listing
+}
+
+/// Lines matching `pattern` in every command's output log and every finding's
+/// evidence, grouped under the command or finding they came from
+fn grep_session(pattern: &regex::Regex, commands: &[MonitoredCommand], finding_store: &FindingStore) -> String {
+    let mut sources: Vec<(String, String)> = Vec::new();
+    
+    let mut commands: Vec<&MonitoredCommand> = commands.iter().collect();
+    commands.sort_by_key(|cmd| cmd.start_time);
+    for cmd in commands {
+        // Logs can hold binary output from some tools
+        if let Ok(output) = std::fs::read(&cmd.output_file) {
+            sources.push((format!("{} {}", cmd.id, cmd.command), String::from_utf8_lossy(&output).into_owned()));
+        }
+    }
+    
+    let findings = finding_store.lock().unwrap();
+    let mut findings: Vec<&DocumentedFinding> = findings.values().collect();
+    findings.sort_by_key(|finding| finding.discovery_date);
+    for finding in findings {
+        sources.push((format!("{} {} (evidence)", finding.id, finding.title), finding.raw_evidence.clone()));
+    }
+    
+    let mut listing = String::new();
+    let mut count = 0;
+    'sources: for (source, text) in &sources {
+        let mut header = Some(source);
+        for (number, line) in text.lines().enumerate() {
+            if !pattern.is_match(line) {
+                continue;
+            }
+            if count == GREP_MAX_MATCHES {
+                listing.push_str(&format!("\n(stopped after {} matches; narrow the pattern to see the rest)", GREP_MAX_MATCHES));
+                break 'sources;
+            }
+            if let Some(header) = header.take() {
+                listing.push_str(&format!("\n  {}", header));
+            }
+            listing.push_str(&format!("\n    {}: {}", number + 1, line.trim_end()));
+            count += 1;
+        }
+    }
+    
+    if count == 0 {
+        return format!("No matches for {} in {} command log(s) and finding(s).", pattern, sources.len());
+    }
+    format!("{} match(es) for {}:{}", count, pattern, listing)
+}
+
+/// The last `count` commands for `!history`, oldest first so the latest ends up next to the prompt
+fn list_history(commands: &[MonitoredCommand], count: usize) -> String {
+    if commands.is_empty() {
+        return "No commands have run yet.".to_string();
+    }
+    
+    let mut commands: Vec<&MonitoredCommand> = commands.iter().collect();
+    commands.sort_by_key(|cmd| cmd.start_time);
+    let recent = &commands[commands.len().saturating_sub(count)..];
+    
+    let mut listing = format!("Last {} of {} command(s):", recent.len(), commands.len());
+    for cmd in recent {
+        let duration = cmd.end_time.unwrap_or_else(chrono::Utc::now).signed_duration_since(cmd.start_time);
+        let (status, detail) = match &cmd.status {
+            CommandStatus::Failed(reason) => ("Failed", Some(reason.clone())),
+            CommandStatus::Running => ("Running", None),
+            CommandStatus::Completed => ("Completed", None),
+            CommandStatus::Detached => ("Detached", None),
+        };
+        let summary = cmd.summary().or(detail)
+            .unwrap_or_else(|| format!("{} findings recorded", cmd.findings.len()));
+        let summary: String = summary.lines().next().unwrap_or("").chars().take(HISTORY_SUMMARY_WIDTH).collect();
+        
+        listing.push_str(&format!(
+            "\n  {} {} [{} {}] {}\n      {}",
+            cmd.id,
+            cmd.start_time.with_timezone(&chrono::Local).format("%H:%M"),
+            status,
+            format_duration(duration),
+            cmd.command,
+            summary
+        ));
+    }
+    listing
+}
+
+/// A duration as `45s`, `3m12s` or `1h04m`
+fn format_duration(duration: chrono::Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m{:02}s", seconds / 60, seconds % 60),
+        _ => format!("{}h{:02}m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
+
+/// `hacksor(example.com)[2 running|3 findings]> `, with the current session's
+/// target, the running commands and the findings not yet listed or shown
+fn status_prompt(engine: &PentestEngine, monitor: &CommandMonitor, finding_store: &FindingStore, read_findings: &HashSet<String>) -> String {
+    let mut prompt = String::from("hacksor");
+    if let Some(session) = engine.get_current_session() {
+        prompt.push_str(&format!("({})", session.target.domain));
+    }
+    
+    let running = monitor.get_active_commands().len();
+    let unread = finding_store.lock().unwrap().values()
+        .filter(|finding| finding.status != FindingStatus::FalsePositive && !read_findings.contains(&finding.id))
+        .count();
+    let mut status = Vec::new();
+    if running > 0 {
+        status.push(format!("{} running", running));
+    }
+    if unread > 0 {
+        status.push(format!("{} finding{}", unread, if unread == 1 { "" } else { "s" }));
+    }
+    if !status.is_empty() {
+        prompt.push_str(&format!("[{}]", status.join("|")));
+    }
+    prompt.push_str("> ");
+    prompt
+}
+
+/// The findings for `!findings`, most severe first, marking the ones not listed before
+fn list_findings(finding_store: &FindingStore, severity: Option<&FindingSeverity>, target: Option<&str>, read_findings: &mut HashSet<String>) -> String {
+    let findings = finding_store.lock().unwrap();
+    let target = target.map(|target| target.to_lowercase());
+    let mut matching: Vec<&DocumentedFinding> = findings.values()
+        .filter(|finding| severity.is_none_or(|severity| &finding.severity == severity))
+        .filter(|finding| target.as_ref().is_none_or(|target| finding.discovery_command.to_lowercase().contains(target)))
+        .collect();
+    if matching.is_empty() {
+        return if findings.is_empty() {
+            "No findings documented yet.".to_string()
+        } else {
+            "No findings match.".to_string()
+        };
+    }
+    matching.sort_by(|a, b| b.severity.rank().cmp(&a.severity.rank())
+        .then(a.discovery_date.cmp(&b.discovery_date)));
+    
+    let mut listing = format!("{} finding(s):", matching.len());
+    let mut unread = false;
+    for finding in matching {
+        let assignee = finding.assignee.as_deref()
+            .map(|assignee| format!(", {}", assignee))
+            .unwrap_or_default();
+        // Listing them counts as reading them for the prompt's unread count
+        let marker = if read_findings.insert(finding.id.clone()) { '*' } else { ' ' };
+        unread |= marker == '*';
+        listing.push_str(&format!(
+            "\n {} {} [{:?}] {} ({:?}{})",
+            marker, finding.id, finding.severity, finding.title, finding.status, assignee
+        ));
+    }
+    if unread {
+        listing.push_str("\n* not listed before");
+    }
+    listing.push_str("\nUse !finding show <id> for the full details.");
+    listing
+}
+
+/// A finding in full for `!finding show`, covering what its Markdown file holds
+fn describe_finding(finding: &DocumentedFinding) -> String {
+    let mut view = format!(
+        "{}: {}\nSeverity: {:?}  Status: {:?}  Confidence: {:?}\nDiscovered: {}",
+        finding.id,
+        finding.title,
+        finding.severity,
+        finding.status,
+        finding.confidence,
+        finding.discovery_date.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+    if let Some(cvss) = &finding.cvss {
+        view.push_str(&format!("\nCVSS v3.1: {:.1} ({})", cvss.score, cvss.vector));
+    }
+    if let Some(cwe) = &finding.cwe {
+        view.push_str(&format!("\nCWE: {}", cwe));
+    }
+    if let Some(owasp) = &finding.owasp {
+        view.push_str(&format!("\nOWASP Top 10: {}", owasp));
+    }
+    if let Some(assignee) = &finding.assignee {
+        view.push_str(&format!("\nAssignee: {}", assignee));
+    }
+    for (tracker, issue) in &finding.external_refs {
+        view.push_str(&format!("\n{} issue: {}", tracker, issue));
+    }
+    
+    view.push_str(&format!("\n\nDescription:\n  {}", finding.description));
+    if !finding.related_cves.is_empty() {
+        let cves: Vec<String> = finding.related_cves.iter()
+            .map(|cve| format!("{} {}", cve.id, cve.summary))
+            .collect();
+        view.push_str(&format!("\nRelated CVEs:\n  {}", cves.join("\n  ")));
+    }
+    view.push_str(&format!("\nDiscovered by:\n  {}", finding.discovery_command));
+    view.push_str(&format!("\nEvidence:\n  {}", finding.raw_evidence.trim_end().replace('\n', "\n  ")));
+    
+    if !finding.follow_up_actions.is_empty() {
+        let actions: Vec<String> = finding.follow_up_actions.iter()
+            .map(|action| format!("{} ({:?})", action.description, action.status))
+            .collect();
+        view.push_str(&format!("\nFollow-up actions:\n  {}", actions.join("\n  ")));
+    }
+    if !finding.notes.is_empty() {
+        let notes: Vec<String> = finding.notes.iter()
+            .map(|note| format!("{} {}", note.timestamp.format("%Y-%m-%d %H:%M"), note.text))
+            .collect();
+        view.push_str(&format!("\nNotes:\n  {}", notes.join("\n  ")));
+    }
+    if !finding.exploits.is_empty() {
+        let exploits: Vec<String> = finding.exploits.iter().map(|exploit| exploit.summary()).collect();
+        view.push_str(&format!("\nExploit candidates:\n  {}", exploits.join("\n  ")));
+    }
+    if !finding.screenshots.is_empty() {
+        view.push_str(&format!("\nScreenshots: {}", finding.screenshots.len()));
+    }
+    view.push_str(&format!("\nFile: {}", finding.file_path.display()));
+    view
+}
+
+/// Attach the exploits a finished `searchsploit -j` action found to the finding it
+/// followed up on, returning a line offering to mirror them
+fn attach_exploit_candidates(action: &FollowUpAction, finding_store: &FindingStore, events: Option<&FindingEventStream>, evidence: Option<&EvidenceRepo>) -> Option<String> {
+    let finding_id = action.finding_id.as_deref()?;
+    if !action.command.as_deref().is_some_and(searchsploit::is_json_search) {
+        return None;
+    }
+    let exploits = searchsploit::parse_results(action.result.as_deref()?);
+    if exploits.is_empty() {
+        return None;
+    }
+    
+    let finding = {
+        let mut findings = finding_store.lock().unwrap();
+        let finding = findings.get_mut(finding_id)?;
+        for exploit in exploits {
+            if !finding.exploits.iter().any(|known| known.edb_id == exploit.edb_id) {
+                finding.exploits.push(exploit);
+            }
         }
+        finding.clone()
+    };
+    
+    if let Err(e) = write_finding_file(&finding) {
+        eprintln!("Failed to save exploit candidates for {}: {}", finding.id, e);
+    }
+    if let Some(events) = events {
+        events.emit(FindingEventKind::Updated, &finding);
+    }
+    if let Some(evidence) = evidence {
+        evidence.commit(format!("Attach exploit candidates to {}", finding.id));
+    }
+    
+    let verified = finding.exploits.iter().filter(|exploit| exploit.verified).count();
+    Some(format!(
+        "[Hacksor] {} exploit candidates ({} verified) attached to {}; copy them into the session with !mirror {} <edb-id>...",
+        finding.exploits.len(), verified, finding.id, finding.id
+    ))
+}
+
+/// Ask the AI to plan manual verification of imported scanner results
+fn scanner_import_note(summary: &str) -> String {
+    format!(
+        "{}. The findings are documented; scanner results are unverified, so suggest manual follow-up to confirm or rule out the most severe ones.",
+        summary.trim_end_matches('.')
+    )
+}
+
+/// Wait for an OpenVAS task to stop and import its report, summarising what was imported
+async fn import_gvm_scan(client: &GmpClient, scan: &GvmScan, monitor: &CommandMonitor) -> Result<String> {
+    let status = client.wait_for(&scan.task_id).await?;
+    let report = client.report(&scan.report_id).await?;
+    let (raised, hosts) = gvm::import_report(&report, &scan.report_id, monitor, false).await?;
+    Ok(format!("OpenVAS task {} {}: imported {} findings across {} hosts", scan.task_id, status.to_lowercase(), raised, hosts.len()))
+}
+
+/// Print the commands that are still running
+fn print_running_commands(monitor: &CommandMonitor) -> Result<()> {
+    let mut stdout = io::stdout();
+    
+    execute!(
+        stdout,
+        SetForegroundColor(Color::Yellow),
+        Print("\n[Hacksor] The following commands are still running:\n"),
+        ResetColor
+    )?;
+    
+    for cmd in monitor.get_active_commands() {
+        let elapsed = chrono::Utc::now().signed_duration_since(cmd.start_time);
+        execute!(
+            stdout,
+            SetForegroundColor(Color::Blue),
+            Print(format!("ID: {} - Command: {} (running for {}s)\n", cmd.id, cmd.command, elapsed.num_seconds())),
+            ResetColor
+        )?;
     }
+    
+    Ok(())
+}
 
+/// Terminate or detach running commands, flush their output and persist the session state
+async fn shutdown_session(monitor: &CommandMonitor, finding_store: &FindingStore, evidence: Option<&EvidenceRepo>, work_dir: &Path, terminate: bool) -> Result<()> {
+    let mut stdout = io::stdout();
+    
+    if terminate {
+        for cmd_id in monitor.terminate_all().await {
+            execute!(
+                stdout,
+                SetForegroundColor(Color::Red),
+                Print(format!("[ERROR] Failed to terminate command: {}\n", cmd_id)),
+                ResetColor
+            )?;
+        }
+    } else {
+        for cmd in monitor.detach_all() {
+            let (stdout_file, stderr_file) = cmd.raw_output_files();
+            execute!(
+                stdout,
+                SetForegroundColor(Color::Yellow),
+                Print(format!(
+                    "[Hacksor] Detached `{}`; it keeps writing to {} and {}\n",
+                    cmd.command, stdout_file.display(), stderr_file.display()
+                )),
+                ResetColor
+            )?;
+        }
+    }
+    
+    monitor.flush_output_files()?;
+    
+    let state_file = work_dir.join("session_state.json");
+    monitor.save_state(&state_file)?;
+    monitor.get_tech_inventory().save(&work_dir.join("tech_inventory.json"))?;
+    monitor.get_asset_inventory().save(&work_dir.join("assets.json"))?;
+    monitor.get_asset_inventory().save_intel(&work_dir.join("passive_intel.json"))?;
+    save_findings(finding_store, &work_dir.join(FINDINGS_FILE))?;
+    if let Some(evidence) = evidence {
+        evidence.commit("Save session state");
+        evidence.flush().await;
+    }
+    
+    execute!(
+        stdout,
+        SetForegroundColor(Color::Cyan),
+        Print(format!("[Hacksor] Session state saved to {}\n", state_file.display())),
+        ResetColor
+    )?;
+    
+    monitor.get_notifier().session_ended(&session_summary(monitor, finding_store)).await;
+    
     Ok(())
 }
 
+/// What the session did, for the webhooks' end-of-session message
+fn session_summary(monitor: &CommandMonitor, finding_store: &FindingStore) -> String {
+    let commands = monitor.get_all_commands();
+    let failed = commands.iter()
+        .filter(|cmd| matches!(cmd.status, CommandStatus::Failed(_)))
+        .count();
+    let findings = reported_findings(finding_store, false, None);
+    
+    let mut summary = format!("Session ended: {} commands run ({} failed), {} findings", commands.len(), failed, findings.len());
+    let severities = [FindingSeverity::Critical, FindingSeverity::High, FindingSeverity::Medium, FindingSeverity::Low, FindingSeverity::Info];
+    let counts: Vec<String> = severities.iter()
+        .filter_map(|severity| {
+            let count = findings.iter().filter(|finding| &finding.severity == severity).count();
+            (count > 0).then(|| format!("{} {:?}", count, severity))
+        })
+        .collect();
+    if !counts.is_empty() {
+        summary.push_str(&format!(" ({})", counts.join(", ")));
+    }
+    for finding in findings.iter().filter(|finding| finding.severity.rank() >= FindingSeverity::High.rank()) {
+        summary.push_str(&format!("\n- {:?} {}: {}", finding.severity, finding.id, finding.title));
+    }
+    summary
+}
+
 // Process the AI response to extract both the display text and autonomous commands
-fn process_response(response: &str) -> (String, Vec<String>) {
+fn process_response(response: &str, safety_policy: &SafetyPolicy) -> (String, Vec<String>) {
     // Extract commands from code blocks - this is the most reliable method
     let mut commands = extract_commands(response);
     
@@ -899,7 +3800,9 @@ fn process_response(response: &str) -> (String, Vec<String>) {
         .collect();
     
     // Apply safety modifications to commands based on target
-    let cleaned_commands = apply_target_based_safety(&cleaned_commands);
+    let cleaned_commands: Vec<String> = cleaned_commands.iter()
+        .map(|cmd| safety_policy.apply(cmd))
+        .collect();
     
     // Sanitize the response - remove action markers for display
     let display_response = response
@@ -911,65 +3814,6 @@ fn process_response(response: &str) -> (String, Vec<String>) {
     (display_response, cleaned_commands)
 }
 
-// Apply safety modifications to commands based on target domain
-fn apply_target_based_safety(commands: &[String]) -> Vec<String> {
-    let prestigious_domains = [
-        "edu", "gov", "mil", "harvard", "stanford", "mit", "yale", 
-        "princeton", "columbia", "cornell", "dartmouth", "brown", "upenn",
-        "berkeley", "ucla", "usc", "duke", "jhu", "nih", "nasa", "noaa", "usgs"
-    ];
-    
-    commands.iter().map(|cmd| {
-        let mut modified_cmd = cmd.clone();
-        
-        // Check if command targets a prestigious domain
-        let targets_prestigious = prestigious_domains.iter()
-            .any(|domain| cmd.contains(domain));
-            
-        if targets_prestigious {
-            // Modify nmap commands to be less aggressive
-            if cmd.starts_with("nmap") {
-                // Remove -T4, -T5 aggressive timing and replace with -T2
-                if cmd.contains(" -T4") || cmd.contains(" -T5") {
-                    modified_cmd = modified_cmd.replace(" -T4", " -T2").replace(" -T5", " -T2");
-                }
-                
-                // If no timing specified, add -T2
-                if !modified_cmd.contains(" -T") {
-                    modified_cmd = format!("{} -T2", modified_cmd);
-                }
-                
-                // Replace -A with more targeted flags if present
-                if modified_cmd.contains(" -A") {
-                    modified_cmd = modified_cmd.replace(" -A", " -sV");
-                }
-            }
-            
-            // Reduce threads for directory brute forcing
-            if cmd.starts_with("gobuster") || cmd.contains("ffuf") || cmd.contains("dirsearch") {
-                // Replace high thread counts with lower ones
-                let re = regex::Regex::new(r" -t (\d+)").unwrap();
-                if let Some(caps) = re.captures(&modified_cmd) {
-                    if let Some(thread_match) = caps.get(1) {
-                        if let Ok(thread_count) = thread_match.as_str().parse::<i32>() {
-                            if thread_count > 10 {
-                                modified_cmd = re.replace(&modified_cmd, " -t 10").to_string();
-                            }
-                        }
-                    }
-                }
-                
-                // If no thread specified, add a conservative one
-                if !modified_cmd.contains(" -t ") {
-                    modified_cmd = format!("{} -t 10", modified_cmd);
-                }
-            }
-        }
-        
-        modified_cmd
-    }).collect()
-}
-
 fn setup_terminal() -> Result<()> {
     // Clear screen
     let mut stdout = io::stdout();
@@ -978,9 +3822,26 @@ fn setup_terminal() -> Result<()> {
     Ok(())
 }
 
-fn display_hacksor_welcome() -> Result<()> {
+fn display_hacksor_welcome(banner_config: &BannerConfig, config_dir: &Path) -> Result<()> {
     let mut stdout = io::stdout();
     
+    // A custom banner is printed as it is, so it may carry its own colors
+    if let Some(file) = &banner_config.file {
+        let path = config_dir.join(file);
+        match std::fs::read_to_string(&path) {
+            Ok(banner) => {
+                execute!(stdout, Print(format!("{}\n\n", banner.trim_end())))?;
+                return Ok(());
+            },
+            Err(e) => execute!(
+                stdout,
+                SetForegroundColor(Color::Red),
+                Print(format!("[ERROR] Failed to read banner {}: {}; showing the default\n", path.display(), e)),
+                ResetColor
+            )?,
+        }
+    }
+    
     // ASCII art banner
     let banner = r"
     ██╗  ██╗ █████╗  ██████╗██╗  ██╗███████╗ ██████╗ ██████╗ 
@@ -1112,31 +3973,6 @@ async fn execute_command(command: &str) -> Result<()> {
     Ok(())
 }
 
-/// Determine the command type based on the command string
-fn determine_command_type(command: &str) -> CommandType {
-    let command = command.to_lowercase();
-    
-    if command.contains("nmap") || command.contains("ping") || command.contains("dig") || 
-       command.contains("whois") || command.contains("traceroute") || command.contains("host") ||
-       command.contains("subfinder") || command.contains("amass") || command.contains("assetfinder") {
-        CommandType::Reconnaissance
-    } else if command.contains("gobuster") || command.contains("dirsearch") || command.contains("nikto") || 
-              command.contains("wfuzz") || command.contains("ffuf") || command.contains("dirb") {
-        CommandType::Scanning
-    } else if command.contains("sqlmap") || command.contains("metasploit") || command.contains("msfconsole") ||
-              command.contains("exploitdb") || command.contains("searchsploit") {
-        CommandType::Exploitation
-    } else if command.contains("nuclei") || command.contains("nessus") || command.contains("openvas") ||
-              command.contains("zap") || command.contains("burpsuite") {
-        CommandType::Vulnerability
-    } else if command.contains("echo") || command.contains("cat") || command.contains("grep") || 
-              command.contains("find") || command.contains("awk") || command.contains("sed") {
-        CommandType::Documentation
-    } else {
-        CommandType::Generic
-    }
-}
-
 /// Analyze command output to provide meaningful interpretation
 fn analyze_command_output(command: &str, output: &str) -> String {
     // Different analysis based on command type
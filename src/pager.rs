@@ -0,0 +1,134 @@
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+use anyhow::{Result, anyhow};
+use crossterm::{
+    cursor::MoveToColumn,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    execute,
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
+    terminal::{self, Clear, ClearType},
+};
+
+/// What to do after a screenful
+enum Step {
+    Page,
+    Line,
+    Stop,
+}
+
+/// Print `text` in `color`, through `$PAGER` or the built-in pager when it
+/// wouldn't fit on the screen. Output that isn't going to a person is printed
+/// as it is
+pub fn page(text: &str, color: Color) -> Result<()> {
+    let mut stdout = io::stdout();
+    let interactive = stdout.is_terminal() && io::stdin().is_terminal();
+    let (columns, rows) = terminal::size().unwrap_or((80, 24));
+    let lines = screen_lines(text, columns as usize);
+
+    // Leave a row for the prompt that follows
+    if !interactive || lines.len() < rows as usize {
+        execute!(stdout, SetForegroundColor(color), Print(format!("{}\n", text)), ResetColor)?;
+        return Ok(());
+    }
+
+    if let Some(pager) = env::var("PAGER").ok().filter(|pager| !pager.trim().is_empty()) {
+        match external(&pager, text) {
+            Ok(()) => return Ok(()),
+            Err(e) => execute!(
+                stdout,
+                SetForegroundColor(Color::Red),
+                Print(format!("[ERROR] {:#}; using the built-in pager\n", e)),
+                ResetColor
+            )?,
+        }
+    }
+
+    builtin(&lines, color, rows as usize - 1)
+}
+
+/// Hand the text to the user's pager and wait for it to close
+fn external(pager: &str, text: &str) -> Result<()> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(pager)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("Failed to start pager `{}`: {}", pager, e))?;
+
+    // A pager quit early closes its input; that's not an error
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(anyhow!("Pager `{}` failed ({})", pager, status));
+    }
+    Ok(())
+}
+
+/// A screenful at a time, then a page or a line per key until the end or `q`
+fn builtin(lines: &[String], color: Color, page_size: usize) -> Result<()> {
+    let mut stdout = io::stdout();
+    let mut shown = 0;
+    let mut step = page_size;
+
+    loop {
+        let end = (shown + step).min(lines.len());
+        execute!(stdout, SetForegroundColor(color), Print(format!("{}\n", lines[shown..end].join("\n"))), ResetColor)?;
+        shown = end;
+        if shown == lines.len() {
+            return Ok(());
+        }
+
+        execute!(
+            stdout,
+            SetAttribute(Attribute::Reverse),
+            Print(format!("-- More ({}%): Space next page, Enter next line, q stop --", shown * 100 / lines.len())),
+            SetAttribute(Attribute::Reset)
+        )?;
+        let next = read_step();
+        execute!(stdout, MoveToColumn(0), Clear(ClearType::CurrentLine))?;
+
+        step = match next? {
+            Step::Page => page_size,
+            Step::Line => 1,
+            Step::Stop => return Ok(()),
+        };
+    }
+}
+
+fn read_step() -> Result<Step> {
+    terminal::enable_raw_mode()?;
+    let step = loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Char(' ') | KeyCode::PageDown => break Ok(Step::Page),
+                KeyCode::Enter | KeyCode::Down | KeyCode::Char('j') => break Ok(Step::Line),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break Ok(Step::Stop),
+                KeyCode::Char('q') | KeyCode::Esc => break Ok(Step::Stop),
+                _ => {},
+            },
+            Ok(_) => {},
+            Err(e) => break Err(e.into()),
+        }
+    };
+    terminal::disable_raw_mode()?;
+    step
+}
+
+/// The text split into the rows it takes up on a screen `columns` wide
+fn screen_lines(text: &str, columns: usize) -> Vec<String> {
+    let columns = columns.max(1);
+    let mut rows = Vec::new();
+    for line in text.lines() {
+        let chars: Vec<char> = line.chars().collect();
+        if chars.is_empty() {
+            rows.push(String::new());
+        }
+        for chunk in chars.chunks(columns) {
+            rows.push(chunk.iter().collect());
+        }
+    }
+    rows
+}
@@ -0,0 +1,131 @@
+use std::io::{self, IsTerminal};
+use anyhow::Result;
+
+/// Colorless stdout for when it is piped or redirected, so logs and other
+/// programs don't get escape codes. Everything written is flushed through
+/// when this is dropped
+pub struct PlainStdout {
+    #[cfg(unix)]
+    filter: filter::Filter,
+}
+
+impl PlainStdout {
+    /// Start filtering stdout if it isn't a terminal
+    pub fn start() -> Result<Option<Self>> {
+        if io::stdout().is_terminal() {
+            return Ok(None);
+        }
+        crossterm::style::force_color_output(false);
+
+        // Colors are off, but resets and styled text are still written
+        #[cfg(unix)]
+        return Ok(Some(Self { filter: filter::Filter::start()? }));
+        #[cfg(not(unix))]
+        return Ok(None);
+    }
+}
+
+#[cfg(unix)]
+impl Drop for PlainStdout {
+    fn drop(&mut self) {
+        self.filter.stop();
+    }
+}
+
+#[cfg(unix)]
+mod filter {
+    use std::fs::File;
+    use std::io::{self, Read, Write};
+    use std::os::fd::{AsRawFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
+    use std::thread::{self, JoinHandle};
+    use anyhow::{Result, anyhow};
+    use regex::bytes::Regex;
+
+    /// stdout redirected into a pipe that a thread copies to the real stdout
+    /// without escape sequences
+    pub struct Filter {
+        original: RawFd,
+        copier: Option<JoinHandle<()>>,
+    }
+
+    impl Filter {
+        pub fn start() -> Result<Self> {
+            io::stdout().flush()?;
+
+            // SAFETY: plain descriptor calls; every descriptor created here is
+            // owned until it is handed over, so it is closed on any error, and
+            // `original` is closed in `stop`. All are close-on-exec so that
+            // commands started meanwhile don't hold the pipe open
+            unsafe {
+                let mut pipe = [0; 2];
+                if libc::pipe2(pipe.as_mut_ptr(), libc::O_CLOEXEC) != 0 {
+                    return Err(anyhow!("Failed to filter stdout: {}", io::Error::last_os_error()));
+                }
+                let reader = File::from_raw_fd(pipe[0]);
+                let writer = OwnedFd::from_raw_fd(pipe[1]);
+                let original = duplicate(libc::STDOUT_FILENO)?;
+                let output = duplicate(libc::STDOUT_FILENO)?;
+                if libc::dup2(writer.as_raw_fd(), libc::STDOUT_FILENO) < 0 {
+                    return Err(anyhow!("Failed to filter stdout: {}", io::Error::last_os_error()));
+                }
+                drop(writer);
+
+                let output = File::from(output);
+                Ok(Self {
+                    original: original.into_raw_fd(),
+                    copier: Some(thread::spawn(move || copy(reader, output))),
+                })
+            }
+        }
+
+        /// Put stdout back and wait for what was written to be copied through
+        pub fn stop(&mut self) {
+            let _ = io::stdout().flush();
+            // SAFETY: restores the descriptor duplicated in `start`, which closes
+            // the pipe's last write end so the copier sees the end of it
+            unsafe {
+                libc::dup2(self.original, libc::STDOUT_FILENO);
+                libc::close(self.original);
+            }
+            if let Some(copier) = self.copier.take() {
+                let _ = copier.join();
+            }
+        }
+    }
+
+    /// A close-on-exec copy of a descriptor
+    unsafe fn duplicate(fd: RawFd) -> Result<OwnedFd> {
+        let copy = libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 0);
+        if copy < 0 {
+            return Err(anyhow!("Failed to filter stdout: {}", io::Error::last_os_error()));
+        }
+        Ok(OwnedFd::from_raw_fd(copy))
+    }
+
+    /// Copy whole lines, or whatever has arrived once the writer pauses, with
+    /// escape sequences and carriage returns removed
+    fn copy(mut reader: File, mut output: File) {
+        let escapes = Regex::new(r"\x1b\[[0-9;?]*[ -/]*[@-~]|[\r\x07]").unwrap();
+        let mut buffer = [0u8; 4096];
+        let mut pending = Vec::new();
+
+        loop {
+            let read = match reader.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(read) => read,
+            };
+            pending.extend_from_slice(&buffer[..read]);
+
+            let end = match pending.iter().rposition(|byte| *byte == b'\n') {
+                Some(end) => end + 1,
+                None if read < buffer.len() => pending.len(),
+                None => continue,
+            };
+            let chunk: Vec<u8> = pending.drain(..end).collect();
+            if output.write_all(&escapes.replace_all(&chunk, &b""[..])).is_err() {
+                break;
+            }
+        }
+        let _ = output.write_all(&escapes.replace_all(&pending, &b""[..]));
+    }
+}
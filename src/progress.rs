@@ -0,0 +1,249 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use chrono::Utc;
+use crossterm::{
+    cursor::{MoveTo, RestorePosition, SavePosition},
+    queue,
+    style::Print,
+    terminal::{self, Clear, ClearType},
+};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle, TermLike};
+
+use crate::terminal::{CommandMonitor, MonitoredCommand};
+
+/// How often the status line is redrawn from the monitor
+const TICK: Duration = Duration::from_millis(120);
+
+/// Terminal height below which no row is taken for the status line
+const MIN_ROWS: u16 = 5;
+
+/// Longest command shown per running command
+const COMMAND_WIDTH: usize = 40;
+
+/// A spinner on the bottom row of the terminal while commands run, with their
+/// elapsed times and, for brute-forcers, how far through the wordlist they are.
+/// The row is only taken while something is running, and the prompt and output
+/// scroll above it
+pub struct ScanProgress {
+    stop: Arc<AtomicBool>,
+    updater: Option<JoinHandle<()>>,
+}
+
+impl ScanProgress {
+    pub fn start(monitor: CommandMonitor) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let updater = thread::spawn(move || {
+            let mut status = StatusLine::default();
+            while !stopped.load(Ordering::Relaxed) {
+                status.update(&monitor.get_active_commands());
+                thread::sleep(TICK);
+            }
+            status.hide();
+        });
+        Self { stop, updater: Some(updater) }
+    }
+}
+
+impl Drop for ScanProgress {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(updater) = self.updater.take() {
+            let _ = updater.join();
+        }
+    }
+}
+
+/// The bar while commands are running, and the terminal height the bottom
+/// row was reserved for
+#[derive(Default)]
+struct StatusLine {
+    bar: Option<ProgressBar>,
+    reserved_rows: Option<u16>,
+}
+
+impl StatusLine {
+    fn update(&mut self, active: &[MonitoredCommand]) {
+        if active.is_empty() {
+            self.hide();
+            return;
+        }
+
+        // Too small a terminal, or not a real one, has no row to spare
+        let rows = terminal::size().map(|(_, rows)| rows).unwrap_or(0);
+        if rows < MIN_ROWS {
+            self.hide();
+            return;
+        }
+        if self.reserved_rows != Some(rows) {
+            reserve_bottom_row(rows);
+            self.reserved_rows = Some(rows);
+        }
+
+        // A single brute-forcer with a known size gets a real progress bar
+        let progress = match active {
+            [command] => wordlist_progress(command),
+            _ => None,
+        };
+        let bar = self.bar.get_or_insert_with(|| {
+            let bar = ProgressBar::with_draw_target(None, ProgressDrawTarget::term_like(Box::new(BottomRow::default())));
+            bar.set_style(ProgressStyle::with_template("{spinner:.cyan} {wide_msg}").unwrap());
+            bar
+        });
+        bar.set_message(describe(active));
+        match progress {
+            Some((attempts, total)) => {
+                if bar.length().is_none() {
+                    bar.set_style(ProgressStyle::with_template("{spinner:.cyan} {wide_msg} [{bar:20.cyan/blue}] {percent:>3}%").unwrap());
+                }
+                bar.set_length(total);
+                bar.set_position(attempts.min(total));
+            },
+            None if bar.length().is_some() => {
+                bar.set_style(ProgressStyle::with_template("{spinner:.cyan} {wide_msg}").unwrap());
+                bar.unset_length();
+            },
+            None => {},
+        }
+        bar.tick();
+    }
+
+    /// Clear the row and give it back to the scrolling output
+    fn hide(&mut self) {
+        if let Some(bar) = self.bar.take() {
+            bar.finish_and_clear();
+        }
+        if let Some(rows) = self.reserved_rows.take() {
+            release_bottom_row(rows);
+        }
+    }
+}
+
+/// `2 running: nmap -sV example.com 1m12s · gobuster dir -u ... 20s`
+fn describe(active: &[MonitoredCommand]) -> String {
+    let now = Utc::now();
+    let commands: Vec<String> = active.iter()
+        .map(|cmd| {
+            let mut command: String = cmd.command.chars().take(COMMAND_WIDTH).collect();
+            if cmd.command.chars().count() > COMMAND_WIDTH {
+                command.push_str("...");
+            }
+            format!("{} {}", command, crate::format_duration(now - cmd.start_time))
+        })
+        .collect();
+    format!("{} running: {}", active.len(), commands.join(" · "))
+}
+
+/// Attempts made and the total to try, when a brute-forcer has reported them
+fn wordlist_progress(command: &MonitoredCommand) -> Option<(u64, u64)> {
+    let stats = command.brute_force_stats.as_ref()?;
+    let total = stats.total.or(stats.wordlist_size).filter(|total| *total > 0)?;
+    Some((stats.attempts?, total))
+}
+
+/// Scroll the screen up a line if the cursor is on the last row, then limit
+/// scrolling to the rows above it
+fn reserve_bottom_row(rows: u16) {
+    let mut stdout = io::stdout();
+    let _ = queue!(
+        stdout,
+        Print("\x1bD"),
+        SavePosition,
+        Print(format!("\x1b[1;{}r", rows.saturating_sub(1))),
+        RestorePosition,
+        Print("\x1bM")
+    );
+    let _ = stdout.flush();
+}
+
+/// Give the bottom row back when exiting without dropping `ScanProgress`
+pub fn restore_terminal() {
+    release_bottom_row(terminal::size().map(|(_, rows)| rows).unwrap_or(24));
+}
+
+fn release_bottom_row(rows: u16) {
+    let mut stdout = io::stdout();
+    let _ = queue!(
+        stdout,
+        SavePosition,
+        Print("\x1b[r"),
+        MoveTo(0, rows.saturating_sub(1)),
+        Clear(ClearType::CurrentLine),
+        RestorePosition
+    );
+    let _ = stdout.flush();
+}
+
+/// An indicatif draw target that collects a frame and paints it on the
+/// terminal's bottom row, leaving the cursor where the prompt has it
+#[derive(Default)]
+struct BottomRow {
+    frame: Mutex<String>,
+}
+
+impl fmt::Debug for BottomRow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BottomRow").finish()
+    }
+}
+
+impl TermLike for BottomRow {
+    fn width(&self) -> u16 {
+        terminal::size().map(|(columns, _)| columns).unwrap_or(80)
+    }
+
+    fn height(&self) -> u16 {
+        1
+    }
+
+    // Only one line is drawn, so there is nowhere to move to
+    fn move_cursor_up(&self, _n: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn move_cursor_down(&self, _n: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn move_cursor_right(&self, _n: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn move_cursor_left(&self, _n: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn write_line(&self, s: &str) -> io::Result<()> {
+        self.write_str(s)
+    }
+
+    fn write_str(&self, s: &str) -> io::Result<()> {
+        self.frame.lock().unwrap().push_str(&s.replace('\r', ""));
+        Ok(())
+    }
+
+    fn clear_line(&self) -> io::Result<()> {
+        self.frame.lock().unwrap().clear();
+        Ok(())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        let frame = std::mem::take(&mut *self.frame.lock().unwrap());
+        let rows = terminal::size().map(|(_, rows)| rows).unwrap_or(24);
+        let mut stdout = io::stdout();
+        queue!(
+            stdout,
+            SavePosition,
+            MoveTo(0, rows.saturating_sub(1)),
+            Clear(ClearType::CurrentLine),
+            Print(frame),
+            RestorePosition
+        )?;
+        stdout.flush()
+    }
+}
@@ -1,11 +1,20 @@
-use std::sync::Arc;
-use anyhow::{Result, Context};
-use tokio::sync::mpsc;
-use std::process::{Command, Stdio};
-use std::time::Duration;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use anyhow::{Result, Context, anyhow};
+use tokio::sync::{mpsc, oneshot, OwnedSemaphorePermit, Semaphore};
+use std::fs;
+use std::time::{Duration, Instant};
+use chrono::Local;
+use regex::Regex;
 
-use super::command_monitor::CommandMonitor;
+use crate::config::ActionConfig;
+use super::command_monitor::{CommandMonitor, CommandStatus, determine_command_type};
+use crate::utils::extract_target_from_command;
+use super::testing_window::TestingWindow;
 use super::auto_documentation::{FollowUpAction, ActionStatus};
+use super::action_queue::ActionQueue;
+use super::approval::ApprovalDecision;
 
 /// Executes follow-up actions based on security findings
 pub struct ActionExecutor {
@@ -13,8 +22,13 @@ pub struct ActionExecutor {
     action_rx: mpsc::Receiver<FollowUpAction>,
     result_tx: mpsc::Sender<FollowUpAction>,
     running: bool,
-    max_concurrent: usize,
-    current_executing: usize,
+    /// One permit per action allowed to run at once, held by the action's task
+    permits: Arc<Semaphore>,
+    policy: ExecutionPolicy,
+    canceller: ActionCanceller,
+    rate_limiter: TargetRateLimiter,
+    /// Actions only start while this window is open
+    testing_window: Option<TestingWindow>,
 }
 
 impl ActionExecutor {
@@ -22,18 +36,37 @@ impl ActionExecutor {
         monitor: Arc<CommandMonitor>,
         action_rx: mpsc::Receiver<FollowUpAction>,
         result_tx: mpsc::Sender<FollowUpAction>,
-        max_concurrent: usize
+        max_concurrent: usize,
+        config: &ActionConfig,
     ) -> Self {
+        let canceller = ActionCanceller {
+            queue: monitor.get_action_queue(),
+            running: Arc::new(Mutex::new(HashMap::new())),
+        };
+        
         Self {
             monitor,
             action_rx,
             result_tx,
             running: false,
-            max_concurrent,
-            current_executing: 0,
+            permits: Arc::new(Semaphore::new(max_concurrent)),
+            policy: ExecutionPolicy::from_config(config),
+            canceller,
+            rate_limiter: TargetRateLimiter::new(config.max_per_target_per_minute),
+            testing_window: None,
         }
     }
     
+    /// Hold actions in the `Scheduled` state while the testing window is closed
+    pub fn set_testing_window(&mut self, window: TestingWindow) {
+        self.testing_window = Some(window);
+    }
+    
+    /// Handle for cancelling actions while the executor runs
+    pub fn canceller(&self) -> ActionCanceller {
+        self.canceller.clone()
+    }
+    
     /// Start the action executor
     pub async fn start(&mut self) -> Result<()> {
         if self.running {
@@ -42,90 +75,499 @@ impl ActionExecutor {
         
         self.running = true;
         
+        // Finished actions report back here so their dependents can be released
+        let (done_tx, mut done_rx) = mpsc::channel::<()>(100);
+        let mut waiting: BinaryHeap<QueuedAction> = BinaryHeap::new();
+        let mut sequence = 0;
+        
+        // IDs of dropped duplicate actions and the queued action that replaced them
+        let mut duplicates: HashMap<String, String> = HashMap::new();
+        
+        // Actions held back by the per-target rate limit are retried as their window frees up
+        let mut rate_limit_tick = tokio::time::interval(Duration::from_secs(1));
+        
         // Main execution loop
-        while let Some(action) = self.action_rx.recv().await {
-            // Skip already completed or failed actions
-            if action.status == ActionStatus::Completed || action.status == ActionStatus::Failed {
-                continue;
-            }
-            
-            // Wait if we're at max concurrent actions
-            while self.current_executing >= self.max_concurrent {
-                tokio::time::sleep(Duration::from_millis(500)).await;
+        let queue = self.monitor.get_action_queue();
+        loop {
+            tokio::select! {
+                received = self.action_rx.recv() => {
+                    let Some(mut action) = received else { break };
+                    
+                    // Skip already completed or failed actions
+                    if action.status.is_finished() {
+                        continue;
+                    }
+                    
+                    // Depend on whatever replaced a dropped duplicate
+                    for dependency in &mut action.depends_on {
+                        if let Some(replacement) = duplicates.get(dependency) {
+                            *dependency = replacement.clone();
+                        }
+                    }
+                    
+                    // The same command against the same target is already queued
+                    if let Some(existing) = queue.find_duplicate(&action) {
+                        duplicates.insert(action.id, existing.id);
+                        continue;
+                    }
+                    
+                    // Persist the action so it is resumed if Hacksor restarts before it runs
+                    if let Err(e) = queue.update(&action) {
+                        eprintln!("Failed to persist follow-up action: {}", e);
+                    }
+                    
+                    waiting.push(QueuedAction { action, sequence });
+                    sequence += 1;
+                },
+                Some(()) = done_rx.recv() => {},
+                _ = rate_limit_tick.tick(), if !waiting.is_empty() => {},
             }
             
-            // Increment executing count
-            self.current_executing += 1;
+            self.dispatch(&mut waiting, &queue, &done_tx).await;
+        }
+        
+        self.running = false;
+        Ok(())
+    }
+    
+    /// Start waiting actions whose dependencies have completed, highest priority first and
+    /// up to the concurrency limit. Actions depending on one that failed are failed as well.
+    async fn dispatch(&mut self, waiting: &mut BinaryHeap<QueuedAction>, queue: &ActionQueue, done_tx: &mpsc::Sender<()>) {
+        let mut held_back = Vec::new();
+        while self.permits.available_permits() > 0 {
+            let Some(mut queued) = waiting.pop() else { break };
             
-            // Clone necessary data for the async task
-            let result_tx = self.result_tx.clone();
-            let mut action_copy = action.clone();
+            // Cancelled while it was waiting
+            if queue.status(&queued.action.id) == Some(ActionStatus::Failed) {
+                continue;
+            }
             
-            // Execute action in a separate task
-            tokio::spawn(async move {
-                // Update status to in-progress
-                action_copy.status = ActionStatus::InProgress;
-                
-                // Execute the command if present
-                if let Some(cmd) = &action_copy.command {
-                    match execute_command(cmd).await {
-                        Ok(output) => {
-                            // Update action with result
-                            action_copy.result = Some(output);
-                            action_copy.status = ActionStatus::Completed;
-                        },
-                        Err(e) => {
-                            // Update action with error
-                            action_copy.result = Some(format!("ERROR: {}", e));
-                            action_copy.status = ActionStatus::Failed;
+            match dependency_state(&queued.action, queue) {
+                DependencyState::Waiting => held_back.push(queued),
+                DependencyState::Ready => {
+                    let Ok(permit) = self.permits.clone().try_acquire_owned() else {
+                        held_back.push(queued);
+                        break;
+                    };
+                    
+                    // Outside the allowed testing hours
+                    if self.testing_window.as_ref().is_some_and(|window| !window.is_open(Local::now())) {
+                        if queued.action.status != ActionStatus::Scheduled {
+                            queued.action.status = ActionStatus::Scheduled;
+                            if let Err(e) = queue.update(&queued.action) {
+                                eprintln!("Failed to persist follow-up action: {}", e);
+                            }
                         }
+                        held_back.push(queued);
+                        continue;
                     }
-                } else {
-                    // No command to execute, just mark as completed
-                    action_copy.status = ActionStatus::Completed;
-                }
-                
-                // Send the updated action back
-                if let Err(e) = result_tx.send(action_copy).await {
-                    eprintln!("Failed to send action result: {}", e);
+                    
+                    // Too many actions have hit this target in the last minute
+                    let target = queued.action.command.as_deref().and_then(extract_target_from_command);
+                    if !self.rate_limiter.try_acquire(target.as_deref()) {
+                        held_back.push(queued);
+                        continue;
+                    }
+                    
+                    let Some(cancel_rx) = self.canceller.register(&queued.action.id) else {
+                        continue;
+                    };
+                    
+                    tokio::spawn(run_action(
+                        queued.action,
+                        self.monitor.clone(),
+                        self.canceller.clone(),
+                        cancel_rx,
+                        self.policy.clone(),
+                        self.result_tx.clone(),
+                        ExecutionSlot { permit, done_tx: done_tx.clone() },
+                    ));
+                },
+                DependencyState::Failed(dependency) => {
+                    let mut action = queued.action;
+                    action.status = ActionStatus::Failed;
+                    action.result = Some(format!("Skipped: dependency {} did not complete", dependency));
+                    if let Err(e) = queue.update(&action) {
+                        eprintln!("Failed to persist follow-up action: {}", e);
+                    }
+                    if let Err(e) = self.result_tx.send(action).await {
+                        eprintln!("Failed to send action result: {}", e);
+                    }
+                    
+                    // Actions already held back may depend on the one just failed
+                    waiting.extend(held_back.drain(..));
+                },
+            }
+        }
+        waiting.extend(held_back);
+    }
+}
+
+/// Caps how many actions may start against the same target within a minute, so a burst
+/// of findings does not turn into a flood of requests to one host
+struct TargetRateLimiter {
+    /// Zero disables the limit
+    per_minute: u32,
+    started: HashMap<String, VecDeque<Instant>>,
+}
+
+impl TargetRateLimiter {
+    fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute,
+            started: HashMap::new(),
+        }
+    }
+    
+    /// Record an action starting against `target`, unless the target's limit is reached
+    fn try_acquire(&mut self, target: Option<&str>) -> bool {
+        let Some(target) = target.filter(|_| self.per_minute > 0) else {
+            return true;
+        };
+        
+        let started = self.started.entry(target.to_lowercase()).or_default();
+        while started.front().is_some_and(|time| time.elapsed() >= Duration::from_secs(60)) {
+            started.pop_front();
+        }
+        
+        if started.len() >= self.per_minute as usize {
+            return false;
+        }
+        started.push_back(Instant::now());
+        true
+    }
+}
+
+/// Limits applied to each action's command
+#[derive(Debug, Clone)]
+struct ExecutionPolicy {
+    /// Longest a single attempt may run
+    timeout: Duration,
+    /// Further attempts after a transient network failure
+    max_retries: u32,
+    /// Wait before the first retry, doubled for each one after it
+    backoff: Duration,
+}
+
+impl ExecutionPolicy {
+    fn from_config(config: &ActionConfig) -> Self {
+        Self {
+            timeout: Duration::from_secs(config.timeout_secs),
+            max_retries: config.max_retries,
+            backoff: Duration::from_secs(config.retry_backoff_secs),
+        }
+    }
+}
+
+/// Result recorded for actions stopped with `!cancel-action`
+const CANCELLED: &str = "Cancelled by user";
+
+/// Cancels queued or running follow-up actions from the interactive commands
+#[derive(Debug, Clone)]
+pub struct ActionCanceller {
+    queue: ActionQueue,
+    /// Running actions by ID
+    running: Arc<Mutex<HashMap<String, oneshot::Sender<()>>>>,
+}
+
+impl ActionCanceller {
+    /// Cancel the unfinished action whose ID starts with `id`. A running action has its
+    /// command killed; a waiting one is marked failed so it never starts.
+    pub fn cancel(&self, id: &str) -> Result<FollowUpAction> {
+        let unfinished = |action: &FollowUpAction| !action.status.is_finished();
+        let matches: Vec<FollowUpAction> = self.queue.actions().into_iter()
+            .filter(|action| action.id.starts_with(id) && unfinished(action))
+            .collect();
+        let action = match matches.as_slice() {
+            [action] => action.clone(),
+            [] => return Err(anyhow!("No pending or running follow-up action with ID {}", id)),
+            _ => return Err(anyhow!("{} follow-up actions match {}; give more of the ID", matches.len(), id)),
+        };
+        
+        // Holding the lock keeps the action from being started or finishing meanwhile
+        let mut running = self.running.lock().unwrap();
+        if let Some(cancel_tx) = running.remove(&action.id) {
+            let _ = cancel_tx.send(());
+        } else if self.queue.status(&action.id).is_some_and(|status| matches!(status, ActionStatus::Pending | ActionStatus::Scheduled)) {
+            self.queue.update(&FollowUpAction {
+                status: ActionStatus::Failed,
+                result: Some(CANCELLED.to_string()),
+                ..action.clone()
+            })?;
+        } else {
+            return Err(anyhow!("Follow-up action {} has already finished", action.id));
+        }
+        
+        Ok(action)
+    }
+    
+    /// Track an action about to start, unless it was cancelled while waiting
+    fn register(&self, id: &str) -> Option<oneshot::Receiver<()>> {
+        let mut running = self.running.lock().unwrap();
+        if self.queue.status(id) == Some(ActionStatus::Failed) {
+            return None;
+        }
+        
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        running.insert(id.to_string(), cancel_tx);
+        Some(cancel_rx)
+    }
+    
+    /// Persist a finished action and stop tracking it
+    fn finish(&self, action: &FollowUpAction) {
+        let mut running = self.running.lock().unwrap();
+        if let Err(e) = self.queue.update(action) {
+            eprintln!("Failed to persist follow-up action: {}", e);
+        }
+        running.remove(&action.id);
+    }
+}
+
+/// A waiting action, ordered by priority and then by arrival
+struct QueuedAction {
+    action: FollowUpAction,
+    sequence: u64,
+}
+
+impl Ord for QueuedAction {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.action.priority.cmp(&other.action.priority)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+impl PartialOrd for QueuedAction {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for QueuedAction {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueuedAction {}
+
+enum DependencyState {
+    Ready,
+    /// A dependency has not finished, or has not been queued yet
+    Waiting,
+    /// The ID of a dependency that failed
+    Failed(String),
+}
+
+fn dependency_state(action: &FollowUpAction, queue: &ActionQueue) -> DependencyState {
+    let mut state = DependencyState::Ready;
+    for dependency in &action.depends_on {
+        match queue.status(dependency) {
+            Some(ActionStatus::Completed) => {},
+            Some(ActionStatus::Failed) => return DependencyState::Failed(dependency.clone()),
+            _ => state = DependencyState::Waiting,
+        }
+    }
+    state
+}
+
+/// Run one action, persisting its progress, and report the outcome
+async fn run_action(
+    mut action: FollowUpAction,
+    monitor: Arc<CommandMonitor>,
+    canceller: ActionCanceller,
+    mut cancel_rx: oneshot::Receiver<()>,
+    policy: ExecutionPolicy,
+    result_tx: mpsc::Sender<FollowUpAction>,
+    slot: ExecutionSlot,
+) {
+    // Update status to in-progress
+    action.status = ActionStatus::InProgress;
+    if let Err(e) = canceller.queue.update(&action) {
+        eprintln!("Failed to persist follow-up action: {}", e);
+    }
+    
+    // Execute the command if present, asking about exactly what will run:
+    // the command after the policy, tool paths, throttling and proxying
+    if let Some(cmd) = action.command.clone() {
+        let approval = monitor.get_approval_gate();
+        let prepared = match monitor.prepare_command(&cmd).await {
+            Ok(prepared) if approval.reviews_actions() => match approval.review(&action.description, &prepared).await {
+                ApprovalDecision::Approve => Ok(Some(prepared)),
+                ApprovalDecision::Reject => Ok(None),
+                ApprovalDecision::Edit(edited) => {
+                    action.command = Some(edited.clone());
+                    monitor.prepare_command(&edited).await.map(Some)
+                },
+            },
+            Ok(prepared) if action.requires_confirmation => Ok(approval.confirm(&prepared).await.then_some(prepared)),
+            Ok(prepared) => Ok(approval.request(&prepared).await.then_some(prepared)),
+            Err(e) => Err(e),
+        };
+        
+        match prepared {
+            Err(e) => {
+                action.result = Some(format!("ERROR: {}", e));
+                action.status = ActionStatus::Failed;
+            },
+            Ok(None) => {
+                action.result = Some("Rejected by user".to_string());
+                action.status = ActionStatus::Failed;
+            },
+            Ok(Some(cmd)) => {
+                // Dropping the command's future kills its process
+                tokio::select! {
+                    biased;
+                    _ = &mut cancel_rx => {
+                        action.result = Some(CANCELLED.to_string());
+                        action.status = ActionStatus::Failed;
+                    },
+                    (status, output) = execute_with_retries(&monitor, &cmd, &policy) => {
+                        action.result = Some(output);
+                        action.status = status;
+                    },
                 }
-            });
+            },
+        }
+    } else {
+        // No command to execute, just mark as completed
+        action.status = ActionStatus::Completed;
+    }
+    
+    canceller.finish(&action);
+    
+    // Send the updated action back
+    if let Err(e) = result_tx.send(action).await {
+        eprintln!("Failed to send action result: {}", e);
+    }
+    
+    slot.release().await;
+}
+
+/// A running action's share of the concurrency limit
+struct ExecutionSlot {
+    permit: OwnedSemaphorePermit,
+    /// Wakes the executor so it can start the next waiting action
+    done_tx: mpsc::Sender<()>,
+}
+
+impl ExecutionSlot {
+    async fn release(self) {
+        drop(self.permit);
+        if let Err(e) = self.done_tx.send(()).await {
+            eprintln!("Failed to release follow-up action slot: {}", e);
         }
+    }
+}
+
+/// Run an action's command, retrying transient network failures with exponential backoff.
+/// The output of failed attempts is kept ahead of the final one.
+async fn execute_with_retries(monitor: &Arc<CommandMonitor>, command: &str, policy: &ExecutionPolicy) -> (ActionStatus, String) {
+    let mut history = String::new();
+    let mut backoff = policy.backoff;
+    let mut attempt = 1;
+    loop {
+        let (status, output) = match tokio::time::timeout(policy.timeout, execute_command(monitor, command)).await {
+            Ok(Ok(run)) if !run.success && is_transient_failure(&run.output) => (None, run.output),
+            Ok(Ok(run)) => (Some(ActionStatus::Completed), run.output),
+            Ok(Err(e)) => (Some(ActionStatus::Failed), format!("ERROR: {}", e)),
+            Err(_) => (Some(ActionStatus::Failed), format!("Timed out after {}s", policy.timeout.as_secs())),
+        };
         
-        self.running = false;
-        Ok(())
+        let status = match status {
+            Some(status) => status,
+            None if attempt > policy.max_retries => ActionStatus::Failed,
+            None => {
+                history.push_str(&format!(
+                    "=== ATTEMPT {} FAILED (transient network error, retrying in {}s) ===\n{}\n",
+                    attempt, backoff.as_secs(), output
+                ));
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+                continue;
+            },
+        };
+        
+        if history.is_empty() {
+            return (status, output);
+        }
+        return (status, format!("{}=== ATTEMPT {} ===\n{}", history, attempt, output));
     }
 }
 
-/// Execute a command and capture its output
-async fn execute_command(command: &str) -> Result<String> {
-    println!("Executing follow-up action: {}", command);
+/// Whether a failed command's output points at a network problem that may clear up on its own
+fn is_transient_failure(output: &str) -> bool {
+    let pattern = Regex::new(
+        r"(?i)could not resolve host|temporary failure in name resolution|connection (timed out|refused|reset)|network is unreachable|no route to host|operation timed out|i/o timeout|tls handshake timeout"
+    ).unwrap();
+    pattern.is_match(output)
+}
+
+/// Output of a finished command
+struct CommandRun {
+    output: String,
+    /// Exited with status 0
+    success: bool,
+}
+
+/// Execute a prepared command through the monitor, so it is recorded in the command
+/// history and analysed for findings, and capture its output once it finishes
+async fn execute_command(monitor: &Arc<CommandMonitor>, command: &str) -> Result<CommandRun> {
+    let command_id = monitor.execute_approved_command(command, determine_command_type(command))?;
+    
+    // Stop the command if the action is cancelled or times out while it runs
+    let mut guard = TerminateOnDrop {
+        monitor: monitor.clone(),
+        command_id: Some(command_id.clone()),
+    };
     
-    // Create and execute the process
-    let output = Command::new("bash")
-        .arg("-c")
-        .arg(command)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()
-        .context("Failed to execute command")?;
+    // The caller bounds how long this may take
+    monitor.wait_for_command_completion(&command_id, u64::MAX).await;
+    guard.command_id = None;
+    
+    let finished = monitor.get_command(&command_id)
+        .context(format!("Command {} disappeared from the monitor", command_id))?;
+    let log = fs::read_to_string(&finished.output_file)
+        .context(format!("Failed to read command output {:?}", finished.output_file))?;
     
     // Combine stdout and stderr
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    let stdout: Vec<&str> = log.lines().filter_map(|line| line.strip_prefix("[STDOUT] ")).collect();
+    let stderr: Vec<&str> = log.lines().filter_map(|line| line.strip_prefix("[STDERR] ")).collect();
     
     let mut combined = String::new();
     
     if !stdout.is_empty() {
         combined.push_str("=== STDOUT ===\n");
-        combined.push_str(&stdout);
+        combined.push_str(&stdout.join("\n"));
         combined.push_str("\n");
     }
     
     if !stderr.is_empty() {
         combined.push_str("=== STDERR ===\n");
-        combined.push_str(&stderr);
+        combined.push_str(&stderr.join("\n"));
     }
     
-    Ok(combined)
-} 
\ No newline at end of file
+    Ok(CommandRun {
+        output: combined,
+        success: finished.status == CommandStatus::Completed,
+    })
+}
+
+/// Terminates a monitored command that is abandoned before it finishes
+struct TerminateOnDrop {
+    monitor: Arc<CommandMonitor>,
+    command_id: Option<String>,
+}
+
+impl Drop for TerminateOnDrop {
+    fn drop(&mut self) {
+        if let Some(command_id) = self.command_id.take() {
+            let monitor = self.monitor.clone();
+            tokio::spawn(async move {
+                if let Err(e) = monitor.terminate_command(&command_id).await {
+                    eprintln!("Failed to stop follow-up action command: {}", e);
+                }
+            });
+        }
+    }
+}
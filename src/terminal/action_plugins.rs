@@ -0,0 +1,72 @@
+use anyhow::{Result, Context};
+use async_trait::async_trait;
+use regex::Regex;
+
+use crate::config::ActionHookConfig;
+use super::auto_documentation::{DocumentedFinding, FollowUpAction};
+use crate::utils::{extract_target_from_command, shell_quote};
+
+/// Custom follow-up behaviour for newly documented findings, e.g. "on a new
+/// subdomain, notify the client channel and run my script". Register
+/// implementations with `AutoDocumentation::register_action_plugin`; the
+/// actions they return are queued next to the built-in ones.
+#[async_trait]
+pub trait ActionPlugin: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Follow-up actions for a finding; return none to ignore it
+    async fn generate(&self, finding: &DocumentedFinding) -> Result<Vec<FollowUpAction>>;
+}
+
+/// Runs a configured command for findings whose title matches, see `[[action_hooks]]`
+pub struct CommandHook {
+    name: String,
+    title: Regex,
+    command: String,
+    requires_confirmation: bool,
+}
+
+impl CommandHook {
+    pub fn from_config(config: &ActionHookConfig) -> Result<Self> {
+        Ok(Self {
+            name: config.name.clone(),
+            title: Regex::new(&config.title)
+                .context(format!("Invalid title pattern in action hook {}", config.name))?,
+            command: config.command.clone(),
+            requires_confirmation: config.requires_confirmation,
+        })
+    }
+
+    /// The hook's command with `{id}`, `{title}`, `{severity}`, `{target}` and `{file}`
+    /// replaced by shell-quoted values from the finding
+    fn command_for(&self, finding: &DocumentedFinding) -> String {
+        let target = extract_target_from_command(&finding.discovery_command).unwrap_or_default();
+        self.command
+            .replace("{id}", &shell_quote(&finding.id))
+            .replace("{title}", &shell_quote(&finding.title))
+            .replace("{severity}", &shell_quote(&format!("{:?}", finding.severity)))
+            .replace("{target}", &shell_quote(&target))
+            .replace("{file}", &shell_quote(&finding.file_path.to_string_lossy()))
+    }
+}
+
+#[async_trait]
+impl ActionPlugin for CommandHook {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn generate(&self, finding: &DocumentedFinding) -> Result<Vec<FollowUpAction>> {
+        if !self.title.is_match(&finding.title) {
+            return Ok(Vec::new());
+        }
+
+        let mut action = FollowUpAction::for_finding(
+            finding,
+            format!("Run action hook {} for {}", self.name, finding.id),
+            Some(self.command_for(finding)),
+        );
+        action.requires_confirmation = self.requires_confirmation;
+        Ok(vec![action])
+    }
+}
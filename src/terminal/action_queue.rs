@@ -0,0 +1,113 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use anyhow::{Result, Context};
+
+use super::auto_documentation::{ActionStatus, FollowUpAction};
+use crate::utils::extract_target_from_command;
+use super::command_monitor::normalize_command;
+
+/// Follow-up actions and their status, persisted as JSON in the work dir so
+/// pending verification steps survive a restart
+#[derive(Debug, Clone)]
+pub struct ActionQueue {
+    path: PathBuf,
+    actions: Arc<Mutex<Vec<FollowUpAction>>>,
+}
+
+impl ActionQueue {
+    /// Load the queue from `path`, starting empty if the file does not exist
+    pub fn load(path: &Path) -> Result<Self> {
+        let actions = if path.exists() {
+            let content = fs::read_to_string(path)?;
+            serde_json::from_str(&content)
+                .context(format!("Failed to parse action queue {:?}", path))?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            actions: Arc::new(Mutex::new(actions)),
+        })
+    }
+
+    /// Record a new action or its latest status, and persist the queue
+    pub fn update(&self, action: &FollowUpAction) -> Result<()> {
+        {
+            let mut actions = self.actions.lock().unwrap();
+            match actions.iter_mut().find(|queued| queued.id == action.id) {
+                Some(queued) => *queued = action.clone(),
+                None => actions.push(action.clone()),
+            }
+        }
+        self.save()
+    }
+
+    /// Every action, in the order it was queued
+    pub fn actions(&self) -> Vec<FollowUpAction> {
+        self.actions.lock().unwrap().clone()
+    }
+
+    /// Current status of the action with `id`, if it has been queued
+    pub fn status(&self, id: &str) -> Option<ActionStatus> {
+        self.actions.lock().unwrap().iter()
+            .find(|action| action.id == id)
+            .map(|action| action.status.clone())
+    }
+
+    /// An unfinished action, other than `action` itself, that runs the same command against
+    /// the same target
+    pub fn find_duplicate(&self, action: &FollowUpAction) -> Option<FollowUpAction> {
+        let key = action.command.as_deref().map(dedup_key)?;
+        self.actions.lock().unwrap().iter()
+            .filter(|queued| queued.id != action.id)
+            .filter(|queued| !queued.status.is_finished())
+            .find(|queued| queued.command.as_deref().map(dedup_key).as_ref() == Some(&key))
+            .cloned()
+    }
+
+    /// Actions that had not finished when the queue was saved. Ones interrupted
+    /// mid-run are returned as pending so they start over.
+    pub fn unfinished(&self) -> Vec<FollowUpAction> {
+        self.actions.lock().unwrap().iter()
+            .filter(|action| !action.status.is_finished())
+            .map(|action| FollowUpAction {
+                status: ActionStatus::Pending,
+                ..action.clone()
+            })
+            .collect()
+    }
+
+    fn save(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(&*self.actions.lock().unwrap())?;
+        fs::write(&self.path, content)
+            .context(format!("Failed to save action queue to {:?}", self.path))?;
+        Ok(())
+    }
+}
+
+/// Commands are the same when they differ only in spacing and quoting; flag
+/// values count, so `-p 80` and `-p 443` are different actions
+fn dedup_key(command: &str) -> (String, Option<String>) {
+    (normalize_command(command), extract_target_from_command(command))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_command_with_different_spacing_is_a_duplicate() {
+        assert_eq!(dedup_key("nmap -sV -p 80  example.com"), dedup_key("nmap -sV -p 80 'example.com'"));
+    }
+
+    #[test]
+    fn flag_values_tell_commands_apart() {
+        assert_ne!(
+            dedup_key("hydra -l admin -p secret example.com ssh"),
+            dedup_key("hydra -l secret -p admin example.com ssh"),
+        );
+        assert_ne!(dedup_key("nmap -p 80 -T4 example.com"), dedup_key("nmap -p 4 -T80 example.com"));
+    }
+}
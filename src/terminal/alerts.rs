@@ -0,0 +1,118 @@
+use std::env;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use crossterm::{
+    execute,
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor},
+};
+use notify_rust::Notification;
+
+use crate::config::AlertConfig;
+use super::auto_documentation::DocumentedFinding;
+
+/// Raises an alert when a finding at or above the configured severity is
+/// documented. Its webhook_url is posted to by the `Notifier` like any other webhook
+#[derive(Debug, Clone)]
+pub struct Alerter {
+    config: AlertConfig,
+}
+
+impl Alerter {
+    pub fn new(config: AlertConfig) -> Self {
+        Self { config }
+    }
+
+    /// Whether a finding is severe enough to alert on
+    pub fn should_alert(&self, finding: &DocumentedFinding) -> bool {
+        self.config.enabled && finding.severity.rank() >= self.config.threshold.rank()
+    }
+
+    /// Ring the bell or play the sound configured for a finding's severity
+    pub fn sound(&self, finding: &DocumentedFinding) {
+        if !self.config.enabled {
+            return;
+        }
+        let sound = match self.config.sounds.get(&finding.severity) {
+            Some(sound) => sound.trim(),
+            None if self.config.bell && self.should_alert(finding) => "bell",
+            None => return,
+        };
+
+        if sound == "bell" {
+            print!("\x07");
+            let _ = io::stdout().flush();
+            return;
+        }
+        match Command::new("sh").arg("-c").arg(sound)
+            .stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(mut player) => {
+                thread::spawn(move || player.wait());
+            },
+            Err(e) => eprintln!("Failed to play alert sound `{}`: {}", sound, e),
+        }
+    }
+
+    /// Fire the terminal and desktop alerts for a finding
+    pub fn alert(&self, finding: &DocumentedFinding) {
+        if !self.should_alert(finding) {
+            return;
+        }
+
+        let message = format!("{:?} finding {}: {}", finding.severity, finding.id, finding.title);
+
+        // Make the finding stand out from the scrolling output
+        let _ = execute!(
+            io::stdout(),
+            Print("\n"),
+            SetAttribute(Attribute::Bold),
+            SetBackgroundColor(Color::DarkRed),
+            SetForegroundColor(Color::White),
+            Print(format!(" [ALERT] {} ", message)),
+            ResetColor,
+            SetAttribute(Attribute::Reset),
+            Print("\n"),
+        );
+
+        if self.config.desktop {
+            notify_desktop(&format!("Hacksor: {:?} finding", finding.severity), &message, true);
+        }
+    }
+}
+
+/// Show a desktop notification unless the terminal Hacksor runs in is the
+/// focused window, in which case the user has already seen it. `urgent` ones
+/// stay up until dismissed where the desktop supports it
+pub fn notify_desktop(summary: &str, body: &str, urgent: bool) {
+    let mut notification = Notification::new();
+    notification.appname("Hacksor").summary(summary).body(body);
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if urgent {
+        notification.urgency(notify_rust::Urgency::Critical);
+    }
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    let _ = urgent;
+
+    // Asking xdotool about focus and delivery can both block on the desktop,
+    // so neither happens on the caller's thread; no desktop is not an error
+    thread::spawn(move || {
+        if terminal_focused() == Some(true) {
+            return;
+        }
+        let _ = notification.show();
+    });
+}
+
+/// Whether the terminal is the focused window, where that can be told: X11
+/// terminals export their window as WINDOWID, compared with xdotool's active one
+fn terminal_focused() -> Option<bool> {
+    let window: u64 = env::var("WINDOWID").ok()?.trim().parse().ok()?;
+    let output = Command::new("xdotool").arg("getactivewindow").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let active: u64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+    Some(active == window)
+}
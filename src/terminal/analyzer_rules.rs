@@ -0,0 +1,100 @@
+use std::fs;
+use std::path::Path;
+use anyhow::{Result, Context};
+use regex::{Captures, Regex};
+use serde::{Serialize, Deserialize};
+
+use super::command_monitor::{Confidence, FindingSeverity};
+use super::cvss::CvssScore;
+
+/// A finding-detection rule as written in a YAML rule file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyzerRule {
+    pub name: String,
+    pub pattern: String,
+    pub severity: FindingSeverity,
+    /// Finding title; `{0}` is the whole match and `{1}`, `{2}`... are capture groups
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Optional CVSS v3.1 vector assigned to findings from this rule
+    #[serde(default)]
+    pub cvss_vector: Option<String>,
+    #[serde(default)]
+    pub cwe: Option<String>,
+    #[serde(default)]
+    pub owasp: Option<String>,
+    /// How reliable a match is; defaults to Likely
+    #[serde(default = "default_rule_confidence")]
+    pub confidence: Confidence,
+}
+
+fn default_rule_confidence() -> Confidence {
+    Confidence::Likely
+}
+
+/// A rule with its pattern compiled
+#[derive(Debug, Clone)]
+pub struct CompiledRule {
+    pub rule: AnalyzerRule,
+    pub regex: Regex,
+    pub cvss: Option<CvssScore>,
+}
+
+impl CompiledRule {
+    pub fn compile(rule: AnalyzerRule) -> Result<Self> {
+        let regex = Regex::new(&rule.pattern)
+            .context(format!("Invalid pattern in rule '{}'", rule.name))?;
+        let cvss = rule.cvss_vector.as_deref()
+            .map(CvssScore::from_vector)
+            .transpose()
+            .context(format!("Invalid CVSS vector in rule '{}'", rule.name))?;
+        Ok(Self { rule, regex, cvss })
+    }
+
+    /// Render the title and description for a matching line
+    pub fn render(&self, captures: &Captures) -> (String, String) {
+        let title = fill_template(&self.rule.title, captures);
+        let description = match &self.rule.description {
+            Some(description) => fill_template(description, captures),
+            None => format!("Output matched custom rule '{}'", self.rule.name),
+        };
+        (title, description)
+    }
+}
+
+/// Load every `.yaml`/`.yml` rule file in a directory; each file holds a list of rules
+pub fn load_rules_from_dir(dir: &Path) -> Result<Vec<CompiledRule>> {
+    let mut rules = Vec::new();
+
+    if !dir.exists() {
+        return Ok(rules);
+    }
+
+    let mut paths: Vec<_> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| matches!(path.extension().and_then(|ext| ext.to_str()), Some("yaml") | Some("yml")))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let content = fs::read_to_string(&path)?;
+        let file_rules: Vec<AnalyzerRule> = serde_yaml::from_str(&content)
+            .context(format!("Failed to parse rule file {:?}", path))?;
+
+        for rule in file_rules {
+            rules.push(CompiledRule::compile(rule)
+                .context(format!("Failed to load rule from {:?}", path))?);
+        }
+    }
+
+    Ok(rules)
+}
+
+fn fill_template(template: &str, captures: &Captures) -> String {
+    let mut text = template.to_string();
+    for (i, group) in captures.iter().enumerate() {
+        text = text.replace(&format!("{{{}}}", i), group.map_or("", |m| m.as_str()));
+    }
+    text
+}
@@ -0,0 +1,138 @@
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::oneshot;
+
+/// The user's answer to an approval prompt
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApprovalDecision {
+    Approve,
+    Reject,
+    /// Run this command instead of the one proposed
+    Edit(String),
+}
+
+/// A command waiting for the user to approve or reject it
+struct PendingApproval {
+    command: String,
+    /// Follow-up action the command belongs to; these can also be edited
+    action: Option<String>,
+    responder: oneshot::Sender<ApprovalDecision>,
+}
+
+/// Routes commands through an interactive confirmation before they run.
+///
+/// Background tasks call `request` and wait; the main input loop answers
+/// the oldest pending request with the next line the user types.
+#[derive(Clone, Default)]
+pub struct ApprovalGate {
+    enabled: Arc<AtomicBool>,
+    review_actions: Arc<AtomicBool>,
+    pending: Arc<Mutex<VecDeque<PendingApproval>>>,
+}
+
+impl ApprovalGate {
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::SeqCst);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::SeqCst)
+    }
+
+    /// Present every generated follow-up action for approve/skip/edit before it runs
+    pub fn set_review_actions(&self, review: bool) {
+        self.review_actions.store(review, Ordering::SeqCst);
+    }
+
+    pub fn reviews_actions(&self) -> bool {
+        self.review_actions.load(Ordering::SeqCst)
+    }
+
+    /// Ask the user to approve a command, returning whether it may run
+    pub async fn request(&self, command: &str) -> bool {
+        if !self.is_enabled() {
+            return true;
+        }
+
+        self.confirm(command).await
+    }
+
+    /// Ask the user to approve a command even when approval mode is off
+    pub async fn confirm(&self, command: &str) -> bool {
+        self.ask(command, None).await == ApprovalDecision::Approve
+    }
+
+    /// Present a follow-up action's command, which the user may approve, skip or replace
+    pub async fn review(&self, action: &str, command: &str) -> ApprovalDecision {
+        self.ask(command, Some(action)).await
+    }
+
+    async fn ask(&self, command: &str, action: Option<&str>) -> ApprovalDecision {
+        let (responder, response) = oneshot::channel();
+        {
+            let mut pending = self.pending.lock().unwrap();
+            pending.push_back(PendingApproval {
+                command: command.to_string(),
+                action: action.map(String::from),
+                responder,
+            });
+
+            // Only prompt for the head of the queue; the rest are prompted as it drains
+            if pending.len() == 1 {
+                print_prompt(&pending[0]);
+            }
+        }
+
+        response.await.unwrap_or(ApprovalDecision::Reject)
+    }
+
+    /// Whether a command is waiting for an answer
+    pub fn has_pending(&self) -> bool {
+        !self.pending.lock().unwrap().is_empty()
+    }
+
+    /// The question for the oldest pending request
+    pub fn pending_prompt(&self) -> Option<String> {
+        self.pending.lock().unwrap().front().map(prompt_text)
+    }
+
+    /// Answer the oldest pending request with the user's input
+    pub fn answer(&self, input: &str) -> Option<(String, ApprovalDecision)> {
+        let mut pending = self.pending.lock().unwrap();
+        let request = pending.pop_front()?;
+
+        let input = input.trim();
+        let decision = match input.to_lowercase().as_str() {
+            "y" | "yes" => ApprovalDecision::Approve,
+            "a" | "approve" if request.action.is_some() => ApprovalDecision::Approve,
+            _ => match input.strip_prefix("e ").map(str::trim) {
+                Some(edited) if request.action.is_some() && !edited.is_empty() => ApprovalDecision::Edit(edited.to_string()),
+                _ => ApprovalDecision::Reject,
+            },
+        };
+        let _ = request.responder.send(decision.clone());
+
+        if let Some(next) = pending.front() {
+            print_prompt(next);
+        }
+
+        Some((request.command, decision))
+    }
+}
+
+fn prompt_text(request: &PendingApproval) -> String {
+    match &request.action {
+        Some(action) => format!(
+            "Follow-up action: {}\n    $ {}\n[a]pprove, [s]kip, or [e] <command> to run an edited command?",
+            action, request.command
+        ),
+        None => format!("Run `{}`? [y/N]", request.command),
+    }
+}
+
+fn print_prompt(request: &PendingApproval) {
+    print!("\n[APPROVAL] {} ", prompt_text(request));
+    let _ = io::stdout().flush();
+}
@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use anyhow::{Result, Context};
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AssetKind {
+    Host,
+    Subdomain,
+    Url,
+    Service,
+}
+
+/// Something discovered about a target during the session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Asset {
+    /// Unique key, e.g. `service:example.com:443/tcp`
+    pub id: String,
+    pub kind: AssetKind,
+    /// Host name or IP this asset belongs to
+    pub host: String,
+    /// Display value: the host, subdomain, URL or `port/protocol`
+    pub value: String,
+    pub service: Option<String>,
+    pub first_seen: DateTime<Utc>,
+    /// Commands whose output revealed the asset
+    pub command_ids: Vec<String>,
+    /// Documented findings related to the asset
+    pub finding_ids: Vec<String>,
+}
+
+/// What a passive source reports about a host, e.g. VirusTotal's resolutions
+/// and categories, shown with the host's assets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PassiveIntel {
+    pub host: String,
+    pub source: String,
+    pub collected: DateTime<Utc>,
+    pub lines: Vec<String>,
+}
+
+/// Hosts, subdomains, URLs and services discovered by the analyzers
+#[derive(Debug, Clone, Default)]
+pub struct AssetInventory {
+    assets: Arc<Mutex<BTreeMap<String, Asset>>>,
+    /// Keyed by host, then source
+    intel: Arc<Mutex<BTreeMap<(String, String), PassiveIntel>>>,
+}
+
+impl AssetInventory {
+    pub fn record_host(&self, host: &str, command_id: &str) {
+        let host = host.to_lowercase();
+        self.record(format!("host:{}", host), AssetKind::Host, &host, &host, None, command_id);
+    }
+
+    /// Record a subdomain, returning whether it is new to the inventory, so a
+    /// subdomain several sources find is only reported once
+    pub fn record_subdomain(&self, subdomain: &str, command_id: &str) -> bool {
+        let subdomain = subdomain.to_lowercase();
+        self.record(format!("subdomain:{}", subdomain), AssetKind::Subdomain, &subdomain, &subdomain, None, command_id)
+    }
+
+    /// Record a URL and its host, returning whether the URL is new to the inventory
+    pub fn record_url(&self, url: &str, command_id: &str) -> bool {
+        let host = url.trim_start_matches("http://").trim_start_matches("https://");
+        let host = host.split(['/', ':', '?']).next().unwrap_or(host).to_lowercase();
+        self.record_host(&host, command_id);
+        self.record(format!("url:{}", url), AssetKind::Url, &host, url, None, command_id)
+    }
+
+    /// Record an open port, e.g. `port` = `443/tcp`, `service` = `https`
+    pub fn record_service(&self, host: &str, port: &str, service: Option<&str>, command_id: &str) {
+        let host = host.to_lowercase();
+        self.record_host(&host, command_id);
+        self.record(
+            format!("service:{}:{}", host, port),
+            AssetKind::Service,
+            &host,
+            port,
+            service.filter(|service| !service.is_empty()),
+            command_id,
+        );
+    }
+
+    /// Record an asset, returning whether it is new
+    fn record(&self, id: String, kind: AssetKind, host: &str, value: &str, service: Option<&str>, command_id: &str) -> bool {
+        let mut assets = self.assets.lock().unwrap();
+        let new = !assets.contains_key(&id);
+        let asset = assets.entry(id.clone()).or_insert_with(|| Asset {
+            id,
+            kind,
+            host: host.to_string(),
+            value: value.to_string(),
+            service: None,
+            first_seen: Utc::now(),
+            command_ids: Vec::new(),
+            finding_ids: Vec::new(),
+        });
+
+        if asset.service.is_none() {
+            asset.service = service.map(String::from);
+        }
+        if !asset.command_ids.iter().any(|id| id == command_id) {
+            asset.command_ids.push(command_id.to_string());
+        }
+        new
+    }
+
+    /// Relate a documented finding to a host, creating the host if it is new
+    pub fn link_finding(&self, host: &str, finding_id: &str) {
+        let host = host.to_lowercase();
+        let mut assets = self.assets.lock().unwrap();
+        let asset = assets.entry(format!("host:{}", host)).or_insert_with(|| Asset {
+            id: format!("host:{}", host),
+            kind: AssetKind::Host,
+            host: host.clone(),
+            value: host.clone(),
+            service: None,
+            first_seen: Utc::now(),
+            command_ids: Vec::new(),
+            finding_ids: Vec::new(),
+        });
+
+        if !asset.finding_ids.iter().any(|id| id == finding_id) {
+            asset.finding_ids.push(finding_id.to_string());
+        }
+    }
+
+    /// Attach a passive source's report to a host, replacing its earlier one
+    pub fn record_intel(&self, host: &str, source: &str, lines: Vec<String>) {
+        let host = host.to_lowercase();
+        self.intel.lock().unwrap().insert((host.clone(), source.to_string()), PassiveIntel {
+            host,
+            source: source.to_string(),
+            collected: Utc::now(),
+            lines,
+        });
+    }
+
+    /// The passive sources' reports on a host
+    pub fn intel_for(&self, host: &str) -> Vec<PassiveIntel> {
+        let host = host.to_lowercase();
+        self.intel.lock().unwrap().values()
+            .filter(|intel| intel.host == host)
+            .cloned()
+            .collect()
+    }
+
+    /// Every asset belonging to a host or one of its subdomains
+    pub fn assets_for(&self, host: &str) -> Vec<Asset> {
+        let host = host.to_lowercase();
+        let suffix = format!(".{}", host);
+        let assets = self.assets.lock().unwrap();
+        assets.values()
+            .filter(|asset| asset.host == host || asset.host.ends_with(&suffix))
+            .cloned()
+            .collect()
+    }
+
+    /// Web pages revealed by a command: its URLs, and its subdomains over HTTPS
+    pub fn web_urls_for_command(&self, command_id: &str) -> Vec<String> {
+        let assets = self.assets.lock().unwrap();
+        assets.values()
+            .filter(|asset| asset.command_ids.iter().any(|id| id == command_id))
+            .filter_map(|asset| match asset.kind {
+                AssetKind::Url => Some(asset.value.clone()),
+                AssetKind::Subdomain => Some(format!("https://{}", asset.value)),
+                _ => None,
+            })
+            .collect()
+    }
+    
+    /// URLs taking query parameters, for parameter-based testing
+    pub fn parameterized_urls(&self) -> Vec<String> {
+        let assets = self.assets.lock().unwrap();
+        assets.values()
+            .filter(|asset| asset.kind == AssetKind::Url && asset.value.contains('?'))
+            .map(|asset| asset.value.clone())
+            .collect()
+    }
+    
+    /// Known hosts and subdomains
+    pub fn hosts(&self) -> Vec<String> {
+        let assets = self.assets.lock().unwrap();
+        assets.values()
+            .filter(|asset| matches!(asset.kind, AssetKind::Host | AssetKind::Subdomain))
+            .map(|asset| asset.value.clone())
+            .collect()
+    }
+
+    /// Write the inventory to a JSON file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let assets: Vec<Asset> = self.assets.lock().unwrap().values().cloned().collect();
+        let content = serde_json::to_string_pretty(&assets)?;
+        fs::write(path, content)
+            .context(format!("Failed to write asset inventory to {:?}", path))?;
+        Ok(())
+    }
+
+    /// Write the passive sources' reports to a JSON file, if there are any
+    pub fn save_intel(&self, path: &Path) -> Result<()> {
+        let intel: Vec<PassiveIntel> = self.intel.lock().unwrap().values().cloned().collect();
+        if intel.is_empty() {
+            return Ok(());
+        }
+        let content = serde_json::to_string_pretty(&intel)?;
+        fs::write(path, content)
+            .context(format!("Failed to write passive intel to {:?}", path))?;
+        Ok(())
+    }
+}
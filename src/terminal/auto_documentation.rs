@@ -1,16 +1,33 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use anyhow::{Result, Context, anyhow};
 use tokio::sync::mpsc;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs::{self, OpenOptions};
 use std::io::Write;
+use std::process::Stdio;
+use std::time::Duration;
 use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
 use uuid::Uuid;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use regex::Regex;
 
-use super::command_monitor::{SecurityFinding, FindingSeverity, CommandMonitor};
+use super::command_monitor::{SecurityFinding, FindingSeverity, Confidence, CommandMonitor};
+use super::cvss::{self, CvssScore};
+use super::cve_db::CveMatch;
+use super::nvd::{self, NvdClient};
+use super::searchsploit::ExploitCandidate;
+use super::alerts::Alerter;
+use super::events::{FindingEventKind, FindingEventStream};
+use super::timeline::ActivityKind;
+use super::screenshots::{Screenshot, Screenshotter};
+use super::severity_matrix::SeverityMatrix;
+use super::action_plugins::ActionPlugin;
+use super::evidence_git::EvidenceRepo;
+use super::finding_ids::FindingIdScheme;
+use super::report_templates::{ReportTemplates, SUMMARY_TEMPLATE, HTML_TEMPLATE};
+use crate::config::DefaultCredentialsConfig;
+use crate::utils::{extract_target_from_command, shell_quote};
 
 /// Represents a documented finding in Markdown format
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +42,36 @@ pub struct DocumentedFinding {
     pub follow_up_actions: Vec<FollowUpAction>,
     pub status: FindingStatus,
     pub file_path: PathBuf,
+    #[serde(default)]
+    pub cvss: Option<CvssScore>,
+    #[serde(default)]
+    pub cwe: Option<String>,
+    #[serde(default)]
+    pub owasp: Option<String>,
+    #[serde(default)]
+    pub related_cves: Vec<CveMatch>,
+    #[serde(default)]
+    pub confidence: Confidence,
+    #[serde(default)]
+    pub screenshots: Vec<Screenshot>,
+    /// Exploit-DB candidates from the finding's `searchsploit -j` follow-up
+    #[serde(default)]
+    pub exploits: Vec<ExploitCandidate>,
+    /// Issues created for this finding in external trackers, e.g. `jira` -> `SEC-42`
+    #[serde(default)]
+    pub external_refs: BTreeMap<String, String>,
+    /// Tester responsible for the finding
+    #[serde(default)]
+    pub assignee: Option<String>,
+    #[serde(default)]
+    pub notes: Vec<FindingNote>,
+}
+
+/// A tester's note on a finding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FindingNote {
+    pub timestamp: DateTime<Utc>,
+    pub text: String,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -34,34 +81,109 @@ pub enum FindingStatus {
     Verified,
     Documented,
     Closed,
+    FalsePositive,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FollowUpAction {
     pub id: String,
+    /// Finding the action follows up on
+    #[serde(default)]
+    pub finding_id: Option<String>,
     pub description: String,
     pub command: Option<String>,
     pub status: ActionStatus,
     pub result: Option<String>,
+    /// Always ask the user before running, even outside approval mode
+    #[serde(default)]
+    pub requires_confirmation: bool,
+    /// IDs of actions that must complete before this one runs
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// Ready actions run highest priority first
+    #[serde(default)]
+    pub priority: ActionPriority,
+}
+
+impl FollowUpAction {
+    /// A pending action for `finding`, prioritised by its severity
+    pub fn for_finding(finding: &DocumentedFinding, description: impl Into<String>, command: Option<String>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            finding_id: Some(finding.id.clone()),
+            description: description.into(),
+            command,
+            status: ActionStatus::Pending,
+            result: None,
+            requires_confirmation: false,
+            depends_on: Vec::new(),
+            priority: ActionPriority::for_severity(&finding.severity),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ActionPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Critical,
+}
+
+impl ActionPriority {
+    /// Priority for verifying a finding of the given severity
+    pub fn for_severity(severity: &FindingSeverity) -> Self {
+        match severity {
+            FindingSeverity::Critical => ActionPriority::Critical,
+            FindingSeverity::High => ActionPriority::High,
+            FindingSeverity::Medium => ActionPriority::Normal,
+            FindingSeverity::Low | FindingSeverity::Info => ActionPriority::Low,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ActionStatus {
     Pending,
+    /// Waiting for the testing window to open
+    Scheduled,
     InProgress,
     Completed,
     Failed,
 }
 
+impl ActionStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, ActionStatus::Completed | ActionStatus::Failed)
+    }
+}
+
+/// Documented findings by ID, shared with the interactive commands
+pub type FindingStore = Arc<Mutex<HashMap<String, DocumentedFinding>>>;
+
 /// Manages automatic documentation of security findings
 pub struct AutoDocumentation {
     monitor: Arc<CommandMonitor>,
     finding_rx: mpsc::Receiver<SecurityFinding>,
-    documented_findings: HashMap<String, DocumentedFinding>,
+    documented_findings: FindingStore,
     work_dir: PathBuf,
     findings_dir: PathBuf,
     running: bool,
     follow_up_tx: mpsc::Sender<FollowUpAction>,
+    default_credentials: DefaultCredentialsConfig,
+    alerter: Option<Alerter>,
+    events: Option<FindingEventStream>,
+    templates: Arc<ReportTemplates>,
+    screenshotter: Option<Screenshotter>,
+    severity_matrix: SeverityMatrix,
+    evidence: Option<EvidenceRepo>,
+    finding_ids: FindingIdScheme,
+    plugins: Vec<Box<dyn ActionPlugin>>,
+    /// Looks up the CVEs findings mention; without it a lookup command is queued
+    nvd: Option<NvdClient>,
+    /// Documented finding IDs by the merge key of the finding they came from
+    merged: HashMap<String, String>,
 }
 
 impl AutoDocumentation {
@@ -78,14 +200,91 @@ impl AutoDocumentation {
         Ok(Self {
             monitor,
             finding_rx,
-            documented_findings: HashMap::new(),
+            documented_findings: Arc::new(Mutex::new(HashMap::new())),
             work_dir,
             findings_dir,
             running: false,
             follow_up_tx,
+            default_credentials: DefaultCredentialsConfig::default(),
+            alerter: None,
+            events: None,
+            templates: Arc::new(ReportTemplates::builtin()),
+            screenshotter: None,
+            severity_matrix: SeverityMatrix::default(),
+            evidence: None,
+            finding_ids: FindingIdScheme::default(),
+            plugins: Vec::new(),
+            nvd: None,
+            merged: HashMap::new(),
         })
     }
     
+    /// Get the shared store of documented findings
+    pub fn get_finding_store(&self) -> FindingStore {
+        self.documented_findings.clone()
+    }
+    
+    /// Get a handle for generating reports on demand, with the templates and
+    /// evidence repository set so far
+    pub fn get_report_generator(&self) -> ReportGenerator {
+        ReportGenerator {
+            monitor: self.monitor.clone(),
+            findings: self.documented_findings.clone(),
+            templates: self.templates.clone(),
+            evidence: self.evidence.clone(),
+        }
+    }
+    
+    /// Set the alerter fired for severe findings
+    pub fn set_alerter(&mut self, alerter: Alerter) {
+        self.alerter = Some(alerter);
+    }
+    
+    /// Set the stream that created and updated findings are published to
+    pub fn set_event_stream(&mut self, events: FindingEventStream) {
+        self.events = Some(events);
+    }
+    
+    /// Set the templates reports are rendered with
+    pub fn set_report_templates(&mut self, templates: ReportTemplates) {
+        self.templates = Arc::new(templates);
+    }
+    
+    /// Set the screenshotter used for discovered web paths and subdomains
+    pub fn set_screenshotter(&mut self, screenshotter: Screenshotter) {
+        self.screenshotter = Some(screenshotter);
+    }
+    
+    /// Set the format documented findings are numbered with
+    pub fn set_finding_ids(&mut self, finding_ids: FindingIdScheme) {
+        self.finding_ids = finding_ids;
+    }
+    
+    /// Set the git repository findings and reports are committed to
+    pub fn set_evidence_repo(&mut self, evidence: EvidenceRepo) {
+        self.evidence = Some(evidence);
+    }
+    
+    /// Set the client-specific severities that replace the analyzers' defaults
+    pub fn set_severity_matrix(&mut self, matrix: SeverityMatrix) {
+        self.severity_matrix = matrix;
+    }
+    
+    /// Set the default credentials tested against detected admin interfaces
+    pub fn set_default_credentials(&mut self, config: DefaultCredentialsConfig) {
+        self.default_credentials = config;
+    }
+    
+    /// Set the NVD client CVE findings are followed up with
+    pub fn set_nvd_client(&mut self, nvd: NvdClient) {
+        self.nvd = Some(nvd);
+    }
+    
+    /// Add a plugin that generates follow-up actions alongside the built-in ones
+    pub fn register_action_plugin(&mut self, plugin: Box<dyn ActionPlugin>) {
+        self.plugins.push(plugin);
+    }
+    
     /// Start the auto-documentation process
     pub async fn start(&mut self) -> Result<()> {
         if self.running {
@@ -96,14 +295,49 @@ impl AutoDocumentation {
         
         // Main documentation loop
         while let Some(finding) = self.finding_rx.recv().await {
+            let command_id = finding.command_id.clone();
+            
+            // Findings that grow over the session update what was documented first
+            if let Some(id) = finding.merge_key.as_ref().and_then(|key| self.merged.get(key)).cloned() {
+                if let Err(e) = self.merge_finding(&id, finding) {
+                    eprintln!("Failed to update finding {}: {}", id, e);
+                }
+                continue;
+            }
+            
             // Generate a documented finding
             let documented = self.document_finding(finding).await?;
+            match &self.alerter {
+                Some(alerter) if alerter.should_alert(&documented) => alerter.alert(&documented),
+                _ => println!("\n[FINDING {}] {:?}: {}", documented.id, documented.severity, documented.title),
+            }
+            if let Some(alerter) = &self.alerter {
+                alerter.sound(&documented);
+            }
+            self.monitor.get_notifier().finding_documented(&documented);
+            
+            // Screenshot discovered pages in the background and add them to the finding
+            self.capture_screenshots(&documented, &command_id);
             
             // Generate follow-up actions
-            let actions = self.generate_follow_up_actions(&documented).await?;
+            let mut actions = self.generate_follow_up_actions(&documented).await?;
+            for plugin in &self.plugins {
+                match plugin.generate(&documented).await {
+                    Ok(generated) => actions.extend(generated),
+                    Err(e) => eprintln!("Action plugin {} failed for {}: {:#}", plugin.name(), documented.id, e),
+                }
+            }
             
             // Queue follow-up actions
             for action in actions {
+                // Actions resolved while they were generated, such as NVD lookups, only need recording
+                if action.status.is_finished() {
+                    if let Err(e) = self.add_follow_up_to_finding(&documented.id, action) {
+                        eprintln!("Failed to record follow-up action: {}", e);
+                    }
+                    continue;
+                }
+                
                 if let Err(e) = self.follow_up_tx.send(action).await {
                     eprintln!("Failed to queue follow-up action: {}", e);
                 }
@@ -120,13 +354,21 @@ impl AutoDocumentation {
         let command = self.monitor.get_command(&finding.command_id)
             .context("Failed to get command information for finding")?;
         
-        // Create a unique ID for the documented finding if not already existing
-        let doc_id = format!("FINDING-{}", Uuid::new_v4().to_string().split('-').next().unwrap_or("UNKNOWN"));
+        // Apply the engagement's severity matrix over the analyzer's default
+        let target = extract_target_from_command(&command.command);
+        let severity = self.severity_matrix.severity_for(&finding.title, target.as_deref())
+            .unwrap_or(finding.severity);
+        
+        // Number the finding using the engagement's ID scheme
+        let doc_id = self.finding_ids.next(&severity)?;
+        if let Some(key) = finding.merge_key {
+            self.merged.insert(key, doc_id.clone());
+        }
         
         // Create file path for the finding
         let file_name = format!("{}_{}_{}.md", 
             chrono::Utc::now().format("%Y%m%d"),
-            doc_id,
+            doc_id.replace(['/', '\\'], "_"),
             finding.title.to_lowercase().replace(' ', "_").replace(|c: char| !c.is_alphanumeric() && c != '_', "")
         );
         
@@ -137,24 +379,112 @@ impl AutoDocumentation {
             id: doc_id,
             title: finding.title,
             description: finding.description,
-            severity: finding.severity,
+            severity,
             discovery_date: finding.timestamp,
             discovery_command: command.command.clone(),
             raw_evidence: finding.raw_output,
             follow_up_actions: Vec::new(),
             status: FindingStatus::New,
             file_path: file_path.clone(),
+            cvss: finding.cvss,
+            cwe: finding.cwe,
+            owasp: finding.owasp,
+            related_cves: finding.related_cves,
+            confidence: finding.confidence,
+            screenshots: Vec::new(),
+            exploits: Vec::new(),
+            external_refs: BTreeMap::new(),
+            assignee: None,
+            notes: Vec::new(),
         };
         
+        // Relate the finding to the host it was found on
+        if let Some(target) = extract_target_from_command(&documented.discovery_command) {
+            self.monitor.get_asset_inventory().link_finding(&target, &documented.id);
+        }
+        
         // Save the finding to disk
-        self.save_finding_to_file(&documented)?;
+        write_finding_file(&documented)?;
         
         // Store in memory
-        self.documented_findings.insert(documented.id.clone(), documented.clone());
+        self.documented_findings.lock().unwrap().insert(documented.id.clone(), documented.clone());
+        
+        if let Some(events) = &self.events {
+            events.emit(FindingEventKind::Created, &documented);
+        }
+        self.monitor.get_timeline().record(
+            ActivityKind::FindingCreated,
+            format!("[{:?}] {}", documented.severity, documented.title),
+            Some(&documented.id),
+        );
+        if let Some(evidence) = &self.evidence {
+            evidence.commit(format!("Document {}: [{:?}] {}", documented.id, documented.severity, documented.title));
+        }
         
         Ok(documented)
     }
     
+    /// Replace a documented finding's description and evidence with those of a
+    /// later finding under the same merge key, keeping its ID, status and notes
+    fn merge_finding(&self, finding_id: &str, finding: SecurityFinding) -> Result<()> {
+        let updated = self.documented_findings.lock().unwrap().get_mut(finding_id).map(|documented| {
+            documented.description = finding.description;
+            documented.raw_evidence = finding.raw_output;
+            documented.clone()
+        });
+        let Some(documented) = updated else {
+            return Err(anyhow!("Finding not found: {}", finding_id));
+        };
+        
+        write_finding_file(&documented)?;
+        if let Some(events) = &self.events {
+            events.emit(FindingEventKind::Updated, &documented);
+        }
+        if let Some(evidence) = &self.evidence {
+            evidence.commit(format!("Update {}: {}", documented.id, documented.title));
+        }
+        println!("\n[FINDING {} UPDATED] {}", documented.id, documented.description);
+        Ok(())
+    }
+    
+    /// Capture the pages a finding's command discovered, then re-save the finding with them
+    fn capture_screenshots(&self, finding: &DocumentedFinding, command_id: &str) {
+        let Some(screenshotter) = self.screenshotter.clone().filter(|s| s.applies_to(&finding.title)) else {
+            return;
+        };
+        let urls = self.monitor.get_asset_inventory().web_urls_for_command(command_id);
+        if urls.is_empty() {
+            return;
+        }
+        
+        let store = self.documented_findings.clone();
+        let events = self.events.clone();
+        let evidence = self.evidence.clone();
+        let finding_id = finding.id.clone();
+        tokio::spawn(async move {
+            let screenshots = screenshotter.capture_all(&urls).await;
+            if screenshots.is_empty() {
+                return;
+            }
+            
+            let updated = store.lock().unwrap().get_mut(&finding_id).map(|finding| {
+                finding.screenshots = screenshots;
+                finding.clone()
+            });
+            if let Some(finding) = updated {
+                if let Err(e) = write_finding_file(&finding) {
+                    eprintln!("Failed to save screenshots for finding {}: {}", finding.id, e);
+                }
+                if let Some(events) = &events {
+                    events.emit(FindingEventKind::Updated, &finding);
+                }
+                if let Some(evidence) = &evidence {
+                    evidence.commit(format!("Add screenshots to {}", finding.id));
+                }
+            }
+        });
+    }
+    
     /// Generate follow-up actions based on the finding
     async fn generate_follow_up_actions(&self, finding: &DocumentedFinding) -> Result<Vec<FollowUpAction>> {
         let mut actions = Vec::new();
@@ -162,10 +492,14 @@ impl AutoDocumentation {
         // Common follow-up: Document the finding fully
         actions.push(FollowUpAction {
             id: Uuid::new_v4().to_string(),
+            finding_id: Some(finding.id.clone()),
             description: format!("Update documentation for finding {}", finding.id),
             command: None,
             status: ActionStatus::Pending,
             result: None,
+            requires_confirmation: false,
+            depends_on: Vec::new(),
+            priority: ActionPriority::Low,
         });
         
         // Different follow-up actions based on finding type
@@ -190,10 +524,14 @@ impl AutoDocumentation {
                     
                     actions.push(FollowUpAction {
                         id: Uuid::new_v4().to_string(),
+                        finding_id: Some(finding.id.clone()),
                         description: format!("Perform service version detection on ports: {}", ports),
                         command: Some(command),
                         status: ActionStatus::Pending,
                         result: None,
+                        requires_confirmation: false,
+                        depends_on: Vec::new(),
+                        priority: ActionPriority::for_severity(&finding.severity),
                     });
                 }
             }
@@ -217,14 +555,33 @@ impl AutoDocumentation {
                 }
                 
                 // Create follow-up action to check for alive hosts
+                let alive_check_id = Uuid::new_v4().to_string();
+                let alive_subdomains_file = self.work_dir.join("alive_subdomains.txt");
                 actions.push(FollowUpAction {
-                    id: Uuid::new_v4().to_string(),
+                    id: alive_check_id.clone(),
+                    finding_id: Some(finding.id.clone()),
                     description: "Check which subdomains are active and resolve".to_string(),
                     command: Some(format!("cat {:?} | httpx -silent -o {:?}", 
                         subdomains_file, 
-                        self.work_dir.join("alive_subdomains.txt"))),
+                        alive_subdomains_file)),
                     status: ActionStatus::Pending,
                     result: None,
+                    requires_confirmation: false,
+                    depends_on: Vec::new(),
+                    priority: ActionPriority::for_severity(&finding.severity),
+                });
+                
+                // Only scan the hosts the alive check found
+                actions.push(FollowUpAction {
+                    id: Uuid::new_v4().to_string(),
+                    finding_id: Some(finding.id.clone()),
+                    description: "Scan active subdomains for known vulnerabilities".to_string(),
+                    command: Some(format!("nuclei -silent -l {:?}", alive_subdomains_file)),
+                    status: ActionStatus::Pending,
+                    result: None,
+                    requires_confirmation: false,
+                    depends_on: vec![alive_check_id],
+                    priority: ActionPriority::for_severity(&finding.severity),
                 });
             }
         } else if finding.title.contains("Path") || finding.title.contains("Directory") {
@@ -232,10 +589,14 @@ impl AutoDocumentation {
             // No specific command here as it depends on the type of path/directory
             actions.push(FollowUpAction {
                 id: Uuid::new_v4().to_string(),
+                finding_id: Some(finding.id.clone()),
                 description: "Manually analyze discovered paths for security vulnerabilities".to_string(),
                 command: None,
                 status: ActionStatus::Pending,
                 result: None,
+                requires_confirmation: false,
+                depends_on: Vec::new(),
+                priority: ActionPriority::for_severity(&finding.severity),
             });
         } else if finding.title.contains("Version") {
             // For version disclosures, look for known vulnerabilities
@@ -250,114 +611,93 @@ impl AutoDocumentation {
                         // Search for known vulnerabilities
                         actions.push(FollowUpAction {
                             id: Uuid::new_v4().to_string(),
+                            finding_id: Some(finding.id.clone()),
                             description: format!("Search for known vulnerabilities in {} {}", software, version),
-                            command: Some(format!("searchsploit {} {}", software, version)),
+                            command: Some(format!("searchsploit -j {} {}", software, version)),
                             status: ActionStatus::Pending,
                             result: None,
+                            requires_confirmation: false,
+                            depends_on: Vec::new(),
+                            priority: ActionPriority::for_severity(&finding.severity),
                         });
                     }
                 }
             }
         } else if finding.title.contains("CVE") {
-            // For CVEs, get more information
-            let cve_pattern = Regex::new(r"(CVE-\d{4}-\d{4,7})").unwrap();
-            
-            if let Some(cap) = cve_pattern.captures(&finding.description) {
-                if let Some(cve_id) = cap.get(1) {
-                    // Look up CVE details
-                    actions.push(FollowUpAction {
-                        id: Uuid::new_v4().to_string(),
-                        description: format!("Gather detailed information about {}", cve_id.as_str()),
-                        command: Some(format!("curl -s https://cve.circl.lu/api/cve/{}", cve_id.as_str())),
-                        status: ActionStatus::Pending,
-                        result: None,
-                    });
+            // For CVEs, pull NVD's description, CVSS vector and references
+            for id in nvd::cve_ids(&finding.description) {
+                let description = format!("Gather detailed information about {}", id);
+                let Some(nvd) = &self.nvd else {
+                    let command = format!("curl -s '{}?cveId={}'", nvd::API_URL, id);
+                    actions.push(FollowUpAction::for_finding(finding, description, Some(command)));
+                    continue;
+                };
+                
+                let mut action = FollowUpAction::for_finding(finding, description, None);
+                match nvd.lookup(&id).await {
+                    Ok(details) => {
+                        action.status = ActionStatus::Completed;
+                        action.result = Some(details.report());
+                    },
+                    Err(e) => {
+                        action.status = ActionStatus::Failed;
+                        action.result = Some(format!("NVD lookup failed: {:#}", e));
+                    },
                 }
+                actions.push(action);
+            }
+        } else if finding.title.starts_with("Admin Interface Detected") {
+            // Trying credentials against a live login is intrusive, so it is opt-in and always confirmed
+            let url = finding.raw_evidence.lines()
+                .find_map(|line| line.strip_prefix("url: "));
+            
+            if let (true, Some(url)) = (self.default_credentials.enabled, url) {
+                let credentials = self.default_credentials.credentials.iter()
+                    .map(|credential| shell_quote(credential))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                
+                actions.push(FollowUpAction {
+                    id: Uuid::new_v4().to_string(),
+                    finding_id: Some(finding.id.clone()),
+                    description: format!("Test {} default credentials against {}", self.default_credentials.credentials.len(), url),
+                    command: Some(format!(
+                        "for cred in {}; do echo \"$cred $(curl -sk -o /dev/null -w '%{{http_code}}' -u \"$cred\" {})\"; done",
+                        credentials, shell_quote(url)
+                    )),
+                    status: ActionStatus::Pending,
+                    result: None,
+                    requires_confirmation: true,
+                    depends_on: Vec::new(),
+                    priority: ActionPriority::for_severity(&finding.severity),
+                });
             }
         } else if finding.title.contains("XSS") || finding.title.contains("Injection") {
             // For potential XSS/Injection, suggest manual verification
             actions.push(FollowUpAction {
                 id: Uuid::new_v4().to_string(),
+                finding_id: Some(finding.id.clone()),
                 description: format!("Manually verify the {} finding", 
                     if finding.title.contains("XSS") { "XSS" } else { "SQL Injection" }),
                 command: None,
                 status: ActionStatus::Pending,
                 result: None,
+                requires_confirmation: false,
+                depends_on: Vec::new(),
+                priority: ActionPriority::for_severity(&finding.severity),
             });
         }
         
         Ok(actions)
     }
     
-    /// Save a documented finding to a Markdown file
-    fn save_finding_to_file(&self, finding: &DocumentedFinding) -> Result<()> {
-        let mut file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&finding.file_path)?;
-        
-        // Write Markdown format
-        writeln!(file, "# {} ({})", finding.title, finding.id)?;
-        writeln!(file, "")?;
-        writeln!(file, "## Description")?;
-        writeln!(file, "{}", finding.description)?;
-        writeln!(file, "")?;
-        writeln!(file, "**Severity:** {:?}", finding.severity)?;
-        writeln!(file, "**Discovery Date:** {}", finding.discovery_date.format("%Y-%m-%d %H:%M:%S UTC"))?;
-        writeln!(file, "**Status:** {:?}", finding.status)?;
-        writeln!(file, "")?;
-        writeln!(file, "## Discovery Method")?;
-        writeln!(file, "```")?;
-        writeln!(file, "{}", finding.discovery_command)?;
-        writeln!(file, "```")?;
-        writeln!(file, "")?;
-        writeln!(file, "## Evidence")?;
-        writeln!(file, "```")?;
-        writeln!(file, "{}", finding.raw_evidence)?;
-        writeln!(file, "```")?;
-        writeln!(file, "")?;
-        
-        // Write follow-up actions if any
-        if !finding.follow_up_actions.is_empty() {
-            writeln!(file, "## Follow-up Actions")?;
-            writeln!(file, "")?;
-            
-            for (i, action) in finding.follow_up_actions.iter().enumerate() {
-                writeln!(file, "### Action {}: {}", i+1, action.description)?;
-                writeln!(file, "**Status:** {:?}", action.status)?;
-                
-                if let Some(cmd) = &action.command {
-                    writeln!(file, "**Command:**")?;
-                    writeln!(file, "```")?;
-                    writeln!(file, "{}", cmd)?;
-                    writeln!(file, "```")?;
-                }
-                
-                if let Some(result) = &action.result {
-                    writeln!(file, "**Result:**")?;
-                    writeln!(file, "```")?;
-                    writeln!(file, "{}", result)?;
-                    writeln!(file, "```")?;
-                }
-                
-                writeln!(file, "")?;
-            }
-        }
-        
-        // Write notes section
-        writeln!(file, "## Notes")?;
-        writeln!(file, "_Add your notes here_")?;
-        
-        Ok(())
-    }
-    
     /// Update a documented finding with follow-up action results
     pub fn update_finding_with_action_result(&mut self, action: &FollowUpAction) -> Result<()> {
         // Find the matching finding and action
         let mut finding_to_save = None;
         
-        'outer: for finding in self.documented_findings.values_mut() {
+        let mut findings = self.documented_findings.lock().unwrap();
+        'outer: for finding in findings.values_mut() {
             for follow_up in &mut finding.follow_up_actions {
                 if follow_up.id == action.id {
                     // Update the action
@@ -373,7 +713,13 @@ impl AutoDocumentation {
         
         // Save the updated finding if found
         if let Some(finding) = finding_to_save {
-            self.save_finding_to_file(&finding)?;
+            write_finding_file(&finding)?;
+            if let Some(events) = &self.events {
+                events.emit(FindingEventKind::Updated, &finding);
+            }
+            if let Some(evidence) = &self.evidence {
+                evidence.commit(format!("Record follow-up action result for {}", finding.id));
+            }
             Ok(())
         } else {
             Err(anyhow!("Could not find matching action ID in any finding"))
@@ -382,98 +728,271 @@ impl AutoDocumentation {
     
     /// Add a follow-up action to a finding
     pub fn add_follow_up_to_finding(&mut self, finding_id: &str, action: FollowUpAction) -> Result<()> {
-        let finding_opt = self.documented_findings.get_mut(finding_id).map(|finding| {
+        let finding_opt = self.documented_findings.lock().unwrap().get_mut(finding_id).map(|finding| {
             finding.follow_up_actions.push(action.clone());
             finding.clone()
         });
         
         if let Some(finding) = finding_opt {
-            self.save_finding_to_file(&finding)?;
+            write_finding_file(&finding)?;
+            if let Some(events) = &self.events {
+                events.emit(FindingEventKind::Updated, &finding);
+            }
+            if let Some(evidence) = &self.evidence {
+                evidence.commit(format!("Add follow-up action to {}", finding.id));
+            }
             Ok(())
         } else {
             Err(anyhow!("Finding not found: {}", finding_id))
         }
     }
+
+}
+
+/// Longest a PDF converter may run before the report is given up on
+const PDF_TIMEOUT_SECS: u64 = 120;
+
+/// Renders reports from the documented findings. Cheap to clone, so reports can
+/// be generated from the prompt while documentation carries on in the background
+#[derive(Clone)]
+pub struct ReportGenerator {
+    monitor: Arc<CommandMonitor>,
+    findings: FindingStore,
+    templates: Arc<ReportTemplates>,
+    evidence: Option<EvidenceRepo>,
+}
+
+impl ReportGenerator {
+    /// Generate a summary report of all findings, optionally dropping those below `min_confidence`
+    pub fn generate_summary_report(&self, output_file: &PathBuf, include_false_positives: bool, min_confidence: Option<Confidence>) -> Result<()> {
+        fs::write(output_file, self.render(SUMMARY_TEMPLATE, include_false_positives, min_confidence)?)?;
+        if let Some(evidence) = &self.evidence {
+            evidence.commit(format!("Generate summary report {}", output_file.display()));
+        }
+        Ok(())
+    }
     
-    /// Generate a summary report of all findings
-    pub fn generate_summary_report(&self, output_file: &PathBuf) -> Result<()> {
-        let mut file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(output_file)?;
-        
-        // Collect findings by severity
-        let mut critical = Vec::new();
-        let mut high = Vec::new();
-        let mut medium = Vec::new();
-        let mut low = Vec::new();
-        let mut info = Vec::new();
-        
-        for finding in self.documented_findings.values() {
-            match finding.severity {
-                FindingSeverity::Critical => critical.push(finding),
-                FindingSeverity::High => high.push(finding),
-                FindingSeverity::Medium => medium.push(finding),
-                FindingSeverity::Low => low.push(finding),
-                FindingSeverity::Info => info.push(finding),
-            }
+    /// Generate a self-contained HTML report with color-coded severities and collapsible evidence
+    pub fn generate_html_report(&self, output_file: &PathBuf, include_false_positives: bool, min_confidence: Option<Confidence>) -> Result<()> {
+        fs::write(output_file, self.render(HTML_TEMPLATE, include_false_positives, min_confidence)?)?;
+        if let Some(evidence) = &self.evidence {
+            evidence.commit(format!("Generate HTML report {}", output_file.display()));
         }
+        Ok(())
+    }
+    
+    /// Generate a PDF report by converting the HTML report with `command`, in which
+    /// `{input}` and `{output}` are replaced with the HTML and PDF paths. The HTML
+    /// is kept next to the PDF
+    pub async fn generate_pdf_report(&self, output_file: &Path, command: &str, include_false_positives: bool, min_confidence: Option<Confidence>) -> Result<()> {
+        let html_file = output_file.with_extension("html");
+        fs::write(&html_file, self.render(HTML_TEMPLATE, include_false_positives, min_confidence)?)?;
         
-        // Write summary report
-        writeln!(file, "# Security Assessment Summary Report")?;
-        writeln!(file, "Generated: {}\n", chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"))?;
-        
-        writeln!(file, "## Findings Overview")?;
-        writeln!(file, "| Severity | Count |")?;
-        writeln!(file, "|----------|-------|")?;
-        writeln!(file, "| Critical | {} |", critical.len())?;
-        writeln!(file, "| High     | {} |", high.len())?;
-        writeln!(file, "| Medium   | {} |", medium.len())?;
-        writeln!(file, "| Low      | {} |", low.len())?;
-        writeln!(file, "| Info     | {} |", info.len())?;
-        writeln!(file, "| **Total**    | **{}** |", 
-                 critical.len() + high.len() + medium.len() + low.len() + info.len())?;
-        writeln!(file, "")?;
-        
-        // Write finding details by severity
-        for (severity, findings) in [
-            ("Critical", critical),
-            ("High", high),
-            ("Medium", medium),
-            ("Low", low),
-            ("Info", info),
-        ] {
-            if !findings.is_empty() {
-                writeln!(file, "## {} Findings", severity)?;
-                writeln!(file, "")?;
-                
-                for finding in findings {
-                    writeln!(file, "### {} ({})", finding.title, finding.id)?;
-                    writeln!(file, "{}", finding.description)?;
-                    writeln!(file, "")?;
-                }
-            }
+        let command = command
+            .replace("{input}", &shell_quote(&html_file.to_string_lossy()))
+            .replace("{output}", &shell_quote(&output_file.to_string_lossy()));
+        let run = tokio::process::Command::new("bash")
+            .arg("-c")
+            .arg(&command)
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output();
+        let output = tokio::time::timeout(Duration::from_secs(PDF_TIMEOUT_SECS), run).await
+            .map_err(|_| anyhow!("PDF conversion did not finish within {}s: {}", PDF_TIMEOUT_SECS, command))?
+            .context(format!("Failed to run PDF converter: {}", command))?;
+        if !output.status.success() || !output_file.exists() {
+            return Err(anyhow!(
+                "PDF conversion failed ({}): {}\nThe HTML report is at {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim(),
+                html_file.display()
+            ));
         }
         
+        if let Some(evidence) = &self.evidence {
+            evidence.commit(format!("Generate PDF report {}", output_file.display()));
+        }
         Ok(())
     }
+    
+    fn render(&self, template: &str, include_false_positives: bool, min_confidence: Option<Confidence>) -> Result<String> {
+        let reported = reported_findings(&self.findings, include_false_positives, min_confidence);
+        self.templates.render(template, &reported, &self.monitor.get_all_commands(), &self.monitor.get_timeline().entries())
+    }
+}
+
+/// Snapshot of a session's documented findings, written when the session ends
+pub const FINDINGS_FILE: &str = "findings.json";
+
+/// Save every documented finding so later sessions can compare against it
+pub fn save_findings(store: &FindingStore, path: &Path) -> Result<()> {
+    let findings: Vec<DocumentedFinding> = store.lock().unwrap().values().cloned().collect();
+    let content = serde_json::to_string_pretty(&findings)?;
+    fs::write(path, content)
+        .context(format!("Failed to save findings to {:?}", path))?;
+    Ok(())
+}
+
+/// Load the findings of a session directory. Sessions that ended without
+/// saving a snapshot are rebuilt from the latest entry per finding in `findings.jsonl`.
+pub fn load_findings(session_dir: &Path) -> Result<Vec<DocumentedFinding>> {
+    let snapshot = session_dir.join(FINDINGS_FILE);
+    if snapshot.exists() {
+        let content = fs::read_to_string(&snapshot)?;
+        return serde_json::from_str(&content)
+            .context(format!("Failed to parse findings snapshot {:?}", snapshot));
+    }
+
+    let event_log = session_dir.join("findings.jsonl");
+    if !event_log.exists() {
+        return Err(anyhow!("No findings recorded in {:?}", session_dir));
+    }
+
+    let mut findings: HashMap<String, DocumentedFinding> = HashMap::new();
+    for line in fs::read_to_string(&event_log)?.lines().filter(|line| !line.trim().is_empty()) {
+        let mut event: serde_json::Value = serde_json::from_str(line)
+            .context(format!("Failed to parse finding event in {:?}", event_log))?;
+        let finding: DocumentedFinding = serde_json::from_value(event["finding"].take())?;
+        findings.insert(finding.id.clone(), finding);
+    }
+    Ok(findings.into_values().collect())
+}
+
+/// Findings to include in a report, highest CVSS score first.
+/// False positives are left out unless asked for.
+pub fn reported_findings(store: &FindingStore, include_false_positives: bool, min_confidence: Option<Confidence>) -> Vec<DocumentedFinding> {
+    let mut findings: Vec<DocumentedFinding> = store.lock().unwrap().values()
+        .filter(|finding| include_false_positives || finding.status != FindingStatus::FalsePositive)
+        .filter(|finding| min_confidence.as_ref().is_none_or(|min| finding.confidence.rank() >= min.rank()))
+        .cloned()
+        .collect();
+    findings.sort_by(|a, b| {
+        cvss::sort_score(b.cvss.as_ref(), &b.severity)
+            .total_cmp(&cvss::sort_score(a.cvss.as_ref(), &a.severity))
+    });
+    findings
 }
 
-/// Extracts target domain/IP from a command string
-fn extract_target_from_command(command: &str) -> Option<String> {
-    // Simple heuristic - grab the last term which looks like a domain or IP
-    let terms: Vec<&str> = command.split_whitespace().collect();
+/// Write a documented finding to its Markdown file
+pub fn write_finding_file(finding: &DocumentedFinding) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(&finding.file_path)?;
     
-    // Patterns to match domains and IPs
-    let domain_pattern = Regex::new(r"^[a-zA-Z0-9][-a-zA-Z0-9]*\.[a-zA-Z0-9]+(?:\.[a-zA-Z0-9]+)*$").unwrap();
-    let ip_pattern = Regex::new(r"^\d{1,3}\.\d{1,3}\.\d{1,3}\.\d{1,3}$").unwrap();
+    // Write Markdown format
+    writeln!(file, "# {} ({})", finding.title, finding.id)?;
+    writeln!(file)?;
+    writeln!(file, "## Description")?;
+    writeln!(file, "{}", finding.description)?;
+    writeln!(file)?;
+    writeln!(file, "**Severity:** {:?}", finding.severity)?;
+    writeln!(file, "**Confidence:** {:?}", finding.confidence)?;
+    if let Some(cvss) = &finding.cvss {
+        writeln!(file, "**CVSS v3.1:** {:.1} (`{}`)", cvss.score, cvss.vector)?;
+    }
+    if let Some(cwe) = &finding.cwe {
+        writeln!(file, "**CWE:** [{}](https://cwe.mitre.org/data/definitions/{}.html)", cwe, cwe.trim_start_matches("CWE-"))?;
+    }
+    if let Some(owasp) = &finding.owasp {
+        writeln!(file, "**OWASP Top 10:** {}", owasp)?;
+    }
+    writeln!(file, "**Discovery Date:** {}", finding.discovery_date.format("%Y-%m-%d %H:%M:%S UTC"))?;
+    writeln!(file, "**Status:** {:?}", finding.status)?;
+    if let Some(assignee) = &finding.assignee {
+        writeln!(file, "**Assignee:** {}", assignee)?;
+    }
+    for (tracker, issue) in &finding.external_refs {
+        writeln!(file, "**{} Issue:** {}", tracker, issue)?;
+    }
+    writeln!(file)?;
+    if !finding.related_cves.is_empty() {
+        writeln!(file, "## Related CVEs")?;
+        writeln!(file, "| CVE | CVSS | Summary |")?;
+        writeln!(file, "|-----|------|---------|")?;
+        for cve in &finding.related_cves {
+            let score = cve.cvss.as_ref()
+                .map(|cvss| format!("{:.1}", cvss.score))
+                .unwrap_or_else(|| "-".to_string());
+            writeln!(file, "| [{}](https://nvd.nist.gov/vuln/detail/{}) | {} | {} |", cve.id, cve.id, score, cve.summary)?;
+        }
+        writeln!(file)?;
+    }
+    
+    if !finding.exploits.is_empty() {
+        writeln!(file, "## Exploit Candidates")?;
+        writeln!(file, "| EDB-ID | Title | Type | Platform | Verified | CVEs |")?;
+        writeln!(file, "|--------|-------|------|----------|----------|------|")?;
+        for exploit in &finding.exploits {
+            let title = match &exploit.mirrored {
+                Some(path) => format!("{} (mirrored to `{}`)", exploit.title, path.display()),
+                None => exploit.title.clone(),
+            };
+            writeln!(file, "| [{}](https://www.exploit-db.com/exploits/{}) | {} | {} | {} | {} | {} |",
+                exploit.edb_id, exploit.edb_id, title, exploit.kind, exploit.platform,
+                if exploit.verified { "yes" } else { "no" }, exploit.cves.join(", "))?;
+        }
+        writeln!(file)?;
+    }
     
-    for term in terms.iter().rev() {
-        if domain_pattern.is_match(term) || ip_pattern.is_match(term) {
-            return Some(term.to_string());
+    if !finding.screenshots.is_empty() {
+        writeln!(file, "## Screenshots")?;
+        for screenshot in &finding.screenshots {
+            let name = screenshot.path.file_name().unwrap_or_default().to_string_lossy();
+            writeln!(file, "**{}**", screenshot.url)?;
+            writeln!(file)?;
+            writeln!(file, "![{}](../screenshots/{})", screenshot.url, name)?;
+            writeln!(file)?;
         }
     }
     
-    None
-} 
\ No newline at end of file
+    writeln!(file, "## Discovery Method")?;
+    writeln!(file, "```")?;
+    writeln!(file, "{}", finding.discovery_command)?;
+    writeln!(file, "```")?;
+    writeln!(file)?;
+    writeln!(file, "## Evidence")?;
+    writeln!(file, "```")?;
+    writeln!(file, "{}", finding.raw_evidence)?;
+    writeln!(file, "```")?;
+    writeln!(file)?;
+    
+    // Write follow-up actions if any
+    if !finding.follow_up_actions.is_empty() {
+        writeln!(file, "## Follow-up Actions")?;
+        writeln!(file)?;
+        
+        for (i, action) in finding.follow_up_actions.iter().enumerate() {
+            writeln!(file, "### Action {}: {}", i+1, action.description)?;
+            writeln!(file, "**Status:** {:?}", action.status)?;
+            
+            if let Some(cmd) = &action.command {
+                writeln!(file, "**Command:**")?;
+                writeln!(file, "```")?;
+                writeln!(file, "{}", cmd)?;
+                writeln!(file, "```")?;
+            }
+            
+            if let Some(result) = &action.result {
+                writeln!(file, "**Result:**")?;
+                writeln!(file, "```")?;
+                writeln!(file, "{}", result)?;
+                writeln!(file, "```")?;
+            }
+            
+            writeln!(file)?;
+        }
+    }
+    
+    // Write notes section
+    writeln!(file, "## Notes")?;
+    if finding.notes.is_empty() {
+        writeln!(file, "_Add your notes here_")?;
+    }
+    for note in &finding.notes {
+        writeln!(file, "- **{}** {}", note.timestamp.format("%Y-%m-%d %H:%M:%S UTC"), note.text)?;
+    }
+    
+    Ok(())
+}
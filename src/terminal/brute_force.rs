@@ -0,0 +1,227 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::sync::{Mutex, OnceLock};
+use regex::Regex;
+use serde::{Serialize, Deserialize};
+
+use super::throttle::tool_name;
+
+/// Directory/file brute-forcers whose output lists one result per line
+const PATH_TOOLS: &[&str] = &["gobuster", "ffuf", "dirb", "dirsearch", "wfuzz", "feroxbuster"];
+/// Credential brute-forcers
+const LOGIN_TOOLS: &[&str] = &["hydra", "medusa"];
+
+/// How effective a brute-force run has been so far
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BruteForceStats {
+    /// Requests or login attempts made, from the tool's progress output
+    pub attempts: Option<u64>,
+    /// Total the tool reports it will try
+    pub total: Option<u64>,
+    /// Entries in the wordlist passed on the command line
+    pub wordlist_size: Option<u64>,
+    /// Paths returned with a non-404 status, or valid credentials found
+    pub hits: u64,
+    /// Result count per HTTP status code
+    pub status_codes: BTreeMap<u16, u64>,
+}
+
+impl BruteForceStats {
+    /// Whether a tool is a brute-forcer these stats apply to
+    pub fn applies_to(tool: &str) -> bool {
+        PATH_TOOLS.contains(&tool) || LOGIN_TOOLS.contains(&tool)
+    }
+
+    /// Start tracking a command, counting the wordlist it was given
+    pub async fn for_command(command: &str) -> Self {
+        let wordlist_size = match wordlist_path(command) {
+            Some(path) => count_wordlist(path).await,
+            None => None,
+        };
+        Self {
+            wordlist_size,
+            ..Self::default()
+        }
+    }
+
+    /// Share of attempts that produced a hit, as a percentage
+    pub fn hit_rate(&self) -> Option<f64> {
+        self.attempts.filter(|&attempts| attempts > 0)
+            .map(|attempts| self.hits as f64 * 100.0 / attempts as f64)
+    }
+
+    /// Share of the wordlist tried so far, as a percentage
+    pub fn coverage(&self) -> Option<f64> {
+        let size = self.wordlist_size.or(self.total).filter(|&size| size > 0)?;
+        self.attempts.map(|attempts| (attempts as f64 * 100.0 / size as f64).min(100.0))
+    }
+
+    /// One-line description for the command summary
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+
+        match (self.attempts, self.wordlist_size.or(self.total)) {
+            (Some(attempts), Some(size)) => parts.push(format!(
+                "{}/{} tried ({:.1}% of wordlist)", attempts, size, self.coverage().unwrap_or(0.0))),
+            (Some(attempts), None) => parts.push(format!("{} tried", attempts)),
+            _ => {}
+        }
+
+        match self.hit_rate() {
+            Some(rate) => parts.push(format!("{} hits ({:.2}% hit rate)", self.hits, rate)),
+            None => parts.push(format!("{} hits", self.hits)),
+        }
+
+        if !self.status_codes.is_empty() {
+            let codes = self.status_codes.iter()
+                .map(|(code, count)| format!("{}: {}", code, count))
+                .collect::<Vec<_>>()
+                .join(", ");
+            parts.push(format!("status codes {}", codes));
+        }
+
+        format!("Brute force: {}", parts.join(", "))
+    }
+}
+
+/// Patterns for progress, results and found credentials in brute-forcer output
+#[derive(Debug)]
+pub struct BruteForcePatterns {
+    /// gobuster `Progress: 4614 / 4615`, ffuf `:: Progress: [4614/4614]`
+    progress: Regex,
+    /// hydra `[STATUS] 64.00 tries/min, 64 tries in 00:01h, 14344335 to do`
+    hydra_status: Regex,
+    /// hydra `overall 16 tasks, 14344399 login tries`
+    hydra_total: Regex,
+    /// Status code of a reported path in gobuster, ffuf, dirsearch, dirb, wfuzz and feroxbuster output
+    status_codes: Vec<Regex>,
+    /// hydra `[22][ssh] host: 10.0.0.5   login: root   password: toor`, medusa `ACCOUNT FOUND`
+    login_found: Regex,
+    /// medusa `ACCOUNT CHECK: [ssh] Host: ... User: root (1 of 1, 0 complete) Password: 123456 (2 of 10 complete)`
+    medusa_attempt: Regex,
+}
+
+impl BruteForcePatterns {
+    pub fn new() -> Self {
+        Self {
+            progress: Regex::new(r"Progress:\s*\[?\s*(\d+)\s*/\s*(\d+)").unwrap(),
+            hydra_status: Regex::new(r"\[STATUS\].*?\b(\d+) tries in .*?(\d+) to do").unwrap(),
+            hydra_total: Regex::new(r"(\d+) login tries").unwrap(),
+            status_codes: vec![
+                Regex::new(r"\(Status: (\d{3})\)").unwrap(),
+                Regex::new(r"\[Status: (\d{3}),").unwrap(),
+                Regex::new(r"^\[\d{2}:\d{2}:\d{2}\] (\d{3}) -").unwrap(),
+                Regex::new(r"\(CODE:(\d{3})\|").unwrap(),
+                Regex::new(r"^\d{9}:\s+(\d{3})\s").unwrap(),
+                Regex::new(r"^(\d{3})\s+[A-Z]+\s+\d+l\s").unwrap(),
+            ],
+            login_found: Regex::new(r"^\[\d+\]\[[\w-]+\] host: \S+\s+login: |ACCOUNT FOUND:").unwrap(),
+            medusa_attempt: Regex::new(r"^ACCOUNT CHECK:").unwrap(),
+        }
+    }
+
+    /// Fold newly received output lines into the stats
+    pub fn update(&self, stats: &mut BruteForceStats, output: &str) {
+        for line in output.lines() {
+            // Progress updates are separated by carriage returns, so the last one is current
+            if let Some(captures) = self.progress.captures_iter(line).last() {
+                stats.attempts = captures[1].parse().ok();
+                stats.total = captures[2].parse().ok();
+            } else if let Some(captures) = self.hydra_status.captures(line) {
+                let tried: Option<u64> = captures[1].parse().ok();
+                let remaining: Option<u64> = captures[2].parse().ok();
+                stats.attempts = tried;
+                if let (Some(tried), Some(remaining)) = (tried, remaining) {
+                    stats.total = Some(tried + remaining);
+                }
+            } else if let Some(captures) = self.hydra_total.captures(line) {
+                stats.total = captures[1].parse().ok();
+            }
+
+            if self.medusa_attempt.is_match(line) {
+                *stats.attempts.get_or_insert(0) += 1;
+            }
+
+            if self.login_found.is_match(line) {
+                stats.hits += 1;
+                continue;
+            }
+
+            let code = self.status_codes.iter()
+                .find_map(|pattern| pattern.captures(line))
+                .and_then(|captures| captures[1].parse::<u16>().ok());
+            if let Some(code) = code {
+                *stats.status_codes.entry(code).or_insert(0) += 1;
+                if code != 404 {
+                    stats.hits += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Wordlist given to a brute-forcer: `-w` for path tools (ffuf's `path:KEYWORD` form included),
+/// `-P` for hydra/medusa, or dirb's second positional argument
+fn wordlist_path(command: &str) -> Option<String> {
+    let tool = tool_name(command);
+    let args: Vec<&str> = command.split_whitespace()
+        .skip_while(|arg| *arg != tool)
+        .collect();
+
+    let flag_value = |flags: &[&str]| {
+        args.windows(2)
+            .find(|pair| flags.contains(&pair[0]))
+            .map(|pair| pair[1].to_string())
+    };
+
+    let path = if LOGIN_TOOLS.contains(&tool) {
+        flag_value(&["-P"])
+    } else if tool == "dirb" {
+        args.iter().skip(1).filter(|arg| !arg.starts_with('-')).nth(1).map(|arg| arg.to_string())
+    } else {
+        flag_value(&["-w", "--wordlist", "--wordlists"])
+    }?;
+
+    // ffuf accepts `wordlist.txt:FUZZ`
+    let path = match path.rsplit_once(':') {
+        Some((file, keyword)) if keyword.chars().all(|c| c.is_ascii_uppercase()) => file.to_string(),
+        _ => path,
+    };
+    Some(path.trim_matches(|c| c == '"' || c == '\'').to_string())
+}
+
+/// Wordlist sizes already counted, by path; the same lists are used over and
+/// over in a session and the large ones take a while to read
+fn wordlist_sizes() -> &'static Mutex<HashMap<String, u64>> {
+    static SIZES: OnceLock<Mutex<HashMap<String, u64>>> = OnceLock::new();
+    SIZES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Number of entries in a wordlist, counted off the async runtime
+async fn count_wordlist(path: String) -> Option<u64> {
+    if let Some(size) = wordlist_sizes().lock().unwrap().get(&path) {
+        return Some(*size);
+    }
+    let file = path.clone();
+    let size = tokio::task::spawn_blocking(move || count_entries(&file)).await.ok()??;
+    wordlist_sizes().lock().unwrap().insert(path, size);
+    Some(size)
+}
+
+/// Entries in a wordlist file, skipping blank lines and comments, read a line
+/// at a time rather than loaded whole
+fn count_entries(path: &str) -> Option<u64> {
+    let mut reader = BufReader::new(File::open(path).ok()?);
+    let mut line = Vec::new();
+    let mut count = 0;
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line).ok()? == 0 {
+            return Some(count);
+        }
+        if !line.trim_ascii().is_empty() && !line.starts_with(b"#") {
+            count += 1;
+        }
+    }
+}
@@ -0,0 +1,351 @@
+use std::env;
+use std::net::IpAddr;
+use std::time::Duration;
+use anyhow::{Result, Context, anyhow};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::config::CensysConfig;
+use super::command_monitor::{CommandMonitor, Confidence, FindingSeverity, SecurityFinding, create_finding};
+use super::output_analyzer::subdomains_finding;
+
+const API_URL: &str = "https://search.censys.io/api/v2";
+
+/// Certificates fetched per lookup; the search's largest page
+const MAX_CERTIFICATES: usize = 100;
+
+/// Subdomains named in a lookup's summary; its finding lists them all
+const MAX_LISTED_SUBDOMAINS: usize = 10;
+
+/// Longest banner kept per service in a finding's evidence
+const MAX_BANNER_CHARS: usize = 2000;
+
+/// Looks up Censys' scans of a host and the certificates naming a domain, a
+/// second opinion to Shodan that also turns up subdomains
+pub struct CensysClient {
+    api_id: String,
+    api_secret: String,
+    certificates: bool,
+    client: reqwest::Client,
+}
+
+/// Censys' record of a host
+#[derive(Debug, Clone, Deserialize)]
+pub struct CensysHost {
+    pub ip: String,
+    #[serde(default)]
+    pub services: Vec<CensysService>,
+    #[serde(default)]
+    pub autonomous_system: Option<AutonomousSystem>,
+    #[serde(default)]
+    pub operating_system: Option<Software>,
+    #[serde(default)]
+    pub dns: Option<HostDns>,
+    #[serde(default)]
+    pub last_updated_at: Option<String>,
+}
+
+/// A service Censys found listening
+#[derive(Debug, Clone, Deserialize)]
+pub struct CensysService {
+    pub port: u16,
+    #[serde(default)]
+    pub service_name: String,
+    #[serde(default)]
+    pub transport_protocol: Option<String>,
+    #[serde(default)]
+    pub software: Vec<Software>,
+    #[serde(default)]
+    pub banner: Option<String>,
+    #[serde(default)]
+    pub observed_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Software {
+    #[serde(default)]
+    pub product: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AutonomousSystem {
+    #[serde(default)]
+    pub asn: Option<u32>,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct HostDns {
+    #[serde(default)]
+    pub names: Vec<String>,
+}
+
+/// A certificate from Censys' certificate search
+#[derive(Debug, Clone, Deserialize)]
+pub struct CensysCertificate {
+    #[serde(default)]
+    pub fingerprint_sha256: String,
+    #[serde(default)]
+    pub names: Vec<String>,
+    #[serde(default)]
+    pub parsed: Option<ParsedCertificate>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ParsedCertificate {
+    #[serde(default)]
+    pub issuer_dn: Option<String>,
+    #[serde(default)]
+    pub validity_period: Option<ValidityPeriod>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ValidityPeriod {
+    #[serde(default)]
+    pub not_after: Option<String>,
+}
+
+impl CensysClient {
+    pub fn new(config: &CensysConfig) -> Result<Self> {
+        let api_id = config.api_id.clone()
+            .or_else(|| env::var("CENSYS_API_ID").ok())
+            .filter(|id| !id.is_empty());
+        let api_secret = config.api_secret.clone()
+            .or_else(|| env::var("CENSYS_API_SECRET").ok())
+            .filter(|secret| !secret.is_empty());
+        let (Some(api_id), Some(api_secret)) = (api_id, api_secret) else {
+            return Err(anyhow!("No Censys API credentials: set api_id and api_secret in [censys] or the CENSYS_API_ID and CENSYS_API_SECRET environment variables"));
+        };
+
+        Ok(Self {
+            api_id,
+            api_secret,
+            certificates: config.certificates,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(config.timeout_secs))
+                .build()?,
+        })
+    }
+
+    /// Censys' record of an IP, or of the host it has seen serving a domain
+    pub async fn host(&self, target: &str) -> Result<CensysHost> {
+        let ip = match target.parse::<IpAddr>() {
+            Ok(ip) => ip.to_string(),
+            Err(_) => self.find_host(target).await?,
+        };
+        let record = self.get(&format!("/hosts/{}", ip), &[]).await?;
+        serde_json::from_value(record).context(format!("Unexpected Censys record for {}", ip))
+    }
+
+    /// Found through Censys' DNS data rather than our resolver, so the lookup stays passive
+    async fn find_host(&self, domain: &str) -> Result<String> {
+        let query = format!("dns.names: \"{}\"", domain);
+        let results = self.get("/hosts/search", &[("q", query.as_str()), ("per_page", "1")]).await?;
+        results["hits"].as_array()
+            .and_then(|hits| hits.first())
+            .and_then(|hit| hit["ip"].as_str())
+            .map(String::from)
+            .ok_or_else(|| anyhow!("Censys has no host serving {}", domain))
+    }
+
+    /// Certificates naming a domain or its subdomains; none for IPs or when
+    /// certificate search is turned off
+    pub async fn certificates(&self, target: &str) -> Result<Vec<CensysCertificate>> {
+        if !self.certificates || target.parse::<IpAddr>().is_ok() {
+            return Ok(Vec::new());
+        }
+        let query = format!("names: {}", target);
+        let per_page = MAX_CERTIFICATES.to_string();
+        let results = self.get("/certificates/search", &[("q", query.as_str()), ("per_page", per_page.as_str())]).await?;
+        serde_json::from_value(results["hits"].clone()).context("Unexpected Censys certificate search results")
+    }
+
+    /// The `result` of a call; Censys wraps every response in one
+    async fn get(&self, path: &str, query: &[(&str, &str)]) -> Result<Value> {
+        let response = self.client.get(format!("{}{}", API_URL, path))
+            .basic_auth(&self.api_id, Some(&self.api_secret))
+            .query(query)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to reach Censys: {}", e))?;
+
+        let status = response.status();
+        let mut body: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            return Err(match body["error"].as_str() {
+                Some(error) => anyhow!("{} ({})", error, status),
+                None => anyhow!("{}", status),
+            });
+        }
+        Ok(body["result"].take())
+    }
+}
+
+impl CensysHost {
+    /// `443/tcp HTTP nginx 1.18.0` for each service
+    fn services(&self) -> Vec<String> {
+        self.services.iter()
+            .map(|service| {
+                let software = service.software.iter()
+                    .filter_map(|software| {
+                        let product = software.product.as_deref()?;
+                        Some(format!("{} {}", product, software.version.as_deref().unwrap_or("")).trim_end().to_string())
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{} {} {}", port_name(service), service.service_name, software).trim_end().to_string()
+            })
+            .collect()
+    }
+
+    /// One paragraph on the host, for the chat and the AI
+    pub fn summary(&self, target: &str) -> String {
+        let mut summary = format!("Censys has {} on record for {}", self.ip, target);
+        if let Some(last_updated) = &self.last_updated_at {
+            summary.push_str(&format!(" (last updated {})", last_updated));
+        }
+        if let Some(name) = self.autonomous_system.as_ref().and_then(|system| system.name.as_ref()) {
+            summary.push_str(&format!(", announced by {}", name));
+        }
+        if let Some(os) = self.operating_system.as_ref().and_then(|os| os.product.as_ref()) {
+            summary.push_str(&format!(", running {}", os));
+        }
+        match self.services() {
+            services if services.is_empty() => summary.push_str(". No services seen."),
+            services => summary.push_str(&format!(". Services: {}.", services.join("; "))),
+        }
+        summary
+    }
+
+    /// The full record as written to the lookup's log
+    pub fn report(&self) -> String {
+        let mut report = format!("IP: {}\n", self.ip);
+        if let Some(dns) = self.dns.as_ref().filter(|dns| !dns.names.is_empty()) {
+            report.push_str(&format!("DNS names: {}\n", dns.names.join(", ")));
+        }
+        if let Some(system) = &self.autonomous_system {
+            let asn = system.asn.map(|asn| format!("AS{} ", asn)).unwrap_or_default();
+            report.push_str(&format!("Autonomous system: {}{}\n", asn, system.name.as_deref().unwrap_or("")));
+        }
+        if let Some(os) = self.operating_system.as_ref().and_then(|os| os.product.as_ref()) {
+            report.push_str(&format!("OS: {}\n", os));
+        }
+        if let Some(last_updated) = &self.last_updated_at {
+            report.push_str(&format!("Last update: {}\n", last_updated));
+        }
+        for (service, description) in self.services.iter().zip(self.services()) {
+            report.push_str(&format!("\n== {}", description));
+            if let Some(observed) = &service.observed_at {
+                report.push_str(&format!(" (seen {})", observed));
+            }
+            report.push_str(&format!("\n{}\n", service.banner.as_deref().unwrap_or("").trim_end()));
+        }
+        report
+    }
+
+    /// A finding for the exposed services. Censys' scans can be days old, so
+    /// it is likely rather than confirmed
+    pub fn findings(&self, target: &str, command_id: &str) -> Vec<SecurityFinding> {
+        let services = self.services();
+        if services.is_empty() {
+            return Vec::new();
+        }
+        let banners: Vec<String> = self.services.iter()
+            .map(|service| format!("{}\n{}", port_name(service), service.banner.as_deref().unwrap_or("").chars().take(MAX_BANNER_CHARS).collect::<String>().trim_end()))
+            .collect();
+        vec![create_finding(
+            "Internet-Exposed Services (Censys)",
+            &format!("Censys has seen {} service(s) on {} ({}): {}", services.len(), target, self.ip, services.join(", ")),
+            FindingSeverity::Info,
+            command_id,
+            &banners.join("\n\n"),
+        ).with_confidence(Confidence::Likely)]
+    }
+}
+
+/// Subdomains of the target named in the certificates, without wildcards
+pub fn subdomains(certificates: &[CensysCertificate], domain: &str) -> Vec<String> {
+    let suffix = format!(".{}", domain.to_lowercase());
+    let mut subdomains: Vec<String> = certificates.iter()
+        .flat_map(|certificate| &certificate.names)
+        .map(|name| name.trim_start_matches("*.").to_lowercase())
+        .filter(|name| name.ends_with(&suffix))
+        .collect();
+    subdomains.sort();
+    subdomains.dedup();
+    subdomains
+}
+
+/// The certificates as written to the lookup's log
+fn certificates_report(certificates: &[CensysCertificate]) -> String {
+    let mut report = String::from("\nCertificates:\n");
+    for certificate in certificates {
+        let parsed = certificate.parsed.as_ref();
+        let issuer = parsed.and_then(|parsed| parsed.issuer_dn.as_deref()).unwrap_or("unknown issuer");
+        let expires = parsed.and_then(|parsed| parsed.validity_period.as_ref()).and_then(|validity| validity.not_after.as_deref()).unwrap_or("unknown");
+        report.push_str(&format!("{} ({}, expires {}): {}\n", certificate.fingerprint_sha256, issuer, expires, certificate.names.join(", ")));
+    }
+    report
+}
+
+/// The command a lookup is recorded as
+pub fn lookup_command(target: &str) -> String {
+    format!("censys host {}", target)
+}
+
+/// Look a target up on Censys and record what it has seen like a command's
+/// results: the host and its certificates in the session's logs, the services
+/// and subdomains in the asset and technology inventories, and findings for
+/// the services and the subdomains no other source found. Returns the host's
+/// summary
+pub async fn passive_recon(client: &CensysClient, monitor: &CommandMonitor, target: &str) -> Result<String> {
+    let started = Utc::now();
+    let host = client.host(target).await?;
+    let certificates = client.certificates(target).await?;
+    let subdomains = subdomains(&certificates, target);
+
+    let mut report = host.report();
+    if !certificates.is_empty() {
+        report.push_str(&certificates_report(&certificates));
+    }
+    let command_id = monitor.record_lookup(&lookup_command(target), started, &report)?;
+
+    let assets = monitor.get_asset_inventory();
+    let technologies = monitor.get_tech_inventory();
+    for service in &host.services {
+        let name = Some(service.service_name.as_str()).filter(|name| !name.is_empty());
+        assets.record_service(target, &port_name(service), name, &command_id);
+        for software in &service.software {
+            if let Some(product) = &software.product {
+                technologies.record(target, product, software.version.as_deref(), "censys");
+            }
+        }
+    }
+    let new_subdomains: Vec<String> = subdomains.iter()
+        .filter(|subdomain| assets.record_subdomain(subdomain, &command_id))
+        .cloned()
+        .collect();
+
+    let mut summary = host.summary(target);
+    if !subdomains.is_empty() {
+        let listed = subdomains.iter().take(MAX_LISTED_SUBDOMAINS).cloned().collect::<Vec<_>>().join(", ");
+        let more = subdomains.len().saturating_sub(MAX_LISTED_SUBDOMAINS);
+        let more = if more > 0 { format!(" and {} more", more) } else { String::new() };
+        summary.push_str(&format!(" Certificates name {} subdomain(s): {}{}.", subdomains.len(), listed, more));
+    }
+    monitor.update_command_summary(&command_id, &summary)?;
+
+    let mut findings = host.findings(target, &command_id);
+    findings.extend(subdomains_finding(&new_subdomains, &command_id));
+    monitor.record_integration_findings(findings).await?;
+    Ok(summary)
+}
+
+/// `443/tcp`
+fn port_name(service: &CensysService) -> String {
+    format!("{}/{}", service.port, service.transport_protocol.as_deref().unwrap_or("tcp").to_lowercase())
+}
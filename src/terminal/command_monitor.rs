@@ -1,16 +1,42 @@
 use std::process::{Command, Stdio};
 use std::io::{BufReader, BufRead};
 use std::sync::{Arc, Mutex};
-use tokio::sync::mpsc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
 use tokio::task;
 use anyhow::{Result, Context, anyhow};
-use std::path::PathBuf;
-use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::fs::{self, File, OpenOptions};
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
 use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
 use uuid::Uuid;
 
+use super::execution_backend::ExecutionBackend;
+use super::throttle::{Throttle, tool_name};
+use super::proxy::ProxyRouter;
+use super::dns::DnsResolvers;
+use super::interactsh::{InteractshListener, OOB_PLACEHOLDER};
+use super::approval::ApprovalGate;
+use super::command_policy::CommandPolicy;
+use crate::config::ToolConfig;
+use super::cvss::{self, CvssScore};
+use super::cve_db::CveMatch;
+use super::taxonomy;
+use super::tech_inventory::TechInventory;
+use super::suppression::SuppressionList;
+use super::assets::AssetInventory;
+use super::triggers::TriggerList;
+use super::timeline::{ActivityKind, Timeline};
+use super::action_queue::ActionQueue;
+use super::brute_force::BruteForceStats;
+use crate::utils::{extract_target_from_command, shell_quote};
+use super::alerts::notify_desktop;
+use super::notifier::Notifier;
+
 /// Represents a command that is either running or completed
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitoredCommand {
@@ -23,13 +49,41 @@ pub struct MonitoredCommand {
     pub results_summary: Option<String>,
     pub findings: Vec<SecurityFinding>,
     pub command_type: CommandType,
+    /// Hit rate, status codes and wordlist coverage for brute-force tools
+    #[serde(default)]
+    pub brute_force_stats: Option<BruteForceStats>,
 }
 
+impl MonitoredCommand {
+    /// Results summary with brute-force statistics appended
+    pub fn summary(&self) -> Option<String> {
+        match (&self.results_summary, &self.brute_force_stats) {
+            (Some(summary), Some(stats)) => Some(format!("{}. {}", summary, stats.summary())),
+            (Some(summary), None) => Some(summary.clone()),
+            (None, Some(stats)) => Some(stats.summary()),
+            (None, None) => None,
+        }
+    }
+
+    /// The files the command's process writes its stdout and stderr to; they
+    /// keep growing if the command is detached and outlives the session
+    pub fn raw_output_files(&self) -> (PathBuf, PathBuf) {
+        (self.output_file.with_extension("stdout"), self.output_file.with_extension("stderr"))
+    }
+}
+
+/// How often a running command's output files are checked for new lines
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Longest a pinned tool may take to report its version before it is killed
+const VERSION_TIMEOUT: Duration = Duration::from_secs(10);
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum CommandStatus {
     Running,
     Completed,
     Failed(String),
+    Detached,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -51,9 +105,61 @@ pub struct SecurityFinding {
     pub command_id: String,
     pub raw_output: String,
     pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub cvss: Option<CvssScore>,
+    #[serde(default)]
+    pub cwe: Option<String>,
+    #[serde(default)]
+    pub owasp: Option<String>,
+    /// Known CVEs matching a disclosed version, highest score first
+    #[serde(default)]
+    pub related_cves: Vec<CveMatch>,
+    #[serde(default)]
+    pub confidence: Confidence,
+    /// Findings raised again under the same key update the one documented
+    /// first instead of being documented anew
+    #[serde(default)]
+    pub merge_key: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+impl SecurityFinding {
+    /// Set how strongly the evidence supports the finding
+    pub fn with_confidence(mut self, confidence: Confidence) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    /// Make later findings with the same key update this one
+    pub fn with_merge_key(mut self, key: String) -> Self {
+        self.merge_key = Some(key);
+        self
+    }
+}
+
+/// How strongly the evidence supports a finding
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum Confidence {
+    /// Directly observed, e.g. an open port or returned file contents
+    Confirmed,
+    /// Strong indicator that still needs verification
+    Likely,
+    /// Keyword or heuristic match only
+    #[default]
+    Tentative,
+}
+
+impl Confidence {
+    /// Ordering used for filtering, Confirmed highest
+    pub fn rank(&self) -> u8 {
+        match self {
+            Confidence::Confirmed => 2,
+            Confidence::Likely => 1,
+            Confidence::Tentative => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum FindingSeverity {
     Critical,
     High,
@@ -62,6 +168,31 @@ pub enum FindingSeverity {
     Info,
 }
 
+impl FindingSeverity {
+    /// Numeric rank for threshold comparisons; higher is more severe
+    pub fn rank(&self) -> u8 {
+        match self {
+            FindingSeverity::Critical => 4,
+            FindingSeverity::High => 3,
+            FindingSeverity::Medium => 2,
+            FindingSeverity::Low => 1,
+            FindingSeverity::Info => 0,
+        }
+    }
+    
+    /// Parse a severity name, ignoring case
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "critical" => Some(FindingSeverity::Critical),
+            "high" => Some(FindingSeverity::High),
+            "medium" => Some(FindingSeverity::Medium),
+            "low" => Some(FindingSeverity::Low),
+            "info" => Some(FindingSeverity::Info),
+            _ => None,
+        }
+    }
+}
+
 /// Monitors and manages command execution
 #[derive(Clone)]
 pub struct CommandMonitor {
@@ -69,6 +200,27 @@ pub struct CommandMonitor {
     active_commands: Arc<Mutex<Vec<MonitoredCommand>>>,
     output_channel: Arc<Mutex<(mpsc::Sender<CommandOutput>, mpsc::Receiver<CommandOutput>)>>,
     finding_channel: Arc<Mutex<(mpsc::Sender<SecurityFinding>, mpsc::Receiver<SecurityFinding>)>>,
+    backend: Arc<Mutex<ExecutionBackend>>,
+    output_files: Arc<Mutex<HashMap<String, Arc<Mutex<File>>>>>,
+    throttle: Arc<Mutex<Option<Throttle>>>,
+    proxy: Arc<Mutex<Option<ProxyRouter>>>,
+    resolvers: Arc<Mutex<Option<DnsResolvers>>>,
+    oob: Arc<Mutex<Option<InteractshListener>>>,
+    approval: ApprovalGate,
+    policy: Arc<Mutex<CommandPolicy>>,
+    /// Configured tool paths and default arguments
+    tools: Arc<Mutex<Vec<ToolConfig>>>,
+    /// Configured tools whose binary and pinned version have been checked
+    verified_tools: Arc<Mutex<HashSet<String>>>,
+    tech_inventory: TechInventory,
+    suppressions: SuppressionList,
+    assets: AssetInventory,
+    triggers: TriggerList,
+    timeline: Timeline,
+    actions: ActionQueue,
+    /// Commands running at least this long raise a desktop notification when they finish
+    notify_after: Arc<Mutex<Option<chrono::Duration>>>,
+    notifier: Notifier,
 }
 
 #[derive(Debug, Clone)]
@@ -87,6 +239,24 @@ impl CommandMonitor {
         let output_dir = work_dir.join("command_output");
         fs::create_dir_all(&output_dir)?;
         
+        // Load false-positive suppressions from earlier sessions
+        let suppressions = SuppressionList::load(&work_dir.join("suppressions.json"))?;
+        
+        // Load the engagement's finding triggers
+        let triggers = TriggerList::load(&work_dir.join("triggers.json"))?;
+        
+        // Continue the session's activity log if it is being resumed
+        let timeline = Timeline::load(&work_dir.join("timeline.jsonl"))?;
+        
+        // Follow-up actions still pending from an earlier run of this session
+        let actions = ActionQueue::load(&work_dir.join("actions.json"))?;
+        
+        // Webhook messages name the session by its directory
+        let session = work_dir.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let notifier = Notifier::new(session);
+        
         // Create channel for command output
         let output_channel = Arc::new(Mutex::new(mpsc::channel::<CommandOutput>(100)));
         
@@ -98,17 +268,184 @@ impl CommandMonitor {
             active_commands: Arc::new(Mutex::new(Vec::new())),
             output_channel,
             finding_channel,
+            backend: Arc::new(Mutex::new(ExecutionBackend::default())),
+            output_files: Arc::new(Mutex::new(HashMap::new())),
+            throttle: Arc::new(Mutex::new(None)),
+            proxy: Arc::new(Mutex::new(None)),
+            resolvers: Arc::new(Mutex::new(None)),
+            oob: Arc::new(Mutex::new(None)),
+            approval: ApprovalGate::default(),
+            policy: Arc::new(Mutex::new(CommandPolicy::default())),
+            tools: Arc::new(Mutex::new(Vec::new())),
+            verified_tools: Arc::new(Mutex::new(HashSet::new())),
+            tech_inventory: TechInventory::default(),
+            suppressions,
+            assets: AssetInventory::default(),
+            triggers,
+            timeline,
+            actions,
+            notify_after: Arc::new(Mutex::new(None)),
+            notifier,
         })
     }
     
+    /// Set the backend used to run monitored commands
+    pub fn set_execution_backend(&self, backend: ExecutionBackend) {
+        *self.backend.lock().unwrap() = backend;
+    }
+    
+    /// Get the backend used to run monitored commands
+    pub fn get_execution_backend(&self) -> ExecutionBackend {
+        self.backend.lock().unwrap().clone()
+    }
+    
+    /// Set the throttle applied to every executed command
+    pub fn set_throttle(&self, throttle: Throttle) {
+        *self.throttle.lock().unwrap() = Some(throttle);
+    }
+    
+    /// Set the listener that hands out `{oob}` callback domains and reports interactions with them
+    pub fn set_oob_listener(&self, listener: InteractshListener) {
+        *self.oob.lock().unwrap() = Some(listener);
+    }
+    
+    /// Route every executed command through an outbound proxy, or stop doing so
+    pub fn set_proxy(&self, proxy: Option<ProxyRouter>) {
+        *self.proxy.lock().unwrap() = proxy;
+    }
+    
+    /// Pass the engagement's resolvers to DNS tools, or stop doing so
+    pub fn set_resolvers(&self, resolvers: Option<DnsResolvers>) {
+        *self.resolvers.lock().unwrap() = resolvers;
+    }
+    
+    /// Get the engagement's resolvers, if any are configured
+    pub fn get_resolvers(&self) -> Option<DnsResolvers> {
+        self.resolvers.lock().unwrap().clone()
+    }
+    
+    /// Set the tools whose binary path and default arguments are applied to commands
+    pub fn set_tools(&self, tools: Vec<ToolConfig>) {
+        *self.tools.lock().unwrap() = tools;
+        self.verified_tools.lock().unwrap().clear();
+    }
+    
+    /// Notify the desktop when a command that ran at least this long finishes, or stop doing so
+    pub fn set_notify_after(&self, duration: Option<chrono::Duration>) {
+        *self.notify_after.lock().unwrap() = duration;
+    }
+    
+    /// Set the allow/deny policy enforced on every command
+    pub fn set_command_policy(&self, policy: CommandPolicy) {
+        *self.policy.lock().unwrap() = policy;
+    }
+    
+    /// Get the chat webhooks told about the session's progress
+    pub fn get_notifier(&self) -> Notifier {
+        self.notifier.clone()
+    }
+    
+    /// Get the per-target technology inventory built up during the session
+    pub fn get_tech_inventory(&self) -> TechInventory {
+        self.tech_inventory.clone()
+    }
+    
+    /// Get the session's directory, where its logs and evidence are kept
+    pub fn get_work_dir(&self) -> &Path {
+        &self.work_dir
+    }
+    
+    /// Get the hosts, subdomains, URLs and services discovered during the session
+    pub fn get_asset_inventory(&self) -> AssetInventory {
+        self.assets.clone()
+    }
+    
+    /// Get the false-positive suppression list
+    pub fn get_suppressions(&self) -> SuppressionList {
+        self.suppressions.clone()
+    }
+    
+    /// Get the user-defined finding triggers
+    pub fn get_triggers(&self) -> TriggerList {
+        self.triggers.clone()
+    }
+    
+    /// Get the session's chronological activity log
+    pub fn get_timeline(&self) -> Timeline {
+        self.timeline.clone()
+    }
+    
+    /// Get the persisted queue of follow-up actions
+    pub fn get_action_queue(&self) -> ActionQueue {
+        self.actions.clone()
+    }
+    
+    /// Get the gate used to confirm commands before they run
+    pub fn get_approval_gate(&self) -> ApprovalGate {
+        self.approval.clone()
+    }
+    
     /// Executes a command and monitors its output
     pub async fn execute_command(&self, command: &str, command_type: CommandType) -> Result<String> {
         // Validate the command before execution
-        let validated_command = self.validate_and_fix_command(command)?;
+        let validated_command = self.prepare_command(command).await?;
+        
+        // Ask the user first when approval mode is enabled
+        if !self.approval.request(&validated_command).await {
+            return Err(anyhow!("Command rejected by user: {}", validated_command));
+        }
         
+        self.start_command(validated_command, command_type)
+    }
+    
+    /// Execute a command from `prepare_command` the user has already approved,
+    /// skipping the approval prompt; it runs exactly as it was shown
+    pub fn execute_approved_command(&self, prepared: &str, command_type: CommandType) -> Result<String> {
+        self.start_command(prepared.to_string(), command_type)
+    }
+    
+    /// Record a lookup made through a third-party API instead of a command, e.g.
+    /// a Shodan search, so its results are logged and documented like a command's
+    pub fn record_lookup(&self, command: &str, started: DateTime<Utc>, output: &str) -> Result<String> {
+        let command_id = Uuid::new_v4().to_string();
+        let output_file = self.work_dir
+            .join("command_output")
+            .join(format!("{}_{}.log", started.format("%Y%m%d_%H%M%S"), command_id));
+        fs::write(&output_file, output)
+            .context(format!("Failed to write lookup output {}", output_file.display()))?;
+
+        let lookup = MonitoredCommand {
+            id: command_id.clone(),
+            command: command.to_string(),
+            start_time: started,
+            end_time: Some(chrono::Utc::now()),
+            status: CommandStatus::Completed,
+            output_file,
+            results_summary: None,
+            findings: Vec::new(),
+            command_type: CommandType::Reconnaissance,
+            brute_force_stats: None,
+        };
+        self.timeline.record(ActivityKind::CommandFinished, finished_description(&lookup), Some(&command_id));
+        self.active_commands.lock().unwrap().push(lookup);
+
+        Ok(command_id)
+    }
+
+    fn start_command(&self, validated_command: String, command_type: CommandType) -> Result<String> {
         // Generate unique ID for this command
         let command_id = Uuid::new_v4().to_string();
         
+        // Blind checks get a callback domain of their own, so interactions can be traced back to them
+        let validated_command = if validated_command.contains(OOB_PLACEHOLDER) {
+            match self.oob.lock().unwrap().as_ref() {
+                Some(oob) => oob.substitute(&validated_command, &command_id)?,
+                None => return Err(anyhow!("{} needs out-of-band callbacks; enable [interactsh] in the configuration", OOB_PLACEHOLDER)),
+            }
+        } else {
+            validated_command
+        };
+        
         // Create output file
         let output_file = self.work_dir
             .join("command_output")
@@ -125,6 +462,7 @@ impl CommandMonitor {
             results_summary: None,
             findings: Vec::new(),
             command_type,
+            brute_force_stats: None,
         };
         
         // Store command in active commands
@@ -132,6 +470,7 @@ impl CommandMonitor {
             let mut commands = self.active_commands.lock().unwrap();
             commands.push(monitored_command.clone());
         }
+        self.timeline.record(ActivityKind::CommandStarted, format!("Started `{}`", validated_command), Some(&command_id));
         
         // Clone the output sender for the spawned tasks
         let output_tx = self.output_channel.lock().unwrap().0.clone();
@@ -145,87 +484,63 @@ impl CommandMonitor {
                 .open(&output_file)?
         ));
         
+        // Keep a handle so the file can be flushed on shutdown
+        self.output_files.lock().unwrap().insert(command_id.clone(), output_file_handler.clone());
+        
         // Log that we're executing the command
         println!("\n=== Executing command: {} ===\n", validated_command);
         
-        // Create a process that captures stdout and stderr
-        let mut process = Command::new("bash")
-            .arg("-c")
-            .arg(&validated_command)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
+        // The process writes to files of its own rather than pipes to Hacksor,
+        // so a detached command keeps running and writing after the session ends
+        let (stdout_path, stderr_path) = monitored_command.raw_output_files();
+        let stdout = File::create(&stdout_path)
+            .context(format!("Failed to create {}", stdout_path.display()))?;
+        let stderr = File::create(&stderr_path)
+            .context(format!("Failed to create {}", stderr_path.display()))?;
+        let mut process = self.get_execution_backend()
+            .build_process(&validated_command, &command_id, &self.work_dir)
+            .stdout(Stdio::from(stdout))
+            .stderr(Stdio::from(stderr))
             .spawn()
             .context(format!("Failed to spawn command process: {}", validated_command))?;
         
-        // Capture stdout
-        let stdout = process.stdout.take()
-            .context("Failed to capture stdout")?;
-        
-        let stdout_reader = BufReader::new(stdout);
-        let stdout_tx = output_tx.clone();
-        let stdout_cmd_id = command_id.clone();
-        let stdout_file = output_file_handler.clone();
-        
-        task::spawn(async move {
-            for line in stdout_reader.lines() {
-                if let Ok(line) = line {
-                    // Log to file
-                    if let Ok(mut file) = stdout_file.lock() {
-                        let _ = writeln!(file, "[STDOUT] {}", line);
-                    }
-                    
-                    // Send to channel
-                    let output = CommandOutput {
-                        command_id: stdout_cmd_id.clone(),
-                        line: line.clone(),
-                        is_error: false,
-                    };
-                    
-                    if let Err(e) = stdout_tx.send(output).await {
-                        eprintln!("Error sending command output: {}", e);
-                    }
-                }
-            }
-        });
-        
-        // Capture stderr
-        let stderr = process.stderr.take()
-            .context("Failed to capture stderr")?;
-        
-        let stderr_reader = BufReader::new(stderr);
-        let stderr_tx = output_tx.clone();
-        let stderr_cmd_id = command_id.clone();
-        let stderr_file = output_file_handler.clone();
-        
-        task::spawn(async move {
-            for line in stderr_reader.lines() {
-                if let Ok(line) = line {
-                    // Log to file
-                    if let Ok(mut file) = stderr_file.lock() {
-                        let _ = writeln!(file, "[STDERR] {}", line);
-                    }
-                    
-                    // Send to channel
-                    let output = CommandOutput {
-                        command_id: stderr_cmd_id.clone(),
-                        line: line.clone(),
-                        is_error: true,
-                    };
-                    
-                    if let Err(e) = stderr_tx.send(output).await {
-                        eprintln!("Error sending command error output: {}", e);
-                    }
-                }
-            }
-        });
+        // Follow both files into the log and the output channel until the process exits
+        let finished = Arc::new(AtomicBool::new(false));
+        let followers = [(stdout_path, false), (stderr_path, true)].map(|(path, is_error)| follow_output(
+            path,
+            is_error,
+            command_id.clone(),
+            finished.clone(),
+            output_file_handler.clone(),
+            output_tx.clone(),
+        ));
         
         // Clone for task
         let active_commands = self.active_commands.clone();
+        let timeline = self.timeline.clone();
+        let notify_after = self.notify_after.clone();
+        let notifier = self.notifier.clone();
+        let output_files = self.output_files.clone();
         let cmd_id = command_id.clone();
         
-        // Spawn a task to wait for process completion
+        // Wait for the process and its last output on a thread of its own, so
+        // neither the runtime nor shutdown waits on a detached command
+        let (exit_tx, exit_rx) = oneshot::channel();
+        thread::spawn(move || {
+            let result = process.wait();
+            finished.store(true, Ordering::SeqCst);
+            for follower in followers {
+                let _ = follower.join();
+            }
+            let _ = exit_tx.send(result);
+        });
+        
+        // Spawn a task to record the command's completion
         task::spawn(async move {
-            match process.wait() {
+            let Ok(result) = exit_rx.await else {
+                return;
+            };
+            match result {
                 Ok(status) => {
                     // Update command status
                     let mut commands = active_commands.lock().unwrap();
@@ -237,6 +552,9 @@ impl CommandMonitor {
                         } else {
                             cmd.status = CommandStatus::Failed(format!("Command exited with code: {}", status));
                         }
+                        timeline.record(ActivityKind::CommandFinished, finished_description(cmd), Some(&cmd_id));
+                        notify_if_long(cmd, *notify_after.lock().unwrap());
+                        notifier.command_finished(cmd);
                     }
                 },
                 Err(e) => {
@@ -245,16 +563,74 @@ impl CommandMonitor {
                     if let Some(cmd) = commands.iter_mut().find(|cmd| cmd.id == cmd_id) {
                         cmd.end_time = Some(chrono::Utc::now());
                         cmd.status = CommandStatus::Failed(format!("Error waiting for command: {}", e));
+                        timeline.record(ActivityKind::CommandFinished, finished_description(cmd), Some(&cmd_id));
+                        notify_if_long(cmd, *notify_after.lock().unwrap());
+                        notifier.command_finished(cmd);
                     }
                 }
             }
+            
+            // Everything has been logged; stop holding the log open
+            output_files.lock().unwrap().remove(&cmd_id);
         });
         
         Ok(command_id)
     }
     
-    /// Validates and fixes commands to prevent privilege issues
-    fn validate_and_fix_command(&self, command: &str) -> Result<String> {
+    /// Validate a command and apply throttling, producing the command that actually runs
+    pub async fn prepare_command(&self, command: &str) -> Result<String> {
+        self.validate_command(command)?;
+        self.check_tool_installed(command.trim()).await?;
+        let prepared = self.rewrite_command(command);
+        
+        // Enforce the configured allow/deny lists on exactly what will run
+        self.policy.lock().unwrap().check(&prepared)?;
+        
+        // Files outside the work dir and the mounts don't exist inside a container
+        let unmounted = self.get_execution_backend().unmounted_paths(&prepared, &self.work_dir);
+        if !unmounted.is_empty() {
+            return Err(anyhow!(
+                "{} is not available in the container; add its directory to `mounts` under [container] or move it into {}",
+                unmounted.join(", "),
+                self.work_dir.display()
+            ));
+        }
+        Ok(prepared)
+    }
+    
+    /// The command that would run for `command`: privilege fixes, tool paths,
+    /// throttling, resolvers and proxying applied, without any of the checks
+    pub fn rewrite_command(&self, command: &str) -> String {
+        let command = command.trim();
+        
+        // Replace nmap's SYN scan (-sS), which requires root, with a TCP connect scan (-sT)
+        let rewritten = if command.contains("nmap") && command.contains(" -sS") && !command.starts_with("sudo ") {
+            command.replace(" -sS", " -sT")
+        } else {
+            command.to_string()
+        };
+        let rewritten = apply_tool_config(&rewritten, &self.tools.lock().unwrap());
+        
+        // Limit how hard the command hits its target
+        let rewritten = match self.throttle.lock().unwrap().as_ref() {
+            Some(throttle) => throttle.apply(&rewritten),
+            None => rewritten,
+        };
+        
+        // Resolve names through the engagement's DNS servers, before any proxychains wrapping
+        let rewritten = match self.resolvers.lock().unwrap().as_ref() {
+            Some(resolvers) => resolvers.apply(&rewritten),
+            None => rewritten,
+        };
+        
+        match self.proxy.lock().unwrap().as_ref() {
+            Some(proxy) => proxy.apply(&rewritten),
+            None => rewritten,
+        }
+    }
+    
+    /// Refuse commands that can't or shouldn't run as given
+    fn validate_command(&self, command: &str) -> Result<()> {
         // Trim the command to remove leading/trailing whitespace
         let command = command.trim();
         
@@ -263,8 +639,6 @@ impl CommandMonitor {
             return Err(anyhow!("Empty command"));
         }
         
-        let mut fixed_command = command.to_string();
-        
         // Check if command is explanatory text
         let explanatory_markers = [
             "try this", "this will", "command:", "run this", "executing:",
@@ -275,18 +649,12 @@ impl CommandMonitor {
         ];
         
         for marker in &explanatory_markers {
-            if fixed_command.to_lowercase().contains(marker) {
+            if command.to_lowercase().contains(marker) {
                 return Err(anyhow!("This appears to be explanatory text, not a command: '{}'", marker));
             }
         }
         
-        // Fix common command issues
-        
-        // 1. Fix nmap SYN scan (-sS) which requires root
-        if command.contains("nmap") && command.contains(" -sS") && !command.starts_with("sudo ") {
-            // Replace with TCP connect scan (-sT) which doesn't require root
-            fixed_command = fixed_command.replace(" -sS", " -sT");
-        }
+        // 1. nmap SYN scans (-sS) are rewritten to connect scans in `rewrite_command`
         
         // 2. Check for other nmap scans that require privileges
         if command.contains("nmap") && (command.contains(" -sU") || command.contains(" -sN") || 
@@ -297,25 +665,47 @@ impl CommandMonitor {
         }
         
         // 3. Validate the command structure for nmap
-        if fixed_command.starts_with("nmap") || fixed_command.starts_with("sudo nmap") {
+        if command.starts_with("nmap") || command.starts_with("sudo nmap") {
             // Check that it has a valid target
-            if !fixed_command.contains(".com") && !fixed_command.contains(".net") && 
-               !fixed_command.contains(".org") && !fixed_command.contains(".edu") && 
-               !fixed_command.contains(".gov") && !fixed_command.contains(".io") && 
-               !fixed_command.contains(".co") && !fixed_command.contains(" localhost") && 
-               !fixed_command.contains(" 127.0.0.1") && !fixed_command.contains(" 10.") && 
-               !fixed_command.contains(" 192.168.") && !fixed_command.contains(" 172.") {
+            if !command.contains(".com") && !command.contains(".net") && 
+               !command.contains(".org") && !command.contains(".edu") && 
+               !command.contains(".gov") && !command.contains(".io") && 
+               !command.contains(".co") && !command.contains(" localhost") && 
+               !command.contains(" 127.0.0.1") && !command.contains(" 10.") && 
+               !command.contains(" 192.168.") && !command.contains(" 172.") {
                 return Err(anyhow!("Nmap command appears to be missing a valid target"));
             }
         }
         
-        // 4. Validate that the command binary exists (for common commands)
+        Ok(())
+    }
+    
+    /// Check that the command's binary exists: a configured tool at its path and
+    /// pinned version, or one of the common tools in PATH
+    async fn check_tool_installed(&self, command: &str) -> Result<()> {
+        // Tools run inside a container don't need to be installed on the host
+        if self.get_execution_backend().is_container() {
+            return Ok(());
+        }
+        
+        let configured = self.tools.lock().unwrap().iter()
+            .find(|tool| tool.name == tool_name(command))
+            .cloned();
+        if let Some(tool) = configured {
+            if !self.verified_tools.lock().unwrap().contains(&tool.name) {
+                verify_tool(&tool).await?;
+                self.verified_tools.lock().unwrap().insert(tool.name.clone());
+            }
+            return Ok(());
+        }
+        
         let common_tools = ["nmap", "dig", "whois", "ping", "traceroute", "gobuster", "ffuf", "dirb"];
         for tool in common_tools {
-            if fixed_command.starts_with(tool) || fixed_command.starts_with(&format!("sudo {}", tool)) {
-                let check_cmd = Command::new("which")
+            if command.starts_with(tool) || command.starts_with(&format!("sudo {}", tool)) {
+                let check_cmd = tokio::process::Command::new("which")
                     .arg(tool)
                     .output()
+                    .await
                     .context(format!("Failed to check if {} is installed", tool))?;
                 
                 if !check_cmd.status.success() {
@@ -324,7 +714,7 @@ impl CommandMonitor {
             }
         }
         
-        Ok(fixed_command)
+        Ok(())
     }
     
     /// Get output receiver for consuming command output
@@ -364,6 +754,42 @@ impl CommandMonitor {
         commands.clone()
     }
     
+    /// Find a command equivalent to `command` that is running or finished within `window`
+    pub fn find_recent_duplicate(&self, command: &str, window: chrono::Duration) -> Option<MonitoredCommand> {
+        // Compare against the command as it would actually be executed, rewritten
+        // the same way but without running the checks
+        let normalized = normalize_command(&self.rewrite_command(command));
+        let cutoff = chrono::Utc::now() - window;
+        
+        let commands = self.active_commands.lock().unwrap();
+        commands.iter()
+            .filter(|cmd| matches!(cmd.status, CommandStatus::Running | CommandStatus::Completed))
+            .filter(|cmd| cmd.end_time.is_none_or(|end| end >= cutoff))
+            .filter(|cmd| normalize_command(&cmd.command) == normalized)
+            .max_by_key(|cmd| cmd.start_time)
+            .cloned()
+    }
+    
+    /// Whether a finding matches a false-positive suppression for its command's target
+    pub fn is_suppressed(&self, finding: &SecurityFinding) -> bool {
+        let target = self.get_command(&finding.command_id)
+            .and_then(|cmd| extract_target_from_command(&cmd.command));
+        self.suppressions.is_suppressed(&finding.title, target.as_deref())
+    }
+    
+    /// Raise the findings an integration produced for one of its lookups or
+    /// imports, skipping those marked as false positives. Returns the number raised
+    pub async fn record_integration_findings(&self, findings: impl IntoIterator<Item = SecurityFinding>) -> Result<usize> {
+        let mut raised = 0;
+        for finding in findings {
+            if !self.is_suppressed(&finding) {
+                self.add_finding(finding).await?;
+                raised += 1;
+            }
+        }
+        Ok(raised)
+    }
+    
     /// Add a finding to a command
     pub async fn add_finding(&self, finding: SecurityFinding) -> Result<()> {
         // Add finding to command
@@ -399,31 +825,36 @@ impl CommandMonitor {
         }
     }
     
-    /// Save all findings to a report file
-    pub fn generate_findings_report(&self, output_file: &PathBuf) -> Result<()> {
+    /// Attach brute-force statistics to a command
+    pub fn set_brute_force_stats(&self, id: &str, stats: BruteForceStats) {
+        let mut commands = self.active_commands.lock().unwrap();
+        if let Some(cmd) = commands.iter_mut().find(|cmd| cmd.id == id) {
+            cmd.brute_force_stats = Some(stats);
+        }
+    }
+    
+    /// Save all findings to a report file, optionally dropping those below `min_confidence`
+    pub fn generate_findings_report(&self, output_file: &PathBuf, include_suppressed: bool, min_confidence: Option<Confidence>) -> Result<()> {
         let commands = self.active_commands.lock().unwrap();
         
-        // Collect all findings
+        // Collect all findings, leaving out false positives unless asked for
         let mut all_findings = Vec::new();
         for cmd in commands.iter() {
+            let target = extract_target_from_command(&cmd.command);
             for finding in &cmd.findings {
-                all_findings.push((cmd, finding));
+                if min_confidence.as_ref().is_some_and(|min| finding.confidence.rank() < min.rank()) {
+                    continue;
+                }
+                if include_suppressed || !self.suppressions.is_suppressed(&finding.title, target.as_deref()) {
+                    all_findings.push((cmd, finding));
+                }
             }
         }
         
-        // Sort findings by severity
+        // Sort findings by CVSS score, highest first
         all_findings.sort_by(|(_, a), (_, b)| {
-            let severity_order = |s: &FindingSeverity| -> u8 {
-                match s {
-                    FindingSeverity::Critical => 0,
-                    FindingSeverity::High => 1,
-                    FindingSeverity::Medium => 2,
-                    FindingSeverity::Low => 3,
-                    FindingSeverity::Info => 4,
-                }
-            };
-            
-            severity_order(&a.severity).cmp(&severity_order(&b.severity))
+            cvss::sort_score(b.cvss.as_ref(), &b.severity)
+                .total_cmp(&cvss::sort_score(a.cvss.as_ref(), &a.severity))
         });
         
         // Generate report
@@ -436,29 +867,34 @@ impl CommandMonitor {
         writeln!(file, "# Security Findings Report")?;
         writeln!(file, "Generated: {}\n", chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"))?;
         
-        for (severity, findings) in [
-            FindingSeverity::Critical,
-            FindingSeverity::High,
-            FindingSeverity::Medium,
-            FindingSeverity::Low,
-            FindingSeverity::Info,
-        ].iter().map(|sev| {
-            (sev, all_findings.iter().filter(|(_, f)| f.severity == *sev).collect::<Vec<_>>())
-        }) {
-            if !findings.is_empty() {
-                writeln!(file, "## {:?} Findings ({})", severity, findings.len())?;
-                
-                for (cmd, finding) in findings {
-                    writeln!(file, "### {}", finding.title)?;
-                    writeln!(file, "**ID:** {}", finding.id)?;
-                    writeln!(file, "**Description:** {}", finding.description)?;
-                    writeln!(file, "**Command:** {}", cmd.command)?;
-                    writeln!(file, "**Discovered:** {}", finding.timestamp.format("%Y-%m-%d %H:%M:%S UTC"))?;
-                    writeln!(file, "**Raw Output:**\n```\n{}\n```\n", finding.raw_output)?;
-                }
-                
-                writeln!(file, "")?;
+        for (cmd, finding) in &all_findings {
+            writeln!(file, "## {}", finding.title)?;
+            writeln!(file, "**ID:** {}", finding.id)?;
+            writeln!(file, "**Severity:** {:?}", finding.severity)?;
+            writeln!(file, "**Confidence:** {:?}", finding.confidence)?;
+            if let Some(cvss) = &finding.cvss {
+                writeln!(file, "**CVSS:** {:.1} ({})", cvss.score, cvss.vector)?;
+            }
+            if let Some(cwe) = &finding.cwe {
+                writeln!(file, "**CWE:** {}", cwe)?;
+            }
+            if let Some(owasp) = &finding.owasp {
+                writeln!(file, "**OWASP Top 10:** {}", owasp)?;
             }
+            if !finding.related_cves.is_empty() {
+                let cves = finding.related_cves.iter()
+                    .map(|cve| match &cve.cvss {
+                        Some(cvss) => format!("{} ({:.1})", cve.id, cvss.score),
+                        None => cve.id.clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                writeln!(file, "**Related CVEs:** {}", cves)?;
+            }
+            writeln!(file, "**Description:** {}", finding.description)?;
+            writeln!(file, "**Command:** {}", cmd.command)?;
+            writeln!(file, "**Discovered:** {}", finding.timestamp.format("%Y-%m-%d %H:%M:%S UTC"))?;
+            writeln!(file, "**Raw Output:**\n```\n{}\n```\n", finding.raw_output)?;
         }
         
         Ok(())
@@ -498,6 +934,12 @@ impl CommandMonitor {
         
         if let Some(cmd) = cmd_opt {
             if let CommandStatus::Running = cmd.status {
+                // Containerized commands are stopped through the container runtime
+                if self.get_execution_backend().stop(cmd_id) {
+                    self.mark_terminated(cmd_id);
+                    return Ok(());
+                }
+                
                 // Find process by command
                 let ps_output = Command::new("ps")
                     .arg("-ef")
@@ -520,16 +962,7 @@ impl CommandMonitor {
                                     .output();
                                 
                                 // Update command status
-                                {
-                                    let mut commands = self.active_commands.lock().unwrap();
-                                    for cmd in commands.iter_mut() {
-                                        if cmd.id == cmd_id {
-                                            cmd.status = CommandStatus::Failed("Terminated by user".to_string());
-                                            cmd.end_time = Some(chrono::Utc::now());
-                                            break;
-                                        }
-                                    }
-                                }
+                                self.mark_terminated(cmd_id);
                                 
                                 return Ok(());
                             }
@@ -541,6 +974,274 @@ impl CommandMonitor {
         
         Err(anyhow!("Could not find running command with ID: {}", cmd_id))
     }
+    
+    /// Terminate every running command, returning the IDs that could not be stopped
+    pub async fn terminate_all(&self) -> Vec<String> {
+        let mut failed = Vec::new();
+        
+        for cmd in self.get_active_commands() {
+            if self.terminate_command(&cmd.id).await.is_err() {
+                failed.push(cmd.id);
+            }
+        }
+        
+        failed
+    }
+    
+    /// Mark running commands as detached so they keep running after Hacksor
+    /// exits, writing to their own output files. Returns the detached commands
+    pub fn detach_all(&self) -> Vec<MonitoredCommand> {
+        let mut commands = self.active_commands.lock().unwrap();
+        let mut detached = Vec::new();
+        for cmd in commands.iter_mut().filter(|cmd| matches!(cmd.status, CommandStatus::Running)) {
+            cmd.status = CommandStatus::Detached;
+            detached.push(cmd.clone());
+        }
+        detached
+    }
+    
+    /// Flush all command output files to disk
+    pub fn flush_output_files(&self) -> Result<()> {
+        let files = self.output_files.lock().unwrap();
+        for file in files.values() {
+            let mut file = file.lock().unwrap();
+            file.flush()?;
+            file.sync_all()?;
+        }
+        Ok(())
+    }
+    
+    /// Persist all command records to a JSON state file
+    pub fn save_state(&self, path: &Path) -> Result<()> {
+        let commands = self.get_all_commands();
+        let content = serde_json::to_string_pretty(&commands)?;
+        fs::write(path, content)
+            .context(format!("Failed to write session state to {:?}", path))?;
+        Ok(())
+    }
+    
+    /// Mark a command as terminated by the user
+    fn mark_terminated(&self, cmd_id: &str) {
+        let mut commands = self.active_commands.lock().unwrap();
+        if let Some(cmd) = commands.iter_mut().find(|cmd| cmd.id == cmd_id) {
+            cmd.status = CommandStatus::Failed("Terminated by user".to_string());
+            cmd.end_time = Some(chrono::Utc::now());
+            self.timeline.record(ActivityKind::CommandFinished, finished_description(cmd), Some(cmd_id));
+        }
+    }
+}
+
+/// Forward the lines a command's process writes to one of its output files into
+/// the command's log and the output channel as they arrive, until the process
+/// has exited and everything it wrote has been read
+fn follow_output(
+    path: PathBuf,
+    is_error: bool,
+    command_id: String,
+    finished: Arc<AtomicBool>,
+    log: Arc<Mutex<File>>,
+    output_tx: mpsc::Sender<CommandOutput>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let Ok(file) = File::open(&path) else {
+            return;
+        };
+        let mut reader = BufReader::new(file);
+        let mut line = Vec::new();
+        loop {
+            // Checked before reading, so output written just before exiting is still read
+            let done = finished.load(Ordering::SeqCst);
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) if done => break,
+                Ok(0) => {
+                    thread::sleep(FOLLOW_INTERVAL);
+                    continue;
+                },
+                // Wait for the rest of a line that is still being written
+                Ok(_) if !line.ends_with(b"\n") && !done => continue,
+                Ok(_) => {},
+                Err(_) => break,
+            }
+            
+            let text = String::from_utf8_lossy(&line).trim_end_matches(['\n', '\r']).to_string();
+            line.clear();
+            if let Ok(mut log) = log.lock() {
+                let _ = writeln!(log, "{} {}", if is_error { "[STDERR]" } else { "[STDOUT]" }, text);
+            }
+            let output = CommandOutput {
+                command_id: command_id.clone(),
+                line: text,
+                is_error,
+            };
+            if let Err(e) = output_tx.blocking_send(output) {
+                eprintln!("Error sending command output: {}", e);
+            }
+        }
+    })
+}
+
+/// Timeline entry text for a command that has stopped
+fn finished_description(cmd: &MonitoredCommand) -> String {
+    match &cmd.status {
+        CommandStatus::Failed(reason) => format!("Failed `{}`: {}", cmd.command, reason),
+        _ => format!("Finished `{}`", cmd.command),
+    }
+}
+
+/// Tell the desktop a command finished if it ran for at least `notify_after`
+fn notify_if_long(cmd: &MonitoredCommand, notify_after: Option<chrono::Duration>) {
+    let (Some(notify_after), Some(end_time)) = (notify_after, cmd.end_time) else {
+        return;
+    };
+    if end_time - cmd.start_time < notify_after {
+        return;
+    }
+    let summary = match cmd.status {
+        CommandStatus::Failed(_) => "Hacksor: command failed",
+        _ => "Hacksor: command finished",
+    };
+    notify_desktop(summary, &finished_description(cmd), false);
+}
+
+/// Run a configured tool from its configured path, with its default arguments
+/// placed before the ones given
+fn apply_tool_config(command: &str, tools: &[ToolConfig]) -> String {
+    let (prefix, rest) = match command.strip_prefix("sudo ") {
+        Some(rest) => ("sudo ", rest.trim_start()),
+        None => ("", command),
+    };
+    let name = rest.split_whitespace().next().unwrap_or("");
+    let Some(tool) = tools.iter().find(|tool| tool.name == name) else {
+        return command.to_string();
+    };
+    
+    // Paths with spaces or shell characters have to reach the shell as one word
+    let path = tool.path.to_string_lossy();
+    let path = match path.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '.' | '_' | '-' | '+')) {
+        true => path.into_owned(),
+        false => shell_quote(&path),
+    };
+    let mut rewritten = format!("{}{}", prefix, path);
+    for arg in &tool.args {
+        rewritten.push(' ');
+        rewritten.push_str(arg);
+    }
+    rewritten.push_str(&rest[name.len()..]);
+    rewritten
+}
+
+/// Check that a configured tool can be run from its path and, if its version
+/// is pinned, reports that version
+pub async fn verify_tool(tool: &ToolConfig) -> Result<()> {
+    if tool.version.is_none() {
+        // A bare name is looked up in PATH like any other command
+        let found = if tool.path.components().count() > 1 {
+            tool.path.is_file()
+        } else {
+            tokio::process::Command::new("which").arg(&tool.path).output().await
+                .is_ok_and(|output| output.status.success())
+        };
+        
+        return match found {
+            true => Ok(()),
+            false => Err(anyhow!("Tool '{}' was not found at {}", tool.name, tool.path.display())),
+        };
+    }
+    
+    // Some tools wait for input or a network check instead of printing a
+    // version; the process is killed when the timeout drops it
+    let run = tokio::process::Command::new(&tool.path)
+        .args(&tool.version_args)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = match tokio::time::timeout(VERSION_TIMEOUT, run).await {
+        Ok(output) => output.context(format!("Tool '{}' could not be run from {}", tool.name, tool.path.display()))?,
+        Err(_) => return Err(anyhow!(
+            "Tool '{}' at {} did not report its version within {} seconds",
+            tool.name, tool.path.display(), VERSION_TIMEOUT.as_secs()
+        )),
+    };
+    let reported = format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+    
+    match &tool.version {
+        Some(version) if !reports_version(&reported, version) => Err(anyhow!(
+            "Tool '{}' at {} is not the pinned version {} (it reports: {})",
+            tool.name,
+            tool.path.display(),
+            version,
+            reported.lines().find(|line| !line.trim().is_empty()).unwrap_or("nothing").trim(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Whether a tool's version output names exactly this version: `7.94` matches
+/// `Nmap version 7.94SVN` and `v7.94`, but not `7.94.1`, `17.94` or `7.941`
+fn reports_version(reported: &str, version: &str) -> bool {
+    let pattern = format!(r"(?:^|[^\w.]|v){}(?:$|[^\w.]|\.(?:$|\D)|[A-Za-z])", regex::escape(version));
+    regex::Regex::new(&pattern).is_ok_and(|pattern| pattern.is_match(reported))
+}
+
+/// Normalize a command so invocations differing only in whitespace or quoting
+/// compare equal. Token order is kept, since flags pair with the values after them
+pub fn normalize_command(command: &str) -> String {
+    let mut tokens = Vec::new();
+    // The token so far, and whether any of it was quoted or escaped
+    let mut token: Option<(String, bool)> = None;
+    let mut quote = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), _) if c == open => quote = None,
+            (Some(_), _) => token.get_or_insert_with(Default::default).0.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                token.get_or_insert_with(Default::default).1 = true;
+            },
+            (None, '\\') => {
+                let current = token.get_or_insert_with(Default::default);
+                current.0.extend(chars.next());
+                current.1 = true;
+            },
+            (None, c) if c.is_whitespace() => tokens.extend(token.take()),
+            (None, c) => token.get_or_insert_with(Default::default).0.push(c),
+        }
+    }
+    tokens.extend(token);
+
+    tokens.into_iter()
+        .map(|(token, quoted)| match quoted && (token.is_empty() || token.contains(|c: char| c.is_whitespace() || "'\"\\|&;<>()$`*?[]{}#~".contains(c))) {
+            true => format!("'{}'", token.replace('\'', "'\\''")),
+            false => token,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Determine the command type based on the command string
+pub fn determine_command_type(command: &str) -> CommandType {
+    let command = command.to_lowercase();
+    
+    if command.contains("nmap") || command.contains("ping") || command.contains("dig") || 
+       command.contains("whois") || command.contains("traceroute") || command.contains("host") ||
+       command.contains("subfinder") || command.contains("amass") || command.contains("assetfinder") {
+        CommandType::Reconnaissance
+    } else if command.contains("gobuster") || command.contains("dirsearch") || command.contains("nikto") || 
+              command.contains("wfuzz") || command.contains("ffuf") || command.contains("dirb") {
+        CommandType::Scanning
+    } else if command.contains("sqlmap") || command.contains("metasploit") || command.contains("msfconsole") ||
+              command.contains("exploitdb") || command.contains("searchsploit") {
+        CommandType::Exploitation
+    } else if command.contains("nuclei") || command.contains("nessus") || command.contains("openvas") ||
+              command.contains("zap") || command.contains("burpsuite") {
+        CommandType::Vulnerability
+    } else if command.contains("echo") || command.contains("cat") || command.contains("grep") || 
+              command.contains("find") || command.contains("awk") || command.contains("sed") {
+        CommandType::Documentation
+    } else {
+        CommandType::Generic
+    }
 }
 
 /// Helper function to create a new security finding
@@ -551,6 +1252,8 @@ pub fn create_finding(
     command_id: &str,
     raw_output: &str,
 ) -> SecurityFinding {
+    let (cwe, owasp) = taxonomy::classify(title);
+    
     SecurityFinding {
         id: Uuid::new_v4().to_string(),
         title: title.to_string(),
@@ -559,5 +1262,76 @@ pub fn create_finding(
         command_id: command_id.to_string(),
         raw_output: raw_output.to_string(),
         timestamp: chrono::Utc::now(),
+        cvss: cvss::default_score_for(title),
+        cwe: cwe.map(String::from),
+        owasp: owasp.map(String::from),
+        related_cves: Vec::new(),
+        confidence: Confidence::default(),
+        merge_key: None,
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn duplicates_compare_rewritten_commands_without_checking_them() {
+        let work_dir = TempDir::new().unwrap();
+        let monitor = CommandMonitor::new(work_dir.path().to_path_buf()).unwrap();
+        // Recorded as it ran, with the SYN scan rewritten
+        let id = monitor.record_lookup("nmap -sT example.com", Utc::now(), "").unwrap();
+
+        let duplicate = monitor.find_recent_duplicate("nmap  -sS  example.com", chrono::Duration::minutes(5));
+        assert_eq!(duplicate.map(|cmd| cmd.id), Some(id));
+        // Nothing was verified or recorded as verified on the way
+        assert!(monitor.verified_tools.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn configured_tool_paths_are_quoted_when_needed() {
+        let tool = |path: &str| ToolConfig {
+            name: "nmap".to_string(),
+            path: PathBuf::from(path),
+            args: vec!["-Pn".to_string()],
+            version: None,
+            version_args: Vec::new(),
+        };
+
+        let plain = apply_tool_config("nmap -sV example.com", &[tool("/opt/nmap-7.94/bin/nmap")]);
+        assert_eq!(plain, "/opt/nmap-7.94/bin/nmap -Pn -sV example.com");
+
+        let spaced = apply_tool_config("sudo nmap -sV example.com", &[tool("/opt/my tools/nmap")]);
+        assert_eq!(spaced, "sudo '/opt/my tools/nmap' -Pn -sV example.com");
+        assert_eq!(tool_name(&spaced), "nmap");
+    }
+
+    #[test]
+    fn pinned_versions_match_whole_version_numbers() {
+        assert!(reports_version("Nmap version 7.94SVN ( https://nmap.org )", "7.94"));
+        assert!(reports_version("ffuf v2.1.0-dev", "2.1.0"));
+        assert!(reports_version("sqlmap 1.8.4.", "1.8.4"));
+        assert!(!reports_version("Nmap version 7.94.1", "7.94"));
+        assert!(!reports_version("Nmap version 17.94", "7.94"));
+        assert!(!reports_version("Nmap version 7.941", "7.94"));
+    }
+
+    #[test]
+    fn normalizes_whitespace_and_quoting() {
+        assert_eq!(normalize_command("nmap  -p 80\t'example.com'"), "nmap -p 80 example.com");
+        assert_eq!(normalize_command("curl -H \"X-A: b\" example.com"), "curl -H 'X-A: b' example.com");
+        assert_eq!(normalize_command("curl -H 'X-A: b' example.com"), "curl -H 'X-A: b' example.com");
+        assert_eq!(normalize_command("grep 'a|b' out.txt"), "grep 'a|b' out.txt");
+        assert_ne!(normalize_command("grep '|' out.txt"), normalize_command("grep | out.txt"));
+    }
+
+    #[test]
+    fn keeps_flags_with_their_values() {
+        assert_ne!(
+            normalize_command("hydra -l admin -p secret example.com ssh"),
+            normalize_command("hydra -l secret -p admin example.com ssh"),
+        );
+        assert_ne!(normalize_command("nmap -p 80 -T4 example.com"), normalize_command("nmap -p 4 -T80 example.com"));
+    }
+}
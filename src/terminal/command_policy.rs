@@ -0,0 +1,251 @@
+use anyhow::{Result, Context};
+use regex::Regex;
+use thiserror::Error;
+
+use crate::config::CommandPolicyConfig;
+
+/// A command refused by the configured allow/deny lists
+#[derive(Debug, Error)]
+#[error("Command refused by policy: {reason}")]
+pub struct PolicyViolation {
+    pub command: String,
+    pub reason: String,
+}
+
+/// Allow/deny rules checked before any command is executed
+#[derive(Debug, Clone, Default)]
+pub struct CommandPolicy {
+    allowed_binaries: Vec<String>,
+    denied_binaries: Vec<String>,
+    denied_patterns: Vec<Regex>,
+}
+
+impl CommandPolicy {
+    pub fn from_config(config: &CommandPolicyConfig) -> Result<Self> {
+        let denied_patterns = config.denied_patterns.iter()
+            .map(|pattern| Regex::new(pattern)
+                .context(format!("Invalid denied command pattern: {}", pattern)))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            allowed_binaries: config.allowed_binaries.clone(),
+            denied_binaries: config.denied_binaries.clone(),
+            denied_patterns,
+        })
+    }
+
+    /// Check every binary in a command line, including pipelines, chains and
+    /// commands run through wrappers such as sudo, env or xargs. When binaries
+    /// are restricted, anything hiding what runs is refused: command and
+    /// process substitutions, `eval` and shells given a command string
+    pub fn check(&self, command: &str) -> std::result::Result<(), PolicyViolation> {
+        let refuse = |reason: String| PolicyViolation {
+            command: command.to_string(),
+            reason,
+        };
+
+        if let Some(pattern) = self.denied_patterns.iter().find(|pattern| pattern.is_match(command)) {
+            return Err(refuse(format!("matches denied pattern '{}'", pattern.as_str())));
+        }
+
+        if self.allowed_binaries.is_empty() && self.denied_binaries.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(construct) = substitution(command) {
+            return Err(refuse(format!("{} hides which commands run", construct)));
+        }
+
+        for segment in command_segments(command) {
+            let Some(binary) = segment_binary(segment).map_err(refuse)? else {
+                continue;
+            };
+
+            if self.denied_binaries.iter().any(|denied| denied == binary) {
+                return Err(refuse(format!("'{}' is on the denylist", binary)));
+            }
+
+            if !self.allowed_binaries.is_empty() && !self.allowed_binaries.iter().any(|allowed| allowed == binary) {
+                return Err(refuse(format!("'{}' is not on the allowlist ({})", binary, self.allowed_binaries.join(", "))));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A command that runs the command given in its arguments
+struct Wrapper {
+    name: &'static str,
+    /// Options taking a separate value
+    value_flags: &'static [&'static str],
+    /// Arguments between the options and the wrapped command, e.g. timeout's duration
+    positionals: usize,
+}
+
+const WRAPPERS: &[Wrapper] = &[
+    Wrapper { name: "sudo", value_flags: &["-u", "-g", "-C", "-h", "-p", "-r", "-t", "-U"], positionals: 0 },
+    Wrapper { name: "env", value_flags: &["-u", "-C"], positionals: 0 },
+    Wrapper { name: "nice", value_flags: &["-n"], positionals: 0 },
+    Wrapper { name: "nohup", value_flags: &[], positionals: 0 },
+    Wrapper { name: "time", value_flags: &["-f", "-o"], positionals: 0 },
+    Wrapper { name: "timeout", value_flags: &["-s", "-k"], positionals: 1 },
+    Wrapper { name: "stdbuf", value_flags: &["-i", "-o", "-e"], positionals: 0 },
+    Wrapper { name: "xargs", value_flags: &["-I", "-n", "-P", "-d", "-L", "-s", "-a", "-E"], positionals: 0 },
+    Wrapper { name: "command", value_flags: &[], positionals: 0 },
+    Wrapper { name: "exec", value_flags: &["-a"], positionals: 0 },
+    // Added by the proxy and bandwidth settings
+    Wrapper { name: "proxychains", value_flags: &["-f"], positionals: 0 },
+    Wrapper { name: "proxychains4", value_flags: &["-f"], positionals: 0 },
+    Wrapper { name: "trickle", value_flags: &["-d", "-u", "-w", "-t", "-l", "-n"], positionals: 0 },
+];
+
+/// Shells that run a command string given with `-c`
+const SHELLS: &[&str] = &["sh", "bash", "zsh", "dash", "ksh", "fish"];
+
+/// Words that start a segment without being its binary
+const SHELL_KEYWORDS: &[&str] = &["{", "}", "!", "if", "then", "else", "elif", "while", "until", "do"];
+
+/// The first command or process substitution outside single quotes
+fn substitution(command: &str) -> Option<&'static str> {
+    let mut quote = None;
+    let mut previous = ' ';
+    for c in command.chars() {
+        match (quote, c) {
+            (Some('\''), '\'') => quote = None,
+            (Some('\''), _) => {},
+            (_, '`') => return Some("Backtick substitution"),
+            (_, '(') if previous == '$' => return Some("Command substitution"),
+            (None, '(') if matches!(previous, '<' | '>') => return Some("Process substitution"),
+            (Some('"'), '"') => quote = None,
+            (None, '\'' | '"') => quote = Some(c),
+            _ => {},
+        }
+        previous = c;
+    }
+    None
+}
+
+/// Split a command line into the commands of its pipelines, chains and
+/// subshells, leaving quoted text and `2>&1`-style redirections alone
+fn command_segments(command: &str) -> Vec<&str> {
+    let bytes = command.as_bytes();
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut quote = None;
+    for (i, c) in command.char_indices() {
+        match (quote, c) {
+            (Some(open), _) if c == open => quote = None,
+            (Some(_), _) => {},
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '&') if (i > 0 && matches!(bytes[i - 1], b'>' | b'<')) || bytes.get(i + 1) == Some(&b'>') => {},
+            (None, '|' | ';' | '&' | '\n' | '(' | ')') => {
+                segments.push(&command[start..i]);
+                start = i + 1;
+            },
+            _ => {},
+        }
+    }
+    segments.push(&command[start..]);
+    segments
+}
+
+/// The binary a segment runs, looking through wrappers and environment
+/// assignments; an error if it runs a command string instead
+fn segment_binary(segment: &str) -> std::result::Result<Option<&str>, String> {
+    let mut terms = segment.split_whitespace()
+        .map(|term| term.trim_start_matches('\\').trim_matches(['\'', '"']));
+    let mut wrapper: Option<&Wrapper> = None;
+    let mut positionals = 0;
+
+    while let Some(term) = terms.next() {
+        if let Some(current) = wrapper {
+            if term.starts_with('-') {
+                if current.value_flags.contains(&term) {
+                    terms.next();
+                }
+                continue;
+            }
+            if positionals > 0 {
+                positionals -= 1;
+                continue;
+            }
+        }
+        if term.is_empty() || term.contains('=') || (wrapper.is_none() && SHELL_KEYWORDS.contains(&term)) {
+            continue;
+        }
+
+        let binary = term.rsplit('/').next().unwrap_or(term);
+        if let Some(next) = WRAPPERS.iter().find(|known| known.name == binary) {
+            wrapper = Some(next);
+            positionals = next.positionals;
+            continue;
+        }
+        if binary == "eval" || (SHELLS.contains(&binary) && terms.any(|term| term.starts_with('-') && !term.starts_with("--") && term.contains('c'))) {
+            return Err(format!("'{}' runs a command string, which hides which commands run", binary));
+        }
+        return Ok(Some(binary));
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(allowed: &[&str], denied: &[&str]) -> CommandPolicy {
+        CommandPolicy::from_config(&CommandPolicyConfig {
+            allowed_binaries: allowed.iter().map(|binary| binary.to_string()).collect(),
+            denied_binaries: denied.iter().map(|binary| binary.to_string()).collect(),
+            denied_patterns: Vec::new(),
+        }).unwrap()
+    }
+
+    #[test]
+    fn checks_every_command_of_a_line() {
+        let policy = policy(&[], &["nc"]);
+        assert!(policy.check("nmap -sV example.com 2>&1 | tee 'scan|out.txt'").is_ok());
+        assert!(policy.check("nmap example.com && nc example.com 80").is_err());
+        assert!(policy.check("(nc example.com 80)").is_err());
+        assert!(policy.check("/usr/bin/nc example.com 80").is_err());
+    }
+
+    #[test]
+    fn looks_through_wrappers() {
+        let policy = policy(&[], &["nc"]);
+        for command in [
+            "sudo -u root nc example.com 80",
+            "env FOO=bar nc example.com 80",
+            "nice -n 10 nc example.com 80",
+            "timeout 30 nc example.com 80",
+            "echo example.com | xargs -I {} nc {} 80",
+            "proxychains4 -q -f /etc/proxychains.conf nc example.com 80",
+            "trickle -s -d 100 -u 100 nc example.com 80",
+        ] {
+            assert!(policy.check(command).is_err(), "{}", command);
+        }
+        assert!(policy.check("sudo nmap example.com").is_ok());
+    }
+
+    #[test]
+    fn refuses_hidden_commands() {
+        let policy = policy(&["nmap", "echo"], &[]);
+        for command in [
+            "echo $(nc example.com 80)",
+            "echo `nc example.com 80`",
+            "nmap -iL <(nc example.com 80)",
+            "bash -c 'nc example.com 80'",
+            "sh -lc 'nc example.com 80'",
+            "eval nc example.com 80",
+        ] {
+            assert!(policy.check(command).is_err(), "{}", command);
+        }
+        assert!(policy.check("echo '$(not run)'").is_ok());
+    }
+
+    #[test]
+    fn unrestricted_policy_allows_anything() {
+        assert!(policy(&[], &[]).check("bash -c \"$(curl example.com)\"").is_ok());
+    }
+}
@@ -0,0 +1,140 @@
+use std::net::IpAddr;
+use std::time::Duration;
+use anyhow::{Result, Context, anyhow};
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::config::CrtshConfig;
+use super::command_monitor::CommandMonitor;
+use super::output_analyzer::subdomains_finding;
+
+const API_URL: &str = "https://crt.sh/";
+
+/// New subdomains named in a lookup's summary; its finding lists them all
+const MAX_LISTED_SUBDOMAINS: usize = 10;
+
+/// Searches certificate transparency logs through crt.sh for the subdomains
+/// certificates were ever issued for, without an external tool or API key
+pub struct CrtshClient {
+    expired: bool,
+    client: reqwest::Client,
+}
+
+/// A logged certificate
+#[derive(Debug, Clone, Deserialize)]
+pub struct CertificateEntry {
+    pub id: u64,
+    #[serde(default)]
+    pub issuer_name: String,
+    #[serde(default)]
+    pub common_name: String,
+    /// The names the certificate covers, one per line
+    #[serde(default)]
+    pub name_value: String,
+    #[serde(default)]
+    pub not_before: Option<String>,
+    #[serde(default)]
+    pub not_after: Option<String>,
+}
+
+impl CrtshClient {
+    pub fn new(config: &CrtshConfig) -> Result<Self> {
+        Ok(Self {
+            expired: config.expired,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(config.timeout_secs))
+                .build()?,
+        })
+    }
+
+    /// Certificates logged for any subdomain of a domain
+    pub async fn certificates(&self, domain: &str) -> Result<Vec<CertificateEntry>> {
+        if domain.parse::<IpAddr>().is_ok() {
+            return Err(anyhow!("crt.sh only searches domains, not IP addresses"));
+        }
+
+        let pattern = format!("%.{}", domain);
+        let mut request = self.client.get(API_URL)
+            .query(&[("q", pattern.as_str()), ("output", "json")]);
+        if !self.expired {
+            request = request.query(&[("exclude", "expired")]);
+        }
+        let response = request.send()
+            .await
+            .map_err(|e| anyhow!("Failed to reach crt.sh: {}", e))?;
+
+        // crt.sh answers an overloaded query with an HTML error page
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow!("{}", status));
+        }
+        response.json().await.context("crt.sh returned an unexpected response; it may be overloaded, try again later")
+    }
+}
+
+/// The command a lookup is recorded as
+pub fn lookup_command(domain: &str) -> String {
+    format!("crtsh {}", domain)
+}
+
+/// Subdomains of the domain named in the certificates, without wildcards
+pub fn subdomains(entries: &[CertificateEntry], domain: &str) -> Vec<String> {
+    let suffix = format!(".{}", domain.to_lowercase());
+    let mut subdomains: Vec<String> = entries.iter()
+        .flat_map(|entry| entry.name_value.lines().chain([entry.common_name.as_str()]))
+        .map(|name| name.trim().trim_start_matches("*.").to_lowercase())
+        .filter(|name| name.ends_with(&suffix))
+        .collect();
+    subdomains.sort();
+    subdomains.dedup();
+    subdomains
+}
+
+/// The certificates as written to the lookup's log
+fn report(entries: &[CertificateEntry]) -> String {
+    let mut report = String::new();
+    for entry in entries {
+        let names: Vec<&str> = entry.name_value.lines().collect();
+        report.push_str(&format!(
+            "crt.sh ID {} ({}, valid {} to {}): {}\n",
+            entry.id,
+            entry.issuer_name,
+            entry.not_before.as_deref().unwrap_or("?"),
+            entry.not_after.as_deref().unwrap_or("?"),
+            names.join(", "),
+        ));
+    }
+    report
+}
+
+/// Search certificate transparency logs for a domain's subdomains and record
+/// them like a subdomain enumeration tool's results: the certificates in the
+/// session's logs, the subdomains in the asset inventory and the ones no other
+/// source found in a "Subdomains Discovered" finding. Returns a summary
+pub async fn passive_recon(client: &CrtshClient, monitor: &CommandMonitor, target: &str) -> Result<String> {
+    let started = Utc::now();
+    let entries = client.certificates(target).await?;
+    let subdomains = subdomains(&entries, target);
+    let command_id = monitor.record_lookup(&lookup_command(target), started, &report(&entries))?;
+
+    let assets = monitor.get_asset_inventory();
+    let new: Vec<String> = subdomains.iter()
+        .filter(|subdomain| assets.record_subdomain(subdomain, &command_id))
+        .cloned()
+        .collect();
+
+    let mut summary = format!(
+        "crt.sh lists {} certificate(s) for {} naming {} subdomain(s), {} not found by other sources",
+        entries.len(), target, subdomains.len(), new.len(),
+    );
+    if !new.is_empty() {
+        let listed = new.iter().take(MAX_LISTED_SUBDOMAINS).cloned().collect::<Vec<_>>().join(", ");
+        let more = new.len().saturating_sub(MAX_LISTED_SUBDOMAINS);
+        let more = if more > 0 { format!(" and {} more", more) } else { String::new() };
+        summary.push_str(&format!(": {}{}", listed, more));
+    }
+    monitor.update_command_summary(&command_id, &summary)?;
+
+    monitor.record_integration_findings(subdomains_finding(&new, &command_id)).await?;
+    Ok(summary)
+}
@@ -0,0 +1,173 @@
+use std::cmp::Ordering;
+use std::fs;
+use std::path::Path;
+use anyhow::{Result, Context};
+use serde::{Serialize, Deserialize};
+
+use super::cvss::CvssScore;
+
+/// A CVE record in the local dataset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CveRecord {
+    pub id: String,
+    /// Product name as it appears in banners, e.g. `apache`, `nginx`, `openssh`
+    pub product: String,
+    /// Exact affected versions
+    #[serde(default)]
+    pub versions: Vec<String>,
+    /// Affected range, NVD style
+    #[serde(default)]
+    pub version_start_including: Option<String>,
+    #[serde(default)]
+    pub version_end_excluding: Option<String>,
+    #[serde(default)]
+    pub version_end_including: Option<String>,
+    #[serde(default)]
+    pub cvss_vector: Option<String>,
+    #[serde(default)]
+    pub summary: String,
+}
+
+/// A CVE matched against a disclosed software version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CveMatch {
+    pub id: String,
+    pub cvss: Option<CvssScore>,
+    pub summary: String,
+    /// Advisories, patches and exploits, when looked up on NVD
+    #[serde(default)]
+    pub references: Vec<String>,
+}
+
+/// Local CVE dataset used to correlate version disclosures with known vulnerabilities
+#[derive(Debug, Clone, Default)]
+pub struct CveDatabase {
+    records: Vec<CveRecord>,
+}
+
+impl CveDatabase {
+    /// Load a JSON list of CVE records, starting empty if the file does not exist
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path)?;
+        let records = serde_json::from_str(&content)
+            .context(format!("Failed to parse CVE dataset {:?}", path))?;
+        Ok(Self { records })
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// CVEs affecting a product version, highest CVSS score first
+    pub fn lookup(&self, product: &str, version: &str, limit: usize) -> Vec<CveMatch> {
+        let mut matches: Vec<CveMatch> = self.records.iter()
+            .filter(|record| record.product.eq_ignore_ascii_case(product))
+            .filter(|record| record.affects(version))
+            .map(|record| CveMatch {
+                id: record.id.clone(),
+                cvss: record.cvss_vector.as_deref().and_then(|vector| CvssScore::from_vector(vector).ok()),
+                summary: record.summary.clone(),
+                references: Vec::new(),
+            })
+            .collect();
+
+        let score = |m: &CveMatch| m.cvss.as_ref().map_or(0.0, |cvss| cvss.score);
+        matches.sort_by(|a, b| score(b).total_cmp(&score(a)));
+        matches.truncate(limit);
+        matches
+    }
+}
+
+impl CveRecord {
+    fn affects(&self, version: &str) -> bool {
+        if self.versions.iter().any(|affected| affected == version) {
+            return true;
+        }
+
+        let has_range = self.version_start_including.is_some()
+            || self.version_end_excluding.is_some()
+            || self.version_end_including.is_some();
+        if !has_range {
+            return false;
+        }
+
+        let after_start = self.version_start_including.as_deref()
+            .is_none_or(|start| compare_versions(version, start) != Ordering::Less);
+        let before_end = match (&self.version_end_excluding, &self.version_end_including) {
+            (Some(end), _) => compare_versions(version, end) == Ordering::Less,
+            (None, Some(end)) => compare_versions(version, end) != Ordering::Greater,
+            (None, None) => true,
+        };
+        after_start && before_end
+    }
+}
+
+/// Compare dotted versions numerically, e.g. `2.4.9` < `2.4.49`
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |version: &str| -> Vec<u64> {
+        version.split(['.', '-', 'p'])
+            .map(|part| part.chars().take_while(|c| c.is_ascii_digit()).collect::<String>())
+            .map(|digits| digits.parse().unwrap_or(0))
+            .collect()
+    };
+    let (a, b) = (parts(a), parts(b));
+
+    for i in 0..a.len().max(b.len()) {
+        let ordering = a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_versions_numerically() {
+        assert_eq!(compare_versions("2.4.9", "2.4.49"), Ordering::Less);
+        assert_eq!(compare_versions("2.4.49", "2.4.49"), Ordering::Equal);
+        assert_eq!(compare_versions("10.0", "9.9.9"), Ordering::Greater);
+    }
+
+    #[test]
+    fn missing_components_count_as_zero() {
+        assert_eq!(compare_versions("1.2", "1.2.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.2", "1.2.1"), Ordering::Less);
+    }
+
+    #[test]
+    fn compares_openssh_patch_levels() {
+        assert_eq!(compare_versions("8.2p1", "8.2p2"), Ordering::Less);
+        assert_eq!(compare_versions("8.9p1", "8.2p1"), Ordering::Greater);
+        assert_eq!(compare_versions("7.4", "7.4p1"), Ordering::Less);
+    }
+
+    #[test]
+    fn looks_up_openssh_ranges() {
+        let db = CveDatabase {
+            records: vec![CveRecord {
+                id: "CVE-2023-38408".to_string(),
+                product: "openssh".to_string(),
+                versions: Vec::new(),
+                version_start_including: None,
+                version_end_excluding: Some("9.3p2".to_string()),
+                version_end_including: None,
+                cvss_vector: None,
+                summary: String::new(),
+            }],
+        };
+        assert_eq!(db.lookup("OpenSSH", "8.2p1", 5).len(), 1);
+        assert!(db.lookup("OpenSSH", "9.4p1", 5).is_empty());
+    }
+}
@@ -0,0 +1,215 @@
+use anyhow::{Result, anyhow};
+use serde::{Serialize, Deserialize};
+
+use super::command_monitor::FindingSeverity;
+
+/// A CVSS v3.1 base vector and its computed score
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CvssScore {
+    pub vector: String,
+    pub score: f32,
+}
+
+impl CvssScore {
+    /// Parse a `CVSS:3.1/AV:N/AC:L/...` vector and compute its base score
+    pub fn from_vector(vector: &str) -> Result<Self> {
+        Ok(Self {
+            vector: vector.to_string(),
+            score: base_score(vector)?,
+        })
+    }
+}
+
+/// Default CVSS vector for the finding types the analyzer produces
+pub fn default_vector_for(title: &str) -> Option<&'static str> {
+    let title = title.to_lowercase();
+
+    if title.contains("sql injection") {
+        Some("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H")
+    } else if title.contains("xss") {
+        Some("CVSS:3.1/AV:N/AC:L/PR:N/UI:R/S:C/C:L/I:L/A:N")
+    } else if title.contains("remote file inclusion") || title.contains("php wrapper") {
+        Some("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H")
+    } else if title.contains("local file inclusion") || title.contains("path traversal") {
+        Some("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:N/A:N")
+    } else if title.contains("open redirect") {
+        Some("CVSS:3.1/AV:N/AC:L/PR:N/UI:R/S:C/C:L/I:L/A:N")
+    } else if title.contains("sensitive path") {
+        Some("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:L/I:N/A:N")
+    } else if title.contains("tls protocol") || title.contains("tls cipher") {
+        Some("CVSS:3.1/AV:N/AC:H/PR:N/UI:N/S:U/C:H/I:N/A:N")
+    } else if title.contains("tls certificate") || title.contains("hsts") {
+        Some("CVSS:3.1/AV:N/AC:H/PR:N/UI:R/S:U/C:L/I:L/A:N")
+    } else if title.contains("version disclosure") {
+        Some("CVSS:3.1/AV:N/AC:H/PR:N/UI:N/S:U/C:L/I:N/A:N")
+    } else {
+        None
+    }
+}
+
+/// Default CVSS score for a finding type, if one is defined
+pub fn default_score_for(title: &str) -> Option<CvssScore> {
+    default_vector_for(title).and_then(|vector| CvssScore::from_vector(vector).ok())
+}
+
+/// Score used to order findings: the CVSS score when known, otherwise
+/// the lower bound of the severity's CVSS range
+pub fn sort_score(cvss: Option<&CvssScore>, severity: &FindingSeverity) -> f32 {
+    match cvss {
+        Some(cvss) => cvss.score,
+        None => match severity {
+            FindingSeverity::Critical => 9.0,
+            FindingSeverity::High => 7.0,
+            FindingSeverity::Medium => 4.0,
+            FindingSeverity::Low => 0.1,
+            FindingSeverity::Info => 0.0,
+        },
+    }
+}
+
+/// The severity band a CVSS score falls in
+pub fn severity_for_score(score: f32) -> FindingSeverity {
+    match score {
+        score if score >= 9.0 => FindingSeverity::Critical,
+        score if score >= 7.0 => FindingSeverity::High,
+        score if score >= 4.0 => FindingSeverity::Medium,
+        score if score > 0.0 => FindingSeverity::Low,
+        _ => FindingSeverity::Info,
+    }
+}
+
+/// Compute the CVSS v3.1 base score of a vector
+pub fn base_score(vector: &str) -> Result<f32> {
+    let metrics = vector.strip_prefix("CVSS:3.1/")
+        .or_else(|| vector.strip_prefix("CVSS:3.0/"))
+        .ok_or_else(|| anyhow!("Not a CVSS v3 vector: {}", vector))?;
+
+    let metric = |name: &str| -> Result<&str> {
+        metrics.split('/')
+            .filter_map(|part| part.split_once(':'))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+            .ok_or_else(|| anyhow!("Missing {} metric in CVSS vector: {}", name, vector))
+    };
+
+    let invalid = |name: &str| anyhow!("Invalid {} value in CVSS vector: {}", name, vector);
+    let scope_changed = match metric("S")? {
+        "U" => false,
+        "C" => true,
+        _ => return Err(invalid("S")),
+    };
+
+    let attack_vector = match metric("AV")? {
+        "N" => 0.85,
+        "A" => 0.62,
+        "L" => 0.55,
+        "P" => 0.2,
+        _ => return Err(invalid("AV")),
+    };
+    let attack_complexity = match metric("AC")? {
+        "L" => 0.77,
+        "H" => 0.44,
+        _ => return Err(invalid("AC")),
+    };
+    let privileges_required = match (metric("PR")?, scope_changed) {
+        ("N", _) => 0.85,
+        ("L", false) => 0.62,
+        ("L", true) => 0.68,
+        ("H", false) => 0.27,
+        ("H", true) => 0.5,
+        _ => return Err(invalid("PR")),
+    };
+    let user_interaction = match metric("UI")? {
+        "N" => 0.85,
+        "R" => 0.62,
+        _ => return Err(invalid("UI")),
+    };
+
+    let impact_value = |name: &str| -> Result<f64> {
+        match metric(name)? {
+            "H" => Ok(0.56),
+            "L" => Ok(0.22),
+            "N" => Ok(0.0),
+            _ => Err(invalid(name)),
+        }
+    };
+    let confidentiality = impact_value("C")?;
+    let integrity = impact_value("I")?;
+    let availability = impact_value("A")?;
+
+    let iss = 1.0 - (1.0 - confidentiality) * (1.0 - integrity) * (1.0 - availability);
+    let impact = if scope_changed {
+        7.52 * (iss - 0.029) - 3.25 * (iss - 0.02_f64).powi(15)
+    } else {
+        6.42 * iss
+    };
+    let exploitability = 8.22 * attack_vector * attack_complexity * privileges_required * user_interaction;
+
+    if impact <= 0.0 {
+        return Ok(0.0);
+    }
+
+    let score = if scope_changed {
+        round_up((1.08 * (impact + exploitability)).min(10.0))
+    } else {
+        round_up((impact + exploitability).min(10.0))
+    };
+
+    Ok(score as f32)
+}
+
+/// Round up to one decimal place as specified in CVSS v3.1 Appendix A
+fn round_up(value: f64) -> f64 {
+    let int_input = (value * 100_000.0).round() as i64;
+    if int_input % 10_000 == 0 {
+        int_input as f64 / 100_000.0
+    } else {
+        ((int_input / 10_000) + 1) as f64 / 10.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_unchanged_scope_vectors() {
+        assert_eq!(base_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H").unwrap(), 9.8);
+        assert_eq!(base_score("CVSS:3.1/AV:N/AC:H/PR:N/UI:N/S:U/C:H/I:N/A:N").unwrap(), 5.9);
+        assert_eq!(base_score("CVSS:3.0/AV:L/AC:L/PR:L/UI:N/S:U/C:H/I:H/A:H").unwrap(), 7.8);
+    }
+
+    #[test]
+    fn scores_changed_scope_vectors() {
+        assert_eq!(base_score(default_vector_for("Reflected XSS").unwrap()).unwrap(), 6.1);
+        assert_eq!(base_score("CVSS:3.1/AV:N/AC:L/PR:L/UI:N/S:C/C:H/I:H/A:H").unwrap(), 9.9);
+        assert_eq!(base_score("CVSS:3.1/AV:N/AC:L/PR:H/UI:N/S:C/C:H/I:H/A:H").unwrap(), 9.1);
+        assert_eq!(base_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:C/C:H/I:H/A:H").unwrap(), 10.0);
+    }
+
+    #[test]
+    fn scores_no_impact_as_zero() {
+        assert_eq!(base_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:N").unwrap(), 0.0);
+        assert_eq!(base_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:C/C:N/I:N/A:N").unwrap(), 0.0);
+    }
+
+    #[test]
+    fn rounds_up_as_in_appendix_a() {
+        assert_eq!(round_up(4.0), 4.0);
+        assert_eq!(round_up(4.02), 4.1);
+        assert_eq!(round_up(4.000_000_1), 4.0);
+        // 0.1 + 0.2 is 0.30000000000000004 in floating point
+        assert_eq!(round_up(0.1 + 0.2), 0.3);
+        assert_eq!(round_up(9.999_9), 10.0);
+    }
+
+    #[test]
+    fn rejects_malformed_vectors() {
+        assert!(base_score("AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H").is_err());
+        assert!(base_score("CVSS:2.0/AV:N/AC:L/Au:N/C:P/I:P/A:P").is_err());
+        assert!(base_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H").is_err());
+        assert!(base_score("CVSS:3.1/AV:X/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H").is_err());
+        assert!(base_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:Q/C:H/I:H/A:H").is_err());
+        assert!(CvssScore::from_vector("").is_err());
+    }
+}
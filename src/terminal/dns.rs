@@ -0,0 +1,339 @@
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use anyhow::{Result, Context, anyhow};
+use chrono::Utc;
+use tokio::net::UdpSocket;
+
+use crate::config::DnsConfig;
+use super::command_monitor::CommandMonitor;
+use super::proxy::insertion_point;
+use super::throttle::{has_flag, tool_name};
+
+/// The plain resolvers, one per line, for tools that take a resolver file
+pub const RESOLVERS_FILE: &str = "resolvers.txt";
+
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+
+/// A DNS server Hacksor queries and hands to tools
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resolver {
+    /// Plain DNS over UDP
+    Udp(SocketAddr),
+    /// DNS over HTTPS (RFC 8484); only Hacksor's own lookups can use it
+    Https(String),
+}
+
+impl Resolver {
+    /// `10.0.0.53`, `10.0.0.53:5353`, `[2001:db8::53]:53` or `https://dns.example/dns-query`
+    pub fn parse(entry: &str) -> Result<Self> {
+        let entry = entry.trim();
+        if entry.starts_with("https://") {
+            return Ok(Resolver::Https(entry.to_string()));
+        }
+        if let Ok(address) = entry.parse::<IpAddr>() {
+            return Ok(Resolver::Udp(SocketAddr::new(address, 53)));
+        }
+        entry.parse::<SocketAddr>()
+            .map(Resolver::Udp)
+            .map_err(|_| anyhow!("Unknown resolver {}; expected an IP address, IP:port or an https:// DoH URL", entry))
+    }
+
+    /// As written in resolver files: the address, with the port when it is not 53
+    fn file_entry(&self) -> Option<String> {
+        match self {
+            Resolver::Udp(address) if address.port() == 53 => Some(address.ip().to_string()),
+            Resolver::Udp(address) => Some(address.to_string()),
+            Resolver::Https(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Resolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Resolver::Udp(address) => write!(f, "{}", address),
+            Resolver::Https(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+/// How a tool is given the resolvers
+enum ResolverArg {
+    /// The path of `resolvers.txt`
+    File,
+    /// The addresses, comma separated; such tools take no ports
+    List,
+    /// dig's `@server`, the first resolver only
+    Dig,
+}
+
+/// A tool's own resolver option
+struct ResolverFlag {
+    tool: &'static str,
+    flag: &'static str,
+    arg: ResolverArg,
+    /// Words after the binary the flag must follow, e.g. amass's subcommand
+    after_words: usize,
+}
+
+const RESOLVER_FLAGS: &[ResolverFlag] = &[
+    ResolverFlag { tool: "subfinder", flag: "-rL", arg: ResolverArg::File, after_words: 0 },
+    ResolverFlag { tool: "massdns", flag: "-r", arg: ResolverArg::File, after_words: 0 },
+    ResolverFlag { tool: "dnsx", flag: "-r", arg: ResolverArg::File, after_words: 0 },
+    ResolverFlag { tool: "shuffledns", flag: "-r", arg: ResolverArg::File, after_words: 0 },
+    ResolverFlag { tool: "puredns", flag: "-r", arg: ResolverArg::File, after_words: 1 },
+    ResolverFlag { tool: "amass", flag: "-r", arg: ResolverArg::List, after_words: 1 },
+    ResolverFlag { tool: "nmap", flag: "--dns-servers", arg: ResolverArg::List, after_words: 0 },
+    ResolverFlag { tool: "dig", flag: "@", arg: ResolverArg::Dig, after_words: 0 },
+];
+
+/// The engagement's DNS resolvers: used for Hacksor's own lookups and passed
+/// to DNS tools through their resolver options, so names resolve as they do
+/// inside a split-horizon network
+#[derive(Debug, Clone)]
+pub struct DnsResolvers {
+    resolvers: Vec<Resolver>,
+    /// `resolvers.txt` in the session directory, when there are plain resolvers
+    file: Option<PathBuf>,
+    timeout: Duration,
+}
+
+impl DnsResolvers {
+    /// Parse the configured resolvers and write the plain ones to `resolvers.txt`
+    /// in the session directory
+    pub fn new(config: &DnsConfig, work_dir: &Path) -> Result<Self> {
+        let resolvers = config.resolvers.iter()
+            .map(|entry| Resolver::parse(entry))
+            .collect::<Result<Vec<_>>>()?;
+        if resolvers.is_empty() {
+            return Err(anyhow!("[dns] lists no resolvers"));
+        }
+
+        let entries: Vec<String> = resolvers.iter().filter_map(Resolver::file_entry).collect();
+        let file = if entries.is_empty() {
+            None
+        } else {
+            let path = work_dir.join(RESOLVERS_FILE);
+            fs::write(&path, format!("{}\n", entries.join("\n")))
+                .context(format!("Failed to write {:?}", path))?;
+            Some(path)
+        };
+
+        Ok(Self {
+            resolvers,
+            file,
+            timeout: Duration::from_secs(config.timeout_secs),
+        })
+    }
+
+    pub fn resolvers(&self) -> &[Resolver] {
+        &self.resolvers
+    }
+
+    fn udp_addresses(&self) -> Vec<SocketAddr> {
+        self.resolvers.iter()
+            .filter_map(|resolver| match resolver {
+                Resolver::Udp(address) => Some(*address),
+                Resolver::Https(_) => None,
+            })
+            .collect()
+    }
+
+    /// Rewrite a command so the DNS tools in it use the resolvers. Tools are
+    /// only given plain resolvers; with DoH alone, commands are left unchanged
+    pub fn apply(&self, command: &str) -> String {
+        let (prefix, rest) = match command.strip_prefix("sudo ") {
+            Some(rest) => ("sudo ", rest.trim_start()),
+            None => ("", command),
+        };
+        let tool = tool_name(rest);
+        let addresses = self.udp_addresses();
+        let (Some(flag), Some(first)) = (RESOLVER_FLAGS.iter().find(|flag| flag.tool == tool), addresses.first()) else {
+            return command.to_string();
+        };
+
+        let option = match flag.arg {
+            ResolverArg::File if has_flag(rest, flag.flag) => return command.to_string(),
+            ResolverArg::File => match &self.file {
+                Some(file) => format!("{} {}", flag.flag, file.display()),
+                None => return command.to_string(),
+            },
+            ResolverArg::List if has_flag(rest, flag.flag) => return command.to_string(),
+            ResolverArg::List => {
+                let ips: Vec<String> = addresses.iter().map(|address| address.ip().to_string()).collect();
+                format!("{} {}", flag.flag, ips.join(","))
+            },
+            ResolverArg::Dig if rest.split_whitespace().any(|term| term.starts_with('@')) => return command.to_string(),
+            ResolverArg::Dig if first.port() == 53 => format!("@{}", first.ip()),
+            ResolverArg::Dig => format!("@{} -p {}", first.ip(), first.port()),
+        };
+        let at = insertion_point(rest, flag.after_words);
+        format!("{}{} {}{}", prefix, &rest[..at], option, &rest[at..])
+    }
+
+    /// Addresses a host's A and AAAA records hold according to one resolver
+    pub async fn resolve_with(&self, resolver: &Resolver, host: &str) -> Result<Vec<IpAddr>> {
+        let mut addresses = Vec::new();
+        for record_type in [TYPE_A, TYPE_AAAA] {
+            let id = (Utc::now().timestamp_subsec_nanos() & 0xffff) as u16;
+            let query = build_query(id, host, record_type)?;
+            let response = match resolver {
+                Resolver::Udp(address) => self.query_udp(*address, &query).await?,
+                Resolver::Https(url) => self.query_https(url, &query).await?,
+            };
+            addresses.extend(parse_response(id, &response)?);
+        }
+        Ok(addresses)
+    }
+
+    async fn query_udp(&self, address: SocketAddr, query: &[u8]) -> Result<Vec<u8>> {
+        let bind: SocketAddr = match address {
+            SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+        };
+        let socket = UdpSocket::bind(bind).await?;
+        socket.send_to(query, address).await?;
+        let mut buffer = vec![0u8; 4096];
+        let received = tokio::time::timeout(self.timeout, socket.recv(&mut buffer))
+            .await
+            .map_err(|_| anyhow!("timed out"))??;
+        buffer.truncate(received);
+        Ok(buffer)
+    }
+
+    async fn query_https(&self, url: &str, query: &[u8]) -> Result<Vec<u8>> {
+        let response = reqwest::Client::builder()
+            .timeout(self.timeout)
+            .build()?
+            .post(url)
+            .header("Content-Type", "application/dns-message")
+            .header("Accept", "application/dns-message")
+            .body(query.to_vec())
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to reach {}: {}", url, e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow!("{}", status));
+        }
+        Ok(response.bytes().await?.to_vec())
+    }
+}
+
+/// A recursive query for one record type of a name
+fn build_query(id: u16, host: &str, record_type: u16) -> Result<Vec<u8>> {
+    let mut query = Vec::with_capacity(32 + host.len());
+    query.extend(id.to_be_bytes());
+    // Recursion desired, one question
+    query.extend([0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    for label in host.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(anyhow!("{} is not a valid host name", host));
+        }
+        query.push(label.len() as u8);
+        query.extend(label.as_bytes());
+    }
+    query.push(0);
+    query.extend(record_type.to_be_bytes());
+    query.extend(1u16.to_be_bytes());
+    Ok(query)
+}
+
+/// The A and AAAA addresses in a response's answers, following no CNAMEs:
+/// recursive resolvers already include the records they lead to
+fn parse_response(id: u16, response: &[u8]) -> Result<Vec<IpAddr>> {
+    let malformed = || anyhow!("malformed DNS response");
+    let read_u16 = |at: usize| -> Result<u16> {
+        response.get(at..at + 2).map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]])).ok_or_else(malformed)
+    };
+
+    if read_u16(0)? != id {
+        return Err(anyhow!("DNS response does not match the query"));
+    }
+    match read_u16(2)? & 0x000f {
+        0 | 3 => {},
+        rcode => return Err(anyhow!("DNS server answered with error code {}", rcode)),
+    }
+    let questions = read_u16(4)?;
+    let answers = read_u16(6)?;
+
+    let mut at = 12;
+    for _ in 0..questions {
+        at = skip_name(response, at).ok_or_else(malformed)? + 4;
+    }
+    let mut addresses = Vec::new();
+    for _ in 0..answers {
+        at = skip_name(response, at).ok_or_else(malformed)?;
+        let record_type = read_u16(at)?;
+        let length = read_u16(at + 8)? as usize;
+        let data = response.get(at + 10..at + 10 + length).ok_or_else(malformed)?;
+        match (record_type, data.len()) {
+            (TYPE_A, 4) => addresses.push(IpAddr::from(<[u8; 4]>::try_from(data)?)),
+            (TYPE_AAAA, 16) => addresses.push(IpAddr::from(<[u8; 16]>::try_from(data)?)),
+            _ => {},
+        }
+        at += 10 + length;
+    }
+    Ok(addresses)
+}
+
+/// Offset just past a possibly compressed name
+fn skip_name(message: &[u8], mut at: usize) -> Option<usize> {
+    loop {
+        let length = *message.get(at)?;
+        match length {
+            0 => return Some(at + 1),
+            length if length & 0xc0 == 0xc0 => return Some(at + 2),
+            length => at += 1 + length as usize,
+        }
+    }
+}
+
+/// The command a lookup is recorded as
+pub fn lookup_command(host: &str) -> String {
+    format!("resolve {}", host)
+}
+
+/// Resolve a host through every configured resolver and record what each
+/// answered: the answers in the session's logs and as passive intel on the
+/// host, and the addresses in the asset inventory. Resolvers disagreeing is
+/// how split-horizon DNS shows. Returns a summary
+pub async fn lookup(resolvers: &DnsResolvers, monitor: &CommandMonitor, host: &str) -> Result<String> {
+    let started = Utc::now();
+    let mut lines = Vec::new();
+    let mut answers = Vec::new();
+    for resolver in resolvers.resolvers() {
+        match resolvers.resolve_with(resolver, host).await {
+            Ok(addresses) if addresses.is_empty() => lines.push(format!("{}: no addresses", resolver)),
+            Ok(addresses) => {
+                let mut listed: Vec<String> = addresses.iter().map(IpAddr::to_string).collect();
+                listed.sort();
+                lines.push(format!("{}: {}", resolver, listed.join(", ")));
+                answers.push(listed);
+            },
+            Err(e) => lines.push(format!("{}: failed ({:#})", resolver, e)),
+        }
+    }
+    let command_id = monitor.record_lookup(&lookup_command(host), started, &lines.join("\n"))?;
+
+    let assets = monitor.get_asset_inventory();
+    assets.record_host(host, &command_id);
+    for address in answers.iter().flatten() {
+        assets.record_host(address, &command_id);
+    }
+    assets.record_intel(host, "Resolvers", lines.clone());
+
+    let mut distinct = answers.clone();
+    distinct.sort();
+    distinct.dedup();
+    let mut summary = format!("{} resolves as follows: {}", host, lines.join("; "));
+    if distinct.len() > 1 {
+        summary.push_str(". The resolvers disagree, so the name is split-horizon or load balanced");
+    }
+    monitor.update_command_summary(&command_id, &summary)?;
+    Ok(summary)
+}
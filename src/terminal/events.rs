@@ -0,0 +1,135 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+#[cfg(unix)]
+use std::sync::mpsc::{self, SyncSender};
+#[cfg(unix)]
+use std::time::Duration;
+use anyhow::{Result, Context};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::auto_documentation::DocumentedFinding;
+
+/// Events queued for a socket client before it counts as too slow and is dropped
+#[cfg(unix)]
+const CLIENT_BACKLOG: usize = 256;
+
+/// How long a write to a socket client may block its writer thread
+#[cfg(unix)]
+const CLIENT_WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FindingEventKind {
+    Created,
+    Updated,
+}
+
+/// One line of the finding event stream
+#[derive(Debug, Clone, Serialize)]
+pub struct FindingEvent<'a> {
+    pub event: FindingEventKind,
+    pub timestamp: DateTime<Utc>,
+    pub finding: &'a DocumentedFinding,
+}
+
+/// Writes finding events as JSON lines to `findings.jsonl` and any connected socket clients
+#[derive(Debug, Clone)]
+pub struct FindingEventStream {
+    file: Arc<Mutex<File>>,
+    /// Queues of the socket clients' writer threads, so emitting never waits on a client
+    #[cfg(unix)]
+    clients: Arc<Mutex<Vec<SyncSender<Arc<str>>>>>,
+}
+
+impl FindingEventStream {
+    /// Append events to `path`, creating the file if needed
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .context(format!("Failed to open finding event stream {:?}", path))?;
+
+        Ok(Self {
+            file: Arc::new(Mutex::new(file)),
+            #[cfg(unix)]
+            clients: Arc::new(Mutex::new(Vec::new())),
+        })
+    }
+
+    /// Accept clients on a Unix socket; each receives every event emitted after it connects
+    #[cfg(unix)]
+    pub fn listen(&self, socket_path: &Path) -> Result<()> {
+        use std::os::unix::fs::FileTypeExt;
+        use std::os::unix::net::{UnixListener, UnixStream};
+
+        // A socket left behind by an earlier session would make bind fail;
+        // anything else at the path is not ours to remove
+        if let Ok(metadata) = std::fs::symlink_metadata(socket_path) {
+            if !metadata.file_type().is_socket() {
+                return Err(anyhow::anyhow!("Finding event socket path {:?} exists and is not a socket", socket_path));
+            }
+            if UnixStream::connect(socket_path).is_ok() {
+                return Err(anyhow::anyhow!("Another session is already listening on {:?}", socket_path));
+            }
+            std::fs::remove_file(socket_path)?;
+        }
+        let listener = UnixListener::bind(socket_path)
+            .context(format!("Failed to bind finding event socket {:?}", socket_path))?;
+
+        let clients = self.clients.clone();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                if stream.set_write_timeout(Some(CLIENT_WRITE_TIMEOUT)).is_err() {
+                    continue;
+                }
+                let (sender, receiver) = mpsc::sync_channel::<Arc<str>>(CLIENT_BACKLOG);
+                std::thread::spawn(move || {
+                    for line in receiver {
+                        if stream.write_all(line.as_bytes()).is_err() {
+                            break;
+                        }
+                    }
+                });
+                clients.lock().unwrap().push(sender);
+            }
+        });
+
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    pub fn listen(&self, _socket_path: &Path) -> Result<()> {
+        Err(anyhow::anyhow!("Finding event sockets are only supported on Unix"))
+    }
+
+    /// Emit an event for a finding
+    pub fn emit(&self, event: FindingEventKind, finding: &DocumentedFinding) {
+        let event = FindingEvent {
+            event,
+            timestamp: Utc::now(),
+            finding,
+        };
+        let line = match serde_json::to_string(&event) {
+            Ok(line) => line + "\n",
+            Err(e) => {
+                eprintln!("Failed to serialize finding event: {}", e);
+                return;
+            }
+        };
+
+        if let Err(e) = self.file.lock().unwrap().write_all(line.as_bytes()) {
+            eprintln!("Failed to write finding event: {}", e);
+        }
+
+        // Clients that have gone away or fallen too far behind are dropped
+        #[cfg(unix)]
+        {
+            let line: Arc<str> = line.into();
+            self.clients.lock().unwrap().retain(|client| client.try_send(line.clone()).is_ok());
+        }
+    }
+}
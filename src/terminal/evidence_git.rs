@@ -0,0 +1,103 @@
+use std::path::Path;
+use std::process::Stdio;
+use anyhow::{Result, Context, anyhow};
+use tokio::process::Command;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::config::EvidenceGitConfig;
+
+enum Request {
+    Commit(String),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Commits the session directory to a local git repository after each documentation
+/// event, giving a tamper-evident history of the evidence.
+///
+/// Commits run one at a time on a background task, so callers never wait on git.
+#[derive(Debug, Clone)]
+pub struct EvidenceRepo {
+    requests: mpsc::UnboundedSender<Request>,
+}
+
+impl EvidenceRepo {
+    /// Initialize a repository in `dir` unless one exists, and start the commit task
+    pub fn init(dir: &Path, config: &EvidenceGitConfig) -> Result<Self> {
+        if !dir.join(".git").exists() {
+            let status = std::process::Command::new("git")
+                .arg("init")
+                .arg("--quiet")
+                .arg(dir)
+                .status()
+                .context("Failed to run git; is it installed?")?;
+            if !status.success() {
+                return Err(anyhow!("git init failed in {:?}", dir));
+            }
+        }
+
+        let (requests, mut pending) = mpsc::unbounded_channel();
+        let dir = dir.to_path_buf();
+        let config = config.clone();
+        tokio::spawn(async move {
+            while let Some(request) = pending.recv().await {
+                match request {
+                    Request::Commit(message) => {
+                        if let Err(e) = commit_all(&dir, &config, &message).await {
+                            eprintln!("Failed to commit evidence: {:#}", e);
+                        }
+                    },
+                    Request::Flush(done) => {
+                        let _ = done.send(());
+                    },
+                }
+            }
+        });
+
+        Ok(Self { requests })
+    }
+
+    /// Queue a commit of everything that changed in the session directory
+    pub fn commit(&self, message: impl Into<String>) {
+        let _ = self.requests.send(Request::Commit(message.into()));
+    }
+
+    /// Wait until every queued commit has been made
+    pub async fn flush(&self) {
+        let (done, finished) = oneshot::channel();
+        if self.requests.send(Request::Flush(done)).is_ok() {
+            let _ = finished.await;
+        }
+    }
+}
+
+async fn commit_all(dir: &Path, config: &EvidenceGitConfig, message: &str) -> Result<()> {
+    git(dir, config, &["add", "--all"]).await?;
+
+    // Nothing staged means nothing changed since the last commit
+    if git(dir, config, &["diff", "--cached", "--quiet"]).await.is_ok() {
+        return Ok(());
+    }
+
+    git(dir, config, &["commit", "--quiet", "-m", message]).await
+}
+
+async fn git(dir: &Path, config: &EvidenceGitConfig, args: &[&str]) -> Result<()> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .arg("-c")
+        .arg(format!("user.name={}", config.author_name))
+        .arg("-c")
+        .arg(format!("user.email={}", config.author_email))
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .context("Failed to run git")?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(anyhow!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()))
+    }
+}
@@ -0,0 +1,155 @@
+use std::path::Path;
+use std::process::Command;
+
+use crate::config::ContainerConfig;
+
+/// Directories the container image provides for itself, so host paths under
+/// them are left to the image rather than treated as missing mounts
+const IMAGE_DIRS: &[&str] = &["/usr", "/etc", "/bin", "/sbin", "/lib", "/lib64", "/dev", "/proc", "/sys"];
+
+/// Determines where monitored commands are actually run
+#[derive(Debug, Clone, Default)]
+pub enum ExecutionBackend {
+    /// Run tools directly on the host through bash
+    #[default]
+    Host,
+    /// Run each tool inside a fresh container with the work dir mounted
+    Container(ContainerConfig),
+}
+
+impl ExecutionBackend {
+    pub fn from_config(config: &ContainerConfig) -> Self {
+        if config.enabled {
+            ExecutionBackend::Container(config.clone())
+        } else {
+            ExecutionBackend::Host
+        }
+    }
+
+    pub fn is_container(&self) -> bool {
+        matches!(self, ExecutionBackend::Container(_))
+    }
+
+    /// Name given to the container running a specific command
+    pub fn container_name(command_id: &str) -> String {
+        format!("hacksor-{}", command_id)
+    }
+
+    /// Absolute host paths in a command that a container can't see: ones that
+    /// exist on the host outside the work dir and the configured mounts
+    pub fn unmounted_paths(&self, command: &str, work_dir: &Path) -> Vec<String> {
+        let ExecutionBackend::Container(config) = self else {
+            return Vec::new();
+        };
+        let work_dir = std::path::absolute(work_dir).unwrap_or_else(|_| work_dir.to_path_buf());
+
+        command_paths(command)
+            .map(Path::new)
+            .filter(|path| !IMAGE_DIRS.iter().any(|dir| path.starts_with(dir)))
+            .filter(|path| !path.starts_with(&work_dir) && !config.mounts.iter().any(|mount| path.starts_with(mount)))
+            .filter(|path| path.exists())
+            .map(|path| path.display().to_string())
+            .collect()
+    }
+
+    /// Build the process that runs `command` for the given command ID
+    pub fn build_process(&self, command: &str, command_id: &str, work_dir: &Path) -> Command {
+        match self {
+            ExecutionBackend::Host => {
+                let mut process = Command::new("bash");
+                process.arg("-c").arg(command);
+                process
+            },
+            ExecutionBackend::Container(config) => {
+                // The runtime takes an absolute source, and the command refers
+                // to work dir files by where they are mounted
+                let work_dir = std::path::absolute(work_dir).unwrap_or_else(|_| work_dir.to_path_buf());
+                let command = command.replace(&*work_dir.to_string_lossy(), &config.mount_path);
+
+                let mut process = Command::new(&config.runtime);
+                process
+                    .arg("run")
+                    .arg("--rm")
+                    .arg("-i")
+                    .arg("--name")
+                    .arg(Self::container_name(command_id))
+                    .arg("-v")
+                    .arg(format!("{}:{}", work_dir.display(), config.mount_path))
+                    .arg("-w")
+                    .arg(&config.mount_path);
+
+                // Extra host directories appear at the same path inside
+                for mount in &config.mounts {
+                    process.arg("-v").arg(format!("{}:{}", mount.display(), mount.display()));
+                }
+
+                if !config.network.is_empty() {
+                    process.arg("--network").arg(&config.network);
+                }
+
+                process
+                    .args(&config.extra_args)
+                    .arg(&config.image)
+                    .arg("bash")
+                    .arg("-c")
+                    .arg(command);
+                process
+            }
+        }
+    }
+
+    /// Stop the container belonging to a command, if any
+    pub fn stop(&self, command_id: &str) -> bool {
+        match self {
+            ExecutionBackend::Host => false,
+            ExecutionBackend::Container(config) => {
+                Command::new(&config.runtime)
+                    .arg("kill")
+                    .arg(Self::container_name(command_id))
+                    .output()
+                    .map(|output| output.status.success())
+                    .unwrap_or(false)
+            }
+        }
+    }
+}
+
+/// Absolute paths among a command's terms, including `--flag=/path` values
+/// and redirection targets
+fn command_paths(command: &str) -> impl Iterator<Item = &str> {
+    command.split_whitespace()
+        .map(|term| term.rsplit(['<', '>']).next().unwrap_or(term))
+        .map(|term| term.split_once('=').map_or(term, |(_, value)| value))
+        .map(|term| term.trim_matches(['\'', '"']))
+        .filter(|term| term.len() > 1 && term.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn finds_host_paths_the_container_cannot_see() {
+        let work_dir = TempDir::new().unwrap();
+        let elsewhere = TempDir::new().unwrap();
+        let mounted = TempDir::new().unwrap();
+        for dir in [&work_dir, &elsewhere, &mounted] {
+            fs::write(dir.path().join("targets.txt"), "example.com").unwrap();
+        }
+        let backend = ExecutionBackend::Container(ContainerConfig {
+            mounts: vec![mounted.path().to_path_buf()],
+            ..ContainerConfig::default()
+        });
+        let path = |dir: &TempDir| dir.path().join("targets.txt").display().to_string();
+
+        let command = format!(
+            "nmap -iL {} --excludefile={} -oN {} 2>/dev/null >{}",
+            path(&elsewhere), path(&mounted), path(&work_dir), path(&elsewhere),
+        );
+        assert_eq!(backend.unmounted_paths(&command, work_dir.path()), vec![path(&elsewhere), path(&elsewhere)]);
+        assert!(ExecutionBackend::Host.unmounted_paths(&command, work_dir.path()).is_empty());
+        assert!(backend.unmounted_paths("nmap -iL /nonexistent/targets.txt example.com", work_dir.path()).is_empty());
+    }
+}
@@ -0,0 +1,238 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use anyhow::{Result, Context};
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use super::auto_documentation::{DocumentedFinding, FindingStatus};
+use crate::utils::extract_target_from_command;
+use super::command_monitor::{Confidence, FindingSeverity};
+use super::cvss;
+
+/// Version of the JSON export layout; bump when fields change incompatibly
+pub const JSON_EXPORT_SCHEMA: &str = "hacksor-findings/1";
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Top level of the JSON export
+#[derive(Debug, Serialize)]
+struct JsonExport<'a> {
+    schema: &'static str,
+    generated: String,
+    tool: &'static str,
+    tool_version: &'static str,
+    findings: Vec<ExportedFinding<'a>>,
+}
+
+/// A finding in the JSON export, with its target resolved
+#[derive(Debug, Serialize)]
+struct ExportedFinding<'a> {
+    target: Option<String>,
+    #[serde(flatten)]
+    finding: &'a DocumentedFinding,
+}
+
+/// Writes a set of findings to a file in one export format
+pub type Exporter = fn(&[DocumentedFinding], &Path) -> Result<()>;
+
+/// Write findings in Hacksor's canonical JSON layout
+pub fn write_json(findings: &[DocumentedFinding], output_file: &Path) -> Result<()> {
+    let export = JsonExport {
+        schema: JSON_EXPORT_SCHEMA,
+        generated: Utc::now().to_rfc3339(),
+        tool: "hacksor",
+        tool_version: env!("CARGO_PKG_VERSION"),
+        findings: findings.iter()
+            .map(|finding| ExportedFinding {
+                target: extract_target_from_command(&finding.discovery_command),
+                finding,
+            })
+            .collect(),
+    };
+
+    let content = serde_json::to_string_pretty(&export)?;
+    fs::write(output_file, content)
+        .context(format!("Failed to write JSON export to {:?}", output_file))?;
+    Ok(())
+}
+
+/// Write findings as a SARIF 2.1.0 log for code-scanning and vulnerability management tools
+pub fn write_sarif(findings: &[DocumentedFinding], output_file: &Path) -> Result<()> {
+    // One rule per distinct finding title
+    let mut rules: BTreeMap<String, Value> = BTreeMap::new();
+    let mut results = Vec::new();
+
+    for finding in findings {
+        let rule_id = rule_id(&finding.title);
+        rules.entry(rule_id.clone()).or_insert_with(|| {
+            let mut tags = vec!["security".to_string()];
+            tags.extend(finding.cwe.clone());
+            json!({
+                "id": rule_id,
+                "name": finding.title,
+                "shortDescription": { "text": finding.title },
+                "helpUri": finding.cwe.as_deref()
+                    .map(|cwe| format!("https://cwe.mitre.org/data/definitions/{}.html", cwe.trim_start_matches("CWE-"))),
+                "properties": {
+                    "tags": tags,
+                    "security-severity": security_severity(finding),
+                },
+            })
+        });
+
+        let target = extract_target_from_command(&finding.discovery_command);
+        let uri = target.clone().unwrap_or_else(|| finding.file_path.display().to_string());
+        results.push(json!({
+            "ruleId": rule_id,
+            "level": sarif_level(&finding.severity),
+            "message": { "text": finding.description },
+            "locations": [{
+                "physicalLocation": { "artifactLocation": { "uri": uri } },
+                "logicalLocations": [{ "name": target.as_deref().unwrap_or("unknown"), "kind": "resource" }],
+            }],
+            "partialFingerprints": { "hacksorFindingId": finding.id },
+            "properties": {
+                "severity": format!("{:?}", finding.severity),
+                "confidence": format!("{:?}", finding.confidence),
+                "status": format!("{:?}", finding.status),
+                "cvssVector": finding.cvss.as_ref().map(|cvss| cvss.vector.clone()),
+                "owasp": finding.owasp,
+                "relatedCves": finding.related_cves.iter().map(|cve| cve.id.clone()).collect::<Vec<_>>(),
+                "command": finding.discovery_command,
+                "discovered": finding.discovery_date.to_rfc3339(),
+            },
+        }));
+    }
+
+    let log = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "Hacksor",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules.into_values().collect::<Vec<_>>(),
+                },
+            },
+            "results": results,
+        }],
+    });
+
+    let content = serde_json::to_string_pretty(&log)?;
+    fs::write(output_file, content)
+        .context(format!("Failed to write SARIF export to {:?}", output_file))?;
+    Ok(())
+}
+
+/// Write findings in DefectDojo's Generic Findings Import format
+pub fn write_defectdojo(findings: &[DocumentedFinding], output_file: &Path) -> Result<()> {
+    let findings: Vec<Value> = findings.iter()
+        .map(|finding| {
+            let target = extract_target_from_command(&finding.discovery_command);
+            let mut endpoints: Vec<String> = finding.screenshots.iter()
+                .map(|screenshot| screenshot.url.clone())
+                .collect();
+            if endpoints.is_empty() {
+                endpoints.extend(target.clone());
+            }
+
+            let references = finding.related_cves.iter()
+                .map(|cve| format!("https://nvd.nist.gov/vuln/detail/{}", cve.id))
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            json!({
+                "title": finding.title,
+                "description": finding.description,
+                "severity": format!("{:?}", finding.severity),
+                "date": finding.discovery_date.format("%Y-%m-%d").to_string(),
+                "cwe": finding.cwe.as_deref()
+                    .and_then(|cwe| cwe.trim_start_matches("CWE-").parse::<u32>().ok()),
+                "cve": finding.related_cves.first().map(|cve| cve.id.clone()),
+                "cvssv3": finding.cvss.as_ref().map(|cvss| cvss.vector.clone()),
+                "cvssv3_score": finding.cvss.as_ref().map(|cvss| cvss.score),
+                "references": references,
+                "steps_to_reproduce": format!("Run:\n{}\n\nEvidence:\n{}", finding.discovery_command, finding.raw_evidence),
+                "unique_id_from_tool": finding.id,
+                "vuln_id_from_tool": rule_id(&finding.title),
+                "endpoints": endpoints,
+                "active": finding.status != FindingStatus::FalsePositive && finding.status != FindingStatus::Closed,
+                "verified": finding.status == FindingStatus::Verified || finding.confidence == Confidence::Confirmed,
+                "false_p": finding.status == FindingStatus::FalsePositive,
+                "static_finding": false,
+                "dynamic_finding": true,
+                "tags": ["hacksor".to_string(), format!("confidence:{:?}", finding.confidence).to_lowercase()],
+            })
+        })
+        .collect();
+
+    let content = serde_json::to_string_pretty(&json!({ "findings": findings }))?;
+    fs::write(output_file, content)
+        .context(format!("Failed to write DefectDojo export to {:?}", output_file))?;
+    Ok(())
+}
+
+/// Write findings as a flat CSV table for spreadsheets and client trackers
+pub fn write_csv(findings: &[DocumentedFinding], output_file: &Path) -> Result<()> {
+    let mut content = String::from("id,title,severity,cvss,asset,status,discovery_date\r\n");
+    for finding in findings {
+        let row = [
+            finding.id.clone(),
+            finding.title.clone(),
+            format!("{:?}", finding.severity),
+            finding.cvss.as_ref().map(|cvss| format!("{:.1}", cvss.score)).unwrap_or_default(),
+            extract_target_from_command(&finding.discovery_command).unwrap_or_default(),
+            format!("{:?}", finding.status),
+            finding.discovery_date.format("%Y-%m-%d %H:%M:%S").to_string(),
+        ];
+        content.push_str(&row.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(","));
+        content.push_str("\r\n");
+    }
+
+    fs::write(output_file, content)
+        .context(format!("Failed to write CSV export to {:?}", output_file))?;
+    Ok(())
+}
+
+/// Quote a CSV field (RFC 4180) when it contains a delimiter, quote or line break.
+/// Fields starting with a formula character are prefixed with `'` so spreadsheets
+/// don't evaluate tool output.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Stable rule ID derived from a finding title, e.g. `hacksor/open-ports-discovered`
+fn rule_id(title: &str) -> String {
+    let slug = title.to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    format!("hacksor/{}", slug)
+}
+
+fn sarif_level(severity: &FindingSeverity) -> &'static str {
+    match severity {
+        FindingSeverity::Critical | FindingSeverity::High => "error",
+        FindingSeverity::Medium => "warning",
+        FindingSeverity::Low | FindingSeverity::Info => "note",
+    }
+}
+
+/// CVSS score as a string, the form GitHub code scanning reads; falls back to the severity's band
+fn security_severity(finding: &DocumentedFinding) -> String {
+    format!("{:.1}", cvss::sort_score(finding.cvss.as_ref(), &finding.severity))
+}
@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use anyhow::{Result, Context, anyhow};
+use regex::{Captures, Regex};
+use uuid::Uuid;
+
+use crate::config::FindingIdConfig;
+use super::command_monitor::FindingSeverity;
+
+/// Placeholders understood in a finding ID format
+const PLACEHOLDERS: &[&str] = &["uuid", "seq", "severity", "sev", "severity_seq", "date"];
+
+/// Placeholders that differ between findings; a format needs at least one
+const UNIQUE_PLACEHOLDERS: &[&str] = &["seq", "severity_seq", "uuid"];
+
+/// Generates finding IDs from the engagement's format, e.g. `ACME-WEB-{seq:03}` -> `ACME-WEB-001`.
+///
+/// Counters are persisted so a resumed session continues numbering where it left off.
+#[derive(Debug, Clone)]
+pub struct FindingIdScheme {
+    format: String,
+    counters_path: Option<PathBuf>,
+    counters: Arc<Mutex<BTreeMap<String, u32>>>,
+}
+
+impl Default for FindingIdScheme {
+    fn default() -> Self {
+        Self {
+            format: FindingIdConfig::default().format,
+            counters_path: None,
+            counters: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+}
+
+impl FindingIdScheme {
+    /// Check the ID format only uses known placeholders and gives every finding its own ID
+    pub fn validate(config: &FindingIdConfig) -> Result<()> {
+        let mut unique = false;
+        for captures in placeholder_regex().captures_iter(&config.format) {
+            if !PLACEHOLDERS.contains(&&captures[1]) {
+                return Err(anyhow!(
+                    "Unknown placeholder {{{}}} in finding ID format; use one of: {}",
+                    &captures[1],
+                    PLACEHOLDERS.join(", ")
+                ));
+            }
+            unique |= UNIQUE_PLACEHOLDERS.contains(&&captures[1]);
+        }
+        if !unique {
+            return Err(anyhow!(
+                "Finding ID format {} would give findings the same ID; include one of {{{}}}",
+                config.format,
+                UNIQUE_PLACEHOLDERS.join("}, {")
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn new(config: &FindingIdConfig, counters_path: &Path) -> Result<Self> {
+        Self::validate(config)?;
+
+        let counters = if counters_path.exists() {
+            let content = fs::read_to_string(counters_path)?;
+            serde_json::from_str(&content)
+                .context(format!("Failed to parse finding ID counters {:?}", counters_path))?
+        } else {
+            BTreeMap::new()
+        };
+
+        Ok(Self {
+            format: config.format.clone(),
+            counters_path: Some(counters_path.to_path_buf()),
+            counters: Arc::new(Mutex::new(counters)),
+        })
+    }
+
+    /// The ID for the next finding of this severity
+    pub fn next(&self, severity: &FindingSeverity) -> Result<String> {
+        let severity_name = format!("{:?}", severity);
+        let (seq, severity_seq) = {
+            let mut counters = self.counters.lock().unwrap();
+            let uses = |name: &str| self.format.contains(&format!("{{{}", name));
+            let mut bump = |key: &str| {
+                let counter = counters.entry(key.to_string()).or_insert(0);
+                *counter += 1;
+                *counter
+            };
+            let seq = if uses("seq") { bump("all") } else { 0 };
+            let severity_seq = if uses("severity_seq") { bump(&severity_name) } else { 0 };
+            (seq, severity_seq)
+        };
+        self.save()?;
+
+        let id = placeholder_regex().replace_all(&self.format, |captures: &Captures| {
+            let width = captures.get(2).and_then(|width| width.as_str().parse().ok()).unwrap_or(0);
+            match &captures[1] {
+                "uuid" => Uuid::new_v4().to_string().split('-').next().unwrap_or("UNKNOWN").to_string(),
+                "seq" => format!("{:0width$}", seq, width = width),
+                "severity_seq" => format!("{:0width$}", severity_seq, width = width),
+                "severity" => severity_name.to_uppercase(),
+                "sev" => severity_name[..1].to_string(),
+                "date" => chrono::Utc::now().format("%Y%m%d").to_string(),
+                other => format!("{{{}}}", other),
+            }
+        });
+
+        Ok(id.into_owned())
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.counters_path else {
+            return Ok(());
+        };
+        let content = serde_json::to_string_pretty(&*self.counters.lock().unwrap())?;
+        fs::write(path, content)
+            .context(format!("Failed to save finding ID counters to {:?}", path))?;
+        Ok(())
+    }
+}
+
+/// `{name}` or `{name:width}`
+fn placeholder_regex() -> &'static Regex {
+    static PLACEHOLDER: OnceLock<Regex> = OnceLock::new();
+    PLACEHOLDER.get_or_init(|| Regex::new(r"\{(\w+)(?::(\d+))?\}").unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(format: &str) -> FindingIdConfig {
+        FindingIdConfig { format: format.to_string() }
+    }
+
+    #[test]
+    fn accepts_formats_with_a_unique_part() {
+        for valid in ["ACME-{seq:03}", "{sev}-{severity_seq}", "F-{uuid}", "{date}-{severity}-{seq}"] {
+            assert!(FindingIdScheme::validate(&format(valid)).is_ok(), "{}", valid);
+        }
+    }
+
+    #[test]
+    fn rejects_formats_repeating_ids() {
+        for invalid in ["ACME-WEB", "{severity}-{date}", "{sev}"] {
+            assert!(FindingIdScheme::validate(&format(invalid)).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_placeholders() {
+        assert!(FindingIdScheme::validate(&format("{client}-{seq}")).is_err());
+    }
+
+    #[test]
+    fn numbers_findings_in_sequence() {
+        let scheme = FindingIdScheme { format: "ACME-{seq:03}-{sev}{severity_seq}".to_string(), ..FindingIdScheme::default() };
+        assert_eq!(scheme.next(&FindingSeverity::High).unwrap(), "ACME-001-H1");
+        assert_eq!(scheme.next(&FindingSeverity::Low).unwrap(), "ACME-002-L1");
+        assert_eq!(scheme.next(&FindingSeverity::High).unwrap(), "ACME-003-H2");
+    }
+}
@@ -0,0 +1,306 @@
+use std::collections::BTreeSet;
+use std::env;
+use std::time::Duration;
+use anyhow::{Result, Context, anyhow};
+use chrono::Utc;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::config::GitHubConfig;
+use super::command_monitor::{CommandMonitor, Confidence, FindingSeverity, SecurityFinding, create_finding};
+
+const API_URL: &str = "https://api.github.com/search/code";
+
+/// Code search allows 10 requests a minute
+const SEARCH_INTERVAL: Duration = Duration::from_secs(6);
+
+/// Files named in the description of the finding for files that only mention
+/// the target; its evidence lists them all
+const MAX_LISTED_FILES: usize = 10;
+
+/// Secrets recognised in matched code, with the severity of leaking one. The
+/// assignment pattern's first group is the value; the others match it whole
+const SECRET_PATTERNS: &[(&str, &str, FindingSeverity)] = &[
+    ("private key", r"-----BEGIN (?:RSA |EC |DSA |OPENSSH |PGP )?PRIVATE KEY", FindingSeverity::High),
+    ("AWS access key ID", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b", FindingSeverity::High),
+    ("GitHub token", r"\bgh[pousr]_[A-Za-z0-9]{36,}\b", FindingSeverity::High),
+    ("Slack token", r"\bxox[abposr]-[A-Za-z0-9-]{10,}", FindingSeverity::High),
+    ("Stripe live key", r"\b[rs]k_live_[0-9A-Za-z]{20,}\b", FindingSeverity::High),
+    ("Google API key", r"\bAIza[0-9A-Za-z_-]{35}\b", FindingSeverity::Medium),
+    ("JSON Web Token", r"\beyJ[A-Za-z0-9_-]{10,}\.eyJ[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,}", FindingSeverity::Medium),
+    (
+        "password or key assignment",
+        r#"(?i)(?:password|passwd|pwd|secret|api[_-]?key|access[_-]?token|auth[_-]?token|client[_-]?secret)["']?\s*[:=]\s*["']?([^\s"'<>,;]{6,})"#,
+        FindingSeverity::Medium,
+    ),
+];
+
+/// Searches public code on GitHub for the target's domain alongside words
+/// that suggest credentials, to find secrets committed by its developers
+pub struct GitHubClient {
+    token: String,
+    dorks: Vec<String>,
+    max_results: usize,
+    client: reqwest::Client,
+}
+
+/// A file a search matched, with the matching fragments of its contents
+#[derive(Debug, Clone)]
+pub struct CodeMatch {
+    pub repository: String,
+    pub path: String,
+    pub url: String,
+    pub fragments: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchItem {
+    path: String,
+    html_url: String,
+    repository: Repository,
+    #[serde(default)]
+    text_matches: Vec<TextMatch>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Repository {
+    full_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TextMatch {
+    #[serde(default)]
+    fragment: String,
+}
+
+/// A secret recognised in a file, without its value
+#[derive(Debug, Clone)]
+pub struct LeakedSecret {
+    pub kind: &'static str,
+    pub redacted: String,
+    pub severity: FindingSeverity,
+}
+
+impl GitHubClient {
+    pub fn new(config: &GitHubConfig) -> Result<Self> {
+        let token = config.token.clone()
+            .or_else(|| env::var("GITHUB_TOKEN").ok())
+            .filter(|token| !token.is_empty())
+            .context("No GitHub token: set token in [github] or the GITHUB_TOKEN environment variable; code search needs one")?;
+
+        Ok(Self {
+            token,
+            dorks: config.dorks.clone(),
+            max_results: config.max_results.clamp(1, 100),
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(config.timeout_secs))
+                .user_agent("hacksor")
+                .build()?,
+        })
+    }
+
+    /// Files matching any of the dorks for a domain, each file once
+    pub async fn search(&self, domain: &str) -> Result<Vec<CodeMatch>> {
+        let mut matches: Vec<CodeMatch> = Vec::new();
+        for (i, dork) in self.dorks.iter().enumerate() {
+            if i > 0 {
+                tokio::time::sleep(SEARCH_INTERVAL).await;
+            }
+            for found in self.search_code(&dork.replace("{domain}", domain)).await? {
+                match matches.iter_mut().find(|existing| existing.url == found.url) {
+                    Some(existing) => {
+                        for fragment in found.fragments {
+                            if !existing.fragments.contains(&fragment) {
+                                existing.fragments.push(fragment);
+                            }
+                        }
+                    },
+                    None => matches.push(found),
+                }
+            }
+        }
+        Ok(matches)
+    }
+
+    async fn search_code(&self, query: &str) -> Result<Vec<CodeMatch>> {
+        let per_page = self.max_results.to_string();
+        let response = self.client.get(API_URL)
+            .bearer_auth(&self.token)
+            // Asks for the matching fragments along with each file
+            .header("Accept", "application/vnd.github.text-match+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            .query(&[("q", query), ("per_page", per_page.as_str())])
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to reach GitHub: {}", e))?;
+
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            return Err(match body["message"].as_str() {
+                Some(message) => anyhow!("{} ({}) searching for {}", message, status, query),
+                None => anyhow!("{} searching for {}", status, query),
+            });
+        }
+
+        let items: Vec<SearchItem> = serde_json::from_value(body["items"].clone())
+            .context("GitHub returned an unexpected code search response")?;
+        Ok(items.into_iter()
+            .map(|item| CodeMatch {
+                repository: item.repository.full_name,
+                path: item.path,
+                url: item.html_url,
+                fragments: item.text_matches.into_iter().map(|text| text.fragment).collect(),
+            })
+            .collect())
+    }
+}
+
+/// The command a search is recorded as
+pub fn lookup_command(domain: &str) -> String {
+    format!("github search {}", domain)
+}
+
+/// `AKIA************`: enough of a value to recognise it, not to use it
+fn redact(value: &str) -> String {
+    let shown: String = value.chars().take(4).collect();
+    format!("{}{}", shown, "*".repeat(value.chars().count().saturating_sub(4).min(12)))
+}
+
+fn secret_patterns() -> Vec<(&'static str, Regex, FindingSeverity)> {
+    SECRET_PATTERNS.iter()
+        .map(|(kind, pattern, severity)| (*kind, Regex::new(pattern).unwrap(), severity.clone()))
+        .collect()
+}
+
+impl CodeMatch {
+    /// Secrets in the matched fragments, each value once
+    pub fn secrets(&self) -> Vec<LeakedSecret> {
+        let mut seen = BTreeSet::new();
+        let mut secrets = Vec::new();
+        for (kind, pattern, severity) in secret_patterns() {
+            for fragment in &self.fragments {
+                for captures in pattern.captures_iter(fragment) {
+                    let value = captures.get(1).or_else(|| captures.get(0)).map(|value| value.as_str()).unwrap_or_default();
+                    if seen.insert(value.to_string()) {
+                        secrets.push(LeakedSecret { kind, redacted: redact(value), severity: severity.clone() });
+                    }
+                }
+            }
+        }
+        secrets
+    }
+
+    /// The fragments with every recognised secret redacted, so the session's
+    /// logs and reports never hold a usable credential
+    pub fn redacted_fragments(&self) -> Vec<String> {
+        let patterns = secret_patterns();
+        self.fragments.iter()
+            .map(|fragment| {
+                let mut fragment = fragment.clone();
+                for (_, pattern, _) in &patterns {
+                    fragment = pattern.replace_all(&fragment, |captures: &regex::Captures| match captures.get(1) {
+                        Some(value) => captures[0].replace(value.as_str(), &redact(value.as_str())),
+                        None => redact(&captures[0]),
+                    }).into_owned();
+                }
+                fragment
+            })
+            .collect()
+    }
+
+    /// `owner/repo: path/to/file (https://github.com/...)`
+    fn reference(&self) -> String {
+        format!("{}: {} ({})", self.repository, self.path, self.url)
+    }
+
+    fn report(&self) -> String {
+        let mut report = format!("{}\n", self.reference());
+        for fragment in self.redacted_fragments() {
+            report.push_str(&format!("---\n{}\n", fragment.trim_end()));
+        }
+        report
+    }
+
+    /// A finding for a file holding secrets, as severe as the worst of them.
+    /// The values are not tried, so whether they still work is unknown
+    fn secrets_finding(&self, domain: &str, secrets: &[LeakedSecret], command_id: &str) -> SecurityFinding {
+        let kinds: BTreeSet<&str> = secrets.iter().map(|secret| secret.kind).collect();
+        let severity = secrets.iter()
+            .map(|secret| secret.severity.clone())
+            .max_by_key(FindingSeverity::rank)
+            .unwrap_or(FindingSeverity::Medium);
+        let listed: Vec<String> = secrets.iter().map(|secret| format!("{} {}", secret.kind, secret.redacted)).collect();
+        create_finding(
+            "Leaked Secrets in Public GitHub Code",
+            &format!(
+                "{} in the public repository {} mentions {} and contains {}: {}",
+                self.path, self.repository, domain, kinds.into_iter().collect::<Vec<_>>().join(", "), self.url,
+            ),
+            severity,
+            command_id,
+            &format!("{}\nSecrets: {}", self.report(), listed.join(", ")),
+        ).with_confidence(Confidence::Likely)
+    }
+}
+
+/// A finding listing files that mention the domain without a recognisable
+/// secret; they may still hold internal hostnames or credentials to check by hand
+fn references_finding(domain: &str, matches: &[&CodeMatch], command_id: &str) -> Option<SecurityFinding> {
+    if matches.is_empty() {
+        return None;
+    }
+    let listed = matches.iter().take(MAX_LISTED_FILES).map(|found| found.reference()).collect::<Vec<_>>().join(", ");
+    let more = matches.len().saturating_sub(MAX_LISTED_FILES);
+    let more = if more > 0 { format!(" and {} more", more) } else { String::new() };
+    let evidence: Vec<String> = matches.iter().map(|found| found.report()).collect();
+    Some(create_finding(
+        "Target Referenced in Public GitHub Code",
+        &format!(
+            "{} file(s) in public repositories mention {} next to credential keywords: {}{}",
+            matches.len(), domain, listed, more,
+        ),
+        FindingSeverity::Info,
+        command_id,
+        &evidence.join("\n"),
+    ).with_confidence(Confidence::Tentative))
+}
+
+/// Search public GitHub code for a domain and record the results like a
+/// command's: the matched files (secrets redacted) in the session's logs, the
+/// repositories as passive intel on the domain, a finding per file holding
+/// secrets and one for the other files. Returns a summary
+pub async fn passive_recon(client: &GitHubClient, monitor: &CommandMonitor, target: &str) -> Result<String> {
+    let started = Utc::now();
+    let matches = client.search(target).await?;
+    let log: Vec<String> = matches.iter().map(CodeMatch::report).collect();
+    let command_id = monitor.record_lookup(&lookup_command(target), started, &log.join("\n"))?;
+
+    let repositories: BTreeSet<&str> = matches.iter().map(|found| found.repository.as_str()).collect();
+    let assets = monitor.get_asset_inventory();
+    assets.record_host(target, &command_id);
+    let mut intel = vec![format!("{} file(s) in {} public repositories mention it", matches.len(), repositories.len())];
+    intel.extend(repositories.iter().map(|repository| format!("  {}", repository)));
+    assets.record_intel(target, "GitHub", intel);
+
+    let mut findings = Vec::new();
+    let mut references = Vec::new();
+    for found in &matches {
+        let secrets = found.secrets();
+        if secrets.is_empty() {
+            references.push(found);
+        } else {
+            findings.push(found.secrets_finding(target, &secrets, &command_id));
+        }
+    }
+    let summary = format!(
+        "GitHub code search found {} file(s) in {} public repositories mentioning {}, {} with secrets in them",
+        matches.len(), repositories.len(), target, findings.len(),
+    );
+    monitor.update_command_summary(&command_id, &summary)?;
+
+    findings.extend(references_finding(target, &references, &command_id));
+    monitor.record_integration_findings(findings).await?;
+    Ok(summary)
+}
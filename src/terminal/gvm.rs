@@ -0,0 +1,411 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use anyhow::{Result, Context, anyhow};
+use chrono::Utc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+
+use crate::config::GvmConfig;
+use super::command_monitor::{CommandMonitor, Confidence, FindingSeverity, SecurityFinding, create_finding};
+use super::cve_db::CveMatch;
+use super::cvss::{self, CvssScore};
+
+/// Longest detection output kept as a finding's evidence
+const MAX_OUTPUT_CHARS: usize = 4000;
+
+/// Talks GMP to gvmd over its Unix socket, authenticating on every connection
+#[derive(Clone)]
+pub struct GmpClient {
+    socket: PathBuf,
+    username: String,
+    password: String,
+    scan_config: String,
+    scanner: String,
+    port_list: String,
+    poll_interval: Duration,
+}
+
+/// A scan started with `start_scan`
+#[derive(Debug, Clone)]
+pub struct GvmScan {
+    pub task_id: String,
+    pub report_id: String,
+}
+
+/// A GVM report's results
+#[derive(Debug, Clone)]
+pub struct GvmReport {
+    pub id: String,
+    pub results: Vec<GvmResult>,
+}
+
+/// One NVT's result on one host and port
+#[derive(Debug, Clone)]
+pub struct GvmResult {
+    pub name: String,
+    pub host: String,
+    pub hostname: Option<String>,
+    /// `443/tcp`, or `general/tcp` for host-level results
+    pub port: String,
+    pub oid: String,
+    /// `Critical`, `High`, `Medium`, `Low` or `Log`
+    pub threat: String,
+    pub severity: f32,
+    /// Quality of detection, 0-100: how reliable the check is
+    pub qod: u8,
+    pub cvss_vector: Option<String>,
+    pub summary: String,
+    pub solution: String,
+    /// What the check found
+    pub output: String,
+    pub cves: Vec<String>,
+}
+
+impl GmpClient {
+    pub fn new(config: &GvmConfig) -> Result<Self> {
+        let password = config.password.clone()
+            .or_else(|| env::var("GVM_PASSWORD").ok())
+            .filter(|password| !password.is_empty())
+            .ok_or_else(|| anyhow!("No GVM password configured; set password in [gvm] or GVM_PASSWORD"))?;
+
+        Ok(Self {
+            socket: config.socket.clone(),
+            username: config.username.clone(),
+            password,
+            scan_config: config.scan_config.clone(),
+            scanner: config.scanner.clone(),
+            port_list: config.port_list.clone(),
+            poll_interval: Duration::from_secs(config.poll_secs.max(1)),
+        })
+    }
+
+    /// Create a target and a task scanning it, and start the task
+    pub async fn start_scan(&self, target: &str) -> Result<GvmScan> {
+        let name = format!("Hacksor {} {}", target, Utc::now().format("%Y-%m-%d %H:%M:%S"));
+        let response = self.request(&format!(
+            "<create_target><name>{}</name><hosts>{}</hosts><port_list id=\"{}\"/></create_target>",
+            xml_escape(&name), xml_escape(target), xml_escape(&self.port_list)
+        )).await?;
+        let target_id = created_id(&response)?;
+
+        let response = self.request(&format!(
+            "<create_task><name>{}</name><config id=\"{}\"/><target id=\"{}\"/><scanner id=\"{}\"/></create_task>",
+            xml_escape(&name), xml_escape(&self.scan_config), target_id, xml_escape(&self.scanner)
+        )).await?;
+        let task_id = created_id(&response)?;
+
+        let response = self.request(&format!("<start_task task_id=\"{}\"/>", task_id)).await?;
+        let document = roxmltree::Document::parse(&response)?;
+        let report_id = document.descendants()
+            .find(|node| node.has_tag_name("report_id"))
+            .and_then(|node| node.text())
+            .ok_or_else(|| anyhow!("gvmd did not return a report for task {}", task_id))?
+            .to_string();
+
+        Ok(GvmScan { task_id, report_id })
+    }
+
+    /// The task's status and progress, e.g. `("Running", 42)`
+    pub async fn task_status(&self, task_id: &str) -> Result<(String, i32)> {
+        let response = self.request(&format!("<get_tasks task_id=\"{}\"/>", xml_escape(task_id))).await?;
+        let document = roxmltree::Document::parse(&response)?;
+        let task = document.descendants()
+            .find(|node| node.has_tag_name("task"))
+            .ok_or_else(|| anyhow!("gvmd has no task {}", task_id))?;
+        Ok((text(child(task, "status")), text(child(task, "progress")).parse().unwrap_or(0)))
+    }
+
+    /// Poll a task until it stops, returning its final status
+    pub async fn wait_for(&self, task_id: &str) -> Result<String> {
+        loop {
+            let (status, _) = self.task_status(task_id).await?;
+            if matches!(status.as_str(), "Done" | "Stopped" | "Interrupted") {
+                return Ok(status);
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+
+    /// A report's results, however far its task has got
+    pub async fn report(&self, report_id: &str) -> Result<GvmReport> {
+        let response = self.request(&format!(
+            "<get_reports report_id=\"{}\" details=\"1\" ignore_pagination=\"1\" filter=\"apply_overrides=1 min_qod=0 rows=-1\"/>",
+            xml_escape(report_id)
+        )).await?;
+        GvmReport::parse(&response)
+    }
+
+    /// Send one command after authenticating, returning gvmd's response
+    async fn request(&self, command: &str) -> Result<String> {
+        let mut stream = UnixStream::connect(&self.socket)
+            .await
+            .context(format!("Failed to connect to gvmd at {:?}", self.socket))?;
+
+        let authenticate = format!(
+            "<authenticate><credentials><username>{}</username><password>{}</password></credentials></authenticate>",
+            xml_escape(&self.username), xml_escape(&self.password)
+        );
+        exchange(&mut stream, &authenticate).await.context("GVM authentication failed")?;
+        exchange(&mut stream, command).await
+    }
+}
+
+/// Write a command and read its response, failing unless gvmd reports success
+async fn exchange(stream: &mut UnixStream, command: &str) -> Result<String> {
+    stream.write_all(command.as_bytes()).await?;
+
+    // Responses are a single element; keep reading until it parses
+    let mut response = Vec::new();
+    let mut chunk = [0u8; 16 * 1024];
+    loop {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Err(anyhow!("gvmd closed the connection"));
+        }
+        response.extend_from_slice(&chunk[..read]);
+        let Ok(text) = std::str::from_utf8(&response) else {
+            continue;
+        };
+        let Ok(document) = roxmltree::Document::parse(text) else {
+            continue;
+        };
+
+        let root = document.root_element();
+        let status = root.attribute("status").unwrap_or_default();
+        if !status.starts_with('2') {
+            return Err(anyhow!("{} ({})", root.attribute("status_text").unwrap_or("GMP command failed"), status));
+        }
+        return Ok(text.to_string());
+    }
+}
+
+/// The `id` gvmd gives a created object
+fn created_id(response: &str) -> Result<String> {
+    let document = roxmltree::Document::parse(response)?;
+    document.root_element().attribute("id")
+        .map(String::from)
+        .ok_or_else(|| anyhow!("gvmd did not return the new object's ID"))
+}
+
+fn child<'a, 'input>(parent: roxmltree::Node<'a, 'input>, name: &str) -> Option<roxmltree::Node<'a, 'input>> {
+    parent.children().find(|child| child.has_tag_name(name))
+}
+
+/// Trimmed text of an element, empty when it is missing
+fn text(node: Option<roxmltree::Node>) -> String {
+    node.and_then(|node| node.text())
+        .map(|text| text.trim().to_string())
+        .unwrap_or_default()
+}
+
+fn xml_escape(value: &str) -> String {
+    value.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+impl GvmReport {
+    /// Read a report exported from GSA as XML
+    pub fn load(path: &Path) -> Result<Self> {
+        let xml = fs::read_to_string(path)
+            .context(format!("Failed to read GVM report {:?}", path))?;
+        Self::parse(&xml).context(format!("Failed to parse GVM report {:?}", path))
+    }
+
+    /// Parse a `get_reports` response or an exported report
+    pub fn parse(xml: &str) -> Result<Self> {
+        let document = roxmltree::Document::parse(xml)?;
+        let report = document.descendants()
+            .find(|node| node.has_tag_name("report") && node.attribute("id").is_some())
+            .ok_or_else(|| anyhow!("No <report> element; is this a GVM XML report?"))?;
+        let results = report.descendants()
+            .filter(|node| node.has_tag_name("results"))
+            .flat_map(|results| results.children().filter(|node| node.has_tag_name("result")))
+            .map(GvmResult::from_node)
+            .filter(|result| !result.host.is_empty())
+            .collect();
+
+        Ok(Self {
+            id: report.attribute("id").unwrap_or_default().to_string(),
+            results,
+        })
+    }
+
+    /// Results grouped by host
+    pub fn hosts(&self) -> BTreeMap<&str, Vec<&GvmResult>> {
+        let mut hosts: BTreeMap<&str, Vec<&GvmResult>> = BTreeMap::new();
+        for result in &self.results {
+            hosts.entry(result.host.as_str()).or_default().push(result);
+        }
+        hosts
+    }
+}
+
+impl GvmResult {
+    fn from_node(node: roxmltree::Node) -> Self {
+        let host = child(node, "host");
+        let nvt = child(node, "nvt");
+        let nvt_child = |name: &str| nvt.and_then(|nvt| child(nvt, name));
+
+        // Tags are `key=value` pairs separated by `|`
+        let tags = text(nvt_child("tags"));
+        let tag = |key: &str| tags.split('|')
+            .find_map(|pair| pair.strip_prefix(key).and_then(|rest| rest.strip_prefix('=')))
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+
+        let cvss_vector = nvt.into_iter()
+            .flat_map(|nvt| nvt.descendants())
+            .filter(|node| node.has_tag_name("severity") && node.attribute("type").is_some_and(|kind| kind.starts_with("cvss_base_v3")))
+            .find_map(|severity| child(severity, "value").and_then(|value| value.text()))
+            .map(|vector| vector.trim().to_string())
+            .or_else(|| Some(tag("cvss_base_vector")).filter(|vector| vector.starts_with("CVSS:3")));
+        let cves = nvt.into_iter()
+            .flat_map(|nvt| nvt.descendants())
+            .filter(|node| node.has_tag_name("ref") && node.attribute("type") == Some("cve"))
+            .filter_map(|node| node.attribute("id"))
+            .map(String::from)
+            .collect();
+
+        let solution = match text(nvt_child("solution")) {
+            solution if solution.is_empty() => tag("solution"),
+            solution => solution,
+        };
+
+        Self {
+            name: text(child(node, "name")),
+            host: host.and_then(|host| host.text()).unwrap_or_default().trim().to_string(),
+            hostname: host.and_then(|host| child(host, "hostname"))
+                .and_then(|hostname| hostname.text())
+                .map(|hostname| hostname.trim().to_string())
+                .filter(|hostname| !hostname.is_empty()),
+            port: text(child(node, "port")),
+            oid: nvt.and_then(|nvt| nvt.attribute("oid")).unwrap_or_default().to_string(),
+            threat: text(child(node, "threat")),
+            severity: text(child(node, "severity")).parse().unwrap_or(0.0),
+            qod: text(child(node, "qod").and_then(|qod| child(qod, "value"))).parse().unwrap_or(0),
+            cvss_vector,
+            summary: tag("summary"),
+            solution,
+            output: text(child(node, "description")),
+            cves,
+        }
+    }
+
+    fn finding_severity(&self) -> FindingSeverity {
+        match self.threat.as_str() {
+            "Log" | "" => FindingSeverity::Info,
+            _ => cvss::severity_for_score(self.severity),
+        }
+    }
+
+    /// Version checks and banner matches have a lower QoD than active checks
+    fn confidence(&self) -> Confidence {
+        match self.qod {
+            95.. => Confidence::Confirmed,
+            70.. => Confidence::Likely,
+            _ => Confidence::Tentative,
+        }
+    }
+
+    /// `443/tcp`, or none for host-level results such as `general/tcp`
+    fn port_name(&self) -> Option<&str> {
+        self.port.split_once('/')
+            .filter(|(port, _)| port.parse::<u16>().is_ok())
+            .map(|_| self.port.as_str())
+    }
+
+    fn finding(&self, command_id: &str) -> SecurityFinding {
+        let host = match &self.hostname {
+            Some(hostname) => format!("{} ({})", self.host, hostname),
+            None => self.host.clone(),
+        };
+        let location = match self.port_name() {
+            Some(port) => format!("{} {}", host, port),
+            None => host,
+        };
+
+        let mut evidence: String = self.output.chars().take(MAX_OUTPUT_CHARS).collect();
+        if !self.solution.is_empty() {
+            evidence.push_str(&format!("\n\nSolution: {}", self.solution));
+        }
+
+        let mut finding = create_finding(
+            &self.name,
+            &format!("OpenVAS NVT {} on {} (QoD {}%): {}", self.oid, location, self.qod, self.summary),
+            self.finding_severity(),
+            command_id,
+            &evidence,
+        ).with_confidence(self.confidence());
+        if let Some(cvss) = self.cvss_vector.as_deref().and_then(|vector| CvssScore::from_vector(vector).ok()) {
+            finding.cvss = Some(cvss);
+        }
+        finding.related_cves = self.cves.iter()
+            .map(|id| CveMatch {
+                id: id.clone(),
+                cvss: None,
+                summary: String::new(),
+                references: Vec::new(),
+            })
+            .collect();
+        finding
+    }
+}
+
+/// The command a host's imported results are recorded as
+pub fn import_command(source: &str, host: &str) -> String {
+    format!("gvm import {} {}", source, host)
+}
+
+/// Record a host's results like a command's: the detection output in the
+/// session's logs, its ports in the asset inventory and a finding per result.
+/// Log-level results are only imported with `include_info`. Returns the number
+/// of findings raised
+pub async fn import_host(host: &str, results: &[&GvmResult], source: &str, monitor: &CommandMonitor, include_info: bool) -> Result<usize> {
+    let log: Vec<String> = results.iter()
+        .map(|result| format!("[{}] {} {} (NVT {})\n{}", result.threat, result.port, result.name, result.oid, result.output))
+        .collect();
+    let command_id = monitor.record_lookup(&import_command(source, host), Utc::now(), &log.join("\n\n"))?;
+
+    let assets = monitor.get_asset_inventory();
+    let mut findings = Vec::new();
+    for result in results {
+        if let Some(port) = result.port_name() {
+            assets.record_service(host, port, None, &command_id);
+        }
+        if result.finding_severity() == FindingSeverity::Info && !include_info {
+            continue;
+        }
+        findings.push(result.finding(&command_id));
+    }
+    let raised = monitor.record_integration_findings(findings).await?;
+
+    let ports: BTreeSet<&str> = results.iter().filter_map(|result| result.port_name()).collect();
+    monitor.update_command_summary(&command_id, &format!(
+        "Imported {} OpenVAS results for {} ({} ports), {} findings raised",
+        results.len(), host, ports.len(), raised
+    ))?;
+    Ok(raised)
+}
+
+/// Import every host in a report, skipping hosts already imported from `source`.
+/// Returns the findings raised and the hosts imported
+pub async fn import_report(report: &GvmReport, source: &str, monitor: &CommandMonitor, include_info: bool) -> Result<(usize, Vec<String>)> {
+    let imported: Vec<String> = monitor.get_all_commands().into_iter().map(|cmd| cmd.command).collect();
+    let mut raised = 0;
+    let mut hosts = Vec::new();
+    for (host, results) in report.hosts() {
+        if imported.contains(&import_command(source, host)) {
+            continue;
+        }
+        raised += import_host(host, &results, source, monitor, include_info).await?;
+        hosts.push(host.to_string());
+    }
+    Ok((raised, hosts))
+}
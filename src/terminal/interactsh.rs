@@ -0,0 +1,137 @@
+use std::collections::{HashMap, HashSet};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use anyhow::{Result, Context, anyhow};
+use regex::Regex;
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+
+use crate::config::InteractshConfig;
+use super::command_monitor::{CommandMonitor, Confidence, FindingSeverity, create_finding};
+
+/// Replaced in a command with a callback domain unique to that command
+pub const OOB_PLACEHOLDER: &str = "{oob}";
+
+/// Runs `interactsh-client` for the session and turns interactions with the
+/// callback domains handed to commands into findings against those commands
+#[derive(Clone)]
+pub struct InteractshListener {
+    /// The client's payload domain, once it has registered with the server
+    domain: Arc<Mutex<Option<String>>>,
+    /// Command IDs by the callback label issued to them
+    callbacks: Arc<Mutex<HashMap<String, String>>>,
+    /// Callback label and protocol pairs already reported
+    reported: Arc<Mutex<HashSet<(String, String)>>>,
+}
+
+impl InteractshListener {
+    /// Start the client and report interactions to `monitor` until the session ends
+    pub fn start(config: &InteractshConfig, monitor: CommandMonitor) -> Result<Self> {
+        let mut command = Command::new(&config.command);
+        command.args(["-json", "-v"]);
+        if let Some(server) = &config.server {
+            command.args(["-server", server]);
+        }
+        if let Some(token) = config.token.clone().or_else(|| std::env::var("INTERACTSH_TOKEN").ok()).filter(|token| !token.is_empty()) {
+            command.args(["-token", &token]);
+        }
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .context(format!("Failed to start {}", config.command))?;
+
+        let listener = Self {
+            domain: Arc::new(Mutex::new(None)),
+            callbacks: Arc::new(Mutex::new(HashMap::new())),
+            reported: Arc::new(Mutex::new(HashSet::new())),
+        };
+
+        // The payload domain is logged on stderr, interactions are JSON lines on stdout
+        let stdout = child.stdout.take().context("Failed to capture interactsh-client output")?;
+        let stderr = child.stderr.take().context("Failed to capture interactsh-client output")?;
+        tokio::spawn(listener.clone().read(stdout, monitor.clone()));
+        tokio::spawn(listener.clone().read(stderr, monitor));
+        tokio::spawn(async move {
+            if let Ok(status) = child.wait().await {
+                eprintln!("interactsh-client exited ({}); out-of-band callbacks are no longer received", status);
+            }
+        });
+
+        Ok(listener)
+    }
+
+    /// Replace every `{oob}` in a command with a callback domain issued to `command_id`
+    pub fn substitute(&self, command: &str, command_id: &str) -> Result<String> {
+        let domain = self.domain.lock().unwrap().clone()
+            .ok_or_else(|| anyhow!("interactsh-client has not registered a callback domain yet; try again shortly"))?;
+
+        // A short label keeps the domain usable in payloads with length limits
+        let label: String = command_id.chars().filter(char::is_ascii_alphanumeric).take(12).collect::<String>().to_lowercase();
+        self.callbacks.lock().unwrap().insert(label.clone(), command_id.to_string());
+        Ok(command.replace(OOB_PLACEHOLDER, &format!("{}.{}", label, domain)))
+    }
+
+    /// The client's payload domain, once it has registered
+    pub fn domain(&self) -> Option<String> {
+        self.domain.lock().unwrap().clone()
+    }
+
+    async fn read(self, stream: impl AsyncRead + Unpin, monitor: CommandMonitor) {
+        let domain_pattern = Regex::new(r"^\[INF\]\s+([a-z0-9]{20,}\.[a-z0-9.-]+\.[a-z]{2,})\s*$").unwrap();
+        let mut lines = BufReader::new(stream).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if let Some(captures) = domain_pattern.captures(line.trim()) {
+                self.domain.lock().unwrap().get_or_insert_with(|| captures[1].to_string());
+                continue;
+            }
+            if let Ok(interaction) = serde_json::from_str::<Value>(&line) {
+                if let Err(e) = self.report(&interaction, &monitor).await {
+                    eprintln!("Failed to report out-of-band interaction: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Raise a finding against the command whose callback was contacted. The
+    /// same callback reached again over the same protocol is not reported twice
+    async fn report(&self, interaction: &Value, monitor: &CommandMonitor) -> Result<()> {
+        let field = |name: &str| interaction[name].as_str().unwrap_or_default().to_string();
+        let full_id = field("full-id").to_lowercase();
+        let unique_id = field("unique-id").to_lowercase();
+        let protocol = field("protocol").to_uppercase();
+
+        // `full-id` is `<label>.<unique-id>` for callbacks issued to commands
+        let Some(label) = full_id.strip_suffix(&unique_id).and_then(|prefix| prefix.strip_suffix('.')).and_then(|prefix| prefix.rsplit('.').next()) else {
+            return Ok(());
+        };
+        let Some(command_id) = self.callbacks.lock().unwrap().get(label).cloned() else {
+            return Ok(());
+        };
+        if !self.reported.lock().unwrap().insert((label.to_string(), protocol.clone())) {
+            return Ok(());
+        }
+
+        let command = monitor.get_command(&command_id).map(|cmd| cmd.command).unwrap_or_default();
+        let callback = match self.domain() {
+            Some(domain) => format!("{}.{}", label, domain),
+            None => full_id.clone(),
+        };
+        let finding = create_finding(
+            &format!("Out-of-Band {} Interaction Received", protocol),
+            &format!(
+                "The callback {} given to `{}` was contacted over {} from {} at {}: the blind payload it sent was executed or fetched by the target",
+                callback, command, protocol, field("remote-address"), field("timestamp")
+            ),
+            FindingSeverity::Critical,
+            &command_id,
+            &serde_json::to_string_pretty(interaction)?,
+        ).with_confidence(Confidence::Confirmed);
+
+        monitor.record_integration_findings([finding]).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+use std::env;
+use anyhow::{Result, Context, anyhow};
+use serde_json::{json, Map, Value};
+
+use crate::config::JiraConfig;
+use super::auto_documentation::DocumentedFinding;
+use crate::utils::extract_target_from_command;
+
+/// Key under which created issues are recorded in a finding's external references
+pub const JIRA_REF: &str = "Jira";
+
+/// Longest evidence excerpt put in an issue description
+const MAX_EVIDENCE_CHARS: usize = 10_000;
+
+/// Creates Jira issues from documented findings through the REST API
+pub struct JiraClient {
+    config: JiraConfig,
+    api_token: String,
+    client: reqwest::Client,
+}
+
+impl JiraClient {
+    pub fn new(config: JiraConfig) -> Result<Self> {
+        let api_token = config.api_token.clone()
+            .or_else(|| env::var("JIRA_API_TOKEN").ok())
+            .context("No Jira API token: set api_token in [jira] or the JIRA_API_TOKEN environment variable")?;
+
+        Ok(Self {
+            config,
+            api_token,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// Create an issue for a finding, returning its key, e.g. `SEC-42`
+    pub async fn create_issue(&self, finding: &DocumentedFinding) -> Result<String> {
+        let url = format!("{}/rest/api/2/issue", self.config.url.trim_end_matches('/'));
+        let response = self.client.post(&url)
+            .basic_auth(&self.config.email, Some(&self.api_token))
+            .json(&json!({ "fields": self.issue_fields(finding) }))
+            .send()
+            .await
+            .context(format!("Failed to reach Jira at {}", self.config.url))?;
+
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            return Err(anyhow!("Jira rejected issue for {} ({}): {}", finding.id, status, body));
+        }
+
+        body["key"].as_str()
+            .map(String::from)
+            .ok_or_else(|| anyhow!("Jira response did not include an issue key: {}", body))
+    }
+
+    fn issue_fields(&self, finding: &DocumentedFinding) -> Map<String, Value> {
+        let mut fields = Map::new();
+        fields.insert("project".to_string(), json!({ "key": self.config.project }));
+        fields.insert("issuetype".to_string(), json!({ "name": self.config.issue_type }));
+        fields.insert("summary".to_string(), json!(format!("[{:?}] {}", finding.severity, finding.title)));
+        fields.insert("description".to_string(), json!(issue_description(finding)));
+        fields.insert("labels".to_string(), json!(self.config.labels));
+
+        if let Some(priority) = self.config.priorities.get(&format!("{:?}", finding.severity)) {
+            fields.insert("priority".to_string(), json!({ "name": priority }));
+        }
+
+        for (field, template) in &self.config.fields {
+            fields.insert(field.clone(), json!(fill_placeholders(template, finding)));
+        }
+
+        fields
+    }
+}
+
+/// Issue description in Jira wiki markup
+fn issue_description(finding: &DocumentedFinding) -> String {
+    let mut description = format!("{}\n\n", finding.description);
+    description.push_str(&format!("*Finding ID:* {}\n", finding.id));
+    description.push_str(&format!("*Severity:* {:?}\n", finding.severity));
+    description.push_str(&format!("*Confidence:* {:?}\n", finding.confidence));
+    if let Some(cvss) = &finding.cvss {
+        description.push_str(&format!("*CVSS v3.1:* {:.1} ({})\n", cvss.score, cvss.vector));
+    }
+    if let Some(cwe) = &finding.cwe {
+        description.push_str(&format!("*CWE:* {}\n", cwe));
+    }
+    if let Some(owasp) = &finding.owasp {
+        description.push_str(&format!("*OWASP Top 10:* {}\n", owasp));
+    }
+    if let Some(target) = extract_target_from_command(&finding.discovery_command) {
+        description.push_str(&format!("*Target:* {}\n", target));
+    }
+    description.push_str(&format!("*Discovered:* {}\n", finding.discovery_date.format("%Y-%m-%d %H:%M:%S UTC")));
+    if !finding.related_cves.is_empty() {
+        let cves = finding.related_cves.iter().map(|cve| cve.id.as_str()).collect::<Vec<_>>().join(", ");
+        description.push_str(&format!("*Related CVEs:* {}\n", cves));
+    }
+
+    description.push_str(&format!("\nh3. Discovery Command\n{{noformat}}{}{{noformat}}\n", finding.discovery_command));
+
+    let evidence: String = finding.raw_evidence.chars().take(MAX_EVIDENCE_CHARS).collect();
+    let truncated = if evidence.len() < finding.raw_evidence.len() { "\n(truncated)" } else { "" };
+    description.push_str(&format!("\nh3. Evidence\n{{noformat}}{}{}{{noformat}}\n", evidence, truncated));
+
+    description
+}
+
+fn fill_placeholders(template: &str, finding: &DocumentedFinding) -> String {
+    template
+        .replace("{id}", &finding.id)
+        .replace("{title}", &finding.title)
+        .replace("{severity}", &format!("{:?}", finding.severity))
+        .replace("{cvss}", &finding.cvss.as_ref().map(|cvss| format!("{:.1}", cvss.score)).unwrap_or_default())
+        .replace("{cwe}", finding.cwe.as_deref().unwrap_or(""))
+        .replace("{owasp}", finding.owasp.as_deref().unwrap_or(""))
+        .replace("{target}", &extract_target_from_command(&finding.discovery_command).unwrap_or_default())
+}
@@ -7,12 +7,61 @@ pub mod command_monitor;
 pub mod output_analyzer;
 pub mod auto_documentation;
 pub mod action_executor;
+pub mod action_queue;
+pub mod action_plugins;
+pub mod execution_backend;
+pub mod scheduler;
+pub mod throttle;
+pub mod proxy;
+pub mod dns;
+pub mod safety_policy;
+pub mod approval;
+pub mod command_policy;
+pub mod analyzer_rules;
+pub mod cvss;
+pub mod taxonomy;
+pub mod tech_inventory;
+pub mod suppression;
+pub mod assets;
+pub mod cve_db;
+pub mod nvd;
+pub mod searchsploit;
+pub mod nessus;
+pub mod gvm;
+pub mod interactsh;
+pub mod alerts;
+pub mod notifier;
+pub mod triggers;
+pub mod events;
+pub mod brute_force;
+pub mod export;
+pub mod report_templates;
+pub mod screenshots;
+pub mod jira;
+pub mod shodan;
+pub mod censys;
+pub mod crtsh;
+pub mod wayback;
+pub mod github;
+pub mod virustotal;
+pub mod securitytrails;
+pub mod session_diff;
+pub mod evidence_git;
+pub mod severity_matrix;
+pub mod timeline;
+pub mod finding_ids;
+pub mod testing_window;
+pub mod playbook;
 
 pub use command_monitor::{
-    CommandMonitor, CommandStatus, CommandType
+    CommandMonitor, CommandStatus, CommandType, MonitoredCommand
 };
 pub use auto_documentation::ActionStatus;
 pub use action_executor::ActionExecutor;
+pub use execution_backend::ExecutionBackend;
+pub use scheduler::CommandScheduler;
+pub use throttle::Throttle;
+pub use command_policy::{CommandPolicy, PolicyViolation};
 
 #[derive(Clone)]
 pub struct TerminalManager {
@@ -0,0 +1,233 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use anyhow::{Result, Context, anyhow};
+use chrono::Utc;
+
+use super::command_monitor::{CommandMonitor, Confidence, FindingSeverity, SecurityFinding, create_finding};
+use super::cve_db::CveMatch;
+use super::cvss::{self, CvssScore};
+
+/// Longest plugin output kept as a finding's evidence
+const MAX_PLUGIN_OUTPUT_CHARS: usize = 4000;
+
+/// A `.nessus` (NessusClientData_v2) export
+#[derive(Debug, Clone)]
+pub struct NessusReport {
+    pub name: String,
+    pub hosts: Vec<NessusHost>,
+}
+
+/// A scanned host and the plugins that reported on it
+#[derive(Debug, Clone)]
+pub struct NessusHost {
+    /// The name the host was scanned as, usually its address or FQDN
+    pub name: String,
+    pub items: Vec<NessusItem>,
+}
+
+/// One plugin's result on one port
+#[derive(Debug, Clone)]
+pub struct NessusItem {
+    pub plugin_id: String,
+    pub plugin_name: String,
+    pub port: u16,
+    pub protocol: String,
+    pub service: String,
+    /// 0 (informational) to 4 (critical)
+    pub severity: u8,
+    pub synopsis: String,
+    pub description: String,
+    pub solution: String,
+    pub plugin_output: String,
+    pub cvss3_vector: Option<String>,
+    pub cves: Vec<String>,
+    pub exploit_available: bool,
+}
+
+impl NessusReport {
+    pub fn load(path: &Path) -> Result<Self> {
+        let xml = fs::read_to_string(path)
+            .context(format!("Failed to read Nessus export {:?}", path))?;
+        Self::parse(&xml).context(format!("Failed to parse Nessus export {:?}", path))
+    }
+
+    pub fn parse(xml: &str) -> Result<Self> {
+        let document = roxmltree::Document::parse(xml)?;
+        let report = document.descendants()
+            .find(|node| node.has_tag_name("Report"))
+            .ok_or_else(|| anyhow!("No <Report> element; is this a .nessus (v2) export?"))?;
+
+        let hosts = report.children()
+            .filter(|node| node.has_tag_name("ReportHost"))
+            .map(|host| NessusHost {
+                name: host.attribute("name").unwrap_or_default().to_string(),
+                items: host.children()
+                    .filter(|node| node.has_tag_name("ReportItem"))
+                    .map(NessusItem::from_node)
+                    .collect(),
+            })
+            .filter(|host| !host.name.is_empty())
+            .collect();
+
+        Ok(Self {
+            name: report.attribute("name").unwrap_or_default().to_string(),
+            hosts,
+        })
+    }
+}
+
+impl NessusItem {
+    fn from_node(node: roxmltree::Node) -> Self {
+        let child = |name: &str| node.children()
+            .find(|child| child.has_tag_name(name))
+            .and_then(|child| child.text())
+            .map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty());
+        let attribute = |name: &str| node.attribute(name).unwrap_or_default().to_string();
+
+        Self {
+            plugin_id: attribute("pluginID"),
+            plugin_name: attribute("pluginName"),
+            port: attribute("port").parse().unwrap_or(0),
+            protocol: attribute("protocol"),
+            service: attribute("svc_name"),
+            severity: attribute("severity").parse().unwrap_or(0),
+            synopsis: child("synopsis").unwrap_or_default(),
+            description: child("description").unwrap_or_default(),
+            solution: child("solution").unwrap_or_default(),
+            plugin_output: child("plugin_output").unwrap_or_default(),
+            cvss3_vector: child("cvss3_vector"),
+            cves: node.children()
+                .filter(|child| child.has_tag_name("cve"))
+                .filter_map(|child| child.text())
+                .map(|cve| cve.trim().to_string())
+                .collect(),
+            exploit_available: child("exploit_available").as_deref() == Some("true"),
+        }
+    }
+
+    /// The severity band of the result's CVSS v3 score, or Nessus's own rating
+    /// (0 to 4) for results without one
+    fn finding_severity(&self) -> FindingSeverity {
+        if let Some(cvss) = self.cvss3_vector.as_deref().and_then(|vector| CvssScore::from_vector(vector).ok()) {
+            return cvss::severity_for_score(cvss.score);
+        }
+        match self.severity {
+            4 => FindingSeverity::Critical,
+            3 => FindingSeverity::High,
+            2 => FindingSeverity::Medium,
+            1 => FindingSeverity::Low,
+            _ => FindingSeverity::Info,
+        }
+    }
+
+    /// `443/tcp`, or none for host-level results
+    fn port_name(&self) -> Option<String> {
+        (self.port != 0).then(|| format!("{}/{}", self.port, self.protocol))
+    }
+
+    /// The result as a finding. Nessus infers many results from banners, so
+    /// they are treated as likely until verified by hand
+    fn finding(&self, host: &str, command_id: &str) -> SecurityFinding {
+        let location = match self.port_name() {
+            Some(port) => format!("{} ({} {})", host, port, self.service),
+            None => host.to_string(),
+        };
+        let mut description = format!("Nessus plugin {} on {}: {}", self.plugin_id, location, self.synopsis);
+        if self.exploit_available {
+            description.push_str(" A public exploit is available.");
+        }
+
+        let mut evidence = String::new();
+        if !self.plugin_output.is_empty() {
+            evidence.push_str(&self.plugin_output.chars().take(MAX_PLUGIN_OUTPUT_CHARS).collect::<String>());
+            evidence.push_str("\n\n");
+        }
+        evidence.push_str(&self.description);
+        if !self.solution.is_empty() {
+            evidence.push_str(&format!("\n\nSolution: {}", self.solution));
+        }
+
+        let mut finding = create_finding(
+            &self.plugin_name,
+            &description,
+            self.finding_severity(),
+            command_id,
+            &evidence,
+        ).with_confidence(Confidence::Likely);
+        if let Some(cvss) = self.cvss3_vector.as_deref().and_then(|vector| CvssScore::from_vector(vector).ok()) {
+            finding.cvss = Some(cvss);
+        }
+        finding.related_cves = self.cves.iter()
+            .map(|id| CveMatch {
+                id: id.clone(),
+                cvss: None,
+                summary: String::new(),
+                references: Vec::new(),
+            })
+            .collect();
+        finding
+    }
+}
+
+/// The command an imported host is recorded as
+pub fn import_command(source: &str, host: &str) -> String {
+    format!("nessus import {} {}", source, host)
+}
+
+/// Record a host's results like a command's: the plugin output in the session's
+/// logs, its open ports in the asset inventory and a finding per result.
+/// Informational results are only imported with `include_info`. Returns the
+/// number of findings raised
+pub async fn import_host(host: &NessusHost, source: &str, monitor: &CommandMonitor, include_info: bool) -> Result<usize> {
+    let log: Vec<String> = host.items.iter()
+        .map(|item| format!(
+            "[{:?}] {} {} (plugin {})\n{}",
+            item.finding_severity(),
+            item.port_name().unwrap_or_else(|| "host".to_string()),
+            item.plugin_name,
+            item.plugin_id,
+            item.plugin_output,
+        ))
+        .collect();
+    let command_id = monitor.record_lookup(&import_command(source, &host.name), Utc::now(), &log.join("\n\n"))?;
+
+    let assets = monitor.get_asset_inventory();
+    let mut findings = Vec::new();
+    for item in &host.items {
+        if let Some(port) = item.port_name() {
+            let service = Some(item.service.as_str()).filter(|service| !service.is_empty());
+            assets.record_service(&host.name, &port, service, &command_id);
+        }
+        if item.severity == 0 && !include_info {
+            continue;
+        }
+
+        findings.push(item.finding(&host.name, &command_id));
+    }
+    let raised = monitor.record_integration_findings(findings).await?;
+
+    let ports = host.items.iter().filter_map(NessusItem::port_name).collect::<BTreeSet<_>>();
+    monitor.update_command_summary(&command_id, &format!(
+        "Imported {} Nessus results for {} ({} open ports), {} findings raised",
+        host.items.len(), host.name, ports.len(), raised
+    ))?;
+    Ok(raised)
+}
+
+/// Import every host in a report, skipping hosts already imported from `source`.
+/// Returns the findings raised and the hosts imported
+pub async fn import_report(report: &NessusReport, source: &str, monitor: &CommandMonitor, include_info: bool) -> Result<(usize, Vec<String>)> {
+    let imported: Vec<String> = monitor.get_all_commands().into_iter().map(|cmd| cmd.command).collect();
+    let mut raised = 0;
+    let mut hosts = Vec::new();
+    for host in &report.hosts {
+        if imported.contains(&import_command(source, &host.name)) {
+            continue;
+        }
+        raised += import_host(host, source, monitor, include_info).await?;
+        hosts.push(host.name.clone());
+    }
+    Ok((raised, hosts))
+}
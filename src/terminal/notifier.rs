@@ -0,0 +1,201 @@
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use anyhow::{Result, Context, anyhow};
+use serde_json::{json, Value};
+
+use crate::config::{Config, WebhookConfig, WebhookKind};
+use super::auto_documentation::DocumentedFinding;
+use super::command_monitor::{CommandStatus, MonitoredCommand};
+
+/// Longest message posted; Discord refuses more than 2000 characters
+const MAX_MESSAGE_CHARS: usize = 1900;
+
+/// How long the end-of-session summary may take to deliver before exiting anyway
+const SUMMARY_TIMEOUT_SECS: u64 = 10;
+
+/// How long any other message may take to deliver before it is abandoned
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Posts command completions, new findings and the end-of-session summary to
+/// Slack, Discord and Telegram, for scans left running unattended
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    /// Name of the session the messages are about
+    session: String,
+    webhooks: Arc<Mutex<Vec<WebhookConfig>>>,
+    client: reqwest::Client,
+}
+
+impl Notifier {
+    pub fn new(session: String) -> Self {
+        Self {
+            session,
+            webhooks: Arc::new(Mutex::new(Vec::new())),
+            client: reqwest::Client::builder()
+                .timeout(DELIVERY_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Set the webhooks posted to from now on
+    pub fn set_webhooks(&self, webhooks: Vec<WebhookConfig>) {
+        *self.webhooks.lock().unwrap() = webhooks;
+    }
+
+    /// Post a finished command to the webhooks that want commands that long
+    pub fn command_finished(&self, cmd: &MonitoredCommand) {
+        let Some(end_time) = cmd.end_time else {
+            return;
+        };
+        let duration = end_time - cmd.start_time;
+        let mut message = match &cmd.status {
+            CommandStatus::Failed(reason) => format!("Failed `{}` after {}: {}", cmd.command, crate::format_duration(duration), reason),
+            _ => format!("Finished `{}` in {}", cmd.command, crate::format_duration(duration)),
+        };
+        if let Some(summary) = cmd.summary() {
+            message.push_str(&format!("\n{}", summary));
+        }
+        if !cmd.findings.is_empty() {
+            message.push_str(&format!("\n{} potential findings", cmd.findings.len()));
+        }
+
+        let seconds = duration.num_seconds().max(0) as u64;
+        self.post_in_background(&message, None, |webhook| webhook.commands && seconds >= webhook.min_command_seconds);
+    }
+
+    /// Post a newly documented finding to the webhooks whose threshold it meets
+    pub fn finding_documented(&self, finding: &DocumentedFinding) {
+        let mut message = format!("{:?} finding {}: {}", finding.severity, finding.id, finding.title);
+        if let Some(cvss) = &finding.cvss {
+            message.push_str(&format!(" (CVSS {:.1})", cvss.score));
+        }
+        message.push_str(&format!("\nFound by `{}`", finding.discovery_command));
+
+        let details = json!({
+            "id": finding.id,
+            "title": finding.title,
+            "severity": format!("{:?}", finding.severity),
+            "cvss": finding.cvss.as_ref().map(|cvss| cvss.score),
+            "description": finding.description,
+            "command": finding.discovery_command,
+        });
+        self.post_in_background(&message, Some(details), |webhook| finding.severity.rank() >= webhook.threshold.rank());
+    }
+
+    /// Post the end-of-session summary, waiting for it to be delivered
+    pub async fn session_ended(&self, summary: &str) {
+        let text = self.message(summary);
+        let webhooks = self.matching(|webhook| webhook.session_summary);
+        let sent = tokio::time::timeout(Duration::from_secs(SUMMARY_TIMEOUT_SECS), async {
+            for webhook in &webhooks {
+                if let Err(e) = deliver(&self.client, webhook, &text, None).await {
+                    eprintln!("Failed to post session summary: {:#}", e);
+                }
+            }
+        }).await;
+
+        if sent.is_err() {
+            eprintln!("Gave up posting the session summary after {} seconds", SUMMARY_TIMEOUT_SECS);
+        }
+    }
+
+    fn post_in_background(&self, text: &str, details: Option<Value>, wanted: impl Fn(&WebhookConfig) -> bool) {
+        for webhook in self.matching(wanted) {
+            let client = self.client.clone();
+            let text = self.message(text);
+            let details = details.clone();
+            tokio::spawn(async move {
+                if let Err(e) = deliver(&client, &webhook, &text, details.as_ref()).await {
+                    eprintln!("Failed to post notification: {:#}", e);
+                }
+            });
+        }
+    }
+
+    fn matching(&self, wanted: impl Fn(&WebhookConfig) -> bool) -> Vec<WebhookConfig> {
+        self.webhooks.lock().unwrap().iter()
+            .filter(|webhook| wanted(webhook))
+            .cloned()
+            .collect()
+    }
+
+    /// The text prefixed with the session it is about, cut to what every service accepts
+    fn message(&self, text: &str) -> String {
+        let message = format!("[Hacksor {}] {}", self.session, text);
+        if message.chars().count() <= MAX_MESSAGE_CHARS {
+            return message;
+        }
+        let mut message: String = message.chars().take(MAX_MESSAGE_CHARS).collect();
+        message.push_str("...");
+        message
+    }
+}
+
+/// The configured webhooks, with the `[alerts]` webhook_url as a generic one
+/// for findings at the alert threshold, so every message goes out one way
+pub fn configured_webhooks(config: &Config) -> Vec<WebhookConfig> {
+    let mut webhooks = config.webhooks.clone();
+    if let (true, Some(url)) = (config.alerts.enabled, &config.alerts.webhook_url) {
+        webhooks.push(WebhookConfig {
+            kind: WebhookKind::Generic,
+            url: Some(url.clone()),
+            bot_token: None,
+            chat_id: None,
+            commands: false,
+            min_command_seconds: 0,
+            threshold: config.alerts.threshold.clone(),
+            session_summary: false,
+        });
+    }
+    webhooks
+}
+
+async fn deliver(client: &reqwest::Client, webhook: &WebhookConfig, text: &str, details: Option<&Value>) -> Result<()> {
+    let (url, payload) = request(webhook, text, details)?;
+    let response = client.post(&url)
+        .json(&payload)
+        .send()
+        .await
+        // Telegram's URL carries the bot token
+        .map_err(|e| anyhow!("Failed to reach the {:?} webhook: {}", webhook.kind, e.without_url()))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow!("{:?} webhook rejected the message ({}): {}", webhook.kind, status, body));
+    }
+    Ok(())
+}
+
+/// Where to post and what, in each service's format
+fn request(webhook: &WebhookConfig, text: &str, details: Option<&Value>) -> Result<(String, Value)> {
+    match webhook.kind {
+        WebhookKind::Slack | WebhookKind::Discord | WebhookKind::Generic => {
+            let url = webhook.url.clone()
+                .context(format!("The {:?} webhook has no url", webhook.kind))?;
+            let payload = match (&webhook.kind, details) {
+                (WebhookKind::Discord, _) => json!({ "content": text }),
+                (WebhookKind::Generic, Some(Value::Object(details))) => {
+                    let mut payload = details.clone();
+                    payload.insert("text".to_string(), json!(text));
+                    Value::Object(payload)
+                },
+                _ => json!({ "text": text }),
+            };
+            Ok((url, payload))
+        },
+        WebhookKind::Telegram => {
+            let token = webhook.bot_token.clone()
+                .or_else(|| env::var("TELEGRAM_BOT_TOKEN").ok())
+                .context("No Telegram bot token: set bot_token on the webhook or the TELEGRAM_BOT_TOKEN environment variable")?;
+            let chat_id = webhook.chat_id.as_ref()
+                .context("The Telegram webhook has no chat_id")?;
+            Ok((
+                format!("https://api.telegram.org/bot{}/sendMessage", token),
+                json!({ "chat_id": chat_id, "text": text }),
+            ))
+        },
+    }
+}
@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use anyhow::{Result, Context, anyhow};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::config::NvdConfig;
+use super::cve_db::CveMatch;
+use super::cvss::CvssScore;
+
+pub const API_URL: &str = "https://services.nvd.nist.gov/rest/json/cves/2.0";
+
+/// NVD's public rate limits: 5 requests per 30 seconds without a key, 50 with one
+const INTERVAL_WITHOUT_KEY: Duration = Duration::from_secs(6);
+const INTERVAL_WITH_KEY: Duration = Duration::from_millis(600);
+
+/// References kept per CVE, tagged ones (exploits, patches, advisories) first
+const MAX_REFERENCES: usize = 5;
+
+/// Looks CVEs up on the NVD 2.0 API, keeping each record on disk so a CVE
+/// that keeps appearing in output is only fetched once per `cache_days`
+#[derive(Clone)]
+pub struct NvdClient {
+    api_key: Option<String>,
+    cache_dir: PathBuf,
+    cache_ttl: Duration,
+    client: reqwest::Client,
+    /// Records looked up this run, by CVE ID
+    fetched: Arc<Mutex<HashMap<String, CveDetails>>>,
+    /// When the last request was sent; held across the wait so requests queue up
+    last_request: Arc<tokio::sync::Mutex<Option<Instant>>>,
+}
+
+/// What NVD knows about a CVE
+#[derive(Debug, Clone)]
+pub struct CveDetails {
+    pub id: String,
+    pub description: String,
+    pub published: Option<String>,
+    pub cvss: Option<CvssScore>,
+    pub cwes: Vec<String>,
+    pub references: Vec<CveReference>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CveReference {
+    pub url: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// The `cve` object of an NVD 2.0 response, as cached
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NvdCve {
+    id: String,
+    #[serde(default)]
+    published: Option<String>,
+    #[serde(default)]
+    descriptions: Vec<LangString>,
+    #[serde(default)]
+    metrics: NvdMetrics,
+    #[serde(default)]
+    weaknesses: Vec<NvdWeakness>,
+    #[serde(default)]
+    references: Vec<CveReference>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LangString {
+    lang: String,
+    value: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NvdMetrics {
+    #[serde(default)]
+    cvss_metric_v31: Vec<NvdCvssMetric>,
+    #[serde(default)]
+    cvss_metric_v30: Vec<NvdCvssMetric>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NvdCvssMetric {
+    /// `Primary` for NVD's own assessment, `Secondary` for the CNA's
+    #[serde(rename = "type", default)]
+    kind: String,
+    cvss_data: NvdCvssData,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NvdCvssData {
+    vector_string: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct NvdWeakness {
+    #[serde(default)]
+    description: Vec<LangString>,
+}
+
+impl NvdClient {
+    /// A client caching records under `cache_dir`
+    pub fn new(config: &NvdConfig, cache_dir: &Path) -> Result<Self> {
+        let api_key = config.api_key.clone()
+            .or_else(|| env::var("NVD_API_KEY").ok())
+            .filter(|key| !key.is_empty());
+
+        Ok(Self {
+            api_key,
+            cache_dir: cache_dir.to_path_buf(),
+            cache_ttl: Duration::from_secs(config.cache_days * 24 * 60 * 60),
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(config.timeout_secs))
+                .build()?,
+            fetched: Arc::new(Mutex::new(HashMap::new())),
+            last_request: Arc::new(tokio::sync::Mutex::new(None)),
+        })
+    }
+
+    /// NVD's record of a CVE, from this run, the disk cache or the API
+    pub async fn lookup(&self, id: &str) -> Result<CveDetails> {
+        let id = id.to_uppercase();
+        if let Some(details) = self.fetched.lock().unwrap().get(&id) {
+            return Ok(details.clone());
+        }
+
+        let cve = match self.cached(&id) {
+            Some(cve) => cve,
+            None => {
+                let cve = self.fetch(&id).await?;
+                if let Err(e) = self.store(&id, &cve) {
+                    eprintln!("Failed to cache {}: {:#}", id, e);
+                }
+                cve
+            },
+        };
+
+        let details = CveDetails::from_nvd(serde_json::from_value(cve).context(format!("Unexpected NVD record for {}", id))?);
+        self.fetched.lock().unwrap().insert(id, details.clone());
+        Ok(details)
+    }
+
+    async fn fetch(&self, id: &str) -> Result<Value> {
+        // Wait out the rate limit, holding the lock so concurrent lookups queue behind this one
+        let mut last_request = self.last_request.lock().await;
+        let interval = if self.api_key.is_some() { INTERVAL_WITH_KEY } else { INTERVAL_WITHOUT_KEY };
+        if let Some(elapsed) = last_request.map(|sent| sent.elapsed()) {
+            if elapsed < interval {
+                tokio::time::sleep(interval - elapsed).await;
+            }
+        }
+        *last_request = Some(Instant::now());
+
+        let mut request = self.client.get(API_URL).query(&[("cveId", id)]);
+        if let Some(key) = &self.api_key {
+            request = request.header("apiKey", key);
+        }
+        let response = request.send()
+            .await
+            .map_err(|e| anyhow!("Failed to reach NVD: {}", e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            // NVD explains rejections in a header rather than the body
+            let message = response.headers().get("message").and_then(|message| message.to_str().ok()).map(String::from);
+            return Err(match message {
+                Some(message) => anyhow!("{} ({})", message, status),
+                None => anyhow!("{}", status),
+            });
+        }
+
+        let mut body: Value = response.json().await.context("NVD returned an unexpected response")?;
+        match body["vulnerabilities"][0]["cve"].take() {
+            Value::Null => Err(anyhow!("NVD has no record of {}", id)),
+            cve => Ok(cve),
+        }
+    }
+
+    /// The cached record, unless it is older than `cache_days`
+    fn cached(&self, id: &str) -> Option<Value> {
+        let path = self.cache_path(id);
+        let age = fs::metadata(&path).and_then(|meta| meta.modified()).ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())?;
+        if age > self.cache_ttl {
+            return None;
+        }
+        fs::read_to_string(&path).ok().and_then(|content| serde_json::from_str(&content).ok())
+    }
+
+    fn store(&self, id: &str, cve: &Value) -> Result<()> {
+        fs::create_dir_all(&self.cache_dir)
+            .context(format!("Failed to create NVD cache directory {:?}", self.cache_dir))?;
+        fs::write(self.cache_path(id), serde_json::to_string(cve)?)?;
+        Ok(())
+    }
+
+    fn cache_path(&self, id: &str) -> PathBuf {
+        self.cache_dir.join(format!("{}.json", id))
+    }
+}
+
+impl CveDetails {
+    fn from_nvd(cve: NvdCve) -> Self {
+        let english = |strings: &[LangString]| strings.iter()
+            .find(|string| string.lang == "en")
+            .map(|string| string.value.clone());
+
+        // NVD's own v3.1 assessment, then the CNA's, then v3.0
+        let metrics = &cve.metrics;
+        let vector = metrics.cvss_metric_v31.iter().find(|metric| metric.kind == "Primary")
+            .or_else(|| metrics.cvss_metric_v31.first())
+            .or_else(|| metrics.cvss_metric_v30.iter().find(|metric| metric.kind == "Primary"))
+            .or_else(|| metrics.cvss_metric_v30.first())
+            .map(|metric| metric.cvss_data.vector_string.as_str());
+
+        let mut cwes: Vec<String> = cve.weaknesses.iter()
+            .filter_map(|weakness| english(&weakness.description))
+            .filter(|cwe| cwe.starts_with("CWE-"))
+            .collect();
+        cwes.dedup();
+
+        let mut references = cve.references;
+        references.sort_by_key(|reference| reference.tags.is_empty());
+
+        Self {
+            description: english(&cve.descriptions).unwrap_or_default(),
+            published: cve.published,
+            cvss: vector.and_then(|vector| CvssScore::from_vector(vector).ok()),
+            cwes,
+            references,
+            id: cve.id,
+        }
+    }
+
+    /// The details as a finding's related CVE
+    pub fn to_match(&self) -> CveMatch {
+        CveMatch {
+            id: self.id.clone(),
+            cvss: self.cvss.clone(),
+            summary: self.description.clone(),
+            references: self.references.iter().take(MAX_REFERENCES).map(|reference| reference.url.clone()).collect(),
+        }
+    }
+
+    /// Everything known about the CVE, as the result of a follow-up action
+    pub fn report(&self) -> String {
+        let mut report = format!("{}\n", self.id);
+        if let Some(published) = &self.published {
+            report.push_str(&format!("Published: {}\n", published));
+        }
+        match &self.cvss {
+            Some(cvss) => report.push_str(&format!("CVSS: {:.1} {}\n", cvss.score, cvss.vector)),
+            None => report.push_str("CVSS: not scored yet\n"),
+        }
+        if !self.cwes.is_empty() {
+            report.push_str(&format!("Weaknesses: {}\n", self.cwes.join(", ")));
+        }
+        report.push_str(&format!("\n{}\n", self.description));
+        if !self.references.is_empty() {
+            report.push_str("\nReferences:\n");
+            for reference in &self.references {
+                if reference.tags.is_empty() {
+                    report.push_str(&format!("{}\n", reference.url));
+                } else {
+                    report.push_str(&format!("{} ({})\n", reference.url, reference.tags.join(", ")));
+                }
+            }
+        }
+        report
+    }
+}
+
+/// Distinct CVE IDs mentioned in text, in the order they first appear
+pub fn cve_ids(text: &str) -> Vec<String> {
+    let pattern = Regex::new(r"(?i)\bCVE-\d{4}-\d{4,7}\b").unwrap();
+    let mut ids: Vec<String> = Vec::new();
+    for id in pattern.find_iter(text).map(|m| m.as_str().to_uppercase()) {
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    ids
+}
@@ -1,38 +1,214 @@
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
 use regex::Regex;
 use anyhow::Result;
 use tokio::sync::mpsc;
-use super::command_monitor::{CommandOutput, FindingSeverity, CommandMonitor, create_finding, CommandType};
+use super::command_monitor::{CommandOutput, CommandStatus, Confidence, FindingSeverity, CommandMonitor, SecurityFinding, create_finding, CommandType};
 use std::time::{Duration, Instant};
+use std::fs;
+use std::path::Path;
+use super::analyzer_rules::CompiledRule;
+use crate::utils::extract_target_from_command;
+use super::cve_db::CveDatabase;
+use super::nvd::{self, NvdClient};
+use super::searchsploit;
+use super::tech_inventory;
+use super::throttle::tool_name;
+use super::wayback::{self, UrlScope};
+use super::brute_force::{BruteForcePatterns, BruteForceStats};
+
+/// Analyzed lines kept in memory per command before they are spilled
+const MAX_BUFFERED_LINES: usize = 5000;
+
+/// Number of CVE candidates attached to a version disclosure
+const MAX_RELATED_CVES: usize = 5;
+
+/// Patterns for testssl.sh and sslscan output
+struct TlsPatterns {
+    // testssl.sh: " TLS 1.1    offered (deprecated)"; sslscan: "TLSv1.1   enabled"
+    deprecated_protocol: Regex,
+    // sslscan: "Not valid after:  Jan  1 00:00:00 2020 GMT"
+    not_valid_after: Regex,
+    // testssl.sh: "Certificate Validity (UTC)   expired!"
+    expired: Regex,
+    // sslscan: "Accepted  TLSv1.2  128 bits  RC4-SHA"
+    accepted_cipher: Regex,
+    // testssl.sh: " NULL ciphers (no encryption)   offered (NOT ok)"
+    weak_cipher_category: Regex,
+    // testssl.sh: " Strict Transport Security    not offered"
+    missing_hsts: Regex,
+}
+
+impl TlsPatterns {
+    fn new() -> Self {
+        Self {
+            deprecated_protocol: Regex::new(r"(?i)^\s*(SSLv2|SSLv3|TLS\s?1(?:\.0)?|TLSv1(?:\.0)?|TLS\s?1\.1|TLSv1\.1)\s+(offered|enabled)").unwrap(),
+            not_valid_after: Regex::new(r"(?i)Not valid after:\s+(.+?)\s*$").unwrap(),
+            expired: Regex::new(r"(?i)Certificate Validity.*\bexpired").unwrap(),
+            accepted_cipher: Regex::new(r"(?i)^\s*(?:Accepted|Preferred)\s+(\S+)\s+\d+\s+bits\s+(\S*(?:RC4|DES|NULL|EXP|MD5|ANON)\S*)").unwrap(),
+            weak_cipher_category: Regex::new(r"(?i)^\s*(NULL ciphers|Anonymous NULL Ciphers|Export ciphers|LOW: 64 Bit \+ DES|Triple DES Ciphers|RC4)[^\n]*?(offered \(NOT ok\)|VULNERABLE)").unwrap(),
+            missing_hsts: Regex::new(r"(?i)Strict Transport Security\s+not offered").unwrap(),
+        }
+    }
+}
+
+/// Patterns for dig and dnsrecon output
+struct DnsPatterns {
+    // dig: ";; XFR size: 42 records (messages 1, bytes 1234)"
+    xfr_size: Regex,
+    // dnsrecon: "[+] Zone Transfer was successful!!"
+    dnsrecon_axfr_success: Regex,
+    // dnsrecon: "[-] Wildcard resolution is enabled on this domain"
+    dnsrecon_wildcard: Regex,
+    // dnsrecon: "[*]     TXT example.com v=spf1 include:_spf.google.com ~all"
+    dnsrecon_txt: Regex,
+    // dig: ";example.com.			IN	TXT"
+    dig_question: Regex,
+    // dig: "example.com.		300	IN	TXT	\"v=spf1 -all\""
+    dig_record: Regex,
+}
+
+impl DnsPatterns {
+    fn new() -> Self {
+        Self {
+            xfr_size: Regex::new(r";; XFR size: (\d+) records").unwrap(),
+            dnsrecon_axfr_success: Regex::new(r"(?i)Zone Transfer was successful").unwrap(),
+            dnsrecon_wildcard: Regex::new(r"(?i)Wildcard resolution is enabled").unwrap(),
+            dnsrecon_txt: Regex::new(r"^\[[*+]\]\s+TXT\s+(\S+)\s+(.+)$").unwrap(),
+            dig_question: Regex::new(r"(?m)^;(\S+)\s+IN\s+(\S+)").unwrap(),
+            dig_record: Regex::new(r"^(\S+)\s+\d+\s+IN\s+(\S+)\s+(.+)$").unwrap(),
+        }
+    }
+}
+
+/// Patterns for redirect and file inclusion issues reported by web scanners
+struct WebVulnPatterns {
+    open_redirect: Regex,
+    // Contents of well-known files returned through a traversal payload
+    traversal_response: Regex,
+    // A traversal payload sent as a parameter value: `?file=../../etc/passwd`
+    traversal_parameter: Regex,
+    // A scanner naming the issue it found; payloads alone are in every fuzzing run
+    traversal_claim: Regex,
+    php_wrapper: Regex,
+    remote_inclusion: Regex,
+    // A remote URL sent as a parameter value: `?page=http://attacker/shell.txt`
+    remote_parameter: Regex,
+}
+
+impl WebVulnPatterns {
+    fn new() -> Self {
+        Self {
+            open_redirect: Regex::new(r"(?i)open[\s_-]?redirect|Location:\s*(?:https?:)?//(?:evil|attacker)\.|redirect(?:s|ed)? to (?:https?:)?//(?:evil|attacker)\.").unwrap(),
+            traversal_response: Regex::new(r"root:[x*]:0:0:|\[boot loader\]|; for 16-bit app support").unwrap(),
+            traversal_parameter: Regex::new(r"(?i)[?&][^=\s&]+=[^\s&]*?(?:(?:\.\./){2,}|(?:\.\.\\){2,}|(?:%2e%2e(?:%2f|/)){2,}|(?:\.\.%2f){2,})").unwrap(),
+            traversal_claim: Regex::new(r"(?i)path[\s_-]?traversal|directory[\s_-]?traversal|local[\s_-]?file[\s_-]?inclusion|\blfi\b").unwrap(),
+            php_wrapper: Regex::new(r"(?i)\b(?:php://(?:filter|input)|data://text/plain|expect://|zip://|phar://)").unwrap(),
+            remote_inclusion: Regex::new(r"(?i)remote[\s_-]?file[\s_-]?inclusion").unwrap(),
+            remote_parameter: Regex::new(r"(?i)[?&][^=\s&]+=(?:https?|ftp)://").unwrap(),
+        }
+    }
+}
+
+/// Patterns for email addresses and credential pairs in tool output or dumps
+struct LeakPatterns {
+    email: Regex,
+    // hydra/medusa: "[22][ssh] host: 10.0.0.5   login: root   password: toor"
+    cracked_login: Regex,
+    // "username: admin password: hunter2", "user=admin pass=hunter2"
+    key_value_pair: Regex,
+    // combo lists: "jane@example.com:hunter2"
+    combo_line: Regex,
+}
+
+impl LeakPatterns {
+    fn new() -> Self {
+        Self {
+            email: Regex::new(r"[A-Za-z0-9._%+-]+@([A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,})").unwrap(),
+            cracked_login: Regex::new(r"(?i)host:\s*(\S+)\s+login:\s*(\S+)\s+password:\s*(\S+)").unwrap(),
+            key_value_pair: Regex::new(r"(?i)\b(?:user(?:name)?|login)\s*[:=]\s*(\S+)[\s,;]+(?:pass(?:word)?|pwd)\s*[:=]\s*(\S+)").unwrap(),
+            combo_line: Regex::new(r"^([A-Za-z0-9._%+-]+@([A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,})):(\S+)$").unwrap(),
+        }
+    }
+}
+
+/// Banners identifying admin interfaces that commonly ship with default credentials:
+/// (pattern, interface name, path of the authenticated endpoint)
+fn admin_interface_patterns() -> Vec<(Regex, &'static str, &'static str)> {
+    vec![
+        (Regex::new(r"(?i)/manager/html|Tomcat Web Application Manager|Apache[\s-]Tomcat|Apache-Coyote").unwrap(), "Tomcat Manager", "/manager/html"),
+        (Regex::new(r"(?i)X-Jenkins|\[Jenkins\]|Jenkins ver\.|Jenkins-Crumb").unwrap(), "Jenkins", "/api/json"),
+        (Regex::new(r#"(?i)RouterOS|MikroTik|DD-WRT|OpenWrt|TP-LINK|NETGEAR|Linksys|RomPager|GoAhead-Webs|Basic realm="[^"]*(?:router|gateway|modem)"#).unwrap(), "Router", "/"),
+    ]
+}
+
+/// Email addresses and credential pairs seen for one target during the session
+#[derive(Default)]
+struct LeakedData {
+    emails: HashSet<String>,
+    /// Usernames by a fingerprint of the whole credential pair, so a new
+    /// password for a known account still counts; passwords are never retained
+    credentials: HashMap<u64, String>,
+}
+
+/// Fingerprint of a credential pair, to tell pairs apart without keeping them
+fn credential_fingerprint(username: &str, password: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (username, password).hash(&mut hasher);
+    hasher.finish()
+}
 
 /// Analyzes command output to detect security findings and patterns
 pub struct OutputAnalyzer {
     monitor: Arc<CommandMonitor>,
     output_rx: mpsc::Receiver<CommandOutput>,
     buffer: HashMap<String, Vec<String>>,
-    port_scan_patterns: Vec<Regex>,
+    /// nmap's `80/tcp open http` lines: the port and service
+    open_port_pattern: Regex,
     vulnerability_patterns: Vec<Regex>,
     path_discovery_patterns: Vec<Regex>,
     subdomain_patterns: Vec<Regex>,
+    custom_rules: Vec<CompiledRule>,
+    cve_db: CveDatabase,
+    /// Looks up the CVEs findings mention, when enabled
+    nvd: Option<NvdClient>,
+    tls_patterns: TlsPatterns,
+    dns_patterns: DnsPatterns,
+    web_vuln_patterns: WebVulnPatterns,
+    leak_patterns: LeakPatterns,
+    leaks: Mutex<HashMap<String, LeakedData>>,
+    admin_interface_patterns: Vec<(Regex, &'static str, &'static str)>,
+    url_pattern: Regex,
+    brute_force_patterns: BruteForcePatterns,
+    /// Running statistics for brute-force commands
+    brute_force: Mutex<HashMap<String, BruteForceStats>>,
+    /// The host each nmap run's output was last reporting on, carried between passes
+    nmap_hosts: Mutex<HashMap<String, Option<String>>>,
     last_analyzed: HashMap<String, Instant>,
+    /// Number of buffered lines already analyzed, per command
+    analyzed_lines: HashMap<String, usize>,
+    /// Number of lines dropped from memory per command; they remain in the command's output file
+    spilled_lines: HashMap<String, usize>,
+    /// Findings already emitted, so re-analysis never reports the same thing twice
+    seen_findings: Mutex<HashSet<String>>,
+    /// Ports, subdomains etc. already reported, per command and kind
+    discovered: Mutex<HashMap<(String, &'static str), Vec<String>>>,
     running: bool,
 }
 
 impl OutputAnalyzer {
     pub fn new(monitor: Arc<CommandMonitor>, output_rx: mpsc::Receiver<CommandOutput>) -> Self {
         // Compile regex patterns for different types of findings
-        let port_scan_patterns = vec![
-            // Nmap open port patterns
-            Regex::new(r"(\d+)/(?:tcp|udp)\s+open\s+(\S+)").unwrap(),
-            Regex::new(r"PORT\s+STATE\s+SERVICE(?:\s+VERSION)?").unwrap(),
-        ];
+        // Nmap open port pattern
+        let open_port_pattern = Regex::new(r"(\d+)/(?:tcp|udp)\s+open\s+(\S+)").unwrap();
         
         let vulnerability_patterns = vec![
             // General vulnerability patterns
             Regex::new(r"(?i)vulnerable|vulnerability|exploit|deprecated").unwrap(),
             // Version disclosure patterns
-            Regex::new(r"(?i)(apache|nginx|iis|tomcat|php|mysql|postgresql|mssql)(?:/| |-)(\d+\.\d+\.?\d*)").unwrap(),
+            Regex::new(r"(?i)(apache|nginx|iis|tomcat|php|mysql|postgresql|mssql|openssh)(?:/| |-|_)(\d+\.\d+(?:\.\d+)?(?:p\d+)?)").unwrap(),
             // CVE patterns
             Regex::new(r"(?i)CVE-\d{4}-\d{4,7}").unwrap(),
             // XSS patterns
@@ -44,7 +220,10 @@ impl OutputAnalyzer {
         let path_discovery_patterns = vec![
             // Directory/file patterns
             Regex::new(r"(?i)Status: 200\s+Size:\s+\d+\s+Path:\s+(\S+)").unwrap(),
-            Regex::new(r"(?i)\(Status: 200\)\s+\[Size: \d+\]").unwrap(),
+            // gobuster: `/images               (Status: 200) [Size: 1234]`, full URLs with `-e`
+            Regex::new(r"(?i)^\s*(/\S*|https?://\S+)\s+\(Status: 200\)\s+\[Size: \d+\]").unwrap(),
+            // ffuf: `images                  [Status: 200, Size: 1234, Words: 5, Lines: 2]`
+            Regex::new(r"(?i)^\s*(\S+)\s+\[Status: 200,\s+Size: \d+").unwrap(),
             // Admin/config paths
             Regex::new(r"(?i)/(?:admin|config|setup|install|backup|wp-admin|phpMyAdmin)(?:/|\s|$)").unwrap(),
         ];
@@ -59,15 +238,47 @@ impl OutputAnalyzer {
             monitor,
             output_rx,
             buffer: HashMap::new(),
-            port_scan_patterns,
+            open_port_pattern,
             vulnerability_patterns,
             path_discovery_patterns,
             subdomain_patterns,
+            custom_rules: Vec::new(),
+            cve_db: CveDatabase::default(),
+            nvd: None,
+            tls_patterns: TlsPatterns::new(),
+            dns_patterns: DnsPatterns::new(),
+            web_vuln_patterns: WebVulnPatterns::new(),
+            leak_patterns: LeakPatterns::new(),
+            leaks: Mutex::new(HashMap::new()),
+            admin_interface_patterns: admin_interface_patterns(),
+            url_pattern: Regex::new(r"https?://[^\s/\]\[,]+").unwrap(),
+            brute_force_patterns: BruteForcePatterns::new(),
+            brute_force: Mutex::new(HashMap::new()),
+            nmap_hosts: Mutex::new(HashMap::new()),
             last_analyzed: HashMap::new(),
+            analyzed_lines: HashMap::new(),
+            spilled_lines: HashMap::new(),
+            seen_findings: Mutex::new(HashSet::new()),
+            discovered: Mutex::new(HashMap::new()),
             running: false,
         }
     }
     
+    /// Set user-defined detection rules loaded from rule files
+    pub fn set_custom_rules(&mut self, rules: Vec<CompiledRule>) {
+        self.custom_rules = rules;
+    }
+    
+    /// Set the CVE dataset used to correlate version disclosures
+    pub fn set_cve_database(&mut self, cve_db: CveDatabase) {
+        self.cve_db = cve_db;
+    }
+    
+    /// Set the NVD client used to describe the CVEs that appear in output
+    pub fn set_nvd_client(&mut self, nvd: NvdClient) {
+        self.nvd = Some(nvd);
+    }
+    
     /// Start analyzing command output
     pub async fn start(&mut self) -> Result<()> {
         if self.running {
@@ -77,21 +288,40 @@ impl OutputAnalyzer {
         self.running = true;
         
         // Main analysis loop
-        while let Some(output) = self.output_rx.recv().await {
+        loop {
+            // Wake up periodically so trailing output is analyzed once a command goes quiet
+            let output = match tokio::time::timeout(Duration::from_secs(1), self.output_rx.recv()).await {
+                Ok(Some(output)) => output,
+                Ok(None) => break,
+                Err(_) => {
+                    let pending: Vec<String> = self.buffer.iter()
+                        .filter(|(id, lines)| self.analyzed_lines.get(*id).copied().unwrap_or(0) < lines.len())
+                        .map(|(id, _)| id.clone())
+                        .collect();
+                    for command_id in pending {
+                        self.analyze_new_output(&command_id).await?;
+                    }
+                    continue;
+                }
+            };
+            
             // Add output to buffer
             let buffer = self.buffer.entry(output.command_id.clone()).or_insert_with(Vec::new);
             buffer.push(output.line.clone());
             
+            let buffer_full = buffer.len() >= MAX_BUFFERED_LINES;
+            
             // Check if it's time to analyze this command's output
-            let should_analyze = if let Some(last_analyzed) = self.last_analyzed.get(&output.command_id) {
+            let should_analyze = if buffer_full {
+                true
+            } else if let Some(last_analyzed) = self.last_analyzed.get(&output.command_id) {
                 last_analyzed.elapsed() > Duration::from_secs(5) // Only analyze every 5 seconds
             } else {
                 true
             };
             
             if should_analyze {
-                self.analyze_command_output(&output.command_id).await?;
-                self.last_analyzed.insert(output.command_id.clone(), Instant::now());
+                self.analyze_new_output(&output.command_id).await?;
             }
         }
         
@@ -99,6 +329,30 @@ impl OutputAnalyzer {
         Ok(())
     }
     
+    /// Analyze the lines received since the last pass and advance the high-water mark
+    async fn analyze_new_output(&mut self, command_id: &str) -> Result<()> {
+        self.analyze_command_output(command_id).await?;
+        
+        let analyzed = self.buffer.get(command_id).map_or(0, |lines| lines.len());
+        self.analyzed_lines.insert(command_id.to_string(), analyzed);
+        self.last_analyzed.insert(command_id.to_string(), Instant::now());
+        
+        // Everything analyzed is already in the command's output file, so drop it from
+        // memory once the buffer is large or the command has finished
+        let finished = self.monitor.get_command(command_id)
+            .is_none_or(|cmd| !matches!(cmd.status, CommandStatus::Running));
+        if analyzed >= MAX_BUFFERED_LINES || (finished && analyzed > 0) {
+            if let Some(buffer) = self.buffer.get_mut(command_id) {
+                buffer.clear();
+                buffer.shrink_to_fit();
+            }
+            *self.spilled_lines.entry(command_id.to_string()).or_insert(0) += analyzed;
+            self.analyzed_lines.insert(command_id.to_string(), 0);
+        }
+        
+        Ok(())
+    }
+    
     /// Analyze output of a specific command
     async fn analyze_command_output(&self, command_id: &str) -> Result<()> {
         // Get command information
@@ -113,13 +367,80 @@ impl OutputAnalyzer {
             None => return Ok(()),
         };
         
-        // Skip if buffer is empty
-        if buffer.is_empty() {
+        // Only lines after the high-water mark are new
+        let start = self.analyzed_lines.get(command_id).copied().unwrap_or(0).min(buffer.len());
+        if start == buffer.len() {
             return Ok(());
         }
         
-        // Create analysis context with recent output
-        let context = buffer.join("\n");
+        // Create analysis context with the new output
+        let context = buffer[start..].join("\n");
+        
+        // User-defined rules and triggers apply to every command type
+        self.analyze_custom_rules(&context, command_id).await?;
+        
+        // TLS, DNS and exploit search analysis correlate lines across the whole run.
+        // These tools produce short output, so they still see the full buffer.
+        let tool = tool_name(&command.command);
+        let command_lower = command.command.to_lowercase();
+        let is_tls_scan = command_lower.contains("testssl") || command_lower.contains("sslscan");
+        let is_dns_query = matches!(tool, "dig" | "dnsrecon");
+        let is_exploit_search = tool == "searchsploit";
+        let needs_full_output = is_tls_scan || is_dns_query || is_exploit_search;
+        let spilled = self.spilled_lines.get(command_id).copied().unwrap_or(0);
+        let full_context = if needs_full_output && spilled > 0 {
+            read_output_file(&command.output_file).unwrap_or_else(|_| context.clone())
+        } else if needs_full_output && start > 0 {
+            buffer.join("\n")
+        } else {
+            context.clone()
+        };
+        
+        // TLS scanners get dedicated analysis regardless of command type
+        if is_tls_scan {
+            self.analyze_tls(&full_context, command_id).await?;
+        }
+        
+        // DNS tools get posture analysis regardless of command type
+        if is_dns_query {
+            self.analyze_dns(&command.command, &full_context, command_id).await?;
+        }
+        
+        // Banners from any tool can reveal admin interfaces
+        self.analyze_admin_interfaces(&command.command, &context, command_id).await?;
+        
+        // Emails and credentials can show up in any tool's output
+        self.analyze_leaks(&command.command, &context, command_id).await?;
+        
+        // Fingerprinting tools feed the session's technology inventory
+        self.record_technologies(&command.command, &context, command_id);
+        
+        // URL collectors feed the session's list of parameterized URLs
+        let is_url_collection = command_lower.split(|c: char| c == '|' || c.is_whitespace())
+            .any(|term| matches!(term, "waybackurls" | "gau"));
+        if is_url_collection {
+            self.analyze_collected_urls(&command.command, &context, command_id).await?;
+        }
+        
+        // Brute-forcers get hit rate, status code and wordlist coverage stats
+        if BruteForceStats::applies_to(tool) {
+            let tracked = self.brute_force.lock().unwrap().contains_key(command_id);
+            let started = if tracked { None } else { Some(BruteForceStats::for_command(&command.command).await) };
+            let stats = {
+                let mut brute_force = self.brute_force.lock().unwrap();
+                let stats = brute_force.entry(command_id.to_string())
+                    .or_insert_with(|| started.unwrap_or_default());
+                self.brute_force_patterns.update(stats, &context);
+                stats.clone()
+            };
+            self.monitor.set_brute_force_stats(command_id, stats);
+        }
+        
+        // Exploit searches (e.g. the follow-up to a version disclosure) are parsed as
+        // results; keyword matching would flag every line mentioning an exploit
+        if is_exploit_search {
+            return self.analyze_exploit_search(&full_context, command_id).await;
+        }
         
         // Different analysis based on command type
         match command.command_type {
@@ -131,6 +452,9 @@ impl OutputAnalyzer {
                 self.analyze_subdomains(&context, command_id).await?;
             },
             CommandType::Scanning => {
+                // Look for paths found by directory brute-forcing
+                self.analyze_paths(&context, command_id).await?;
+                
                 // Look for vulnerabilities
                 self.analyze_vulnerabilities(&context, command_id).await?;
             },
@@ -147,42 +471,104 @@ impl OutputAnalyzer {
         Ok(())
     }
     
+    /// Send a finding on unless it was already reported for the command or is suppressed
+    async fn report_finding(&self, mut finding: SecurityFinding) -> Result<()> {
+        let key = format!("{}\u{0}{}\u{0}{}\u{0}{}", finding.command_id, finding.title, finding.description, finding.raw_output);
+        if !self.seen_findings.lock().unwrap().insert(key) {
+            return Ok(());
+        }
+        
+        // Findings marked as false positives are not raised again
+        if self.monitor.is_suppressed(&finding) {
+            return Ok(());
+        }
+        
+        self.attach_cve_details(&mut finding).await;
+        self.monitor.add_finding(finding).await
+    }
+    
+    /// Replace the CVEs a finding mentions with NVD's description, CVSS vector
+    /// and references for them. A finding about CVEs without a score of its own
+    /// takes the highest of theirs
+    async fn attach_cve_details(&self, finding: &mut SecurityFinding) {
+        let Some(nvd) = &self.nvd else {
+            return;
+        };
+        
+        let mentioned = nvd::cve_ids(&format!("{}\n{}\n{}", finding.title, finding.description, finding.raw_output));
+        for id in mentioned.into_iter().take(MAX_RELATED_CVES) {
+            // The follow-up action for the finding reports lookups that fail
+            let Ok(details) = nvd.lookup(&id).await else {
+                continue;
+            };
+            let cve = details.to_match();
+            match finding.related_cves.iter_mut().find(|related| related.id == cve.id) {
+                Some(related) => *related = cve,
+                None => finding.related_cves.push(cve),
+            }
+        }
+        
+        if finding.cvss.is_none() && finding.title.contains("CVE") {
+            finding.cvss = finding.related_cves.iter()
+                .filter_map(|cve| cve.cvss.clone())
+                .max_by(|a, b| a.score.total_cmp(&b.score));
+        }
+    }
+    
+    /// Host targeted by a monitored command
+    fn command_host(&self, command_id: &str) -> Option<String> {
+        self.monitor.get_command(command_id)
+            .and_then(|command| extract_target_from_command(&command.command))
+    }
+    
+    /// Record items discovered by a command, returning the ones not seen before and all seen so far
+    fn track_discovered(&self, command_id: &str, kind: &'static str, items: Vec<String>) -> (Vec<String>, Vec<String>) {
+        let mut discovered = self.discovered.lock().unwrap();
+        let seen = discovered.entry((command_id.to_string(), kind)).or_default();
+        let mut new_items = Vec::new();
+        for item in items {
+            if !seen.contains(&item) {
+                seen.push(item.clone());
+                new_items.push(item);
+            }
+        }
+        (new_items, seen.clone())
+    }
+    
     /// Analyze port scanning output (nmap, etc.)
     async fn analyze_port_scan(&self, context: &str, command_id: &str) -> Result<()> {
         // Look for open ports
         let mut open_ports = Vec::new();
         
         for line in context.lines() {
-            for pattern in &self.port_scan_patterns {
-                if let Some(captures) = pattern.captures(line) {
-                    if captures.len() > 1 {
-                        if let Some(port) = captures.get(1) {
-                            let service = if captures.len() > 2 {
-                                captures.get(2).map_or("", |m| m.as_str())
-                            } else {
-                                ""
-                            };
-                            
-                            open_ports.push((port.as_str().to_string(), service.to_string()));
-                        }
-                    }
-                }
+            if let Some(captures) = self.open_port_pattern.captures(line) {
+                open_ports.push((captures[1].to_string(), captures[2].to_string()));
             }
         }
         
+        // Open ports become service assets of the scanned host
+        if let Some(host) = self.command_host(command_id) {
+            let assets = self.monitor.get_asset_inventory();
+            for (port, service) in &open_ports {
+                assets.record_service(&host, port, Some(service), command_id);
+            }
+        }
+        
+        // Only ports not already reported for this command are new
+        let (open_ports, all_ports) = self.track_discovered(command_id, "port", open_ports.iter()
+            .map(|(port, service)| {
+                if service.is_empty() {
+                    format!("Port {}", port)
+                } else {
+                    format!("Port {} ({})", port, service)
+                }
+            })
+            .collect());
+        
         // If we have open ports, generate a finding
         if !open_ports.is_empty() {
             // Create port list for description
-            let port_list = open_ports.iter()
-                .map(|(port, service)| {
-                    if service.is_empty() {
-                        format!("Port {}", port)
-                    } else {
-                        format!("Port {} ({})", port, service)
-                    }
-                })
-                .collect::<Vec<_>>()
-                .join(", ");
+            let port_list = open_ports.join(", ");
             
             // Create the finding
             let finding = create_finding(
@@ -191,14 +577,14 @@ impl OutputAnalyzer {
                 FindingSeverity::Info,
                 command_id,
                 context,
-            );
+            ).with_confidence(Confidence::Confirmed);
             
-            self.monitor.add_finding(finding).await?;
+            self.report_finding(finding).await?;
             
             // Update command summary
             self.monitor.update_command_summary(
                 command_id,
-                &format!("Detected {} open ports: {}", open_ports.len(), port_list),
+                &format!("Detected {} open ports: {}", all_ports.len(), all_ports.join(", ")),
             )?;
         }
         
@@ -224,7 +610,10 @@ impl OutputAnalyzer {
                                 format!("{} Version Disclosure", software),
                                 format!("Detected {} version {}", software, version),
                                 FindingSeverity::Low,
+                                Confidence::Confirmed,
                                 line.to_string(),
+                                // Known CVEs for the disclosed version
+                                self.cve_db.lookup(software, version, MAX_RELATED_CVES),
                             ));
                         }
                     } 
@@ -237,7 +626,9 @@ impl OutputAnalyzer {
                                 format!("Potential CVE Detected"),
                                 format!("Found reference to {} in output", cve.as_str()),
                                 FindingSeverity::High,
+                                Confidence::Tentative,
                                 line.to_string(),
+                                Vec::new(),
                             ));
                         }
                     }
@@ -249,7 +640,9 @@ impl OutputAnalyzer {
                             format!("Potential Vulnerability Detected"),
                             format!("Detected potential vulnerability indicator in output"),
                             FindingSeverity::Medium,
+                            Confidence::Tentative,
                             line.to_string(),
+                            Vec::new(),
                         ));
                     }
                     // Check for XSS
@@ -259,7 +652,9 @@ impl OutputAnalyzer {
                             format!("Potential XSS Vulnerability"),
                             format!("Detected potential XSS vulnerability indicator"),
                             FindingSeverity::High,
+                            Confidence::Tentative,
                             line.to_string(),
+                            Vec::new(),
                         ));
                     }
                     // Check for SQL injection
@@ -268,24 +663,105 @@ impl OutputAnalyzer {
                             format!("Potential SQL Injection Vulnerability"),
                             format!("Detected potential SQL injection vulnerability indicator"),
                             FindingSeverity::High,
+                            Confidence::Tentative,
                             line.to_string(),
+                            Vec::new(),
                         ));
                     }
                 }
             }
         }
         
+        // Look for open redirects and file inclusion
+        let web = &self.web_vuln_patterns;
+        for line in context.lines() {
+            if web.open_redirect.is_match(line) {
+                findings.push((
+                    "Potential Open Redirect".to_string(),
+                    "Detected an open redirect indicator in scanner output".to_string(),
+                    FindingSeverity::Medium,
+                    Confidence::Tentative,
+                    line.to_string(),
+                    Vec::new(),
+                ));
+            }
+            
+            if web.traversal_response.is_match(line) {
+                findings.push((
+                    "Potential Local File Inclusion".to_string(),
+                    "Output contains system file contents returned through a traversal payload".to_string(),
+                    FindingSeverity::High,
+                    Confidence::Confirmed,
+                    line.to_string(),
+                    Vec::new(),
+                ));
+            } else if web.traversal_claim.is_match(line) && web.traversal_parameter.is_match(line) {
+                findings.push((
+                    "Potential Path Traversal".to_string(),
+                    "A scanner reported directory traversal through a parameter".to_string(),
+                    FindingSeverity::Medium,
+                    Confidence::Tentative,
+                    line.to_string(),
+                    Vec::new(),
+                ));
+            }
+            
+            if web.remote_inclusion.is_match(line) && web.remote_parameter.is_match(line) {
+                findings.push((
+                    "Potential Remote File Inclusion".to_string(),
+                    "A scanner reported remote file inclusion through a parameter given a remote URL".to_string(),
+                    FindingSeverity::High,
+                    Confidence::Tentative,
+                    line.to_string(),
+                    Vec::new(),
+                ));
+            } else if let Some(wrapper) = web.php_wrapper.find(line) {
+                findings.push((
+                    "Potential File Inclusion via PHP Wrapper".to_string(),
+                    format!("Detected use of the {} wrapper in scanner output", wrapper.as_str()),
+                    FindingSeverity::High,
+                    Confidence::Tentative,
+                    line.to_string(),
+                    Vec::new(),
+                ));
+            }
+        }
+        
         // Add all findings
-        for (title, description, severity, raw_output) in findings {
-            let finding = create_finding(
+        for (title, description, severity, confidence, raw_output, related_cves) in findings {
+            let mut finding = create_finding(
                 &title,
                 &description,
                 severity,
                 command_id,
                 &raw_output,
-            );
+            ).with_confidence(confidence);
+            finding.related_cves = related_cves;
             
-            self.monitor.add_finding(finding).await?;
+            self.report_finding(finding).await?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Report exploits from `searchsploit -j` output that reference CVEs
+    async fn analyze_exploit_search(&self, context: &str, command_id: &str) -> Result<()> {
+        // The JSON is only complete once the search has finished printing
+        for exploit in searchsploit::parse_results(context) {
+            if exploit.cves.is_empty() {
+                continue;
+            }
+            
+            let cves = exploit.cves.join(", ");
+            let finding = create_finding(
+                &format!("Public Exploit Available for {}", cves),
+                &format!("Exploit-DB {} \"{}\" exploits {}", exploit.edb_id, exploit.title, cves),
+                FindingSeverity::High,
+                command_id,
+                &serde_json::to_string(&exploit)?,
+            ).with_confidence(Confidence::Likely);
+            
+            self.report_finding(finding).await?;
         }
         
         Ok(())
@@ -315,71 +791,102 @@ impl OutputAnalyzer {
             }
         }
         
-        // Filter out duplicates
+        // Filter out duplicates and subdomains already reported for this command
         subdomains.sort();
         subdomains.dedup();
+        let (subdomains, all_subdomains) = self.track_discovered(command_id, "subdomain", subdomains);
         
         // If we have subdomains, generate a finding
         if !subdomains.is_empty() {
-            // Create subdomain list for description
-            let subdomain_list = subdomains.iter()
-                .take(10) // Limit to 10 for the description
-                .cloned()
-                .collect::<Vec<_>>()
-                .join(", ");
-            
-            let additional = if subdomains.len() > 10 {
-                format!(" and {} more", subdomains.len() - 10)
-            } else {
-                String::new()
-            };
-            
-            // Create the finding
-            let finding = create_finding(
-                &format!("Subdomains Discovered"),
-                &format!("Discovered {} subdomains: {}{}", subdomains.len(), subdomain_list, additional),
-                FindingSeverity::Info,
-                command_id,
-                &subdomains.join("\n"),
-            );
-            
-            self.monitor.add_finding(finding).await?;
+            // Subdomains another tool or a passive source already found are not reported again
+            let assets = self.monitor.get_asset_inventory();
+            let subdomains: Vec<String> = subdomains.into_iter()
+                .filter(|subdomain| assets.record_subdomain(subdomain, command_id))
+                .collect();
+            if let Some(finding) = subdomains_finding(&subdomains, command_id) {
+                self.report_finding(finding).await?;
+            }
             
             // Update command summary
             self.monitor.update_command_summary(
                 command_id,
-                &format!("Discovered {} subdomains", subdomains.len()),
+                &format!("Discovered {} subdomains", all_subdomains.len()),
             )?;
         }
         
         Ok(())
     }
     
-    /// Analyze directory/path discovery output
-    async fn analyze_paths(&self, context: &str, command_id: &str) -> Result<()> {
-        // Extract interesting paths
+    /// Analyze URL collector output (waybackurls, gau): the URLs under the
+    /// collected domain, deduplicated, with the parameterized ones recorded
+    async fn analyze_collected_urls(&self, command: &str, context: &str, command_id: &str) -> Result<()> {
+        let Some(target) = extract_target_from_command(command) else {
+            return Ok(());
+        };
+        let urls = wayback::collect(context.lines(), &UrlScope::new(&target, &[]));
+        let (urls, all_urls) = self.track_discovered(command_id, "url", urls);
+        if urls.is_empty() {
+            return Ok(());
+        }
+        
+        let new = wayback::record_parameterized(&urls, &self.monitor, command_id)?;
+        if let Some(finding) = wayback::parameters_finding(&new, command_id) {
+            self.report_finding(finding).await?;
+        }
+        
+        self.monitor.update_command_summary(
+            command_id,
+            &format!(
+                "Collected {} unique URLs, {} with parameters (saved to {})",
+                all_urls.len(), wayback::parameterized(&all_urls).len(), wayback::PARAMS_FILE
+            ),
+        )?;
+        Ok(())
+    }
+    
+    /// Split path discovery output into regular paths and lines naming admin/sensitive paths
+    fn extract_paths(&self, context: &str) -> (Vec<String>, Vec<String>) {
         let mut paths = Vec::new();
         let mut admin_paths = Vec::new();
         
         for line in context.lines() {
-            for pattern in &self.path_discovery_patterns {
-                if let Some(captures) = pattern.captures(line) {
-                    // Handle admin/sensitive paths
-                    if line.contains("/admin") || 
-                       line.contains("/config") || 
-                       line.contains("/setup") || 
-                       line.contains("/install") || 
-                       line.contains("/backup") || 
-                       line.contains("/wp-admin") || 
-                       line.contains("/phpMyAdmin") {
-                        admin_paths.push(line.to_string());
-                    }
-                    // Handle regular paths
-                    else if captures.len() > 1 {
-                        if let Some(path) = captures.get(1) {
-                            paths.push(path.as_str().to_string());
-                        }
-                    }
+            // A line may match several patterns; the first one wins
+            let Some(captures) = self.path_discovery_patterns.iter().find_map(|pattern| pattern.captures(line)) else {
+                continue;
+            };
+            
+            // Handle admin/sensitive paths
+            if line.contains("/admin") || 
+               line.contains("/config") || 
+               line.contains("/setup") || 
+               line.contains("/install") || 
+               line.contains("/backup") || 
+               line.contains("/wp-admin") || 
+               line.contains("/phpMyAdmin") {
+                admin_paths.push(line.trim().to_string());
+            }
+            // Handle regular paths
+            else if let Some(path) = captures.get(1) {
+                paths.push(path.as_str().to_string());
+            }
+        }
+        
+        (paths, admin_paths)
+    }
+    
+    /// Analyze directory/path discovery output
+    async fn analyze_paths(&self, context: &str, command_id: &str) -> Result<()> {
+        let (paths, admin_paths) = self.extract_paths(context);
+        
+        // Discovered paths become URL assets under the scanned base URL
+        if let Some(command) = self.monitor.get_command(command_id) {
+            let base = self.url_pattern.find(&command.command)
+                .map(|url| url.as_str().to_string())
+                .or_else(|| extract_target_from_command(&command.command).map(|target| format!("http://{}", target)));
+            if let Some(base) = base {
+                let assets = self.monitor.get_asset_inventory();
+                for path in &paths {
+                    assets.record_url(&format!("{}/{}", base.trim_end_matches('/'), path.trim_start_matches('/')), command_id);
                 }
             }
         }
@@ -406,9 +913,9 @@ impl OutputAnalyzer {
                 FindingSeverity::Info,
                 command_id,
                 &paths.join("\n"),
-            );
+            ).with_confidence(Confidence::Confirmed);
             
-            self.monitor.add_finding(finding).await?;
+            self.report_finding(finding).await?;
         }
         
         // Add findings for admin/sensitive paths
@@ -434,9 +941,9 @@ impl OutputAnalyzer {
                 FindingSeverity::Medium,
                 command_id,
                 &admin_paths.join("\n"),
-            );
+            ).with_confidence(Confidence::Likely);
             
-            self.monitor.add_finding(finding).await?;
+            self.report_finding(finding).await?;
         }
         
         // Update command summary
@@ -454,6 +961,425 @@ impl OutputAnalyzer {
         Ok(())
     }
     
+    /// Analyze testssl.sh / sslscan output for TLS configuration weaknesses
+    async fn analyze_tls(&self, context: &str, command_id: &str) -> Result<()> {
+        let patterns = &self.tls_patterns;
+        let mut protocols = Vec::new();
+        let mut ciphers = Vec::new();
+        let mut expired = Vec::new();
+        let mut missing_hsts = None;
+        
+        for line in context.lines() {
+            if let Some(captures) = patterns.deprecated_protocol.captures(line) {
+                let protocol = captures[1].replace(' ', "");
+                if !protocols.iter().any(|(p, _)| *p == protocol) {
+                    protocols.push((protocol, line.trim().to_string()));
+                }
+            }
+            
+            if let Some(captures) = patterns.accepted_cipher.captures(line) {
+                ciphers.push((format!("{} ({})", &captures[2], &captures[1]), line.trim().to_string()));
+            } else if let Some(captures) = patterns.weak_cipher_category.captures(line) {
+                ciphers.push((captures[1].to_string(), line.trim().to_string()));
+            }
+            
+            if patterns.expired.is_match(line) {
+                expired.push(("expired".to_string(), line.trim().to_string()));
+            } else if let Some(captures) = patterns.not_valid_after.captures(line) {
+                let not_after = captures[1].trim().trim_end_matches(" GMT");
+                let parsed = chrono::NaiveDateTime::parse_from_str(
+                    &not_after.split_whitespace().collect::<Vec<_>>().join(" "),
+                    "%b %d %H:%M:%S %Y"
+                );
+                if let Ok(not_after) = parsed {
+                    if not_after < chrono::Utc::now().naive_utc() {
+                        expired.push((not_after.format("%Y-%m-%d").to_string(), line.trim().to_string()));
+                    }
+                }
+            }
+            
+            if missing_hsts.is_none() && patterns.missing_hsts.is_match(line) {
+                missing_hsts = Some(line.trim().to_string());
+            }
+        }
+        
+        // Structured evidence: one "key: value" entry per observation, followed by the source line
+        let evidence = |key: &str, items: &[(String, String)]| {
+            items.iter()
+                .map(|(value, source)| format!("{}: {}\nsource: {}", key, value, source))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        
+        if !protocols.is_empty() {
+            let names = protocols.iter().map(|(p, _)| p.as_str()).collect::<Vec<_>>().join(", ");
+            let finding = create_finding(
+                "Deprecated TLS Protocols Supported",
+                &format!("The server accepts deprecated protocol versions: {}", names),
+                FindingSeverity::Medium,
+                command_id,
+                &evidence("protocol", &protocols),
+            ).with_confidence(Confidence::Confirmed);
+            self.report_finding(finding).await?;
+        }
+        
+        if !ciphers.is_empty() {
+            let names = ciphers.iter().map(|(c, _)| c.as_str()).collect::<Vec<_>>().join(", ");
+            let finding = create_finding(
+                "Weak TLS Ciphers Supported",
+                &format!("The server accepts weak cipher suites: {}", names),
+                FindingSeverity::Medium,
+                command_id,
+                &evidence("cipher", &ciphers),
+            ).with_confidence(Confidence::Confirmed);
+            self.report_finding(finding).await?;
+        }
+        
+        if !expired.is_empty() {
+            let finding = create_finding(
+                "Expired TLS Certificate",
+                "The server presents a certificate that is past its validity period",
+                FindingSeverity::Medium,
+                command_id,
+                &evidence("not_valid_after", &expired),
+            ).with_confidence(Confidence::Confirmed);
+            self.report_finding(finding).await?;
+        }
+        
+        if let Some(source) = missing_hsts {
+            let finding = create_finding(
+                "Missing HSTS Header",
+                "The server does not send a Strict-Transport-Security header",
+                FindingSeverity::Low,
+                command_id,
+                &format!("hsts: not offered\nsource: {}", source),
+            ).with_confidence(Confidence::Confirmed);
+            self.report_finding(finding).await?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Analyze dig / dnsrecon output for zone transfers, email authentication records and wildcards
+    async fn analyze_dns(&self, command: &str, context: &str, command_id: &str) -> Result<()> {
+        let patterns = &self.dns_patterns;
+        let mut findings = Vec::new();
+        
+        // Zone transfer
+        let axfr_records = patterns.xfr_size.captures(context)
+            .and_then(|captures| captures[1].parse::<usize>().ok())
+            .filter(|count| *count > 0);
+        if axfr_records.is_some() || patterns.dnsrecon_axfr_success.is_match(context) {
+            let server = command.split_whitespace()
+                .find_map(|term| term.strip_prefix('@'))
+                .unwrap_or("unknown");
+            let mut evidence = format!("nameserver: {}", server);
+            if let Some(count) = axfr_records {
+                evidence.push_str(&format!("\nrecords_transferred: {}", count));
+            }
+            findings.push((
+                "DNS Zone Transfer Allowed",
+                "The nameserver allows AXFR zone transfers to unauthenticated clients, disclosing every record in the zone".to_string(),
+                FindingSeverity::High,
+                Confidence::Confirmed,
+                evidence,
+            ));
+        }
+        
+        // Wildcard records
+        let wildcard_answer = patterns.dig_question.captures_iter(context)
+            .any(|captures| captures[1].starts_with("*.")) && self.dig_answers(context).next().is_some();
+        if patterns.dnsrecon_wildcard.is_match(context) || wildcard_answer {
+            findings.push((
+                "DNS Wildcard Record Configured",
+                "Arbitrary subdomains resolve, which can mask subdomain takeover and hamper enumeration".to_string(),
+                FindingSeverity::Low,
+                Confidence::Likely,
+                "wildcard: enabled".to_string(),
+            ));
+        }
+        
+        // Email authentication records can only be judged missing once the query has finished
+        let complete = context.contains(";; MSG SIZE") || context.contains("Records Found");
+        if complete {
+            let txt_records: Vec<(String, String)> = if tool_name(command) == "dnsrecon" {
+                context.lines()
+                    .filter_map(|line| patterns.dnsrecon_txt.captures(line.trim()))
+                    .map(|captures| (captures[1].to_string(), captures[2].to_string()))
+                    .collect()
+            } else {
+                self.dig_answers(context)
+                    .filter(|(_, record_type, _)| record_type.eq_ignore_ascii_case("TXT"))
+                    .map(|(name, _, value)| (name, value))
+                    .collect()
+            };
+            let questions: Vec<String> = patterns.dig_question.captures_iter(context)
+                .filter(|captures| captures[2].eq_ignore_ascii_case("TXT"))
+                .map(|captures| captures[1].trim_end_matches('.').to_lowercase())
+                .collect();
+            
+            let has_record = |marker: &str| txt_records.iter().any(|(_, value)| value.contains(marker));
+            
+            // dnsrecon always enumerates the domain's TXT records; dig only when asked
+            let spf_checked = tool_name(command) == "dnsrecon"
+                || questions.iter().any(|name| !name.starts_with('_') && !name.contains("._domainkey."));
+            if spf_checked && !has_record("v=spf1") {
+                findings.push((
+                    "Missing SPF Record",
+                    "The domain publishes no SPF policy, so anyone can send mail claiming to be from it".to_string(),
+                    FindingSeverity::Medium,
+                    Confidence::Confirmed,
+                    "spf: not found".to_string(),
+                ));
+            }
+            
+            if let Some(name) = questions.iter().find(|name| name.starts_with("_dmarc.")) {
+                if !has_record("v=DMARC1") {
+                    findings.push((
+                        "Missing DMARC Record",
+                        "The domain publishes no DMARC policy, so receivers are not told to reject spoofed mail".to_string(),
+                        FindingSeverity::Medium,
+                        Confidence::Confirmed,
+                        format!("query: {}\ndmarc: not found", name),
+                    ));
+                }
+            }
+            
+            if let Some(name) = questions.iter().find(|name| name.contains("._domainkey.")) {
+                if !has_record("v=DKIM1") && !has_record("p=") {
+                    findings.push((
+                        "Missing DKIM Record",
+                        "No DKIM public key is published for the queried selector".to_string(),
+                        FindingSeverity::Low,
+                        Confidence::Likely,
+                        format!("selector: {}\ndkim: not found", name),
+                    ));
+                }
+            }
+        }
+        
+        for (title, description, severity, confidence, evidence) in findings {
+            let finding = create_finding(title, &description, severity, command_id, &evidence)
+                .with_confidence(confidence);
+            self.report_finding(finding).await?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Records in dig answer sections as (name, type, value)
+    fn dig_answers<'a>(&'a self, context: &'a str) -> impl Iterator<Item = (String, String, String)> + 'a {
+        let mut in_answer = false;
+        context.lines().filter_map(move |line| {
+            if line.starts_with(";; ANSWER SECTION:") {
+                in_answer = true;
+                return None;
+            }
+            if line.trim().is_empty() || line.starts_with(";;") {
+                in_answer = false;
+                return None;
+            }
+            if !in_answer {
+                return None;
+            }
+            self.dns_patterns.dig_record.captures(line).map(|captures| (
+                captures[1].trim_end_matches('.').to_lowercase(),
+                captures[2].to_string(),
+                captures[3].to_string(),
+            ))
+        })
+    }
+    
+    /// Detect admin interfaces that commonly ship with default credentials
+    async fn analyze_admin_interfaces(&self, command: &str, context: &str, command_id: &str) -> Result<()> {
+        let mut detected: Vec<(&str, String, String)> = Vec::new();
+        
+        for line in context.lines() {
+            for (pattern, interface, path) in &self.admin_interface_patterns {
+                if !pattern.is_match(line) {
+                    continue;
+                }
+                
+                // Prefer a URL on the line, then an nmap port, then the command's target
+                let base = match self.url_pattern.find(line) {
+                    Some(url) => url.as_str().to_string(),
+                    None => {
+                        let Some(target) = extract_target_from_command(command) else { continue };
+                        match self.open_port_pattern.captures(line) {
+                            Some(captures) if &captures[1] == "443" => format!("https://{}", target),
+                            Some(captures) => format!("http://{}:{}", target, &captures[1]),
+                            None => format!("http://{}", target),
+                        }
+                    },
+                };
+                let url = format!("{}{}", base, path);
+                
+                if !detected.iter().any(|(name, existing, _)| name == interface && *existing == url) {
+                    detected.push((interface, url, line.trim().to_string()));
+                }
+            }
+        }
+        
+        for (interface, url, source) in detected {
+            self.monitor.get_asset_inventory().record_url(&url, command_id);
+            let finding = create_finding(
+                &format!("Admin Interface Detected: {}", interface),
+                &format!("A {} interface is exposed at {}; these commonly ship with default credentials", interface, url),
+                FindingSeverity::Medium,
+                command_id,
+                &format!("interface: {}\nurl: {}\nsource: {}", interface, url, source),
+            ).with_confidence(Confidence::Likely);
+            self.report_finding(finding).await?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Detect email addresses and credential pairs, aggregated per target.
+    ///
+    /// Findings only carry counts and masked samples so reports never repeat the raw data.
+    async fn analyze_leaks(&self, command: &str, context: &str, command_id: &str) -> Result<()> {
+        let patterns = &self.leak_patterns;
+        let default_target = extract_target_from_command(command).unwrap_or_else(|| "unknown".to_string());
+        
+        let mut emails = Vec::new();
+        let mut credentials = Vec::new();
+        for line in context.lines() {
+            let line = line.trim();
+            
+            for captures in patterns.email.captures_iter(line) {
+                emails.push((captures[1].to_lowercase(), captures[0].to_lowercase()));
+            }
+            
+            if let Some(captures) = patterns.cracked_login.captures(line) {
+                credentials.push((captures[1].to_lowercase(), captures[2].to_string(), captures[3].to_string()));
+            } else if let Some(captures) = patterns.combo_line.captures(line) {
+                credentials.push((captures[2].to_lowercase(), captures[1].to_lowercase(), captures[3].to_string()));
+            } else if let Some(captures) = patterns.key_value_pair.captures(line) {
+                credentials.push((default_target.clone(), captures[1].to_string(), captures[2].to_string()));
+            }
+        }
+        
+        // Merge into the session totals and note which targets gained new entries
+        let mut new_emails = Vec::new();
+        let mut new_credentials = Vec::new();
+        {
+            let mut leaks = self.leaks.lock().unwrap();
+            for (target, email) in emails {
+                if leaks.entry(target.clone()).or_default().emails.insert(email) && !new_emails.contains(&target) {
+                    new_emails.push(target);
+                }
+            }
+            for (target, username, password) in credentials {
+                let fingerprint = credential_fingerprint(&username, &password);
+                if leaks.entry(target.clone()).or_default().credentials.insert(fingerprint, username).is_none()
+                    && !new_credentials.contains(&target)
+                {
+                    new_credentials.push(target);
+                }
+            }
+        }
+        
+        let sample = |values: Vec<&String>| {
+            let mut values: Vec<_> = values.into_iter().map(|value| mask_identifier(value)).collect();
+            values.sort();
+            values.dedup();
+            values.truncate(3);
+            values.join(", ")
+        };
+        
+        // One finding of each kind per target, updated as more turn up
+        for target in new_credentials {
+            let (count, accounts) = {
+                let leaks = self.leaks.lock().unwrap();
+                let credentials = &leaks[&target].credentials;
+                (credentials.len(), sample(credentials.values().collect()))
+            };
+            let finding = create_finding(
+                "Leaked Credentials",
+                &format!("{} credential pair(s) for {} appeared in tool output", count, target),
+                FindingSeverity::High,
+                command_id,
+                &format!("target: {}\ncredential_pairs: {}\nsample_accounts: {}", target, count, accounts),
+            ).with_confidence(Confidence::Likely)
+                .with_merge_key(format!("leaked-credentials:{}", target));
+            self.report_finding(finding).await?;
+        }
+        
+        for target in new_emails {
+            let (count, addresses) = {
+                let leaks = self.leaks.lock().unwrap();
+                let emails = &leaks[&target].emails;
+                (emails.len(), sample(emails.iter().collect()))
+            };
+            let finding = create_finding(
+                "Exposed Email Addresses",
+                &format!("{} email address(es) at {} were collected", count, target),
+                FindingSeverity::Low,
+                command_id,
+                &format!("target: {}\nemail_addresses: {}\nsample: {}", target, count, addresses),
+            ).with_confidence(Confidence::Confirmed)
+                .with_merge_key(format!("exposed-emails:{}", target));
+            self.report_finding(finding).await?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Add fingerprints from whatweb, httpx and nmap service detection to the inventory
+    fn record_technologies(&self, command: &str, context: &str, command_id: &str) {
+        let fingerprints = match tool_name(command) {
+            "whatweb" => tech_inventory::parse_whatweb(context),
+            "httpx" => tech_inventory::parse_httpx(context),
+            "nmap" if command.contains("-sV") || command.contains("-A") => {
+                let mut hosts = self.nmap_hosts.lock().unwrap();
+                tech_inventory::parse_nmap_services(context, hosts.entry(command_id.to_string()).or_default())
+            },
+            _ => return,
+        };
+        
+        let source = tool_name(command);
+        let inventory = self.monitor.get_tech_inventory();
+        for (target, name, version) in fingerprints {
+            inventory.record(&target, &name, version.as_deref(), source);
+        }
+    }
+    
+    /// Apply user-defined rules and runtime triggers to command output
+    async fn analyze_custom_rules(&self, context: &str, command_id: &str) -> Result<()> {
+        // Triggers can be added mid-session, so take the current set each pass
+        let triggers = self.monitor.get_triggers().rules();
+        
+        for line in context.lines() {
+            for rule in self.custom_rules.iter().chain(&triggers) {
+                if let Some(captures) = rule.regex.captures(line) {
+                    let (title, description) = rule.render(&captures);
+                    let mut finding = create_finding(
+                        &title,
+                        &description,
+                        rule.rule.severity.clone(),
+                        command_id,
+                        line,
+                    ).with_confidence(rule.rule.confidence.clone());
+                    
+                    if rule.cvss.is_some() {
+                        finding.cvss = rule.cvss.clone();
+                    }
+                    if rule.rule.cwe.is_some() {
+                        finding.cwe = rule.rule.cwe.clone();
+                    }
+                    if rule.rule.owasp.is_some() {
+                        finding.owasp = rule.rule.owasp.clone();
+                    }
+                    
+                    self.report_finding(finding).await?;
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
     /// Analyze generic command output for any potential findings
     async fn analyze_generic_output(&self, context: &str, command_id: &str) -> Result<()> {
         // Try all analyzers
@@ -464,4 +1390,180 @@ impl OutputAnalyzer {
         
         Ok(())
     }
-} 
\ No newline at end of file
+} 
+
+/// The finding for newly discovered subdomains, whether from a tool's output
+/// or a passive source
+pub fn subdomains_finding(subdomains: &[String], command_id: &str) -> Option<SecurityFinding> {
+    if subdomains.is_empty() {
+        return None;
+    }
+    
+    // Create subdomain list for description
+    let subdomain_list = subdomains.iter()
+        .take(10) // Limit to 10 for the description
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    
+    let additional = if subdomains.len() > 10 {
+        format!(" and {} more", subdomains.len() - 10)
+    } else {
+        String::new()
+    };
+    
+    Some(create_finding(
+        "Subdomains Discovered",
+        &format!("Discovered {} subdomains: {}{}", subdomains.len(), subdomain_list, additional),
+        FindingSeverity::Info,
+        command_id,
+        &subdomains.join("\n"),
+    ).with_confidence(Confidence::Likely))
+}
+
+/// Mask an identifier for reporting, e.g. `jane.doe@example.com` -> `j***@example.com`
+fn mask_identifier(value: &str) -> String {
+    let (local, domain) = match value.split_once('@') {
+        Some((local, domain)) => (local, Some(domain)),
+        None => (value, None),
+    };
+    let first = local.chars().next().map(String::from).unwrap_or_default();
+    match domain {
+        Some(domain) => format!("{}***@{}", first, domain),
+        None => format!("{}***", first),
+    }
+}
+
+/// Read a command's output back from its log file, without the stream prefixes
+fn read_output_file(path: &Path) -> Result<String> {
+    let content = fs::read_to_string(path)?;
+    Ok(content.lines()
+        .map(|line| line.strip_prefix("[STDOUT] ")
+            .or_else(|| line.strip_prefix("[STDERR] "))
+            .unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const GOBUSTER_OUTPUT: &str = "\
+===============================================================
+Gobuster v3.6
+===============================================================
+/images               (Status: 200) [Size: 1234]
+/css                  (Status: 200) [Size: 512]
+/admin                (Status: 200) [Size: 2048]
+/private              (Status: 403) [Size: 277]
+===============================================================";
+
+    /// A monitor working in a directory removed when the test ends
+    fn test_monitor() -> (TempDir, Arc<CommandMonitor>) {
+        let work_dir = TempDir::new().unwrap();
+        let monitor = Arc::new(CommandMonitor::new(work_dir.path().to_path_buf()).unwrap());
+        (work_dir, monitor)
+    }
+
+    fn test_analyzer(monitor: Arc<CommandMonitor>) -> OutputAnalyzer {
+        let (_output_tx, output_rx) = mpsc::channel(1);
+        OutputAnalyzer::new(monitor, output_rx)
+    }
+
+    #[test]
+    fn extracts_gobuster_paths() {
+        let (_work_dir, monitor) = test_monitor();
+        let analyzer = test_analyzer(monitor);
+        let (paths, admin_paths) = analyzer.extract_paths(GOBUSTER_OUTPUT);
+
+        assert_eq!(paths, vec!["/images", "/css"]);
+        assert_eq!(admin_paths, vec!["/admin                (Status: 200) [Size: 2048]"]);
+    }
+
+    #[test]
+    fn extracts_ffuf_and_expanded_gobuster_paths() {
+        let (_work_dir, monitor) = test_monitor();
+        let analyzer = test_analyzer(monitor);
+        let output = "\
+images                  [Status: 200, Size: 1234, Words: 5, Lines: 2, Duration: 12ms]
+http://example.com/css (Status: 200) [Size: 512]
+login                   [Status: 302, Size: 0, Words: 1, Lines: 1, Duration: 9ms]";
+        let (paths, admin_paths) = analyzer.extract_paths(output);
+
+        assert_eq!(paths, vec!["images", "http://example.com/css"]);
+        assert!(admin_paths.is_empty());
+    }
+
+    #[tokio::test]
+    async fn scanning_output_becomes_path_findings() {
+        let (_work_dir, monitor) = test_monitor();
+        let mut findings_rx = monitor.get_findings_receiver();
+        let command_id = monitor.execute_command("true", CommandType::Scanning).await.unwrap();
+
+        let mut analyzer = test_analyzer(monitor.clone());
+        analyzer.buffer.insert(command_id.clone(), GOBUSTER_OUTPUT.lines().map(String::from).collect());
+        analyzer.analyze_new_output(&command_id).await.unwrap();
+
+        let mut titles = Vec::new();
+        while let Ok(finding) = findings_rx.try_recv() {
+            titles.push(finding.title);
+        }
+        assert!(titles.iter().any(|title| title == "Interesting Paths Discovered"));
+        assert!(titles.iter().any(|title| title == "Potentially Sensitive Paths Discovered"));
+
+        let summary = monitor.get_command(&command_id).unwrap().results_summary;
+        assert_eq!(summary.as_deref(), Some("Discovered 3 paths (1 potentially sensitive)"));
+    }
+
+    #[tokio::test]
+    async fn repeated_leaks_update_one_finding_per_target() {
+        let (_work_dir, monitor) = test_monitor();
+        let mut findings_rx = monitor.get_findings_receiver();
+        let command_id = monitor.execute_command("true", CommandType::Scanning).await.unwrap();
+        let analyzer = test_analyzer(monitor.clone());
+
+        let combo = "jane@example.com:hunter2";
+        analyzer.analyze_leaks("true", combo, &command_id).await.unwrap();
+        analyzer.analyze_leaks("true", combo, &command_id).await.unwrap();
+        analyzer.analyze_leaks("true", "jane@example.com:hunter3", &command_id).await.unwrap();
+
+        let mut credentials = Vec::new();
+        while let Ok(finding) = findings_rx.try_recv() {
+            if finding.title == "Leaked Credentials" {
+                credentials.push(finding);
+            }
+        }
+        assert_eq!(credentials.len(), 2);
+        assert!(credentials.iter().all(|finding| finding.merge_key.as_deref() == Some("leaked-credentials:example.com")));
+        assert!(credentials[1].description.starts_with("2 credential pair(s)"));
+    }
+
+    #[test]
+    fn file_inclusion_needs_evidence() {
+        let web = WebVulnPatterns::new();
+        let fuzzing = "../../../../etc/passwd          [Status: 404, Size: 12, Words: 2, Lines: 1]";
+        let reported = "[lfi-linux] [http] [high] http://example.com/view.php?file=../../../../etc/passwd";
+        assert!(!(web.traversal_claim.is_match(fuzzing) && web.traversal_parameter.is_match(fuzzing)));
+        assert!(web.traversal_claim.is_match(reported) && web.traversal_parameter.is_match(reported));
+        assert!(web.traversal_response.is_match("root:x:0:0:root:/root:/bin/bash"));
+
+        assert!(!web.remote_inclusion.is_match("+ /rfi/: Directory indexing found."));
+        let remote = "Remote File Inclusion: http://example.com/index.php?page=http://attacker.example/shell.txt";
+        assert!(web.remote_inclusion.is_match(remote) && web.remote_parameter.is_match(remote));
+    }
+
+    #[test]
+    fn nmap_services_are_recorded_across_passes() {
+        let (_work_dir, monitor) = test_monitor();
+        let analyzer = test_analyzer(monitor.clone());
+        let command = "nmap -sV -p- 10.0.0.0/30";
+
+        analyzer.record_technologies(command, "Nmap scan report for 10.0.0.1\nPORT   STATE SERVICE VERSION", "scan");
+        analyzer.record_technologies(command, "22/tcp open  ssh     OpenSSH 8.2p1 Ubuntu", "scan");
+
+        let technologies = monitor.get_tech_inventory().technologies_for("10.0.0.1");
+        assert!(technologies.iter().any(|tech| tech.name == "OpenSSH" && tech.version.as_deref() == Some("8.2p1")));
+    }
+}
@@ -0,0 +1,98 @@
+use std::fs;
+use std::path::Path;
+use anyhow::{Result, Context, anyhow};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+use super::command_monitor::{CommandMonitor, CommandStatus, determine_command_type};
+use crate::utils::shell_quote;
+
+/// A fixed list of commands run in order, e.g. a standard web recon pass
+#[derive(Debug, Clone, Deserialize)]
+pub struct Playbook {
+    pub name: String,
+    pub steps: Vec<PlaybookStep>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlaybookStep {
+    pub name: String,
+    /// `{target}` is replaced by the shell-quoted `--target`
+    pub command: String,
+    /// Run the remaining steps even if this one fails
+    #[serde(default)]
+    pub continue_on_failure: bool,
+}
+
+/// How a playbook run went
+#[derive(Debug, Clone, Default)]
+pub struct PlaybookOutcome {
+    pub succeeded: usize,
+    /// Names of the steps that failed
+    pub failed: Vec<String>,
+    /// Steps not run because an earlier one failed
+    pub skipped: usize,
+}
+
+impl Playbook {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .context(format!("Failed to read playbook {:?}", path))?;
+        let playbook: Playbook = serde_yaml::from_str(&content)
+            .context(format!("Failed to parse playbook {:?}", path))?;
+
+        if playbook.steps.is_empty() {
+            return Err(anyhow!("Playbook {:?} has no steps", path));
+        }
+
+        Ok(playbook)
+    }
+
+    /// The commands of every step, failing if one needs a target and none was given
+    pub fn commands(&self, target: Option<&str>) -> Result<Vec<String>> {
+        self.steps.iter()
+            .map(|step| match target {
+                Some(target) => Ok(step.command.replace("{target}", &shell_quote(target))),
+                None if step.command.contains("{target}") => Err(anyhow!(
+                    "Playbook step '{}' needs a target, pass one with --target", step.name)),
+                None => Ok(step.command.clone()),
+            })
+            .collect()
+    }
+
+    /// Run the steps one after another through the monitor, reporting progress on `tx`
+    pub async fn run(&self, monitor: &CommandMonitor, target: Option<&str>, tx: mpsc::Sender<String>) -> Result<PlaybookOutcome> {
+        let commands = self.commands(target)?;
+        let mut outcome = PlaybookOutcome::default();
+
+        for (i, (step, command)) in self.steps.iter().zip(&commands).enumerate() {
+            let _ = tx.send(format!("[PLAYBOOK] {} step {}/{}: {}",
+                self.name, i + 1, self.steps.len(), step.name)).await;
+
+            let result = match monitor.execute_command(command, determine_command_type(command)).await {
+                Ok(id) => {
+                    monitor.wait_for_command_completion(&id, u64::MAX).await;
+                    match monitor.get_command(&id).map(|cmd| cmd.status) {
+                        Some(CommandStatus::Failed(error)) => Err(anyhow!(error)),
+                        _ => Ok(()),
+                    }
+                },
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(()) => outcome.succeeded += 1,
+                Err(e) => {
+                    let _ = tx.send(format!("[PLAYBOOK] Step '{}' failed: {:#}", step.name, e)).await;
+                    outcome.failed.push(step.name.clone());
+                    if !step.continue_on_failure {
+                        outcome.skipped = self.steps.len() - i - 1;
+                        break;
+                    }
+                },
+            }
+        }
+
+        Ok(outcome)
+    }
+}
@@ -0,0 +1,89 @@
+use crate::config::ProxyConfig;
+use crate::utils::shell_quote;
+use super::throttle::{has_flag, tool_name};
+
+/// A tool's own proxy option
+struct ProxyFlag {
+    tool: &'static str,
+    flag: &'static str,
+    /// Written as `flag=url` rather than `flag url`
+    joined: bool,
+    /// Accepts socks5:// URLs as well as http://
+    socks: bool,
+    /// Words after the binary the flag must follow, e.g. gobuster's mode
+    after_words: usize,
+}
+
+const PROXY_FLAGS: &[ProxyFlag] = &[
+    ProxyFlag { tool: "curl", flag: "--proxy", joined: false, socks: true, after_words: 0 },
+    ProxyFlag { tool: "sqlmap", flag: "--proxy", joined: true, socks: true, after_words: 0 },
+    ProxyFlag { tool: "ffuf", flag: "-x", joined: false, socks: true, after_words: 0 },
+    ProxyFlag { tool: "gobuster", flag: "--proxy", joined: false, socks: true, after_words: 1 },
+    ProxyFlag { tool: "nuclei", flag: "-proxy", joined: false, socks: true, after_words: 0 },
+    ProxyFlag { tool: "httpx", flag: "-http-proxy", joined: false, socks: true, after_words: 0 },
+    ProxyFlag { tool: "wpscan", flag: "--proxy", joined: false, socks: true, after_words: 0 },
+    ProxyFlag { tool: "nikto", flag: "-useproxy", joined: false, socks: false, after_words: 0 },
+];
+
+/// Routes every tool's traffic through the configured proxy, using the tool's
+/// own proxy option where it has one and proxychains otherwise
+#[derive(Debug, Clone)]
+pub struct ProxyRouter {
+    config: ProxyConfig,
+}
+
+impl ProxyRouter {
+    pub fn new(config: ProxyConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.config.url
+    }
+
+    /// Rewrite a command so its traffic goes through the proxy
+    pub fn apply(&self, command: &str) -> String {
+        let (prefix, rest) = match command.strip_prefix("sudo ") {
+            Some(rest) => ("sudo ", rest.trim_start()),
+            None => ("", command),
+        };
+        let tool = tool_name(rest);
+        if tool.is_empty() || tool.starts_with("proxychains") || self.config.bypass.iter().any(|bypass| bypass == tool) {
+            return command.to_string();
+        }
+
+        let socks = self.config.url.starts_with("socks");
+        match PROXY_FLAGS.iter().find(|flag| flag.tool == tool && (flag.socks || !socks)) {
+            Some(flag) if has_flag(rest, flag.flag) => command.to_string(),
+            Some(flag) => {
+                let at = insertion_point(rest, flag.after_words);
+                let option = if flag.joined {
+                    format!("{}={}", flag.flag, self.config.url)
+                } else {
+                    format!("{} {}", flag.flag, self.config.url)
+                };
+                format!("{}{} {}{}", prefix, &rest[..at], option, &rest[at..])
+            },
+            None if self.config.proxychains => {
+                let config_file = self.config.proxychains_config.as_ref()
+                    .map(|path| format!("-f {} ", shell_quote(&path.display().to_string())))
+                    .unwrap_or_default();
+                format!("{}proxychains4 -q {}{}", prefix, config_file, rest)
+            },
+            None => command.to_string(),
+        }
+    }
+}
+
+/// Byte offset just past the binary and the given number of words after it
+pub fn insertion_point(command: &str, after_words: usize) -> usize {
+    let mut at = 0;
+    for _ in 0..=after_words {
+        let start = command[at..].find(|c: char| !c.is_whitespace()).map_or(command.len(), |i| at + i);
+        if command[start..].starts_with('-') {
+            break;
+        }
+        at = command[start..].find(char::is_whitespace).map_or(command.len(), |i| start + i);
+    }
+    at
+}
@@ -0,0 +1,285 @@
+use std::fs;
+use std::path::Path;
+use anyhow::{Result, Context as _};
+use base64::Engine;
+use serde::Serialize;
+use tera::{Context, Tera};
+
+use crate::config::ReportConfig;
+
+use super::auto_documentation::DocumentedFinding;
+use crate::utils::extract_target_from_command;
+use super::command_monitor::{CommandStatus, Confidence, FindingSeverity, MonitoredCommand};
+use super::session_diff::SessionDiff;
+use super::timeline::{Activity, ActivityKind};
+
+/// Markdown summary report
+pub const SUMMARY_TEMPLATE: &str = "summary.md";
+/// Single-file HTML report
+pub const HTML_TEMPLATE: &str = "report.html";
+/// Retest comparison between two sessions
+pub const DIFF_TEMPLATE: &str = "diff.md";
+
+/// Built-in templates, used unless the configuration directory overrides them
+const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    (SUMMARY_TEMPLATE, include_str!("../../templates/summary.md")),
+    (HTML_TEMPLATE, include_str!("../../templates/report.html")),
+    (DIFF_TEMPLATE, include_str!("../../templates/diff.md")),
+];
+
+/// A finding as exposed to templates, with display fields precomputed
+#[derive(Debug, Serialize)]
+struct TemplateFinding<'a> {
+    #[serde(flatten)]
+    finding: &'a DocumentedFinding,
+    target: Option<String>,
+    /// CVSS score with one decimal, or `-`
+    cvss_score: String,
+    discovered: String,
+    /// Output log of the command that produced the finding
+    evidence_log: Option<String>,
+    /// Notes with their timestamps formatted for display
+    dated_notes: Vec<TemplateNote<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct TemplateNote<'a> {
+    time: String,
+    text: &'a str,
+}
+
+/// Findings grouped under the host or URL they were discovered on
+#[derive(Debug, Serialize)]
+struct TargetSection<'a> {
+    name: String,
+    anchor: String,
+    findings: Vec<&'a TemplateFinding<'a>>,
+}
+
+/// An executed command, for the methodology timeline
+#[derive(Debug, Serialize)]
+struct TemplateCommand {
+    id: String,
+    command: String,
+    started: String,
+    finished: String,
+    status: String,
+    summary: Option<String>,
+    output_file: String,
+    finding_count: usize,
+}
+
+/// A timeline entry with its timestamp formatted for display
+#[derive(Debug, Serialize)]
+struct TimelineEntry<'a> {
+    time: String,
+    kind: ActivityKind,
+    description: &'a str,
+    reference: Option<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+struct Count {
+    name: String,
+    count: usize,
+}
+
+/// Client and tester details shown in report headers and footers
+#[derive(Debug, Default, Serialize)]
+struct Branding {
+    client: Option<String>,
+    logo: Option<String>,
+    /// The logo as a `data:` URI, so HTML reports stay a single file
+    logo_data_uri: Option<String>,
+    tester: Option<String>,
+    contact: Option<String>,
+    classification: Option<String>,
+}
+
+impl Branding {
+    fn from_config(config: &ReportConfig) -> Result<Self> {
+        let logo_data_uri = match &config.logo {
+            Some(logo) => {
+                let image = fs::read(logo)
+                    .context(format!("Failed to read report logo {:?}", logo))?;
+                let mime = match logo.extension().and_then(|ext| ext.to_str()).map(str::to_lowercase).as_deref() {
+                    Some("jpg" | "jpeg") => "image/jpeg",
+                    Some("gif") => "image/gif",
+                    Some("svg") => "image/svg+xml",
+                    _ => "image/png",
+                };
+                Some(format!("data:{};base64,{}", mime, base64::engine::general_purpose::STANDARD.encode(image)))
+            },
+            None => None,
+        };
+
+        Ok(Self {
+            client: config.client.clone(),
+            logo: config.logo.as_ref().map(|logo| logo.display().to_string()),
+            logo_data_uri,
+            tester: config.tester.clone(),
+            contact: config.contact.clone(),
+            classification: config.classification.clone(),
+        })
+    }
+}
+
+/// Report templates: the built-in layouts, overridden by files in `<config_dir>/templates/`
+#[derive(Debug)]
+pub struct ReportTemplates {
+    tera: Tera,
+    branding: Branding,
+}
+
+impl ReportTemplates {
+    /// The built-in layouts only
+    pub fn builtin() -> Self {
+        let mut tera = Tera::default();
+        tera.add_raw_templates(BUILTIN_TEMPLATES.to_vec())
+            .expect("built-in report templates are valid");
+        Self {
+            tera,
+            branding: Branding::default(),
+        }
+    }
+
+    pub fn load(config_dir: &Path, branding: &ReportConfig) -> Result<Self> {
+        let mut tera = Self::builtin().tera;
+
+        // Teams can match their own report format by dropping a template with the same name
+        let override_dir = config_dir.join("templates");
+        for (name, _) in BUILTIN_TEMPLATES {
+            let path = override_dir.join(name);
+            if path.exists() {
+                let content = fs::read_to_string(&path)?;
+                tera.add_raw_template(name, &content)
+                    .context(format!("Failed to parse report template {:?}", path))?;
+            }
+        }
+
+        Ok(Self {
+            tera,
+            branding: Branding::from_config(branding)?,
+        })
+    }
+
+    /// Render a report template for a set of findings, already in report order,
+    /// with the commands executed and the activity recorded during the session
+    pub fn render(&self, template: &str, findings: &[DocumentedFinding], commands: &[MonitoredCommand], activity: &[Activity]) -> Result<String> {
+        let severity_counts: Vec<Count> = [
+            FindingSeverity::Critical,
+            FindingSeverity::High,
+            FindingSeverity::Medium,
+            FindingSeverity::Low,
+            FindingSeverity::Info,
+        ].iter()
+            .map(|severity| Count {
+                name: format!("{:?}", severity),
+                count: findings.iter().filter(|finding| &finding.severity == severity).count(),
+            })
+            .collect();
+
+        let confidence_counts: Vec<Count> = [Confidence::Confirmed, Confidence::Likely, Confidence::Tentative].iter()
+            .map(|confidence| Count {
+                name: format!("{:?}", confidence),
+                count: findings.iter().filter(|finding| &finding.confidence == confidence).count(),
+            })
+            .collect();
+
+        let findings = template_findings(findings, commands);
+
+        // Targets in order of their most severe finding, since findings are already sorted
+        let mut targets: Vec<TargetSection> = Vec::new();
+        for finding in &findings {
+            let name = finding.target.clone().unwrap_or_else(|| "Unknown target".to_string());
+            match targets.iter_mut().find(|section| section.name == name) {
+                Some(section) => section.findings.push(finding),
+                None => targets.push(TargetSection {
+                    anchor: format!("target-{}", targets.len() + 1),
+                    name,
+                    findings: vec![finding],
+                }),
+            }
+        }
+
+        let mut commands: Vec<&MonitoredCommand> = commands.iter().collect();
+        commands.sort_by_key(|command| command.start_time);
+        let commands: Vec<TemplateCommand> = commands.into_iter()
+            .map(|command| TemplateCommand {
+                id: command.id.clone(),
+                command: command.command.clone(),
+                started: command.start_time.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+                finished: command.end_time
+                    .map(|end| end.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                status: match &command.status {
+                    CommandStatus::Failed(reason) => format!("Failed: {}", reason),
+                    status => format!("{:?}", status),
+                },
+                summary: command.summary(),
+                output_file: command.output_file.display().to_string(),
+                finding_count: command.findings.len(),
+            })
+            .collect();
+
+        let mut context = Context::new();
+        context.insert("generated", &chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string());
+        context.insert("branding", &self.branding);
+        context.insert("findings", &findings);
+        context.insert("targets", &targets);
+        context.insert("commands", &commands);
+        context.insert("timeline", &activity.iter()
+            .map(|entry| TimelineEntry {
+                time: entry.timestamp.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+                kind: entry.kind,
+                description: &entry.description,
+                reference: entry.reference.as_deref(),
+            })
+            .collect::<Vec<_>>());
+        context.insert("severity_counts", &severity_counts);
+        context.insert("confidence_counts", &confidence_counts);
+
+        self.tera.render(template, &context)
+            .context(format!("Failed to render report template {}", template))
+    }
+
+    /// Render the retest report comparing a baseline session with a retest
+    pub fn render_diff(&self, baseline: &str, retest: &str, target: Option<&str>, diff: &SessionDiff) -> Result<String> {
+        let mut context = Context::new();
+        context.insert("generated", &chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string());
+        context.insert("branding", &self.branding);
+        context.insert("baseline", baseline);
+        context.insert("retest", retest);
+        context.insert("target", &target);
+        context.insert("new", &template_findings(&diff.new, &[]));
+        context.insert("fixed", &template_findings(&diff.fixed, &[]));
+        context.insert("persisting", &template_findings(&diff.persisting, &[]));
+
+        self.tera.render(DIFF_TEMPLATE, &context)
+            .context(format!("Failed to render report template {}", DIFF_TEMPLATE))
+    }
+}
+
+/// Findings with their display fields, in the given order
+fn template_findings<'a>(findings: &'a [DocumentedFinding], commands: &[MonitoredCommand]) -> Vec<TemplateFinding<'a>> {
+    findings.iter()
+        .map(|finding| TemplateFinding {
+            finding,
+            target: extract_target_from_command(&finding.discovery_command),
+            cvss_score: finding.cvss.as_ref()
+                .map(|cvss| format!("{:.1}", cvss.score))
+                .unwrap_or_else(|| "-".to_string()),
+            discovered: finding.discovery_date.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+            evidence_log: commands.iter()
+                .filter(|command| command.command == finding.discovery_command && command.start_time <= finding.discovery_date)
+                .max_by_key(|command| command.start_time)
+                .map(|command| command.output_file.display().to_string()),
+            dated_notes: finding.notes.iter()
+                .map(|note| TemplateNote {
+                    time: note.timestamp.format("%Y-%m-%d %H:%M UTC").to_string(),
+                    text: &note.text,
+                })
+                .collect(),
+        })
+        .collect()
+}
@@ -0,0 +1,320 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+
+use super::throttle::{command_hosts, first_command_end, target_matches, tool_name};
+
+/// Tools whose concurrency is set with `-t <threads>`
+const THREADED_TOOLS: &[&str] = &["gobuster", "ffuf", "dirsearch"];
+
+/// Limits on how aggressively commands may hit matching targets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SafetyRule {
+    pub name: String,
+    /// Domains or IPs, optionally with a leading `*.` wildcard or a trailing
+    /// `.*` for any top-level domain
+    pub targets: Vec<String>,
+    /// Highest nmap timing template (-T0 to -T5) allowed
+    #[serde(default)]
+    pub max_nmap_timing: Option<u8>,
+    /// Most threads allowed for directory brute forcing
+    #[serde(default)]
+    pub max_threads: Option<u32>,
+    /// Flags removed from each tool's commands, with their values; write
+    /// `--flag <value>` for a flag that takes one the policy doesn't know about
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub banned_flags: BTreeMap<String, Vec<String>>,
+    /// Flags swapped for gentler ones per tool, e.g. nmap's -A for -sV
+    #[serde(default)]
+    pub replace_flags: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// Per-target scan limits loaded from the safety policy file; the first rule
+/// matching a command's target applies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SafetyPolicy {
+    #[serde(default)]
+    pub rules: Vec<SafetyRule>,
+}
+
+impl Default for SafetyPolicy {
+    /// Gentle scanning of educational, government and military targets, and
+    /// of well-known universities and agencies under any domain
+    fn default() -> Self {
+        let institutions = [
+            "harvard", "stanford", "mit", "yale", "princeton", "columbia", "cornell", "dartmouth",
+            "brown", "upenn", "berkeley", "ucla", "usc", "duke", "jhu", "nih", "nasa", "noaa", "usgs",
+        ];
+        let targets = ["*.edu", "*.gov", "*.mil"].into_iter().map(str::to_string)
+            .chain(institutions.iter().map(|name| format!("*.{}.*", name)))
+            .collect();
+
+        Self {
+            rules: vec![SafetyRule {
+                name: "sensitive".to_string(),
+                targets,
+                max_nmap_timing: Some(2),
+                max_threads: Some(10),
+                banned_flags: BTreeMap::new(),
+                replace_flags: BTreeMap::from([(
+                    "nmap".to_string(),
+                    BTreeMap::from([("-A".to_string(), "-sV".to_string())]),
+                )]),
+            }],
+        }
+    }
+}
+
+impl SafetyPolicy {
+    /// Load the policy, writing the default one first if the file doesn't exist
+    pub fn load_or_create(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            let policy = Self::default();
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, toml::to_string_pretty(&policy)?)
+                .context(format!("Failed to write safety policy {:?}", path))?;
+            return Ok(policy);
+        }
+
+        let content = fs::read_to_string(path)
+            .context(format!("Failed to read safety policy {:?}", path))?;
+        let policy: SafetyPolicy = toml::from_str(&content)
+            .context(format!("Failed to parse safety policy {:?}", path))?;
+
+        for rule in &policy.rules {
+            if let Some(timing) = rule.max_nmap_timing.filter(|timing| *timing > 5) {
+                return Err(anyhow::anyhow!("Safety rule {} allows nmap timing -T{}, expected 0 to 5", rule.name, timing));
+            }
+        }
+
+        Ok(policy)
+    }
+
+    /// The first rule matching one of the command's hosts
+    pub fn rule_for(&self, command: &str) -> Option<&SafetyRule> {
+        let hosts = command_hosts(command);
+        self.rules.iter().find(|rule| {
+            rule.targets.iter().any(|pattern| hosts.iter().any(|host| target_matches(pattern, host)))
+        })
+    }
+
+    /// Rewrite a command to stay within the limits for its target. Only the
+    /// first command of a pipeline is changed, in place, so the rest of the
+    /// line keeps its spacing and quoting
+    pub fn apply(&self, command: &str) -> String {
+        let Some(rule) = self.rule_for(command) else {
+            return command.to_string();
+        };
+
+        let tool = tool_name(command);
+        let terms = term_spans(&command[..first_command_end(command)]);
+        let term = |i: usize| &command[terms[i].clone()];
+        let banned: Vec<(&str, bool)> = rule.banned_flags.get(tool)
+            .map(|flags| flags.iter().map(|flag| banned_flag(tool, flag)).collect())
+            .unwrap_or_default();
+        let replacements = rule.replace_flags.get(tool);
+        let max_timing = rule.max_nmap_timing.filter(|_| tool == "nmap");
+        let max_threads = rule.max_threads.filter(|_| THREADED_TOOLS.contains(&tool));
+
+        let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+        let (mut timing_set, mut threads_set) = (false, false);
+        // The tool itself is never a flag
+        let mut i = 1;
+        while i < terms.len() {
+            let text = term(i);
+            let next = terms.get(i + 1).map(|span| unquote(&command[span.clone()]));
+
+            if let Some((_, takes_value)) = banned.iter().find(|(flag, _)| text == *flag || text.starts_with(&format!("{}=", flag))) {
+                // Drop the flag, its value and the whitespace before them
+                let last = if *takes_value && !text.contains('=') && next.is_some() { i + 1 } else { i };
+                edits.push((terms[i - 1].end..terms[last].end, String::new()));
+                i = last + 1;
+                continue;
+            }
+
+            if let (Some(max), Some(level)) = (max_timing, text.strip_prefix("-T")) {
+                // -T4, -Taggressive or -T aggressive
+                let (level, last) = match level {
+                    "" => (next.unwrap_or_default(), i + 1),
+                    level => (level, i),
+                };
+                if let Some(level) = timing_level(level) {
+                    timing_set = true;
+                    if level > max {
+                        edits.push((terms[i].start..terms[last].end, format!("-T{}", max)));
+                    }
+                    i = last + 1;
+                    continue;
+                }
+            }
+
+            if let (Some(max), "-t") = (max_threads, text) {
+                threads_set = true;
+                match next {
+                    Some(threads) if threads.parse::<u32>().is_ok_and(|threads| threads <= max) => {},
+                    Some(_) => edits.push((terms[i + 1].clone(), max.to_string())),
+                    None => edits.push((terms[i].clone(), format!("-t {}", max))),
+                }
+                i += 2;
+                continue;
+            }
+
+            if let Some(replacement) = replacements.and_then(|flags| flags.get(text)) {
+                edits.push((terms[i].clone(), replacement.clone()));
+            }
+            i += 1;
+        }
+
+        // Missing limits go at the end of the tool's own arguments, not the end of a pipeline
+        let end = terms.last().map_or(0, |span| span.end);
+        if let (Some(max), false) = (max_timing, timing_set) {
+            edits.push((end..end, format!(" -T{}", max)));
+        }
+        if let (Some(max), false) = (max_threads, threads_set) {
+            edits.push((end..end, format!(" -t {}", max)));
+        }
+
+        // Apply from the end so earlier offsets stay valid; the stable sort
+        // keeps insertions at the same point in the order they were made
+        edits.sort_by_key(|(range, _)| range.start);
+        let mut rewritten = command.to_string();
+        for (range, replacement) in edits.into_iter().rev() {
+            rewritten.replace_range(range, &replacement);
+        }
+        rewritten
+    }
+}
+
+/// Flags of the throttled tools that take a separate value, which goes when the flag is banned
+const VALUE_FLAGS: &[(&str, &[&str])] = &[
+    ("nmap", &["--script", "--script-args", "-p", "-iL", "-D", "-S", "-e", "-g", "--source-port", "--data-length", "--proxies"]),
+    ("gobuster", &["-w", "-x", "-t", "-u", "--delay"]),
+    ("ffuf", &["-w", "-e", "-t", "-u", "-rate", "-recursion-depth"]),
+    ("dirsearch", &["-w", "-e", "-t", "-u", "--max-rate", "-r"]),
+    ("nuclei", &["-t", "-tags", "-severity", "-rl", "-c"]),
+    ("sqlmap", &["--level", "--risk", "--technique", "--threads", "--os-shell", "--tamper"]),
+];
+
+/// A banned flag and whether it takes a value; flags the table above doesn't
+/// know are written `--flag <value>` in the policy when they do
+fn banned_flag<'a>(tool: &str, flag: &'a str) -> (&'a str, bool) {
+    match flag.strip_suffix("<value>") {
+        Some(flag) => (flag.trim_end(), true),
+        None => (flag, VALUE_FLAGS.iter().any(|(name, flags)| *name == tool && flags.contains(&flag))),
+    }
+}
+
+/// nmap's timing template from its number or name
+fn timing_level(level: &str) -> Option<u8> {
+    const NAMES: &[&str] = &["paranoid", "sneaky", "polite", "normal", "aggressive", "insane"];
+    match level.parse::<u8>() {
+        Ok(level) => Some(level),
+        Err(_) => NAMES.iter().position(|name| name.eq_ignore_ascii_case(level)).map(|level| level as u8),
+    }
+}
+
+/// Byte ranges of a command's whitespace-separated terms, quoted whitespace
+/// staying inside its term
+fn term_spans(command: &str) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut start = None;
+    let mut quote = None;
+    for (i, c) in command.char_indices() {
+        match (quote, c) {
+            (Some(open), _) if c == open => quote = None,
+            (Some(_), _) => {},
+            (None, c) if c.is_whitespace() => {
+                if let Some(start) = start.take() {
+                    spans.push(start..i);
+                }
+                continue;
+            },
+            (None, '\'' | '"') => quote = Some(c),
+            _ => {},
+        }
+        start.get_or_insert(i);
+    }
+    if let Some(start) = start {
+        spans.push(start..command.len());
+    }
+    spans
+}
+
+fn unquote(term: &str) -> &str {
+    term.trim_matches(['\'', '"'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> SafetyPolicy {
+        SafetyPolicy {
+            rules: vec![SafetyRule {
+                name: "test".to_string(),
+                targets: vec!["*.edu".to_string()],
+                max_nmap_timing: Some(2),
+                max_threads: Some(10),
+                banned_flags: BTreeMap::from([
+                    ("nmap".to_string(), vec!["--script".to_string(), "-sU".to_string()]),
+                    ("gobuster".to_string(), vec!["--wildcard".to_string()]),
+                ]),
+                replace_flags: BTreeMap::from([(
+                    "nmap".to_string(),
+                    BTreeMap::from([("-A".to_string(), "-sV".to_string())]),
+                )]),
+            }],
+        }
+    }
+
+    #[test]
+    fn default_policy_covers_named_institutions() {
+        let policy = SafetyPolicy::default();
+        assert_eq!(policy.apply("nmap -T4 www.harvard.org"), "nmap -T2 www.harvard.org");
+        assert_eq!(policy.apply("nmap -T4 nasa.com"), "nmap -T2 nasa.com");
+        assert_eq!(policy.apply("nmap -T4 cs.stanford.edu"), "nmap -T2 cs.stanford.edu");
+        assert_eq!(policy.apply("nmap -T4 submit.example.com"), "nmap -T4 submit.example.com");
+    }
+
+    #[test]
+    fn leaves_other_targets_alone() {
+        assert_eq!(policy().apply("nmap -T5 --script vuln example.com"), "nmap -T5 --script vuln example.com");
+    }
+
+    #[test]
+    fn removes_banned_flags_with_their_values() {
+        assert_eq!(policy().apply("nmap -T2 --script vuln -sU school.edu"), "nmap -T2 school.edu");
+        assert_eq!(policy().apply("nmap -T2 --script=vuln school.edu"), "nmap -T2 school.edu");
+    }
+
+    #[test]
+    fn caps_every_timing_form() {
+        assert_eq!(policy().apply("nmap -T4 school.edu"), "nmap -T2 school.edu");
+        assert_eq!(policy().apply("nmap -T aggressive school.edu"), "nmap -T2 school.edu");
+        assert_eq!(policy().apply("nmap -Tinsane school.edu"), "nmap -T2 school.edu");
+        assert_eq!(policy().apply("nmap -Tpolite school.edu"), "nmap -Tpolite school.edu");
+    }
+
+    #[test]
+    fn adds_missing_limits_to_the_tool_not_the_pipeline() {
+        assert_eq!(policy().apply("nmap -A school.edu | tee scan.txt"), "nmap -sV school.edu -T2 | tee scan.txt");
+        assert_eq!(
+            policy().apply("gobuster dir -u https://school.edu -w list.txt > out.txt"),
+            "gobuster dir -u https://school.edu -w list.txt -t 10 > out.txt",
+        );
+        assert_eq!(policy().apply("gobuster dir -u https://school.edu -t 50"), "gobuster dir -u https://school.edu -t 10");
+    }
+
+    #[test]
+    fn keeps_quoting_and_spacing() {
+        assert_eq!(
+            policy().apply("nmap  --script-args 'http.useragent=a  b' -T5 school.edu"),
+            "nmap  --script-args 'http.useragent=a  b' -T2 school.edu",
+        );
+    }
+}
@@ -0,0 +1,362 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use anyhow::{Result, Context, anyhow};
+use chrono::{DateTime, Local, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::command_monitor::{CommandMonitor, CommandType};
+
+/// A command that is run at a given time, optionally repeating
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledCommand {
+    pub id: String,
+    pub command: String,
+    pub command_type: CommandType,
+    pub next_run: DateTime<Utc>,
+    #[serde(default, with = "interval_secs", rename = "every_secs")]
+    pub every: Option<chrono::Duration>,
+    pub runs: u32,
+}
+
+/// Runs scheduled and recurring commands through the command monitor. The
+/// schedule is persisted as JSON in the work dir so it survives a restart.
+#[derive(Clone)]
+pub struct CommandScheduler {
+    monitor: Arc<CommandMonitor>,
+    path: PathBuf,
+    scheduled: Arc<Mutex<Vec<ScheduledCommand>>>,
+}
+
+impl CommandScheduler {
+    /// Load the schedule from the monitor's work dir, starting empty if there is none
+    pub fn new(monitor: Arc<CommandMonitor>) -> Result<Self> {
+        let path = monitor.get_work_dir().join("schedules.json");
+        let scheduled = if path.exists() {
+            let content = fs::read_to_string(&path)?;
+            serde_json::from_str(&content)
+                .context(format!("Failed to parse schedule {:?}", path))?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            monitor,
+            path,
+            scheduled: Arc::new(Mutex::new(scheduled)),
+        })
+    }
+
+    /// Add a command to the schedule and return its ID
+    pub fn schedule(
+        &self,
+        command: &str,
+        command_type: CommandType,
+        next_run: DateTime<Utc>,
+        every: Option<chrono::Duration>,
+    ) -> Result<String> {
+        let id = Uuid::new_v4().to_string().split('-').next().unwrap_or("sched").to_string();
+
+        self.scheduled.lock().unwrap().push(ScheduledCommand {
+            id: id.clone(),
+            command: command.to_string(),
+            command_type,
+            next_run,
+            every,
+            runs: 0,
+        });
+        self.save()?;
+
+        Ok(id)
+    }
+
+    /// Remove a scheduled command
+    pub fn unschedule(&self, id: &str) -> Result<()> {
+        {
+            let mut scheduled = self.scheduled.lock().unwrap();
+            let before = scheduled.len();
+            scheduled.retain(|entry| entry.id != id);
+
+            if scheduled.len() == before {
+                return Err(anyhow!("No scheduled command with ID: {}", id));
+            }
+        }
+        self.save()
+    }
+
+    /// Get all scheduled commands ordered by next run time
+    pub fn get_scheduled(&self) -> Vec<ScheduledCommand> {
+        let mut scheduled = self.scheduled.lock().unwrap().clone();
+        scheduled.sort_by_key(|entry| entry.next_run);
+        scheduled
+    }
+
+    /// Start the scheduler loop, checking for due commands every second. Each
+    /// due command starts in its own task, so one waiting for approval doesn't
+    /// hold up the others
+    pub async fn start(&self) -> Result<()> {
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+
+        loop {
+            interval.tick().await;
+
+            let due = self.take_due(Utc::now());
+            if due.is_empty() {
+                continue;
+            }
+            if let Err(e) = self.save() {
+                eprintln!("[ERROR] {}", e);
+            }
+
+            for entry in due {
+                let monitor = self.monitor.clone();
+                tokio::spawn(async move {
+                    println!("\n[Hacksor] Running scheduled command ({}): {}", entry.id, entry.command);
+
+                    match monitor.execute_command(&entry.command, entry.command_type.clone()).await {
+                        Ok(cmd_id) => println!("[Hacksor] Monitoring scheduled command execution (ID: {})", cmd_id),
+                        Err(e) => eprintln!("[ERROR] Scheduled command '{}' failed to start: {}", entry.command, e),
+                    }
+                });
+            }
+        }
+    }
+
+    /// Collect due commands, rescheduling recurring ones and dropping one-shot ones
+    fn take_due(&self, now: DateTime<Utc>) -> Vec<ScheduledCommand> {
+        let mut scheduled = self.scheduled.lock().unwrap();
+        let mut due = Vec::new();
+
+        scheduled.retain_mut(|entry| {
+            if entry.next_run > now {
+                return true;
+            }
+
+            entry.runs += 1;
+            due.push(entry.clone());
+
+            match entry.every {
+                Some(every) => {
+                    // Skip missed runs instead of firing them all at once
+                    while entry.next_run <= now {
+                        entry.next_run += every;
+                    }
+                    true
+                },
+                None => false,
+            }
+        });
+
+        due
+    }
+
+    fn save(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(&*self.scheduled.lock().unwrap())?;
+        fs::write(&self.path, content)
+            .context(format!("Failed to save schedule to {:?}", self.path))?;
+        Ok(())
+    }
+}
+
+/// Intervals are stored as whole seconds
+mod interval_secs {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use serde::de::Error;
+
+    pub fn serialize<S: Serializer>(every: &Option<chrono::Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+        every.map(|every| every.num_seconds()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<chrono::Duration>, D::Error> {
+        Option::<i64>::deserialize(deserializer)?
+            .map(|secs| chrono::Duration::try_seconds(secs).ok_or_else(|| D::Error::custom(format!("Invalid interval: {}s", secs))))
+            .transpose()
+    }
+}
+
+/// Parse `!schedule` arguments: `"<command>" [--at <time>] [--every <interval>]`
+pub fn parse_schedule_args(args: &str) -> Result<(String, DateTime<Utc>, Option<chrono::Duration>)> {
+    let args = args.trim();
+
+    // The command itself must be quoted so its own flags aren't mistaken for ours
+    let quote = args.chars().next()
+        .filter(|c| *c == '"' || *c == '\'')
+        .ok_or_else(|| anyhow!("The command must be quoted, e.g. !schedule \"subfinder -d example.com\" --every 24h"))?;
+    let end = args[1..].find(quote)
+        .ok_or_else(|| anyhow!("Missing closing quote around the command"))?;
+
+    let command = args[1..end + 1].trim().to_string();
+    if command.is_empty() {
+        return Err(anyhow!("Empty command"));
+    }
+
+    let mut at = None;
+    let mut every = None;
+    let mut options = args[end + 2..].split_whitespace();
+
+    while let Some(option) = options.next() {
+        let value = options.next()
+            .ok_or_else(|| anyhow!("Missing value for {}", option))?;
+
+        match option {
+            "--at" => at = Some(parse_time(value)?),
+            "--every" => every = Some(parse_interval(value)?),
+            _ => return Err(anyhow!("Unknown option: {}", option)),
+        }
+    }
+
+    if at.is_none() && every.is_none() {
+        return Err(anyhow!("Specify when to run the command with --at and/or --every"));
+    }
+
+    // Recurring commands without a start time first run after one interval
+    let next_run = match (at, every) {
+        (Some(at), _) => at,
+        (None, Some(every)) => Utc::now() + every,
+        (None, None) => unreachable!(),
+    };
+
+    Ok((command, next_run, every))
+}
+
+/// Parse an interval such as `30m`, `6h` or `1d`
+fn parse_interval(value: &str) -> Result<chrono::Duration> {
+    let invalid = || anyhow!("Invalid interval '{}', expected e.g. 30m, 6h or 1d", value);
+    let (split, unit) = value.char_indices().last().ok_or_else(invalid)?;
+    let amount: i64 = value[..split].parse().map_err(|_| invalid())?;
+
+    if amount <= 0 {
+        return Err(anyhow!("Interval must be positive: {}", value));
+    }
+
+    let interval = match unit {
+        's' => chrono::Duration::try_seconds(amount),
+        'm' => chrono::Duration::try_minutes(amount),
+        'h' => chrono::Duration::try_hours(amount),
+        'd' => chrono::Duration::try_days(amount),
+        _ => return Err(anyhow!("Invalid interval unit in '{}', expected s, m, h or d", value)),
+    };
+    interval.ok_or_else(|| anyhow!("Interval is too long: {}", value))
+}
+
+/// Parse a local time of day (`02:00`, next occurrence) or a full `YYYY-MM-DDTHH:MM` timestamp
+fn parse_time(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(time) = NaiveTime::parse_from_str(value, "%H:%M") {
+        let now = Local::now();
+        let mut run = now.date_naive().and_time(time);
+        if run <= now.naive_local() {
+            run += chrono::Duration::days(1);
+        }
+        return local_to_utc(run, value);
+    }
+
+    let run = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M")
+        .map_err(|_| anyhow!("Invalid time '{}', expected HH:MM or YYYY-MM-DDTHH:MM", value))?;
+    local_to_utc(run, value)
+}
+
+fn local_to_utc(time: NaiveDateTime, value: &str) -> Result<DateTime<Utc>> {
+    Local.from_local_datetime(&time)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+        .ok_or_else(|| anyhow!("Time does not exist in the local timezone: {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn test_scheduler() -> (TempDir, CommandScheduler) {
+        let work_dir = TempDir::new().unwrap();
+        let monitor = Arc::new(CommandMonitor::new(work_dir.path().to_path_buf()).unwrap());
+        (work_dir, CommandScheduler::new(monitor).unwrap())
+    }
+
+    #[test]
+    fn parses_schedule_args() {
+        let (command, next_run, every) = parse_schedule_args("\"nmap -sV example.com\" --every 6h").unwrap();
+        assert_eq!(command, "nmap -sV example.com");
+        assert_eq!(every, Some(chrono::Duration::hours(6)));
+        assert!(next_run > Utc::now() + chrono::Duration::minutes(359));
+
+        let (command, next_run, every) = parse_schedule_args("'subfinder -d example.com' --at 2030-01-01T02:00 --every 1d").unwrap();
+        assert_eq!(command, "subfinder -d example.com");
+        assert_eq!(next_run, local_to_utc(NaiveDateTime::parse_from_str("2030-01-01T02:00", "%Y-%m-%dT%H:%M").unwrap(), "").unwrap());
+        assert_eq!(every, Some(chrono::Duration::days(1)));
+    }
+
+    #[test]
+    fn rejects_invalid_schedule_args() {
+        for invalid in [
+            "nmap example.com --every 6h",
+            "\"nmap example.com --every 6h",
+            "\"  \" --every 6h",
+            "\"nmap example.com\"",
+            "\"nmap example.com\" --every",
+            "\"nmap example.com\" --in 6h",
+            "\"nmap example.com\" --at 25:00",
+        ] {
+            assert!(parse_schedule_args(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn takes_due_commands_once() {
+        let (_work_dir, scheduler) = test_scheduler();
+        let now = Utc::now();
+        scheduler.schedule("nmap a.example.com", CommandType::Scanning, now - chrono::Duration::minutes(1), None).unwrap();
+        scheduler.schedule("nmap b.example.com", CommandType::Scanning, now + chrono::Duration::hours(1), None).unwrap();
+
+        let due = scheduler.take_due(now);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].command, "nmap a.example.com");
+        assert!(scheduler.take_due(now).is_empty());
+
+        let scheduled = scheduler.get_scheduled();
+        assert_eq!(scheduled.len(), 1);
+        assert_eq!(scheduled[0].command, "nmap b.example.com");
+    }
+
+    #[test]
+    fn recurring_commands_skip_missed_runs() {
+        let (_work_dir, scheduler) = test_scheduler();
+        let now = Utc::now();
+        let start = now - chrono::Duration::minutes(150);
+        scheduler.schedule("nmap example.com", CommandType::Scanning, start, Some(chrono::Duration::hours(1))).unwrap();
+
+        assert_eq!(scheduler.take_due(now).len(), 1);
+        let entry = &scheduler.get_scheduled()[0];
+        assert_eq!(entry.next_run, start + chrono::Duration::hours(3));
+        assert_eq!(entry.runs, 1);
+    }
+
+    #[test]
+    fn schedules_survive_a_restart() {
+        let (work_dir, scheduler) = test_scheduler();
+        let id = scheduler.schedule("nmap example.com", CommandType::Scanning, Utc::now(), Some(chrono::Duration::minutes(30))).unwrap();
+
+        let monitor = Arc::new(CommandMonitor::new(work_dir.path().to_path_buf()).unwrap());
+        let reloaded = CommandScheduler::new(monitor).unwrap().get_scheduled();
+        assert_eq!(reloaded.len(), 1);
+        assert_eq!(reloaded[0].id, id);
+        assert_eq!(reloaded[0].every, Some(chrono::Duration::minutes(30)));
+    }
+
+    #[test]
+    fn parses_intervals() {
+        assert_eq!(parse_interval("30s").unwrap(), chrono::Duration::seconds(30));
+        assert_eq!(parse_interval("30m").unwrap(), chrono::Duration::minutes(30));
+        assert_eq!(parse_interval("6h").unwrap(), chrono::Duration::hours(6));
+        assert_eq!(parse_interval("1d").unwrap(), chrono::Duration::days(1));
+    }
+
+    #[test]
+    fn rejects_invalid_intervals() {
+        for invalid in ["", "m", "30", "0m", "-5m", "5w", "1.5h", "5µ", "µ", "5ñm", "99999999999999999d"] {
+            assert!(parse_interval(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
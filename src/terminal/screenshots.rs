@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use tokio::process::Command;
+
+use crate::config::ScreenshotConfig;
+use crate::utils::shell_quote;
+
+/// A captured page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Screenshot {
+    pub url: String,
+    pub path: PathBuf,
+}
+
+/// Captures web pages with a headless browser (or gowitness etc.) into `<work_dir>/screenshots`
+#[derive(Debug, Clone)]
+pub struct Screenshotter {
+    config: ScreenshotConfig,
+    dir: PathBuf,
+    /// Pages already captured this session, so repeated findings reuse the image
+    captured: Arc<Mutex<HashMap<String, Option<PathBuf>>>>,
+}
+
+impl Screenshotter {
+    pub fn new(config: ScreenshotConfig, work_dir: &Path) -> Result<Self> {
+        let dir = work_dir.join("screenshots");
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            config,
+            dir,
+            captured: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// Whether a finding is about discovered web content worth capturing
+    pub fn applies_to(&self, title: &str) -> bool {
+        self.config.enabled && (
+            title == "Interesting Paths Discovered"
+                || title == "Potentially Sensitive Paths Discovered"
+                || title == "Subdomains Discovered"
+                || title.starts_with("Admin Interface Detected")
+        )
+    }
+
+    /// Capture up to `max_per_finding` of the given pages; pages that fail to load are skipped
+    pub async fn capture_all(&self, urls: &[String]) -> Vec<Screenshot> {
+        let mut screenshots = Vec::new();
+        for url in urls {
+            if screenshots.len() >= self.config.max_per_finding {
+                break;
+            }
+            if let Some(path) = self.capture(url).await {
+                screenshots.push(Screenshot { url: url.clone(), path });
+            }
+        }
+        screenshots
+    }
+
+    async fn capture(&self, url: &str) -> Option<PathBuf> {
+        if let Some(previous) = self.captured.lock().unwrap().get(url) {
+            return previous.clone();
+        }
+
+        let output = self.dir.join(format!("{}.png", file_stem(url)));
+        let command = self.config.command
+            .replace("{url}", &shell_quote(url))
+            .replace("{output}", &shell_quote(&output.to_string_lossy()));
+
+        let run = Command::new("bash")
+            .arg("-c")
+            .arg(&command)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .status();
+        let succeeded = matches!(
+            tokio::time::timeout(Duration::from_secs(self.config.timeout_secs), run).await,
+            Ok(Ok(status)) if status.success()
+        );
+
+        let result = (succeeded && output.exists()).then_some(output);
+        self.captured.lock().unwrap().insert(url.to_string(), result.clone());
+        result
+    }
+}
+
+/// File name for a page, e.g. `https://example.com/admin` -> `https_example.com_admin`
+fn file_stem(url: &str) -> String {
+    url.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-') { c } else { '_' })
+        .collect::<String>()
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
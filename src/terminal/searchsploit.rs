@@ -0,0 +1,126 @@
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use anyhow::{Result, Context, anyhow};
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use tokio::process::Command;
+
+/// Directory in the session that mirrored exploits are copied to
+pub const EXPLOITS_DIR: &str = "exploits";
+
+/// An Exploit-DB entry from `searchsploit -j`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExploitCandidate {
+    pub edb_id: String,
+    pub title: String,
+    /// `remote`, `local`, `webapps`, `dos`...
+    pub kind: String,
+    pub platform: String,
+    pub published: Option<String>,
+    pub cves: Vec<String>,
+    /// Exploit-DB has confirmed the exploit works
+    pub verified: bool,
+    /// Location of the exploit in the local Exploit-DB copy
+    pub path: String,
+    /// Copy in the session's exploits directory, once mirrored
+    #[serde(default)]
+    pub mirrored: Option<PathBuf>,
+}
+
+impl ExploitCandidate {
+    fn from_json(exploit: &Value) -> Option<Self> {
+        let field = |name: &str| exploit[name].as_str().unwrap_or("").trim().to_string();
+        let edb_id = field("EDB-ID");
+        if edb_id.is_empty() {
+            return None;
+        }
+
+        // Older Exploit-DB copies have a single `Date`
+        let published = [field("Date_Published"), field("Date")].into_iter()
+            .find(|date| !date.is_empty());
+        let cves = field("Codes").split(';')
+            .map(str::trim)
+            .filter(|code| code.starts_with("CVE-"))
+            .map(String::from)
+            .collect();
+
+        Some(Self {
+            title: field("Title"),
+            kind: field("Type"),
+            platform: field("Platform"),
+            published,
+            cves,
+            verified: field("Verified") == "1",
+            path: field("Path"),
+            mirrored: None,
+            edb_id,
+        })
+    }
+
+    /// One line summary, e.g. `EDB-50383 [remote/multiple, verified] Apache 2.4.49 - Path Traversal (CVE-2021-41773)`
+    pub fn summary(&self) -> String {
+        let mut tags = format!("{}/{}", self.kind, self.platform);
+        if self.verified {
+            tags.push_str(", verified");
+        }
+        let mut summary = format!("EDB-{} [{}] {}", self.edb_id, tags, self.title);
+        if !self.cves.is_empty() {
+            summary.push_str(&format!(" ({})", self.cves.join(", ")));
+        }
+        summary
+    }
+}
+
+/// Exploits listed in `searchsploit -j` output, verified ones first. Output
+/// that is not complete JSON yet gives none
+pub fn parse_results(output: &str) -> Vec<ExploitCandidate> {
+    let results: Value = match output.find('{').map(|start| serde_json::from_str(output[start..].trim_end())) {
+        Some(Ok(results)) => results,
+        _ => return Vec::new(),
+    };
+
+    let mut exploits: Vec<ExploitCandidate> = results["RESULTS_EXPLOIT"].as_array().into_iter().flatten()
+        .filter_map(ExploitCandidate::from_json)
+        .collect();
+    exploits.sort_by_key(|exploit| !exploit.verified);
+    exploits
+}
+
+/// Whether a command is an exploit search whose results can be parsed
+pub fn is_json_search(command: &str) -> bool {
+    let mut words = command.split_whitespace();
+    words.next().is_some_and(|tool| tool.rsplit('/').next() == Some("searchsploit"))
+        && words.any(|word| word == "-j" || word == "--json")
+}
+
+/// Copy exploits out of the local Exploit-DB with `searchsploit -m` into `dir`,
+/// returning where each one was written
+pub async fn mirror(exploits: &[ExploitCandidate], dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    std::fs::create_dir_all(dir)
+        .context(format!("Failed to create exploits directory {:?}", dir))?;
+
+    let output = Command::new("searchsploit")
+        .arg("-m")
+        .args(exploits.iter().map(|exploit| &exploit.edb_id))
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .context("Failed to run searchsploit")?;
+    if !output.status.success() {
+        return Err(anyhow!("searchsploit -m failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    // searchsploit keeps the exploit's file name from the Exploit-DB copy
+    let mirrored: Vec<(String, PathBuf)> = exploits.iter()
+        .filter_map(|exploit| {
+            let name = Path::new(&exploit.path).file_name()?;
+            let copy = dir.join(name);
+            copy.exists().then(|| (exploit.edb_id.clone(), copy))
+        })
+        .collect();
+    if mirrored.is_empty() {
+        return Err(anyhow!("searchsploit did not copy any exploits: {}", String::from_utf8_lossy(&output.stdout).trim()));
+    }
+    Ok(mirrored)
+}
@@ -0,0 +1,280 @@
+use std::env;
+use std::net::IpAddr;
+use std::time::Duration;
+use anyhow::{Result, Context, anyhow};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::config::SecurityTrailsConfig;
+use super::command_monitor::{CommandMonitor, Confidence, FindingSeverity, SecurityFinding, create_finding};
+use super::output_analyzer::subdomains_finding;
+
+const API_URL: &str = "https://api.securitytrails.com/v1";
+
+/// Record types SecurityTrails keeps history for
+const RECORD_TYPES: &[&str] = &["a", "aaaa", "mx", "ns", "soa", "txt"];
+
+/// Historical records per type listed in a host's passive intel; the
+/// lookup's log has them all
+const MAX_LISTED_RECORDS: usize = 5;
+
+/// Fetches what SecurityTrails has recorded of a domain's DNS over the years,
+/// and its subdomains, to find infrastructure a mature target has forgotten
+pub struct SecurityTrailsClient {
+    api_key: String,
+    record_types: Vec<String>,
+    include_inactive: bool,
+    client: reqwest::Client,
+}
+
+/// The history of one record type, most recently seen first
+#[derive(Debug, Clone)]
+pub struct DnsHistory {
+    pub record_type: String,
+    pub records: Vec<HistoricalRecord>,
+}
+
+/// Values a record held over a period
+#[derive(Debug, Clone, Deserialize)]
+pub struct HistoricalRecord {
+    /// Objects whose fields depend on the type, e.g. `ip` for A records
+    #[serde(default)]
+    pub values: Vec<Value>,
+    #[serde(default)]
+    pub first_seen: Option<String>,
+    #[serde(default)]
+    pub last_seen: Option<String>,
+    #[serde(default)]
+    pub organizations: Vec<String>,
+}
+
+/// Everything a lookup fetched about a domain
+#[derive(Debug, Clone)]
+pub struct DomainHistory {
+    pub subdomains: Vec<String>,
+    pub history: Vec<DnsHistory>,
+}
+
+impl SecurityTrailsClient {
+    pub fn new(config: &SecurityTrailsConfig) -> Result<Self> {
+        let api_key = config.api_key.clone()
+            .or_else(|| env::var("SECURITYTRAILS_API_KEY").ok())
+            .filter(|key| !key.is_empty())
+            .context("No SecurityTrails API key: set api_key in [securitytrails] or the SECURITYTRAILS_API_KEY environment variable")?;
+
+        let record_types: Vec<String> = config.record_types.iter().map(|kind| kind.to_lowercase()).collect();
+        if let Some(unknown) = record_types.iter().find(|kind| !RECORD_TYPES.contains(&kind.as_str())) {
+            return Err(anyhow!("Unknown record type {} in [securitytrails]; expected one of {}", unknown, RECORD_TYPES.join(", ")));
+        }
+
+        Ok(Self {
+            api_key,
+            record_types,
+            include_inactive: config.include_inactive,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(config.timeout_secs))
+                .build()?,
+        })
+    }
+
+    /// The domain's subdomains and the history of each configured record type
+    pub async fn domain_history(&self, domain: &str) -> Result<DomainHistory> {
+        if domain.parse::<IpAddr>().is_ok() {
+            return Err(anyhow!("SecurityTrails history only covers domains, not IP addresses"));
+        }
+
+        let subdomains = self.subdomains(domain).await?;
+        let mut history = Vec::new();
+        for record_type in &self.record_types {
+            let body = self.get(&format!("/history/{}/dns/{}", domain, record_type), &[]).await?;
+            let mut records: Vec<HistoricalRecord> = serde_json::from_value(body["records"].clone())
+                .context(format!("Unexpected SecurityTrails {} history for {}", record_type.to_uppercase(), domain))?;
+            records.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
+            history.push(DnsHistory { record_type: record_type.clone(), records });
+        }
+        Ok(DomainHistory { subdomains, history })
+    }
+
+    /// Full names of the domain's subdomains; SecurityTrails returns the labels
+    async fn subdomains(&self, domain: &str) -> Result<Vec<String>> {
+        let include_inactive = self.include_inactive.to_string();
+        let body = self.get(
+            &format!("/domain/{}/subdomains", domain),
+            &[("children_only", "false"), ("include_inactive", include_inactive.as_str())],
+        ).await?;
+        let mut subdomains: Vec<String> = body["subdomains"].as_array()
+            .map(|labels| labels.iter()
+                .filter_map(Value::as_str)
+                .map(|label| format!("{}.{}", label, domain).to_lowercase())
+                .collect())
+            .unwrap_or_default();
+        subdomains.sort();
+        subdomains.dedup();
+        Ok(subdomains)
+    }
+
+    async fn get(&self, path: &str, query: &[(&str, &str)]) -> Result<Value> {
+        let response = self.client.get(format!("{}{}", API_URL, path))
+            .header("APIKEY", &self.api_key)
+            .query(query)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to reach SecurityTrails: {}", e))?;
+
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            return Err(match body["message"].as_str() {
+                Some(message) => anyhow!("{} ({})", message, status),
+                None => anyhow!("{}", status),
+            });
+        }
+        Ok(body)
+    }
+}
+
+impl HistoricalRecord {
+    /// The record's values, whatever its type
+    pub fn values(&self) -> Vec<String> {
+        self.values.iter()
+            .filter_map(|value| {
+                ["ip", "ipv6", "host", "nameserver", "value", "email"].iter()
+                    .find_map(|field| value[*field].as_str())
+                    .map(String::from)
+            })
+            .collect()
+    }
+
+    /// `203.0.113.10, 203.0.113.11 (Acme Hosting, 2016-03-01 to 2019-07-15)`
+    fn describe(&self) -> String {
+        let mut details = Vec::new();
+        if !self.organizations.is_empty() {
+            details.push(self.organizations.join(", "));
+        }
+        details.push(format!(
+            "{} to {}",
+            self.first_seen.as_deref().unwrap_or("?"),
+            self.last_seen.as_deref().unwrap_or("?"),
+        ));
+        format!("{} ({})", self.values().join(", "), details.join(", "))
+    }
+}
+
+impl DomainHistory {
+    /// Addresses the domain pointed at before its current A and AAAA
+    /// records, with when and who hosted them
+    fn past_addresses(&self) -> Vec<&HistoricalRecord> {
+        self.history.iter()
+            .filter(|history| history.record_type == "a" || history.record_type == "aaaa")
+            .flat_map(|history| {
+                let current = history.records.first().map(HistoricalRecord::values).unwrap_or_default();
+                history.records.iter()
+                    .skip(1)
+                    .filter(move |record| record.values().iter().any(|value| !current.contains(value)))
+            })
+            .collect()
+    }
+
+    /// The lines of the host's passive intel section
+    pub fn intel(&self) -> Vec<String> {
+        let mut lines = vec![format!("{} subdomain(s) known", self.subdomains.len())];
+        for history in &self.history {
+            let record_type = history.record_type.to_uppercase();
+            match history.records.split_first() {
+                None => lines.push(format!("{}: no history", record_type)),
+                Some((current, past)) => {
+                    lines.push(format!("{} now: {}", record_type, current.describe()));
+                    for record in past.iter().take(MAX_LISTED_RECORDS) {
+                        lines.push(format!("  before: {}", record.describe()));
+                    }
+                    if past.len() > MAX_LISTED_RECORDS {
+                        lines.push(format!("  and {} earlier record(s)", past.len() - MAX_LISTED_RECORDS));
+                    }
+                },
+            }
+        }
+        lines
+    }
+
+    /// One paragraph on the domain, for the chat and the AI
+    pub fn summary(&self, domain: &str) -> String {
+        let mut summary = format!("SecurityTrails knows {} subdomain(s) of {}", self.subdomains.len(), domain);
+        let past: Vec<String> = self.past_addresses().iter().map(|record| record.describe()).collect();
+        if past.is_empty() {
+            summary.push_str(", and no earlier addresses for it.");
+        } else {
+            summary.push_str(&format!(". It used to resolve to {}.", past.join("; ")));
+        }
+        summary
+    }
+
+    /// The full history as written to the lookup's log
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        for history in &self.history {
+            report.push_str(&format!("{} history:\n", history.record_type.to_uppercase()));
+            for record in &history.records {
+                report.push_str(&format!("{}\n", record.describe()));
+            }
+            report.push('\n');
+        }
+        report.push_str(&format!("Subdomains:\n{}\n", self.subdomains.join("\n")));
+        report
+    }
+
+    /// A finding for the addresses the domain used to point at. Servers left
+    /// running there may be unpatched, or the origin behind a CDN or WAF
+    /// answering directly; nothing says they still are, so it is tentative
+    pub fn findings(&self, domain: &str, command_id: &str) -> Vec<SecurityFinding> {
+        let past = self.past_addresses();
+        if past.is_empty() {
+            return Vec::new();
+        }
+        let mut addresses: Vec<String> = past.iter().flat_map(|record| record.values()).collect();
+        addresses.sort();
+        addresses.dedup();
+        let evidence: Vec<String> = past.iter().map(|record| record.describe()).collect();
+        vec![create_finding(
+            "Historical DNS Records Point to Other Infrastructure",
+            &format!(
+                "{} used to resolve to {} address(es) it no longer does: {}. Hosts there may still serve old versions of the site or be its origin behind a CDN",
+                domain, addresses.len(), addresses.join(", "),
+            ),
+            FindingSeverity::Info,
+            command_id,
+            &evidence.join("\n"),
+        ).with_confidence(Confidence::Tentative)]
+    }
+}
+
+/// The command a lookup is recorded as
+pub fn lookup_command(domain: &str) -> String {
+    format!("securitytrails history {}", domain)
+}
+
+/// Fetch a domain's DNS history and subdomains from SecurityTrails and record
+/// them like a command's results: the history in the session's logs and as a
+/// passive intel section on the domain's asset profile, the subdomains no
+/// other source found and the earlier addresses as findings. Returns a summary
+pub async fn passive_recon(client: &SecurityTrailsClient, monitor: &CommandMonitor, target: &str) -> Result<String> {
+    let started = Utc::now();
+    let history = client.domain_history(target).await?;
+    let command_id = monitor.record_lookup(&lookup_command(target), started, &history.report())?;
+
+    let assets = monitor.get_asset_inventory();
+    assets.record_host(target, &command_id);
+    assets.record_intel(target, "SecurityTrails", history.intel());
+    let subdomains: Vec<String> = history.subdomains.iter()
+        .filter(|subdomain| assets.record_subdomain(subdomain, &command_id))
+        .cloned()
+        .collect();
+
+    let summary = history.summary(target);
+    monitor.update_command_summary(&command_id, &summary)?;
+
+    let mut findings = history.findings(target, &command_id);
+    findings.extend(subdomains_finding(&subdomains, &command_id));
+    monitor.record_integration_findings(findings).await?;
+    Ok(summary)
+}
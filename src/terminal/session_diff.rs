@@ -0,0 +1,51 @@
+use super::auto_documentation::{DocumentedFinding, FindingStatus};
+use crate::utils::extract_target_from_command;
+
+/// Findings of a retest compared with a baseline assessment
+#[derive(Debug, Default)]
+pub struct SessionDiff {
+    /// Only in the retest
+    pub new: Vec<DocumentedFinding>,
+    /// Only in the baseline
+    pub fixed: Vec<DocumentedFinding>,
+    /// In both; the retest's copy is kept
+    pub persisting: Vec<DocumentedFinding>,
+}
+
+impl SessionDiff {
+    /// Match findings by title and target, ignoring false positives.
+    /// With `target`, only findings on that host are compared.
+    pub fn compare(baseline: &[DocumentedFinding], retest: &[DocumentedFinding], target: Option<&str>) -> Self {
+        let relevant = |finding: &&DocumentedFinding| {
+            finding.status != FindingStatus::FalsePositive
+                && target.is_none_or(|target| finding_target(finding).as_deref()
+                    .is_some_and(|found| found.eq_ignore_ascii_case(target)))
+        };
+        let baseline: Vec<&DocumentedFinding> = baseline.iter().filter(relevant).collect();
+        let retest: Vec<&DocumentedFinding> = retest.iter().filter(relevant).collect();
+
+        let mut diff = Self::default();
+        for finding in &retest {
+            if baseline.iter().any(|previous| same_issue(previous, finding)) {
+                diff.persisting.push((*finding).clone());
+            } else {
+                diff.new.push((*finding).clone());
+            }
+        }
+        diff.fixed = baseline.iter()
+            .filter(|previous| !retest.iter().any(|finding| same_issue(previous, finding)))
+            .map(|previous| (*previous).clone())
+            .collect();
+
+        diff
+    }
+}
+
+fn finding_target(finding: &DocumentedFinding) -> Option<String> {
+    extract_target_from_command(&finding.discovery_command)
+}
+
+/// Finding IDs differ between sessions, so the same issue is recognised by what and where
+fn same_issue(a: &DocumentedFinding, b: &DocumentedFinding) -> bool {
+    a.title == b.title && finding_target(a) == finding_target(b)
+}
@@ -0,0 +1,39 @@
+use anyhow::{Result, Context};
+use regex::Regex;
+
+use crate::config::SeverityOverride;
+use super::command_monitor::FindingSeverity;
+use super::throttle::target_matches;
+
+/// Client-specific severities that replace the analyzers' defaults
+#[derive(Debug, Clone, Default)]
+pub struct SeverityMatrix {
+    overrides: Vec<(Regex, Option<String>, FindingSeverity)>,
+}
+
+impl SeverityMatrix {
+    pub fn from_config(overrides: &[SeverityOverride]) -> Result<Self> {
+        let overrides = overrides.iter()
+            .map(|entry| {
+                let title = Regex::new(&entry.title)
+                    .context(format!("Invalid severity override pattern: {}", entry.title))?;
+                Ok((title, entry.target.clone(), entry.severity.clone()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { overrides })
+    }
+
+    /// Severity for a finding with this title on this target; the first matching override wins
+    pub fn severity_for(&self, title: &str, target: Option<&str>) -> Option<FindingSeverity> {
+        self.overrides.iter()
+            .find(|(pattern, override_target, _)| {
+                pattern.is_match(title) && match (override_target, target) {
+                    (None, _) => true,
+                    (Some(pattern), Some(target)) => target_matches(pattern, target),
+                    (Some(_), None) => false,
+                }
+            })
+            .map(|(_, _, severity)| severity.clone())
+    }
+}
@@ -0,0 +1,298 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::net::IpAddr;
+use std::time::Duration;
+use anyhow::{Result, Context, anyhow};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::config::ShodanConfig;
+use super::command_monitor::{CommandMonitor, Confidence, FindingSeverity, SecurityFinding, create_finding};
+use super::cve_db::CveMatch;
+use super::cvss;
+
+const API_URL: &str = "https://api.shodan.io";
+
+/// Most CVEs attached to a finding; its description lists them all
+const MAX_RELATED_CVES: usize = 5;
+
+/// Longest banner kept per service in a finding's evidence
+const MAX_BANNER_CHARS: usize = 2000;
+
+/// Looks up what Shodan has already collected about a host, so the first
+/// picture of a target comes without sending it a single packet
+pub struct ShodanClient {
+    api_key: String,
+    client: reqwest::Client,
+}
+
+/// Shodan's record of a host
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShodanHost {
+    pub ip_str: String,
+    #[serde(default)]
+    pub hostnames: Vec<String>,
+    #[serde(default)]
+    pub org: Option<String>,
+    #[serde(default)]
+    pub os: Option<String>,
+    #[serde(default)]
+    pub last_update: Option<String>,
+    /// CVEs Shodan associates with the versions the host's banners disclose
+    #[serde(default)]
+    pub vulns: Vec<String>,
+    #[serde(default)]
+    pub data: Vec<ShodanService>,
+}
+
+/// A service Shodan found listening, with the banner it received
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShodanService {
+    pub port: u16,
+    #[serde(default)]
+    pub transport: Option<String>,
+    #[serde(default)]
+    pub product: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub data: String,
+    #[serde(default)]
+    pub timestamp: Option<String>,
+    #[serde(default)]
+    pub vulns: BTreeMap<String, ShodanVuln>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShodanVuln {
+    /// A number, though older records have it as a string
+    #[serde(default)]
+    pub cvss: Value,
+    #[serde(default)]
+    pub summary: String,
+    /// Shodan confirmed it rather than inferring it from a version
+    #[serde(default)]
+    pub verified: bool,
+}
+
+impl ShodanClient {
+    pub fn new(config: &ShodanConfig) -> Result<Self> {
+        let api_key = config.api_key.clone()
+            .or_else(|| env::var("SHODAN_API_KEY").ok())
+            .filter(|key| !key.is_empty())
+            .context("No Shodan API key: set api_key in [shodan] or the SHODAN_API_KEY environment variable")?;
+
+        Ok(Self {
+            api_key,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(config.timeout_secs))
+                .build()?,
+        })
+    }
+
+    /// Shodan's record of an IP, or of the address a domain resolves to
+    pub async fn host(&self, target: &str) -> Result<ShodanHost> {
+        let ip = match target.parse::<IpAddr>() {
+            Ok(ip) => ip.to_string(),
+            Err(_) => self.resolve(target).await?,
+        };
+        let record = self.get(&format!("/shodan/host/{}", ip), &[]).await?;
+        serde_json::from_value(record).context(format!("Unexpected Shodan record for {}", ip))
+    }
+
+    /// Resolved by Shodan rather than our resolver, so the lookup stays passive
+    async fn resolve(&self, domain: &str) -> Result<String> {
+        let resolved = self.get("/dns/resolve", &[("hostnames", domain)]).await?;
+        resolved.as_object()
+            .and_then(|names| names.values().next())
+            .and_then(Value::as_str)
+            .map(String::from)
+            .ok_or_else(|| anyhow!("Shodan has no address for {}", domain))
+    }
+
+    async fn get(&self, path: &str, query: &[(&str, &str)]) -> Result<Value> {
+        let response = self.client.get(format!("{}{}", API_URL, path))
+            .query(&[("key", self.api_key.as_str())])
+            .query(query)
+            .send()
+            .await
+            // The URL carries the API key
+            .map_err(|e| anyhow!("Failed to reach Shodan: {}", e.without_url()))?;
+
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            return Err(match body["error"].as_str() {
+                Some(error) => anyhow!("{} ({})", error, status),
+                None => anyhow!("{}", status),
+            });
+        }
+        Ok(body)
+    }
+}
+
+impl ShodanHost {
+    /// `22/tcp OpenSSH 8.2p1` for each service
+    fn services(&self) -> Vec<String> {
+        self.data.iter()
+            .map(|service| {
+                let software = [service.product.as_deref(), service.version.as_deref()]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                format!("{} {}", port_name(service), software).trim_end().to_string()
+            })
+            .collect()
+    }
+
+    /// Every CVE reported, with the details the banners carried, highest CVSS first
+    fn cves(&self) -> Vec<(CveMatch, Option<f32>, bool)> {
+        let mut cves: Vec<(CveMatch, Option<f32>, bool)> = self.vulns.iter()
+            .map(|id| {
+                let details = self.data.iter().find_map(|service| service.vulns.get(id));
+                let score = details.and_then(|vuln| match &vuln.cvss {
+                    Value::Number(score) => score.as_f64().map(|score| score as f32),
+                    Value::String(score) => score.parse().ok(),
+                    _ => None,
+                });
+                let cve = CveMatch {
+                    id: id.clone(),
+                    cvss: None,
+                    summary: details.map(|vuln| vuln.summary.clone()).unwrap_or_default(),
+                    references: Vec::new(),
+                };
+                (cve, score, details.is_some_and(|vuln| vuln.verified))
+            })
+            .collect();
+        cves.sort_by(|a, b| b.1.unwrap_or(0.0).total_cmp(&a.1.unwrap_or(0.0)).then_with(|| a.0.id.cmp(&b.0.id)));
+        cves
+    }
+
+    /// One paragraph on the host, for the chat and the AI
+    pub fn summary(&self, target: &str) -> String {
+        let mut summary = format!("Shodan has {} on record for {}", self.ip_str, target);
+        if let Some(last_update) = &self.last_update {
+            summary.push_str(&format!(" (last updated {})", last_update));
+        }
+        if let Some(org) = &self.org {
+            summary.push_str(&format!(", run by {}", org));
+        }
+        if let Some(os) = &self.os {
+            summary.push_str(&format!(", running {}", os));
+        }
+        match self.services() {
+            services if services.is_empty() => summary.push_str(". No services seen."),
+            services => summary.push_str(&format!(". Services: {}.", services.join("; "))),
+        }
+        if !self.vulns.is_empty() {
+            let cves: Vec<String> = self.cves().into_iter().map(|(cve, _, _)| cve.id).collect();
+            summary.push_str(&format!(" Reported CVEs: {}.", cves.join(", ")));
+        }
+        summary
+    }
+
+    /// The full record as written to the lookup's log
+    pub fn report(&self) -> String {
+        let mut report = format!("IP: {}\n", self.ip_str);
+        if !self.hostnames.is_empty() {
+            report.push_str(&format!("Hostnames: {}\n", self.hostnames.join(", ")));
+        }
+        if let Some(org) = &self.org {
+            report.push_str(&format!("Organization: {}\n", org));
+        }
+        if let Some(os) = &self.os {
+            report.push_str(&format!("OS: {}\n", os));
+        }
+        if let Some(last_update) = &self.last_update {
+            report.push_str(&format!("Last update: {}\n", last_update));
+        }
+        for (service, description) in self.data.iter().zip(self.services()) {
+            report.push_str(&format!("\n== {}", description));
+            if let Some(timestamp) = &service.timestamp {
+                report.push_str(&format!(" (seen {})", timestamp));
+            }
+            report.push_str(&format!("\n{}\n", service.data.trim_end()));
+        }
+        if !self.vulns.is_empty() {
+            report.push_str("\nReported CVEs:\n");
+            for (cve, score, _) in self.cves() {
+                let score = score.map(|score| format!(" (CVSS {:.1})", score)).unwrap_or_default();
+                report.push_str(&format!("{}{} {}\n", cve.id, score, cve.summary));
+            }
+        }
+        report
+    }
+
+    /// Findings for the exposed services and for the CVEs Shodan reports. Shodan's
+    /// data can be weeks old and its CVEs are mostly guessed from versions, so
+    /// neither is treated as confirmed
+    pub fn findings(&self, target: &str, command_id: &str) -> Vec<SecurityFinding> {
+        let mut findings = Vec::new();
+
+        let services = self.services();
+        if !services.is_empty() {
+            let banners: Vec<String> = self.data.iter()
+                .map(|service| format!("{}\n{}", port_name(service), service.data.chars().take(MAX_BANNER_CHARS).collect::<String>().trim_end()))
+                .collect();
+            findings.push(create_finding(
+                "Internet-Exposed Services (Shodan)",
+                &format!("Shodan has seen {} service(s) on {} ({}): {}", services.len(), target, self.ip_str, services.join(", ")),
+                FindingSeverity::Info,
+                command_id,
+                &banners.join("\n\n"),
+            ).with_confidence(Confidence::Likely));
+        }
+
+        let cves = self.cves();
+        if let Some((_, top_score, _)) = cves.first() {
+            let verified = cves.iter().any(|(_, _, verified)| *verified);
+            let ids: Vec<&str> = cves.iter().map(|(cve, _, _)| cve.id.as_str()).collect();
+            let mut finding = create_finding(
+                "Known Vulnerabilities Reported by Shodan",
+                &format!("Shodan associates {} CVE(s) with the software on {} ({}): {}", cves.len(), target, self.ip_str, ids.join(", ")),
+                top_score.filter(|score| *score > 0.0).map_or(FindingSeverity::Medium, cvss::severity_for_score),
+                command_id,
+                &self.report(),
+            ).with_confidence(if verified { Confidence::Likely } else { Confidence::Tentative });
+            finding.related_cves = cves.into_iter().take(MAX_RELATED_CVES).map(|(cve, _, _)| cve).collect();
+            findings.push(finding);
+        }
+
+        findings
+    }
+}
+
+/// The command a lookup is recorded as
+pub fn lookup_command(target: &str) -> String {
+    format!("shodan host {}", target)
+}
+
+/// Look a target up on Shodan and record what it has seen like a command's
+/// results: the record in the session's logs, the services in the asset and
+/// technology inventories, and findings for them. Returns the host's summary
+pub async fn passive_recon(client: &ShodanClient, monitor: &CommandMonitor, target: &str) -> Result<String> {
+    let started = Utc::now();
+    let host = client.host(target).await?;
+    let command_id = monitor.record_lookup(&lookup_command(target), started, &host.report())?;
+
+    let assets = monitor.get_asset_inventory();
+    let technologies = monitor.get_tech_inventory();
+    for service in &host.data {
+        assets.record_service(target, &port_name(service), service.product.as_deref(), &command_id);
+        if let Some(product) = &service.product {
+            technologies.record(target, product, service.version.as_deref(), "shodan");
+        }
+    }
+
+    let summary = host.summary(target);
+    monitor.update_command_summary(&command_id, &summary)?;
+    monitor.record_integration_findings(host.findings(target, &command_id)).await?;
+    Ok(summary)
+}
+
+/// `443/tcp`
+fn port_name(service: &ShodanService) -> String {
+    format!("{}/{}", service.port, service.transport.as_deref().unwrap_or("tcp"))
+}
@@ -0,0 +1,109 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use anyhow::{Result, Context};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Serialize, Deserialize};
+
+/// A finding marked as a false positive; matching findings are no longer raised
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Suppression {
+    /// Regex matched against finding titles
+    pub pattern: String,
+    /// Target the suppression applies to; `None` applies to every target
+    pub target: Option<String>,
+    /// Finding that was marked as a false positive
+    #[serde(default)]
+    pub finding_id: Option<String>,
+    pub created: DateTime<Utc>,
+}
+
+impl Suppression {
+    /// Suppress findings with exactly this title on a target
+    pub fn for_finding(finding_id: &str, title: &str, target: Option<String>) -> Self {
+        Self {
+            pattern: format!("^{}$", regex::escape(title)),
+            target,
+            finding_id: Some(finding_id.to_string()),
+            created: Utc::now(),
+        }
+    }
+}
+
+/// False-positive suppressions persisted as JSON in the work dir
+#[derive(Debug, Clone)]
+pub struct SuppressionList {
+    path: PathBuf,
+    entries: Arc<Mutex<Vec<(Suppression, Regex)>>>,
+}
+
+impl SuppressionList {
+    /// Load the list from `path`, starting empty if the file does not exist
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut entries = Vec::new();
+
+        if path.exists() {
+            let content = fs::read_to_string(path)?;
+            let suppressions: Vec<Suppression> = serde_json::from_str(&content)
+                .context(format!("Failed to parse suppression list {:?}", path))?;
+
+            for suppression in suppressions {
+                let regex = Regex::new(&suppression.pattern)
+                    .context(format!("Invalid suppression pattern: {}", suppression.pattern))?;
+                entries.push((suppression, regex));
+            }
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries: Arc::new(Mutex::new(entries)),
+        })
+    }
+
+    /// Add a suppression and persist the list
+    pub fn add(&self, suppression: Suppression) -> Result<()> {
+        let regex = Regex::new(&suppression.pattern)
+            .context(format!("Invalid suppression pattern: {}", suppression.pattern))?;
+        self.entries.lock().unwrap().push((suppression, regex));
+        self.save()
+    }
+
+    /// Drop the suppression created when a finding was marked as a false positive,
+    /// returning whether one existed
+    pub fn remove_for_finding(&self, finding_id: &str) -> Result<bool> {
+        let removed = {
+            let mut entries = self.entries.lock().unwrap();
+            let before = entries.len();
+            entries.retain(|(suppression, _)| suppression.finding_id.as_deref() != Some(finding_id));
+            entries.len() != before
+        };
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// Whether a finding with this title on this target has been marked as a false positive
+    pub fn is_suppressed(&self, title: &str, target: Option<&str>) -> bool {
+        let entries = self.entries.lock().unwrap();
+        entries.iter().any(|(suppression, regex)| {
+            let target_matches = match (&suppression.target, target) {
+                (None, _) => true,
+                (Some(suppressed), Some(target)) => suppressed.eq_ignore_ascii_case(target),
+                (Some(_), None) => false,
+            };
+            target_matches && regex.is_match(title)
+        })
+    }
+
+    fn save(&self) -> Result<()> {
+        let suppressions: Vec<Suppression> = self.entries.lock().unwrap().iter()
+            .map(|(suppression, _)| suppression.clone())
+            .collect();
+        let content = serde_json::to_string_pretty(&suppressions)?;
+        fs::write(&self.path, content)
+            .context(format!("Failed to write suppression list to {:?}", self.path))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+/// CWE identifier and OWASP Top 10 (2021) category for a finding type
+pub fn classify(title: &str) -> (Option<&'static str>, Option<&'static str>) {
+    let title = title.to_lowercase();
+
+    if title.contains("xss") || title.contains("cross-site scripting") {
+        (Some("CWE-79"), Some("A03:2021-Injection"))
+    } else if title.contains("sql injection") {
+        (Some("CWE-89"), Some("A03:2021-Injection"))
+    } else if title.contains("sensitive path") {
+        (Some("CWE-538"), Some("A01:2021-Broken Access Control"))
+    } else if title.contains("version disclosure") {
+        (Some("CWE-200"), Some("A05:2021-Security Misconfiguration"))
+    } else if title.contains("tls protocol") || title.contains("tls cipher") {
+        (Some("CWE-327"), Some("A02:2021-Cryptographic Failures"))
+    } else if title.contains("tls certificate") {
+        (Some("CWE-298"), Some("A02:2021-Cryptographic Failures"))
+    } else if title.contains("hsts") {
+        (Some("CWE-319"), Some("A05:2021-Security Misconfiguration"))
+    } else if title.contains("zone transfer") {
+        (Some("CWE-200"), Some("A05:2021-Security Misconfiguration"))
+    } else if title.contains("spf") || title.contains("dmarc") || title.contains("dkim") {
+        (Some("CWE-290"), Some("A05:2021-Security Misconfiguration"))
+    } else if title.contains("wildcard record") {
+        (None, Some("A05:2021-Security Misconfiguration"))
+    } else if title.contains("leaked credentials") {
+        (Some("CWE-522"), Some("A07:2021-Identification and Authentication Failures"))
+    } else if title.contains("leaked secrets") {
+        (Some("CWE-798"), Some("A07:2021-Identification and Authentication Failures"))
+    } else if title.contains("email addresses") {
+        (Some("CWE-359"), Some("A01:2021-Broken Access Control"))
+    } else if title.contains("open redirect") {
+        (Some("CWE-601"), Some("A01:2021-Broken Access Control"))
+    } else if title.contains("path traversal") || title.contains("local file inclusion") {
+        (Some("CWE-22"), Some("A01:2021-Broken Access Control"))
+    } else if title.contains("file inclusion") {
+        (Some("CWE-98"), Some("A03:2021-Injection"))
+    } else if title.contains("admin interface") {
+        (Some("CWE-1392"), Some("A07:2021-Identification and Authentication Failures"))
+    } else if title.contains("cve") {
+        (None, Some("A06:2021-Vulnerable and Outdated Components"))
+    } else {
+        (None, None)
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+use anyhow::{Result, Context};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Serialize, Deserialize};
+
+/// A technology fingerprinted on a target
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Technology {
+    pub name: String,
+    pub version: Option<String>,
+    /// Tool that reported the technology (whatweb, httpx, nmap)
+    pub source: String,
+    pub first_seen: DateTime<Utc>,
+}
+
+/// Per-target technology inventory shared across the session
+#[derive(Debug, Clone, Default)]
+pub struct TechInventory {
+    targets: Arc<Mutex<BTreeMap<String, Vec<Technology>>>>,
+}
+
+impl TechInventory {
+    /// Record a technology for a target, filling in the version if it was not known yet
+    pub fn record(&self, target: &str, name: &str, version: Option<&str>, source: &str) {
+        let mut targets = self.targets.lock().unwrap();
+        let technologies = targets.entry(target.to_lowercase()).or_default();
+
+        match technologies.iter_mut().find(|tech| tech.name.eq_ignore_ascii_case(name)) {
+            Some(tech) => {
+                if tech.version.is_none() {
+                    tech.version = version.map(String::from);
+                }
+            },
+            None => technologies.push(Technology {
+                name: name.to_string(),
+                version: version.map(String::from),
+                source: source.to_string(),
+                first_seen: Utc::now(),
+            }),
+        }
+    }
+
+    /// Technologies known for a target
+    pub fn technologies_for(&self, target: &str) -> Vec<Technology> {
+        let targets = self.targets.lock().unwrap();
+        targets.get(&target.to_lowercase()).cloned().unwrap_or_default()
+    }
+
+    /// Targets on which a technology was seen (case-insensitive substring match)
+    pub fn targets_using(&self, name: &str) -> Vec<String> {
+        let name = name.to_lowercase();
+        let targets = self.targets.lock().unwrap();
+        targets.iter()
+            .filter(|(_, technologies)| technologies.iter().any(|tech| tech.name.to_lowercase().contains(&name)))
+            .map(|(target, _)| target.clone())
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.lock().unwrap().is_empty()
+    }
+
+    /// One line per target, e.g. `example.com: Apache 2.4.41, PHP 7.4.3`
+    pub fn summary(&self) -> String {
+        let targets = self.targets.lock().unwrap();
+        targets.iter()
+            .map(|(target, technologies)| {
+                let list = technologies.iter()
+                    .map(|tech| match &tech.version {
+                        Some(version) => format!("{} {}", tech.name, version),
+                        None => tech.name.clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{}: {}", target, list)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Write the inventory to a JSON file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(&*self.targets.lock().unwrap())?;
+        fs::write(path, content)
+            .context(format!("Failed to write technology inventory to {:?}", path))?;
+        Ok(())
+    }
+}
+
+/// A fingerprint parsed from scanner output: (target, name, version)
+pub type Fingerprint = (String, String, Option<String>);
+
+/// whatweb plugins that describe the response rather than the technology stack
+const WHATWEB_NOISE: &[&str] = &[
+    "Country", "IP", "Title", "Email", "Meta-Author", "Script", "UncommonHeaders",
+    "Cookies", "HttpOnly", "RedirectLocation", "X-UA-Compatible", "HTML5", "Frame",
+    "PasswordField", "Strict-Transport-Security", "X-Frame-Options", "X-XSS-Protection",
+];
+
+/// Parse whatweb output: `http://host [200 OK] Apache[2.4.41], PHP[7.4.3], ...`
+pub fn parse_whatweb(output: &str) -> Vec<Fingerprint> {
+    static LINE_PATTERN: OnceLock<Regex> = OnceLock::new();
+    static PLUGIN_PATTERN: OnceLock<Regex> = OnceLock::new();
+    static VERSION_PATTERN: OnceLock<Regex> = OnceLock::new();
+    let line_pattern = LINE_PATTERN.get_or_init(|| Regex::new(r"^(https?://\S+)\s+\[\d+[^\]]*\]\s*(.*)$").unwrap());
+    let plugin_pattern = PLUGIN_PATTERN.get_or_init(|| Regex::new(r"([A-Za-z][\w.\- ]*?)(?:\[([^\]]*)\])?(?:,\s*|$)").unwrap());
+    let version_pattern = VERSION_PATTERN.get_or_init(|| Regex::new(r"^\d+(?:\.\d+)*\S*$").unwrap());
+    let mut fingerprints = Vec::new();
+
+    for line in output.lines() {
+        let Some(captures) = line_pattern.captures(line.trim()) else { continue };
+        let target = host_of(&captures[1]);
+
+        for plugin in plugin_pattern.captures_iter(&captures[2]) {
+            let name = plugin[1].trim();
+            if name.is_empty() || WHATWEB_NOISE.contains(&name) {
+                continue;
+            }
+            let version = plugin.get(2)
+                .map(|m| m.as_str())
+                .filter(|value| version_pattern.is_match(value))
+                .map(String::from);
+            fingerprints.push((target.clone(), name.to_string(), version));
+        }
+    }
+
+    fingerprints
+}
+
+/// Parse httpx tech-detect output: `https://host [200] [Title] [Apache HTTP Server:2.4.41,PHP:7.4.3]`
+pub fn parse_httpx(output: &str) -> Vec<Fingerprint> {
+    static LINE_PATTERN: OnceLock<Regex> = OnceLock::new();
+    static GROUP_PATTERN: OnceLock<Regex> = OnceLock::new();
+    let line_pattern = LINE_PATTERN.get_or_init(|| Regex::new(r"^(https?://\S+)((?:\s+\[[^\]]*\])+)\s*$").unwrap());
+    let group_pattern = GROUP_PATTERN.get_or_init(|| Regex::new(r"\[([^\]]*)\]").unwrap());
+    let mut fingerprints = Vec::new();
+
+    for line in output.lines() {
+        let Some(captures) = line_pattern.captures(line.trim()) else { continue };
+        let target = host_of(&captures[1]);
+
+        // The technology list is printed last
+        let Some(technologies) = group_pattern.captures_iter(&captures[2]).last() else { continue };
+        let technologies = technologies[1].trim();
+        if technologies.is_empty() || technologies.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+
+        for tech in technologies.split(',') {
+            let (name, version) = match tech.split_once(':') {
+                Some((name, version)) => (name.trim(), Some(version.trim().to_string())),
+                None => (tech.trim(), None),
+            };
+            if !name.is_empty() {
+                fingerprints.push((target.clone(), name.to_string(), version));
+            }
+        }
+    }
+
+    fingerprints
+}
+
+/// Parse nmap service detection: `80/tcp open  http  Apache httpd 2.4.41 ((Ubuntu))`.
+/// Output can arrive in chunks, so `target` carries the host the previous chunk
+/// ended on and is left on the one this chunk ends on
+pub fn parse_nmap_services(output: &str, target: &mut Option<String>) -> Vec<Fingerprint> {
+    static HOST_PATTERN: OnceLock<Regex> = OnceLock::new();
+    static SERVICE_PATTERN: OnceLock<Regex> = OnceLock::new();
+    let host_pattern = HOST_PATTERN.get_or_init(|| Regex::new(r"Nmap scan report for (?:(\S+) \((\S+)\)|(\S+))").unwrap());
+    let service_pattern = SERVICE_PATTERN.get_or_init(|| Regex::new(r"^\d+/(?:tcp|udp)\s+open\s+\S+\s+(.+)$").unwrap());
+    let mut fingerprints = Vec::new();
+
+    for line in output.lines() {
+        if let Some(captures) = host_pattern.captures(line) {
+            *target = captures.get(1).or(captures.get(3)).map(|m| m.as_str().to_lowercase());
+            continue;
+        }
+
+        let (Some(target), Some(captures)) = (target.as_ref(), service_pattern.captures(line.trim())) else { continue };
+
+        // Product words run until the first token that looks like a version
+        let mut product = Vec::new();
+        let mut version = None;
+        for term in captures[1].split_whitespace() {
+            if term.starts_with('(') {
+                break;
+            }
+            if term.chars().next().is_some_and(|c| c.is_ascii_digit()) {
+                version = Some(term.to_string());
+                break;
+            }
+            product.push(term);
+        }
+
+        if !product.is_empty() {
+            fingerprints.push((target.clone(), product.join(" "), version));
+        }
+    }
+
+    fingerprints
+}
+
+fn host_of(url: &str) -> String {
+    let host = url.trim_start_matches("http://").trim_start_matches("https://");
+    host.split(['/', ':']).next().unwrap_or(host).to_lowercase()
+}
@@ -0,0 +1,134 @@
+use std::fmt;
+use anyhow::{Result, Context, anyhow};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDateTime, NaiveTime, Weekday};
+
+use crate::config::TestingWindowConfig;
+
+/// Hours the rules of engagement allow testing in, in local time
+#[derive(Debug, Clone)]
+pub struct TestingWindow {
+    start: NaiveTime,
+    end: NaiveTime,
+    /// Days the window opens on; empty means every day
+    days: Vec<Weekday>,
+}
+
+impl TestingWindow {
+    pub fn from_config(config: &TestingWindowConfig) -> Result<Self> {
+        let parse_time = |time: &str| NaiveTime::parse_from_str(time.trim(), "%H:%M")
+            .context(format!("Invalid testing window time {:?}, expected HH:MM", time));
+        let days = config.days.iter()
+            .map(|day| day.trim().parse::<Weekday>()
+                .map_err(|_| anyhow!("Invalid testing window day {:?}, expected e.g. Mon", day)))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            start: parse_time(&config.start)?,
+            end: parse_time(&config.end)?,
+            days,
+        })
+    }
+
+    pub fn is_open(&self, now: DateTime<Local>) -> bool {
+        let time = now.time();
+        if self.start < self.end {
+            return self.start <= time && time < self.end && self.opens_on(now.weekday());
+        }
+
+        // The window spans midnight (or the whole day when start == end) and
+        // belongs to the day it opened on
+        if time >= self.start {
+            self.opens_on(now.weekday())
+        } else if time < self.end {
+            self.opens_on(now.weekday().pred())
+        } else {
+            false
+        }
+    }
+
+    /// When the window next opens, or `now` if it is open
+    pub fn next_open(&self, now: DateTime<Local>) -> NaiveDateTime {
+        if self.is_open(now) {
+            return now.naive_local();
+        }
+
+        (0..=7)
+            .map(|days| (now.date_naive() + Duration::days(days)).and_time(self.start))
+            .find(|opens| *opens > now.naive_local() && self.opens_on(opens.weekday()))
+            .unwrap_or_else(|| now.naive_local())
+    }
+
+    fn opens_on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+}
+
+impl fmt::Display for TestingWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start.format("%H:%M"), self.end.format("%H:%M"))?;
+        if !self.days.is_empty() {
+            let days = self.days.iter().map(|day| day.to_string()).collect::<Vec<_>>().join(",");
+            write!(f, " {}", days)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn window(start: &str, end: &str, days: &[&str]) -> TestingWindow {
+        TestingWindow::from_config(&TestingWindowConfig {
+            start: start.to_string(),
+            end: end.to_string(),
+            days: days.iter().map(|day| day.to_string()).collect(),
+        }).unwrap()
+    }
+
+    /// A local time in the week of Monday 5 January 2026, clear of any DST change
+    fn at(day: u32, time: &str) -> DateTime<Local> {
+        let naive = NaiveDateTime::parse_from_str(&format!("2026-01-{:02} {}", day, time), "%Y-%m-%d %H:%M").unwrap();
+        Local.from_local_datetime(&naive).unwrap()
+    }
+
+    #[test]
+    fn same_day_window() {
+        let window = window("09:00", "17:00", &["Mon"]);
+        assert!(!window.is_open(at(5, "08:59")));
+        assert!(window.is_open(at(5, "09:00")));
+        assert!(window.is_open(at(5, "16:59")));
+        assert!(!window.is_open(at(5, "17:00")));
+        assert!(!window.is_open(at(6, "10:00")));
+    }
+
+    #[test]
+    fn overnight_window_belongs_to_the_day_it_opens() {
+        let window = window("22:00", "06:00", &["Mon"]);
+        assert!(window.is_open(at(5, "23:00")));
+        assert!(window.is_open(at(6, "05:59")));
+        assert!(!window.is_open(at(6, "06:00")));
+        // Monday morning is the end of Sunday's window
+        assert!(!window.is_open(at(5, "05:00")));
+        assert!(!window.is_open(at(6, "23:00")));
+    }
+
+    #[test]
+    fn equal_start_and_end_is_a_whole_day() {
+        let window = window("08:00", "08:00", &["Mon"]);
+        assert!(!window.is_open(at(5, "07:59")));
+        assert!(window.is_open(at(5, "08:00")));
+        assert!(window.is_open(at(6, "07:59")));
+        assert!(!window.is_open(at(6, "08:00")));
+    }
+
+    #[test]
+    fn next_open_skips_disallowed_days() {
+        let window = window("09:00", "17:00", &["Mon", "Wed"]);
+        assert_eq!(window.next_open(at(5, "10:00")), at(5, "10:00").naive_local());
+        assert_eq!(window.next_open(at(5, "08:00")), at(5, "09:00").naive_local());
+        assert_eq!(window.next_open(at(5, "18:00")), at(7, "09:00").naive_local());
+        assert_eq!(window.next_open(at(10, "12:00")), at(12, "09:00").naive_local());
+    }
+}
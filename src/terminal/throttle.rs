@@ -0,0 +1,284 @@
+use std::process::Command;
+
+use crate::config::RateLimitConfig;
+use crate::utils::term_host;
+
+/// Network tools without a rate option of their own, shaped with trickle when
+/// a bandwidth cap is set. trickle works by preloading into dynamically linked
+/// programs, so anything else is left alone rather than wrapped blindly
+const TRICKLE_TOOLS: &[&str] = &[
+    "curl", "wget", "nikto", "wpscan", "whatweb", "wfuzz", "dirb", "feroxbuster",
+    "hydra", "medusa", "ncrack", "sslscan", "sslyze", "testssl.sh", "enum4linux",
+    "smbclient", "httpx", "subfinder", "amass", "git",
+];
+
+/// Injects rate limiting into commands so one setting controls how hard
+/// every tool hits a target
+#[derive(Debug, Clone)]
+pub struct Throttle {
+    config: RateLimitConfig,
+    /// trickle is installed, so bandwidth caps can be applied
+    trickle: bool,
+}
+
+impl Throttle {
+    pub fn new(config: RateLimitConfig) -> Self {
+        let trickle = has_bandwidth_cap(&config) && trickle_installed();
+        Self { config, trickle }
+    }
+
+    /// Whether a bandwidth cap is configured but can't be applied without trickle
+    pub fn bandwidth_unavailable(&self) -> bool {
+        has_bandwidth_cap(&self.config) && !self.trickle
+    }
+
+    /// Get the requests per minute and bandwidth cap that apply to a command
+    fn limits_for(&self, command: &str) -> (u32, Option<u32>) {
+        let targets = command_hosts(command);
+
+        for rule in &self.config.targets {
+            if targets.iter().any(|target| target_matches(&rule.pattern, target)) {
+                return (rule.requests_per_minute, rule.bandwidth_kbps.or(self.config.bandwidth_kbps));
+            }
+        }
+
+        (self.config.requests_per_minute, self.config.bandwidth_kbps)
+    }
+
+    /// Rewrite a command to respect the configured limits
+    pub fn apply(&self, command: &str) -> String {
+        if !self.config.enabled {
+            return command.to_string();
+        }
+
+        let (requests_per_minute, bandwidth_kbps) = self.limits_for(command);
+        let requests_per_minute = requests_per_minute.max(1);
+        let whole_per_second = whole_per_second(requests_per_minute);
+        let connections = self.config.concurrent_connections.max(1);
+
+        // The tool's own rate and concurrency options
+        let flags: Vec<(&str, String)> = match tool_name(command) {
+            "nmap" => vec![
+                ("--max-rate", per_second(requests_per_minute)),
+                ("--max-parallelism", connections.to_string()),
+            ],
+            "ffuf" => vec![
+                ("-rate", whole_per_second.to_string()),
+                ("-t", connections.to_string()),
+            ],
+            // gobuster's delay applies per thread
+            "gobuster" => {
+                let threads = command[..first_command_end(command)].split_whitespace()
+                    .skip_while(|term| *term != "-t")
+                    .nth(1)
+                    .and_then(|threads| threads.parse::<u64>().ok())
+                    .unwrap_or(connections as u64);
+                // In u64 so a large -t can't overflow, clamped back to what fits
+                let delay = 60_000u64.saturating_mul(threads) / requests_per_minute as u64;
+                vec![
+                    ("-t", connections.to_string()),
+                    ("--delay", format!("{}ms", delay.min(u32::MAX as u64))),
+                ]
+            },
+            "dirsearch" => vec![
+                ("--max-rate", whole_per_second.to_string()),
+                ("-t", connections.to_string()),
+            ],
+            "nuclei" => vec![
+                ("-rlm", requests_per_minute.to_string()),
+                ("-c", connections.to_string()),
+            ],
+            // sqlmap allows at most 10 threads
+            "sqlmap" => vec![
+                ("--delay", format!("{:.2}", 60.0 / requests_per_minute as f64)),
+                ("--threads", connections.min(10).to_string()),
+            ],
+            _ => Vec::new(),
+        };
+
+        if !flags.is_empty() {
+            // Add them to the tool itself rather than the end of a pipeline
+            let end = first_command_end(command);
+            let mut throttled = command[..end].trim_end().to_string();
+            for (flag, value) in flags {
+                if !has_flag(&command[..end], flag) {
+                    throttled.push_str(&format!(" {} {}", flag, value));
+                }
+            }
+            if end < command.len() {
+                throttled.push(' ');
+                throttled.push_str(&command[end..]);
+            }
+            return throttled;
+        }
+
+        match bandwidth_kbps {
+            // Network tools without a rate flag are shaped at the socket level
+            Some(kbps) if self.trickle && TRICKLE_TOOLS.contains(&tool_name(command)) => {
+                format!("trickle -s -d {} -u {} {}", kbps, kbps, command)
+            },
+            _ => command.to_string(),
+        }
+    }
+}
+
+/// Whether throttling is on with a bandwidth cap for some target
+fn has_bandwidth_cap(config: &RateLimitConfig) -> bool {
+    config.enabled && (config.bandwidth_kbps.is_some() || config.targets.iter().any(|rule| rule.bandwidth_kbps.is_some()))
+}
+
+pub fn trickle_installed() -> bool {
+    Command::new("which").arg("trickle").output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// A per-minute rate as requests per second for nmap's `--max-rate`, which
+/// takes fractions: rounded down to two decimals so the limit is never exceeded
+fn per_second(requests_per_minute: u32) -> String {
+    let hundredths = (requests_per_minute as u64 * 100 / 60).max(1);
+    let rate = format!("{}.{:02}", hundredths / 100, hundredths % 100);
+    rate.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// A per-minute rate for tools taking whole requests per second (ffuf, dirsearch):
+/// the nearest one, and at least 1, so rates under 60 a minute run at 1 a second
+fn whole_per_second(requests_per_minute: u32) -> u32 {
+    ((requests_per_minute + 30) / 60).max(1)
+}
+
+/// Get the binary name of a command, ignoring a leading sudo and the binary's directory
+pub fn tool_name(command: &str) -> &str {
+    let command = command.trim_start();
+    let command = command.strip_prefix("sudo ").map_or(command, str::trim_start);
+    // A configured tool's path is quoted when it has spaces in it
+    let binary = match command.chars().next() {
+        Some(quote @ ('\'' | '"')) => command[1..].split(quote).next().unwrap_or(""),
+        _ => command.split_whitespace().next().unwrap_or(""),
+    };
+    binary.rsplit('/').next().unwrap_or(binary)
+}
+
+/// Byte offset where the first command of a pipeline, chain or redirection ends
+pub fn first_command_end(command: &str) -> usize {
+    let mut quote = None;
+    for (i, c) in command.char_indices() {
+        match (quote, c) {
+            (Some(open), _) if c == open => quote = None,
+            (Some(_), _) => {},
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '|' | ';' | '&' | '>' | '<') => return i,
+            _ => {},
+        }
+    }
+    command.len()
+}
+
+/// Whether a command already sets a flag, as `flag value` or `flag=value`
+pub fn has_flag(command: &str, flag: &str) -> bool {
+    command.split_whitespace()
+        .any(|term| term == flag || term.starts_with(&format!("{}=", flag)))
+}
+
+/// Every term of a command with any URL scheme, path and port removed, so
+/// hosts can be matched against target patterns
+pub fn command_hosts(command: &str) -> Vec<&str> {
+    command.split_whitespace().map(term_host).collect()
+}
+
+/// Match a target against a pattern such as `example.com` or `*.example.com`;
+/// `example.*` stands for the name under any top-level domain
+pub fn target_matches(pattern: &str, target: &str) -> bool {
+    if let Some(pattern) = pattern.strip_suffix(".*") {
+        return target.rsplit_once('.').is_some_and(|(name, _)| target_matches(pattern, name));
+    }
+    match pattern.strip_prefix("*.") {
+        Some(domain) => target == domain || target.ends_with(&format!(".{}", domain)),
+        None => target == pattern,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn throttle(requests_per_minute: u32) -> Throttle {
+        Throttle::new(RateLimitConfig {
+            requests_per_minute,
+            concurrent_connections: 10,
+            enabled: true,
+            bandwidth_kbps: None,
+            targets: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn converts_rates_without_truncating() {
+        assert_eq!(per_second(60), "1");
+        assert_eq!(per_second(30), "0.5");
+        assert_eq!(per_second(90), "1.5");
+        assert_eq!(per_second(100), "1.66");
+        assert_eq!(per_second(1), "0.01");
+        assert_eq!(per_second(6000), "100");
+    }
+
+    #[test]
+    fn rounds_whole_rates_to_at_least_one() {
+        assert_eq!(whole_per_second(1), 1);
+        assert_eq!(whole_per_second(89), 1);
+        assert_eq!(whole_per_second(90), 2);
+        assert_eq!(whole_per_second(150), 3);
+    }
+
+    #[test]
+    fn adds_rate_flags_to_the_tool() {
+        assert_eq!(
+            throttle(30).apply("nmap -sV example.com | tee scan.txt"),
+            "nmap -sV example.com --max-rate 0.5 --max-parallelism 10 | tee scan.txt",
+        );
+        assert_eq!(throttle(90).apply("nuclei -u https://example.com"), "nuclei -u https://example.com -rlm 90 -c 10");
+        assert_eq!(throttle(90).apply("ffuf -u https://example.com/FUZZ -t 5"), "ffuf -u https://example.com/FUZZ -t 5 -rate 2");
+    }
+
+    #[test]
+    fn delays_gobuster_per_thread() {
+        assert_eq!(
+            throttle(600).apply("gobuster dir -u https://example.com -w words.txt"),
+            "gobuster dir -u https://example.com -w words.txt -t 10 --delay 1000ms",
+        );
+        assert_eq!(
+            throttle(600).apply("gobuster dir -u https://example.com -t 4"),
+            "gobuster dir -u https://example.com -t 4 --delay 400ms",
+        );
+        assert_eq!(
+            throttle(1).apply("gobuster dir -u https://example.com -t 4294967295"),
+            format!("gobuster dir -u https://example.com -t 4294967295 --delay {}ms", u32::MAX),
+        );
+    }
+
+    #[test]
+    fn delays_sqlmap_with_at_most_ten_threads() {
+        let throttle = Throttle::new(RateLimitConfig {
+            requests_per_minute: 120,
+            concurrent_connections: 20,
+            enabled: true,
+            bandwidth_kbps: None,
+            targets: Vec::new(),
+        });
+        assert_eq!(
+            throttle.apply("sqlmap -u 'https://example.com/?id=1' --batch"),
+            "sqlmap -u 'https://example.com/?id=1' --batch --delay 0.50 --threads 10",
+        );
+    }
+
+    #[test]
+    fn only_checks_the_first_command_for_flags() {
+        assert_eq!(
+            throttle(600).apply("gobuster dir -u https://example.com | grep -t 200"),
+            "gobuster dir -u https://example.com -t 10 --delay 1000ms | grep -t 200",
+        );
+        assert_eq!(
+            throttle(120).apply("sqlmap -u https://example.com/?id=1 && echo --delay"),
+            "sqlmap -u https://example.com/?id=1 --delay 0.50 --threads 10 && echo --delay",
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use anyhow::{Result, Context};
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    CommandStarted,
+    CommandFinished,
+    FindingCreated,
+    /// The user approved or rejected a command, or changed a finding
+    Decision,
+}
+
+/// One entry of the engagement timeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Activity {
+    pub timestamp: DateTime<Utc>,
+    pub kind: ActivityKind,
+    pub description: String,
+    /// Command or finding the entry is about
+    #[serde(default)]
+    pub reference: Option<String>,
+}
+
+/// Chronological activity log of a session, appended to `timeline.jsonl` as it happens
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    file: Arc<Mutex<File>>,
+    entries: Arc<Mutex<Vec<Activity>>>,
+}
+
+impl Timeline {
+    /// Load the entries already in `path` (a resumed session) and append new ones to it
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut entries = Vec::new();
+        if path.exists() {
+            for line in fs::read_to_string(path)?.lines().filter(|line| !line.trim().is_empty()) {
+                let activity: Activity = serde_json::from_str(line)
+                    .context(format!("Failed to parse timeline entry in {:?}", path))?;
+                entries.push(activity);
+            }
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .context(format!("Failed to open timeline {:?}", path))?;
+
+        Ok(Self {
+            file: Arc::new(Mutex::new(file)),
+            entries: Arc::new(Mutex::new(entries)),
+        })
+    }
+
+    pub fn record(&self, kind: ActivityKind, description: impl Into<String>, reference: Option<&str>) {
+        let activity = Activity {
+            timestamp: Utc::now(),
+            kind,
+            description: description.into(),
+            reference: reference.map(String::from),
+        };
+
+        match serde_json::to_string(&activity) {
+            Ok(line) => {
+                if let Err(e) = writeln!(self.file.lock().unwrap(), "{}", line) {
+                    eprintln!("Failed to write timeline entry: {}", e);
+                }
+            },
+            Err(e) => eprintln!("Failed to serialize timeline entry: {}", e),
+        }
+
+        self.entries.lock().unwrap().push(activity);
+    }
+
+    /// Every entry so far, oldest first
+    pub fn entries(&self) -> Vec<Activity> {
+        self.entries.lock().unwrap().clone()
+    }
+}
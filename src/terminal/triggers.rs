@@ -0,0 +1,92 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use anyhow::{Result, Context, anyhow};
+
+use super::analyzer_rules::{AnalyzerRule, CompiledRule};
+use super::command_monitor::{Confidence, FindingSeverity};
+
+/// Engagement-specific finding triggers added at runtime, persisted as JSON in the work dir
+#[derive(Debug, Clone)]
+pub struct TriggerList {
+    path: PathBuf,
+    triggers: Arc<Mutex<Vec<CompiledRule>>>,
+}
+
+impl TriggerList {
+    /// Load the triggers from `path`, starting empty if the file does not exist
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut triggers = Vec::new();
+
+        if path.exists() {
+            let content = fs::read_to_string(path)?;
+            let rules: Vec<AnalyzerRule> = serde_json::from_str(&content)
+                .context(format!("Failed to parse trigger list {:?}", path))?;
+
+            for rule in rules {
+                triggers.push(CompiledRule::compile(rule)?);
+            }
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            triggers: Arc::new(Mutex::new(triggers)),
+        })
+    }
+
+    /// Raise a finding whenever `text` appears in command output; returns the trigger's name
+    pub fn add(&self, text: &str, severity: FindingSeverity, title: &str) -> Result<String> {
+        let name = {
+            let triggers = self.triggers.lock().unwrap();
+            let mut n = triggers.len() + 1;
+            while triggers.iter().any(|trigger| trigger.rule.name == format!("trigger-{}", n)) {
+                n += 1;
+            }
+            format!("trigger-{}", n)
+        };
+
+        let rule = AnalyzerRule {
+            name: name.clone(),
+            pattern: regex::escape(text),
+            severity,
+            title: title.to_string(),
+            description: Some(format!("Output contained \"{}\"", text)),
+            cvss_vector: None,
+            cwe: None,
+            owasp: None,
+            confidence: Confidence::Likely,
+        };
+
+        self.triggers.lock().unwrap().push(CompiledRule::compile(rule)?);
+        self.save()?;
+        Ok(name)
+    }
+
+    /// Remove a trigger by name
+    pub fn remove(&self, name: &str) -> Result<()> {
+        {
+            let mut triggers = self.triggers.lock().unwrap();
+            let before = triggers.len();
+            triggers.retain(|trigger| trigger.rule.name != name);
+            if triggers.len() == before {
+                return Err(anyhow!("Trigger not found: {}", name));
+            }
+        }
+        self.save()
+    }
+
+    /// Current triggers, in the order they were added
+    pub fn rules(&self) -> Vec<CompiledRule> {
+        self.triggers.lock().unwrap().clone()
+    }
+
+    fn save(&self) -> Result<()> {
+        let rules: Vec<AnalyzerRule> = self.triggers.lock().unwrap().iter()
+            .map(|trigger| trigger.rule.clone())
+            .collect();
+        let content = serde_json::to_string_pretty(&rules)?;
+        fs::write(&self.path, content)
+            .context(format!("Failed to write trigger list to {:?}", self.path))?;
+        Ok(())
+    }
+}
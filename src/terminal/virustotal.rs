@@ -0,0 +1,273 @@
+use std::collections::BTreeSet;
+use std::env;
+use std::net::IpAddr;
+use std::time::Duration;
+use anyhow::{Result, Context, anyhow};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::config::VirusTotalConfig;
+use super::command_monitor::{CommandMonitor, Confidence, FindingSeverity, SecurityFinding, create_finding};
+use super::output_analyzer::subdomains_finding;
+
+const API_URL: &str = "https://www.virustotal.com/vtapi/v2";
+
+/// Resolutions listed in a host's passive intel, most recent first; the
+/// lookup's log has them all
+const MAX_LISTED_RESOLUTIONS: usize = 10;
+
+/// Flagged URLs listed in a host's passive intel and the AI's summary
+const MAX_LISTED_URLS: usize = 5;
+
+/// Fetches VirusTotal's report on a domain: the addresses it has resolved to,
+/// the URLs on it antivirus engines flagged and how vendors categorise it
+pub struct VirusTotalClient {
+    api_key: String,
+    client: reqwest::Client,
+}
+
+/// VirusTotal's report on a domain
+#[derive(Debug, Clone, Deserialize)]
+pub struct DomainReport {
+    #[serde(default)]
+    pub resolutions: Vec<Resolution>,
+    #[serde(default)]
+    pub detected_urls: Vec<DetectedUrl>,
+    #[serde(default)]
+    pub subdomains: Vec<String>,
+    /// Vendors' categories, as `"<vendor> category": "<category>"` keys
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Resolution {
+    pub ip_address: String,
+    #[serde(default)]
+    pub last_resolved: Option<String>,
+}
+
+/// A URL on the domain that at least one engine flagged
+#[derive(Debug, Clone, Deserialize)]
+pub struct DetectedUrl {
+    pub url: String,
+    #[serde(default)]
+    pub positives: u32,
+    #[serde(default)]
+    pub total: u32,
+    #[serde(default)]
+    pub scan_date: Option<String>,
+}
+
+impl VirusTotalClient {
+    pub fn new(config: &VirusTotalConfig) -> Result<Self> {
+        let api_key = config.api_key.clone()
+            .or_else(|| env::var("VIRUSTOTAL_API_KEY").ok())
+            .filter(|key| !key.is_empty())
+            .context("No VirusTotal API key: set api_key in [virustotal] or the VIRUSTOTAL_API_KEY environment variable")?;
+
+        Ok(Self {
+            api_key,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(config.timeout_secs))
+                .build()?,
+        })
+    }
+
+    pub async fn domain_report(&self, domain: &str) -> Result<DomainReport> {
+        if domain.parse::<IpAddr>().is_ok() {
+            return Err(anyhow!("VirusTotal domain reports only cover domains, not IP addresses"));
+        }
+
+        let response = self.client.get(format!("{}/domain/report", API_URL))
+            .query(&[("apikey", self.api_key.as_str()), ("domain", domain)])
+            .send()
+            .await
+            // The URL carries the API key
+            .map_err(|e| anyhow!("Failed to reach VirusTotal: {}", e.without_url()))?;
+
+        // The public API answers 204 with no body once its quota is used up
+        let status = response.status();
+        match status.as_u16() {
+            204 => return Err(anyhow!("VirusTotal's request quota is used up; try again in a minute")),
+            403 => return Err(anyhow!("VirusTotal rejected the API key ({})", status)),
+            _ if !status.is_success() => return Err(anyhow!("{}", status)),
+            _ => {},
+        }
+
+        let body: Value = response.json().await.context("VirusTotal returned an unexpected response")?;
+        if body["response_code"].as_i64() != Some(1) {
+            let message = body["verbose_msg"].as_str().unwrap_or("no report");
+            return Err(anyhow!("VirusTotal has no report on {}: {}", domain, message));
+        }
+        serde_json::from_value(body).context(format!("Unexpected VirusTotal report for {}", domain))
+    }
+}
+
+impl DomainReport {
+    /// Distinct categories across vendors, e.g. `information technology`
+    pub fn categories(&self) -> Vec<String> {
+        let mut categories = BTreeSet::new();
+        for (key, value) in &self.other {
+            match value {
+                Value::String(category) if key.ends_with(" category") => {
+                    categories.insert(category.to_lowercase());
+                },
+                // Older reports list them without vendors
+                Value::Array(listed) if key == "categories" => {
+                    categories.extend(listed.iter().filter_map(Value::as_str).map(str::to_lowercase));
+                },
+                _ => {},
+            }
+        }
+        categories.into_iter().collect()
+    }
+
+    /// Resolutions, most recent first
+    fn resolutions(&self) -> Vec<&Resolution> {
+        let mut resolutions: Vec<&Resolution> = self.resolutions.iter().collect();
+        resolutions.sort_by(|a, b| b.last_resolved.cmp(&a.last_resolved));
+        resolutions
+    }
+
+    /// Flagged URLs, most detections first
+    fn detected_urls(&self) -> Vec<&DetectedUrl> {
+        let mut urls: Vec<&DetectedUrl> = self.detected_urls.iter().filter(|url| url.positives > 0).collect();
+        urls.sort_by(|a, b| b.positives.cmp(&a.positives).then_with(|| b.scan_date.cmp(&a.scan_date)));
+        urls
+    }
+
+    /// The lines of the host's passive intel section
+    pub fn intel(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let categories = self.categories();
+        if !categories.is_empty() {
+            lines.push(format!("Categories: {}", categories.join(", ")));
+        }
+        let resolutions = self.resolutions();
+        if !resolutions.is_empty() {
+            let listed: Vec<String> = resolutions.iter()
+                .take(MAX_LISTED_RESOLUTIONS)
+                .map(|resolution| match &resolution.last_resolved {
+                    Some(date) => format!("{} ({})", resolution.ip_address, date),
+                    None => resolution.ip_address.clone(),
+                })
+                .collect();
+            lines.push(format!("Resolved to {} address(es): {}", resolutions.len(), listed.join(", ")));
+        }
+        let urls = self.detected_urls();
+        if urls.is_empty() {
+            lines.push("No URLs flagged by antivirus engines".to_string());
+        } else {
+            lines.push(format!("{} URL(s) flagged by antivirus engines:", urls.len()));
+            for url in urls.iter().take(MAX_LISTED_URLS) {
+                lines.push(format!("  {} ({}/{})", url.url, url.positives, url.total));
+            }
+        }
+        lines
+    }
+
+    /// One paragraph on the domain, for the chat and the AI
+    pub fn summary(&self, domain: &str) -> String {
+        let mut summary = format!("VirusTotal has seen {} resolve to {} address(es)", domain, self.resolutions.len());
+        if let Some(latest) = self.resolutions().first() {
+            summary.push_str(&format!(", most recently {}", latest.ip_address));
+        }
+        let categories = self.categories();
+        if !categories.is_empty() {
+            summary.push_str(&format!("; categorised as {}", categories.join(", ")));
+        }
+        match self.detected_urls() {
+            urls if urls.is_empty() => summary.push_str(". No URLs on it are flagged."),
+            urls => {
+                let listed: Vec<&str> = urls.iter().take(MAX_LISTED_URLS).map(|url| url.url.as_str()).collect();
+                summary.push_str(&format!(". {} URL(s) on it are flagged by antivirus engines: {}.", urls.len(), listed.join(", ")));
+            },
+        }
+        summary
+    }
+
+    /// The full report as written to the lookup's log
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        let categories = self.categories();
+        if !categories.is_empty() {
+            report.push_str(&format!("Categories: {}\n", categories.join(", ")));
+        }
+        report.push_str("\nResolutions:\n");
+        for resolution in self.resolutions() {
+            report.push_str(&format!("{} {}\n", resolution.ip_address, resolution.last_resolved.as_deref().unwrap_or("")));
+        }
+        report.push_str("\nDetected URLs:\n");
+        for url in self.detected_urls() {
+            report.push_str(&format!("{} {}/{} {}\n", url.url, url.positives, url.total, url.scan_date.as_deref().unwrap_or("")));
+        }
+        if !self.subdomains.is_empty() {
+            report.push_str(&format!("\nSubdomains:\n{}\n", self.subdomains.join("\n")));
+        }
+        report
+    }
+
+    /// A finding for URLs antivirus engines flagged, which may mean the site
+    /// was compromised or serves malware. Engines disagree often enough that
+    /// it needs checking by hand
+    pub fn findings(&self, domain: &str, command_id: &str) -> Vec<SecurityFinding> {
+        let urls = self.detected_urls();
+        if urls.is_empty() {
+            return Vec::new();
+        }
+        let evidence: Vec<String> = urls.iter()
+            .map(|url| format!("{} ({}/{} engines, scanned {})", url.url, url.positives, url.total, url.scan_date.as_deref().unwrap_or("unknown")))
+            .collect();
+        vec![create_finding(
+            "URLs Flagged as Malicious by VirusTotal",
+            &format!("Antivirus engines on VirusTotal flagged {} URL(s) on {}, which may indicate a compromise or malware being served", urls.len(), domain),
+            FindingSeverity::Medium,
+            command_id,
+            &evidence.join("\n"),
+        ).with_confidence(Confidence::Tentative)]
+    }
+
+    /// Subdomains of the domain VirusTotal has seen
+    fn subdomains(&self, domain: &str) -> Vec<String> {
+        let suffix = format!(".{}", domain.to_lowercase());
+        let mut subdomains: Vec<String> = self.subdomains.iter()
+            .map(|subdomain| subdomain.to_lowercase())
+            .filter(|subdomain| subdomain.ends_with(&suffix))
+            .collect();
+        subdomains.sort();
+        subdomains.dedup();
+        subdomains
+    }
+}
+
+/// The command a lookup is recorded as
+pub fn lookup_command(domain: &str) -> String {
+    format!("virustotal domain {}", domain)
+}
+
+/// Fetch VirusTotal's report on a domain and record it like a command's
+/// results: the report in the session's logs, a passive intel section on the
+/// domain's asset profile, the subdomains no other source found and flagged
+/// URLs as findings. Returns the report's summary
+pub async fn passive_recon(client: &VirusTotalClient, monitor: &CommandMonitor, target: &str) -> Result<String> {
+    let started = Utc::now();
+    let report = client.domain_report(target).await?;
+    let command_id = monitor.record_lookup(&lookup_command(target), started, &report.report())?;
+
+    let assets = monitor.get_asset_inventory();
+    assets.record_host(target, &command_id);
+    assets.record_intel(target, "VirusTotal", report.intel());
+    let subdomains: Vec<String> = report.subdomains(target).into_iter()
+        .filter(|subdomain| assets.record_subdomain(subdomain, &command_id))
+        .collect();
+
+    let summary = report.summary(target);
+    monitor.update_command_summary(&command_id, &summary)?;
+
+    let mut findings = report.findings(target, &command_id);
+    findings.extend(subdomains_finding(&subdomains, &command_id));
+    monitor.record_integration_findings(findings).await?;
+    Ok(summary)
+}
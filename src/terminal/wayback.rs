@@ -0,0 +1,205 @@
+use std::collections::HashSet;
+use std::fs;
+use std::net::IpAddr;
+use std::time::Duration;
+use anyhow::{Result, Context, anyhow};
+use chrono::Utc;
+use reqwest::Url;
+
+use crate::config::WaybackConfig;
+use super::command_monitor::{CommandMonitor, Confidence, FindingSeverity, SecurityFinding, create_finding};
+
+const API_URL: &str = "https://web.archive.org/cdx/search/cdx";
+
+/// The session's parameterized URLs, one per line, for tools that take a URL
+/// list such as `sqlmap -m`, `dalfox file` or `nuclei -l`
+pub const PARAMS_FILE: &str = "params.txt";
+
+/// Parameterized URLs named in a finding's description; its evidence lists them all
+const MAX_LISTED_URLS: usize = 10;
+
+/// Archived static files say nothing about the application's inputs
+const STATIC_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "svg", "ico", "bmp", "webp", "tif", "tiff",
+    "css", "woff", "woff2", "ttf", "eot", "otf",
+    "mp3", "mp4", "avi", "mov", "webm", "flv",
+];
+
+/// Collects the URLs the Wayback Machine archived for a target through its CDX
+/// API, the source waybackurls and gau query, without an external tool or API key
+pub struct WaybackClient {
+    limit: usize,
+    client: reqwest::Client,
+}
+
+impl WaybackClient {
+    pub fn new(config: &WaybackConfig) -> Result<Self> {
+        Ok(Self {
+            limit: config.limit,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(config.timeout_secs))
+                .build()?,
+        })
+    }
+
+    /// URLs archived for a host and, for a domain, all of its subdomains
+    pub async fn urls(&self, target: &str) -> Result<Vec<String>> {
+        let pattern = match target.parse::<IpAddr>() {
+            Ok(_) => format!("{}/*", target),
+            Err(_) => format!("*.{}/*", target),
+        };
+        let limit = self.limit.to_string();
+        let response = self.client.get(API_URL)
+            .query(&[
+                ("url", pattern.as_str()),
+                ("fl", "original"),
+                ("collapse", "urlkey"),
+                ("limit", limit.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to reach the Wayback Machine: {}", e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow!("{}", status));
+        }
+        let body = response.text().await.context("The Wayback Machine returned an unexpected response")?;
+        Ok(body.lines().map(str::to_string).collect())
+    }
+}
+
+/// The command a lookup is recorded as
+pub fn lookup_command(target: &str) -> String {
+    format!("wayback {}", target)
+}
+
+/// Hosts collected URLs must belong to: the scope's host names when it lists
+/// any, `*.` entries also covering subdomains, otherwise the target and its
+/// subdomains. Address ranges in the scope are not matched against URLs
+pub struct UrlScope {
+    hosts: Vec<String>,
+    domains: Vec<String>,
+}
+
+impl UrlScope {
+    pub fn new(target: &str, scope: &[String]) -> Self {
+        let mut hosts = Vec::new();
+        let mut domains = Vec::new();
+        for entry in scope.iter().map(|entry| entry.trim().to_lowercase()) {
+            if entry.contains('/') || entry.parse::<IpAddr>().is_ok() {
+                continue;
+            }
+            match entry.strip_prefix("*.") {
+                Some(domain) => domains.push(domain.to_string()),
+                None => hosts.push(entry),
+            }
+        }
+        if hosts.is_empty() && domains.is_empty() {
+            domains.push(target.to_lowercase());
+        }
+        Self { hosts, domains }
+    }
+
+    pub fn contains(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+        self.hosts.contains(&host)
+            || self.domains.iter().any(|domain| host == *domain || host.ends_with(&format!(".{}", domain)))
+    }
+}
+
+/// In-scope URLs from a URL collector's output, without static files and
+/// deduplicated: URLs differing only in fragment, default port or parameter
+/// values and order are the same input, so only the first is kept
+pub fn collect<'a>(lines: impl IntoIterator<Item = &'a str>, scope: &UrlScope) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut urls = Vec::new();
+    for line in lines {
+        let Ok(mut url) = Url::parse(line.trim()) else {
+            continue;
+        };
+        if !matches!(url.scheme(), "http" | "https") || !url.host_str().is_some_and(|host| scope.contains(host)) {
+            continue;
+        }
+        let extension = url.path().rsplit_once('.').map(|(_, extension)| extension.to_lowercase());
+        if extension.is_some_and(|extension| STATIC_EXTENSIONS.contains(&extension.as_str())) {
+            continue;
+        }
+
+        url.set_fragment(None);
+        let mut names: Vec<String> = url.query_pairs().map(|(name, _)| name.into_owned()).collect();
+        names.sort();
+        names.dedup();
+        let key = format!("{}://{}{}?{}", url.scheme(), url.host_str().unwrap_or_default(), url.path(), names.join("&"));
+        if seen.insert(key) {
+            urls.push(url.to_string());
+        }
+    }
+    urls
+}
+
+/// URLs with query parameters, the candidates for parameter-based testing
+pub fn parameterized(urls: &[String]) -> Vec<String> {
+    urls.iter()
+        .filter(|url| url.split_once('?').is_some_and(|(_, query)| !query.is_empty()))
+        .cloned()
+        .collect()
+}
+
+/// Record parameterized URLs in the asset inventory and rewrite the session's
+/// `params.txt` with every one known so far, returning the new ones
+pub fn record_parameterized(urls: &[String], monitor: &CommandMonitor, command_id: &str) -> Result<Vec<String>> {
+    let assets = monitor.get_asset_inventory();
+    let new: Vec<String> = parameterized(urls).into_iter()
+        .filter(|url| assets.record_url(url, command_id))
+        .collect();
+
+    let path = monitor.get_work_dir().join(PARAMS_FILE);
+    let mut list = assets.parameterized_urls().join("\n");
+    list.push('\n');
+    fs::write(&path, list).context(format!("Failed to write {:?}", path))?;
+    Ok(new)
+}
+
+/// An informational finding listing parameterized URLs a collector found
+pub fn parameters_finding(urls: &[String], command_id: &str) -> Option<SecurityFinding> {
+    if urls.is_empty() {
+        return None;
+    }
+
+    let listed = urls.iter().take(MAX_LISTED_URLS).cloned().collect::<Vec<_>>().join(", ");
+    let more = urls.len().saturating_sub(MAX_LISTED_URLS);
+    let more = if more > 0 { format!(" and {} more", more) } else { String::new() };
+    Some(create_finding(
+        "Parameterized URLs Collected",
+        &format!(
+            "{} archived URL(s) take query parameters and are candidates for injection testing (listed in {}): {}{}",
+            urls.len(), PARAMS_FILE, listed, more
+        ),
+        FindingSeverity::Info,
+        command_id,
+        &urls.join("\n"),
+    ).with_confidence(Confidence::Likely))
+}
+
+/// Collect the URLs the Wayback Machine archived for a target and record them
+/// like a URL collector's results: the in-scope URLs in the session's logs, the
+/// parameterized ones in the asset inventory and `params.txt`, and those no
+/// other source found in a "Parameterized URLs Collected" finding. Returns a summary
+pub async fn passive_recon(client: &WaybackClient, monitor: &CommandMonitor, target: &str, scope: &[String]) -> Result<String> {
+    let started = Utc::now();
+    let archived = client.urls(target).await?;
+    let urls = collect(archived.iter().map(String::as_str), &UrlScope::new(target, scope));
+    let command_id = monitor.record_lookup(&lookup_command(target), started, &urls.join("\n"))?;
+
+    let parameterized = parameterized(&urls);
+    let new = record_parameterized(&urls, monitor, &command_id)?;
+    let summary = format!(
+        "The Wayback Machine archived {} URL(s) for {}, {} unique and in scope, {} with parameters ({} not found by other sources, saved to {})",
+        archived.len(), target, urls.len(), parameterized.len(), new.len(), PARAMS_FILE,
+    );
+    monitor.update_command_summary(&command_id, &summary)?;
+
+    monitor.record_integration_findings(parameters_finding(&new, &command_id)).await?;
+    Ok(summary)
+}
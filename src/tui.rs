@@ -0,0 +1,623 @@
+use std::collections::VecDeque;
+use std::io::Write;
+use std::time::Duration;
+use anyhow::Result;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph},
+    Frame, Terminal,
+};
+use tokio::sync::mpsc;
+
+use crate::ai::GeminiAI;
+use crate::terminal::CommandMonitor;
+use crate::terminal::auto_documentation::{FindingStatus, FindingStore};
+use crate::terminal::command_monitor::{determine_command_type, FindingSeverity};
+use crate::terminal::safety_policy::SafetyPolicy;
+
+/// Lines of command output kept for scrolling back
+const OUTPUT_HISTORY: usize = 2000;
+
+/// How long AI-proposed commands may run before the next one starts
+const COMMAND_WAIT_SECONDS: u64 = 30;
+
+/// What the full-screen interface works with; the rest of the session is set
+/// up in main exactly as for the line interface
+pub struct Session {
+    pub ai: GeminiAI,
+    pub monitor: CommandMonitor,
+    pub findings: FindingStore,
+    pub safety_policy: SafetyPolicy,
+    /// Output from commands, follow-up actions and playbooks
+    pub output_rx: mpsc::Receiver<String>,
+    pub output_tx: mpsc::Sender<String>,
+}
+
+struct ChatLine {
+    color: Color,
+    text: String,
+}
+
+struct App {
+    session: Session,
+    chat: Vec<ChatLine>,
+    output: VecDeque<String>,
+    input: String,
+    /// Lines scrolled up from the bottom of the chat and output panes
+    chat_scroll: usize,
+    output_scroll: usize,
+    /// Output received while the AI is answering, added to its context once the answer is back
+    unobserved: Vec<String>,
+    waiting_for_ai: bool,
+    /// Set by a first Ctrl-C while commands are running
+    quit_requested: bool,
+    /// Whether running commands are terminated when the interface closes
+    exit: Option<bool>,
+}
+
+/// Run the interface until the user quits, returning whether running commands
+/// should be terminated rather than left running
+pub async fn run(session: Session) -> Result<bool> {
+    let (console_tx, mut console_rx) = mpsc::channel(100);
+    let (event_tx, mut event_rx) = mpsc::channel(100);
+    let (reply_tx, mut reply_rx) = mpsc::channel(1);
+
+    // Everything printed while the interface is up goes to the output pane
+    #[cfg(unix)]
+    let (_capture, screen) = console::ConsoleCapture::start(console_tx)?;
+    #[cfg(not(unix))]
+    let screen = {
+        drop(console_tx);
+        std::io::stdout()
+    };
+
+    let mut screen = Screen::enter(screen)?;
+
+    std::thread::spawn(move || {
+        while !event_tx.is_closed() {
+            match event::poll(Duration::from_millis(200)) {
+                Ok(true) => match event::read() {
+                    Ok(event) => {
+                        if event_tx.blocking_send(event).is_err() {
+                            break;
+                        }
+                    },
+                    Err(_) => break,
+                },
+                Ok(false) => {},
+                Err(_) => break,
+            }
+        }
+    });
+
+    let mut app = App {
+        session,
+        chat: Vec::new(),
+        output: VecDeque::new(),
+        input: String::new(),
+        chat_scroll: 0,
+        output_scroll: 0,
+        unobserved: Vec::new(),
+        waiting_for_ai: false,
+        quit_requested: false,
+        exit: None,
+    };
+
+    // Greet the user, as the line interface does
+    app.ask_ai(&reply_tx);
+
+    let mut refresh = tokio::time::interval(Duration::from_millis(500));
+    loop {
+        screen.terminal.draw(|frame| app.draw(frame))?;
+
+        tokio::select! {
+            Some(output) = app.session.output_rx.recv() => app.on_output(output),
+            Some(line) = console_rx.recv() => app.push_output(line),
+            Some((ai, reply)) = reply_rx.recv() => app.on_reply(ai, reply),
+            Some(event) = event_rx.recv() => app.on_event(event, &reply_tx),
+            // Keep the command timers and findings current
+            _ = refresh.tick() => {},
+        }
+
+        if let Some(terminate) = app.exit {
+            return Ok(terminate);
+        }
+    }
+}
+
+impl App {
+    fn push_chat(&mut self, color: Color, text: impl Into<String>) {
+        self.chat.push(ChatLine { color, text: text.into() });
+        self.chat_scroll = 0;
+    }
+
+    fn push_output(&mut self, text: String) {
+        for line in text.lines() {
+            self.output.push_back(line.to_string());
+            if self.output.len() > OUTPUT_HISTORY {
+                self.output.pop_front();
+            }
+            // Hold the view still while the user reads back
+            if self.output_scroll > 0 {
+                self.output_scroll += 1;
+            }
+        }
+    }
+
+    fn on_output(&mut self, output: String) {
+        if self.waiting_for_ai {
+            self.unobserved.push(output.clone());
+        } else {
+            crate::observe_output(&mut self.session.ai, &self.session.monitor, &output);
+        }
+        self.push_output(output);
+    }
+
+    /// Ask the AI in the background so the panes keep updating meanwhile
+    fn ask_ai(&mut self, reply_tx: &mpsc::Sender<(GeminiAI, Result<String>)>) {
+        let mut ai = self.session.ai.clone();
+        let reply_tx = reply_tx.clone();
+        self.waiting_for_ai = true;
+        tokio::spawn(async move {
+            let reply = ai.get_response().await;
+            let _ = reply_tx.send((ai, reply)).await;
+        });
+    }
+
+    fn on_reply(&mut self, ai: GeminiAI, reply: Result<String>) {
+        self.session.ai = ai;
+        self.waiting_for_ai = false;
+        for output in std::mem::take(&mut self.unobserved) {
+            crate::observe_output(&mut self.session.ai, &self.session.monitor, &output);
+        }
+
+        match reply {
+            Ok(response) => {
+                let (display_response, commands) = crate::process_response(&response, &self.session.safety_policy);
+                self.push_chat(Color::Green, format!("Hacksor: {}", display_response));
+                if !commands.is_empty() {
+                    self.push_chat(Color::Blue, format!("Running {} commands one after another", commands.len()));
+                    let monitor = self.session.monitor.clone();
+                    let output_tx = self.session.output_tx.clone();
+                    tokio::spawn(run_commands(monitor, commands, output_tx));
+                }
+            },
+            Err(e) => self.push_chat(Color::Red, format!("[ERROR] Failed to get AI response: {}", e)),
+        }
+    }
+
+    fn on_event(&mut self, event: Event, reply_tx: &mpsc::Sender<(GeminiAI, Result<String>)>) {
+        let Event::Key(key) = event else {
+            return;
+        };
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                // Like the line interface: a second Ctrl-C terminates running commands
+                if self.session.monitor.get_active_commands().is_empty() || self.quit_requested {
+                    self.exit = Some(true);
+                } else {
+                    self.quit_requested = true;
+                    self.push_chat(Color::Yellow, "Commands are still running. Type 'exit' to terminate them or 'detach' to leave them running (Ctrl-C again terminates)");
+                }
+            },
+            KeyCode::Char(c) => self.input.push(c),
+            KeyCode::Backspace => {
+                self.input.pop();
+            },
+            KeyCode::Esc => self.input.clear(),
+            KeyCode::Up => self.chat_scroll += 1,
+            KeyCode::Down => self.chat_scroll = self.chat_scroll.saturating_sub(1),
+            KeyCode::PageUp => self.output_scroll += 10,
+            KeyCode::PageDown => self.output_scroll = self.output_scroll.saturating_sub(10),
+            KeyCode::Enter => {
+                let input = std::mem::take(&mut self.input);
+                self.on_input(input.trim(), reply_tx);
+            },
+            _ => {},
+        }
+    }
+
+    fn on_input(&mut self, input: &str, reply_tx: &mpsc::Sender<(GeminiAI, Result<String>)>) {
+        let approval_gate = self.session.monitor.get_approval_gate();
+        if approval_gate.has_pending() {
+            if let Some((command, decision)) = approval_gate.answer(input) {
+                let summary = crate::record_approval(&self.session.monitor, &command, &decision);
+                self.push_chat(Color::Cyan, summary);
+            }
+            return;
+        }
+
+        if input.is_empty() {
+            return;
+        }
+        match input.to_lowercase().as_str() {
+            "exit" | "quit" => {
+                self.exit = Some(true);
+                return;
+            },
+            "detach" => {
+                self.exit = Some(false);
+                return;
+            },
+            _ => {},
+        }
+
+        self.push_chat(Color::White, format!("You: {}", input));
+
+        if let Some(command) = input.strip_prefix("!exec") {
+            let command = self.session.safety_policy.apply(command.trim());
+            let monitor = self.session.monitor.clone();
+            let output_tx = self.session.output_tx.clone();
+            tokio::spawn(run_commands(monitor, vec![command], output_tx));
+            return;
+        }
+        if let Some(topic) = input.strip_prefix("!help") {
+            self.push_chat(Color::Cyan, crate::help::help(topic.trim()));
+            return;
+        }
+        if input.starts_with('!') {
+            self.push_chat(Color::Yellow, "Only !exec and !help are available here; run without --tui for the other ! commands");
+            return;
+        }
+        if self.waiting_for_ai {
+            self.push_chat(Color::Yellow, "Still waiting for the answer to the previous message");
+            return;
+        }
+
+        self.session.ai.add_user_message(input);
+
+        // Give the AI the fingerprinted stack so it can pick relevant follow-up checks
+        let inventory = self.session.monitor.get_tech_inventory();
+        if !inventory.is_empty() {
+            self.session.ai.add_assistant_message(&format!(
+                "Technology stack fingerprinted so far:\n{}",
+                inventory.summary()
+            ));
+        }
+
+        self.ask_ai(reply_tx);
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(55), Constraint::Min(5), Constraint::Length(3)])
+            .split(frame.size());
+        let top = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(rows[0]);
+        let side = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+            .split(top[1]);
+
+        self.draw_chat(frame, top[0]);
+        self.draw_commands(frame, side[0]);
+        self.draw_findings(frame, side[1]);
+        self.draw_output(frame, rows[1]);
+        self.draw_input(frame, rows[2]);
+    }
+
+    fn draw_chat(&self, frame: &mut Frame, area: Rect) {
+        let width = area.width.saturating_sub(2) as usize;
+        let mut lines: Vec<Line> = Vec::new();
+        for message in &self.chat {
+            for text in wrap(&message.text, width) {
+                lines.push(Line::styled(text, Style::default().fg(message.color)));
+            }
+        }
+        if self.waiting_for_ai {
+            lines.push(Line::styled("Hacksor is thinking...", Style::default().fg(Color::DarkGray)));
+        }
+
+        let title = if self.chat_scroll > 0 { " Chat (scrolled, Down to follow) " } else { " Chat " };
+        let lines = visible(lines, area.height.saturating_sub(2) as usize, self.chat_scroll);
+        frame.render_widget(Paragraph::new(lines).block(pane(title)), area);
+    }
+
+    fn draw_commands(&self, frame: &mut Frame, area: Rect) {
+        let now = chrono::Utc::now();
+        let active = self.session.monitor.get_active_commands();
+        let items: Vec<ListItem> = active.iter()
+            .map(|cmd| {
+                let elapsed = (now - cmd.start_time).num_seconds().max(0);
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:>3}:{:02} ", elapsed / 60, elapsed % 60), Style::default().fg(Color::Yellow)),
+                    Span::raw(cmd.command.clone()),
+                ]))
+            })
+            .collect();
+
+        let title = format!(" Active commands ({}) ", active.len());
+        frame.render_widget(List::new(items).block(pane(&title)), area);
+    }
+
+    fn draw_findings(&self, frame: &mut Frame, area: Rect) {
+        let mut findings: Vec<_> = self.session.findings.lock().unwrap().values()
+            .filter(|finding| finding.status != FindingStatus::FalsePositive)
+            .cloned()
+            .collect();
+        findings.sort_by(|a, b| b.severity.rank().cmp(&a.severity.rank())
+            .then(a.discovery_date.cmp(&b.discovery_date)));
+
+        let items: Vec<ListItem> = findings.iter()
+            .map(|finding| ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{:<8} ", format!("{:?}", finding.severity)),
+                    Style::default().fg(severity_color(&finding.severity)).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(format!("{} ", finding.id), Style::default().fg(Color::DarkGray)),
+                Span::raw(finding.title.clone()),
+            ])))
+            .collect();
+
+        let title = format!(" Findings ({}) ", findings.len());
+        frame.render_widget(List::new(items).block(pane(&title)), area);
+    }
+
+    fn draw_output(&self, frame: &mut Frame, area: Rect) {
+        let lines: Vec<Line> = self.output.iter()
+            .map(|line| Line::styled(line.clone(), Style::default().fg(output_color(line))))
+            .collect();
+
+        let title = if self.output_scroll > 0 { " Output (scrolled, PgDn to follow) " } else { " Output " };
+        let lines = visible(lines, area.height.saturating_sub(2) as usize, self.output_scroll);
+        frame.render_widget(Paragraph::new(lines).block(pane(title)), area);
+    }
+
+    fn draw_input(&self, frame: &mut Frame, area: Rect) {
+        let (title, style) = match self.session.monitor.get_approval_gate().pending_prompt() {
+            // Keep the whole question on the border, the command included
+            Some(prompt) => (format!(" {} ", prompt.split_whitespace().collect::<Vec<_>>().join(" ")), Style::default().fg(Color::Yellow)),
+            None => (" Message, !exec <command>, !help, exit or detach · Up/Down chat · PgUp/PgDn output ".to_string(), Style::default()),
+        };
+
+        let width = area.width.saturating_sub(2) as usize;
+        let shown: String = {
+            let skip = self.input.chars().count().saturating_sub(width.saturating_sub(1));
+            self.input.chars().skip(skip).collect()
+        };
+        let cursor_x = area.x + 1 + shown.chars().count() as u16;
+        frame.render_widget(Paragraph::new(shown).block(pane(&title).border_style(style)), area);
+        frame.set_cursor(cursor_x, area.y + 1);
+    }
+}
+
+/// Run commands one after another, each given a while to finish before the next starts
+async fn run_commands(monitor: CommandMonitor, commands: Vec<String>, tx: mpsc::Sender<String>) {
+    for command in commands {
+        // Skip commands that already ran recently and surface their results
+        if let Some(previous) = crate::find_duplicate(&monitor, &command) {
+            let _ = tx.send(format!("{}\n[Hacksor] Use !exec to run it again.", crate::describe_cached_result(&previous))).await;
+            continue;
+        }
+
+        let _ = tx.send(format!("[Hacksor] Taking action: {}", command)).await;
+        match monitor.execute_command(&command, determine_command_type(&command)).await {
+            Ok(id) => {
+                if !monitor.wait_for_command_completion(&id, COMMAND_WAIT_SECONDS).await {
+                    let _ = tx.send("[Hacksor] Command is taking a long time to complete, continuing with next steps...".to_string()).await;
+                }
+            },
+            Err(e) => {
+                let _ = tx.send(format!("[ERROR] Failed to execute command '{}': {}", command, e)).await;
+                crate::report_policy_refusal(&tx, &e).await;
+            },
+        }
+    }
+}
+
+fn pane(title: &str) -> Block<'_> {
+    Block::default().borders(Borders::ALL).title(title)
+}
+
+/// The lines that fit in `height`, `scroll` lines up from the bottom
+fn visible(lines: Vec<Line>, height: usize, scroll: usize) -> Vec<Line> {
+    let end = lines.len().saturating_sub(scroll).max(height.min(lines.len()));
+    let start = end.saturating_sub(height);
+    lines.into_iter().skip(start).take(end - start).collect()
+}
+
+/// Word-wrap text to the pane width, breaking words longer than a line
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            let mut word: Vec<char> = word.chars().collect();
+            let used = line.chars().count();
+            if used > 0 && used + 1 + word.len() > width {
+                lines.push(std::mem::take(&mut line));
+            } else if used > 0 {
+                line.push(' ');
+            }
+            while line.chars().count() + word.len() > width {
+                let room = width - line.chars().count();
+                line.extend(word.drain(..room));
+                lines.push(std::mem::take(&mut line));
+            }
+            line.extend(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+fn severity_color(severity: &FindingSeverity) -> Color {
+    match severity {
+        FindingSeverity::Critical => Color::Magenta,
+        FindingSeverity::High => Color::Red,
+        FindingSeverity::Medium => Color::Yellow,
+        FindingSeverity::Low => Color::Blue,
+        FindingSeverity::Info => Color::Gray,
+    }
+}
+
+fn output_color(line: &str) -> Color {
+    if line.starts_with("[ERROR]") || line.contains("[ALERT]") {
+        Color::Red
+    } else if line.starts_with("[FINDING") || line.starts_with("[APPROVAL]") || line.starts_with("[POLICY]") {
+        Color::Yellow
+    } else if line.starts_with("[Hacksor]") || line.starts_with("[PLAYBOOK]") {
+        Color::Cyan
+    } else {
+        Color::Reset
+    }
+}
+
+/// The terminal in raw mode on the alternate screen, restored on drop so it
+/// is usable again however the interface exits
+struct Screen<W: Write> {
+    terminal: Terminal<CrosstermBackend<W>>,
+}
+
+impl<W: Write> Screen<W> {
+    fn enter(mut writer: W) -> Result<Self> {
+        enable_raw_mode()?;
+        execute!(writer, EnterAlternateScreen)?;
+        Ok(Self {
+            terminal: Terminal::new(CrosstermBackend::new(writer))?,
+        })
+    }
+}
+
+impl<W: Write> Drop for Screen<W> {
+    fn drop(&mut self) {
+        let _ = execute!(self.terminal.backend_mut(), LeaveAlternateScreen, crossterm::cursor::Show);
+        let _ = disable_raw_mode();
+    }
+}
+
+#[cfg(unix)]
+mod console {
+    use std::fs::File;
+    use std::io::{self, Read, Write};
+    use std::os::fd::{AsRawFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
+    use anyhow::{Result, anyhow};
+    use regex::Regex;
+    use tokio::sync::mpsc;
+
+    /// stdout and stderr redirected into a pipe, so that what background tasks
+    /// print shows up in the output pane instead of over the interface; the
+    /// originals are put back on drop
+    pub struct ConsoleCapture {
+        stdout: RawFd,
+        stderr: RawFd,
+    }
+
+    impl ConsoleCapture {
+        /// Start capturing into `tx`, returning the terminal to draw on
+        pub fn start(tx: mpsc::Sender<String>) -> Result<(Self, File)> {
+            io::stdout().flush()?;
+
+            // SAFETY: plain descriptor calls; every descriptor created here is
+            // owned until it is handed over, so it is closed on any error, and
+            // the saved originals are closed when the capture is dropped. All
+            // are close-on-exec so that commands started meanwhile don't hold
+            // the pipe open
+            unsafe {
+                let mut pipe = [0; 2];
+                if libc::pipe2(pipe.as_mut_ptr(), libc::O_CLOEXEC) != 0 {
+                    return Err(anyhow!("Failed to capture the console: {}", io::Error::last_os_error()));
+                }
+                let reader = File::from_raw_fd(pipe[0]);
+                let writer = OwnedFd::from_raw_fd(pipe[1]);
+                let stdout = duplicate(libc::STDOUT_FILENO)?;
+                let stderr = duplicate(libc::STDERR_FILENO)?;
+                let screen = duplicate(libc::STDOUT_FILENO)?;
+                if libc::dup2(writer.as_raw_fd(), libc::STDOUT_FILENO) < 0 {
+                    return Err(anyhow!("Failed to capture the console: {}", io::Error::last_os_error()));
+                }
+                if libc::dup2(writer.as_raw_fd(), libc::STDERR_FILENO) < 0 {
+                    let error = io::Error::last_os_error();
+                    libc::dup2(stdout.as_raw_fd(), libc::STDOUT_FILENO);
+                    return Err(anyhow!("Failed to capture the console: {}", error));
+                }
+                drop(writer);
+
+                let capture = Self {
+                    stdout: stdout.into_raw_fd(),
+                    stderr: stderr.into_raw_fd(),
+                };
+                std::thread::spawn(move || forward(reader, tx));
+                Ok((capture, File::from(screen)))
+            }
+        }
+    }
+
+    /// A close-on-exec copy of a descriptor
+    unsafe fn duplicate(fd: RawFd) -> Result<OwnedFd> {
+        let copy = libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 0);
+        if copy < 0 {
+            return Err(anyhow!("Failed to capture the console: {}", io::Error::last_os_error()));
+        }
+        Ok(OwnedFd::from_raw_fd(copy))
+    }
+
+    impl Drop for ConsoleCapture {
+        fn drop(&mut self) {
+            let _ = io::stdout().flush();
+            // SAFETY: restores the descriptors duplicated in `start`
+            unsafe {
+                if self.stdout >= 0 {
+                    libc::dup2(self.stdout, libc::STDOUT_FILENO);
+                    libc::close(self.stdout);
+                }
+                if self.stderr >= 0 {
+                    libc::dup2(self.stderr, libc::STDERR_FILENO);
+                    libc::close(self.stderr);
+                }
+            }
+        }
+    }
+
+    /// Send what is written to the pipe line by line, without colors, until the
+    /// console is restored
+    fn forward(mut reader: File, tx: mpsc::Sender<String>) {
+        let escapes = Regex::new(r"\x1b\[[0-9;?]*[ -/]*[@-~]|[\r\x07]").unwrap();
+        let mut buffer = [0u8; 4096];
+        let mut pending = Vec::new();
+
+        loop {
+            let read = match reader.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(read) => read,
+            };
+            pending.extend_from_slice(&buffer[..read]);
+
+            let mut lines = Vec::new();
+            while let Some(end) = pending.iter().position(|byte| *byte == b'\n') {
+                lines.push(pending.drain(..=end).collect::<Vec<u8>>());
+            }
+            // Prompts don't end in a newline; pass them on once the writer pauses
+            if read < buffer.len() && !pending.is_empty() {
+                lines.push(std::mem::take(&mut pending));
+            }
+
+            for line in lines {
+                let line = String::from_utf8_lossy(&line);
+                let line = escapes.replace_all(line.trim_end(), "");
+                if line.trim().is_empty() {
+                    continue;
+                }
+                if tx.blocking_send(line.into_owned()).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
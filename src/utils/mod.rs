@@ -1,6 +1,8 @@
 use anyhow::Result;
+use regex::Regex;
 use std::path::PathBuf;
 use std::fs;
+use std::sync::OnceLock;
 
 #[allow(dead_code)]
 pub fn ensure_directory(path: &PathBuf) -> Result<()> {
@@ -27,7 +29,6 @@ pub fn format_command_output(output: &[u8]) -> String {
         .join("\n")
 }
 
-#[allow(dead_code)]
 pub fn parse_scope_file(path: &PathBuf) -> Result<Vec<String>> {
     let content = fs::read_to_string(path)?;
     let lines = content.lines()
@@ -35,4 +36,59 @@ pub fn parse_scope_file(path: &PathBuf) -> Result<Vec<String>> {
         .map(String::from)
         .collect();
     Ok(lines)
-} 
\ No newline at end of file
+} 
+
+/// Extracts target domain/IP from a command string
+pub fn extract_target_from_command(command: &str) -> Option<String> {
+    static DOMAIN_PATTERN: OnceLock<Regex> = OnceLock::new();
+    static IP_PATTERN: OnceLock<Regex> = OnceLock::new();
+    let domain_pattern = DOMAIN_PATTERN.get_or_init(|| Regex::new(r"^[a-zA-Z0-9][-a-zA-Z0-9]*\.[a-zA-Z0-9]+(?:\.[a-zA-Z0-9]+)*$").unwrap());
+    let ip_pattern = IP_PATTERN.get_or_init(|| Regex::new(r"^\d{1,3}\.\d{1,3}\.\d{1,3}\.\d{1,3}$").unwrap());
+    
+    // Simple heuristic - grab the last term which looks like a domain or IP, or a URL of one
+    command.split_whitespace()
+        .rev()
+        .filter(|term| !term.starts_with('-'))
+        .map(|term| term_host(term.trim_matches(['\'', '"'])))
+        .find(|host| ip_pattern.is_match(host) || (domain_pattern.is_match(host) && !is_file_name(host)))
+        .map(|host| host.to_lowercase())
+}
+
+/// The host part of a command argument: `https://user@host:8443/path` and `host:80` give `host`
+pub fn term_host(term: &str) -> &str {
+    let term = term.split_once("://").map_or(term, |(_, rest)| rest);
+    let authority = term.split(['/', '?', '#']).next().unwrap_or(term);
+    let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    match host.rsplit_once(':') {
+        Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+        _ => host,
+    }
+}
+
+/// Output files and wordlists look like domains to the pattern above
+fn is_file_name(term: &str) -> bool {
+    const FILE_EXTENSIONS: &[&str] = &[
+        "txt", "lst", "json", "xml", "csv", "log", "out", "nmap", "gnmap", "html", "htm", "yaml", "yml", "conf",
+    ];
+    term.rsplit_once('.')
+        .is_some_and(|(_, extension)| FILE_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+/// Quote a value as a single shell word
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_the_target_of_a_command() {
+        assert_eq!(extract_target_from_command("nmap -sV Example.com").as_deref(), Some("example.com"));
+        assert_eq!(extract_target_from_command("nikto -h https://admin@example.com:8443/login").as_deref(), Some("example.com"));
+        assert_eq!(extract_target_from_command("gobuster dir -u 10.0.0.5:8080 -w list.txt -o out.txt").as_deref(), Some("10.0.0.5"));
+        assert_eq!(extract_target_from_command("curl -sk 'https://example.com/a?b=c'").as_deref(), Some("example.com"));
+        assert_eq!(extract_target_from_command("ls -la"), None);
+    }
+}
\ No newline at end of file